@@ -7,7 +7,7 @@ use vector::{
     buffers::Acker,
     sinks::util::{
         batch::{Batch, BatchConfig, BatchError, BatchSettings, BatchSize, PushResult},
-        BatchSink, Buffer, Compression, Partition, PartitionBatchSink,
+        BatchSink, Buffer, Compression, EncodedEvent, Partition, PartitionBatchSink,
     },
     test_util::{random_lines, runtime},
 };
@@ -24,7 +24,7 @@ fn batching(
             move || {
                 let input = random_lines(event_len)
                     .take(num_events)
-                    .map(|s| s.into_bytes())
+                    .map(|s| EncodedEvent::new(s.into_bytes()))
                     .collect::<Vec<_>>();
                 futures01::stream::iter_ok::<_, ()>(input.into_iter())
             },
@@ -66,9 +66,11 @@ fn partitioned_batching(
                 let input = random_lines(event_len)
                     .take(num_events)
                     .map(|s| s.into_bytes())
-                    .map(|b| InnerBuffer {
-                        inner: b,
-                        key: key.clone(),
+                    .map(|b| {
+                        EncodedEvent::new(InnerBuffer {
+                            inner: b,
+                            key: key.clone(),
+                        })
                     })
                     .collect::<Vec<_>>();
                 futures01::stream::iter_ok::<_, ()>(input.into_iter())
@@ -52,6 +52,7 @@ fn benchmark_files_without_partitions(c: &mut Criterion) {
                         idle_timeout_secs: None,
                         encoding: sinks::file::Encoding::Text.into(),
                         compression: sinks::file::Compression::None,
+                        on_missing: Default::default(),
                     },
                 );
 
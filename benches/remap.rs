@@ -0,0 +1,164 @@
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Benchmark, Criterion};
+use indexmap::IndexMap;
+use vector::{
+    event::Value,
+    transforms::{
+        self,
+        lua::v2::LuaConfig,
+        remap::{Remap, RemapConfig},
+        Transform,
+    },
+    Event,
+};
+
+fn add_fields(c: &mut Criterion) {
+    let num_events: usize = 100_000;
+
+    let key = "the key";
+    let value = "this is the value";
+
+    let key_atom_native = key.into();
+    let value_bytes_native = Bytes::from(value).into();
+    let key_atom_remap = key.into();
+    let value_bytes_remap = Bytes::from(value).into();
+    let key_atom_lua = key.into();
+    let value_bytes_lua = Bytes::from(value).into();
+
+    c.bench(
+        "remap_add_fields",
+        Benchmark::new("native", move |b| {
+            b.iter_with_setup(
+                || {
+                    let mut map = IndexMap::new();
+                    map.insert(
+                        key.to_string().into(),
+                        toml::value::Value::String(value.to_string()),
+                    );
+                    transforms::add_fields::AddFields::new(map, true)
+                },
+                |mut transform| {
+                    for _ in 0..num_events {
+                        let event = Event::new_empty_log();
+                        let event = transform.transform(event).unwrap();
+                        assert_eq!(event.as_log()[&key_atom_native], value_bytes_native);
+                    }
+                },
+            )
+        })
+        .with_function("remap", move |b| {
+            b.iter_with_setup(
+                || {
+                    Remap::new(&RemapConfig {
+                        source: format!(r#"."{}" = "{}""#, key, value),
+                        drop_on_err: false,
+                    })
+                    .unwrap()
+                },
+                |mut transform| {
+                    for _ in 0..num_events {
+                        let event = Event::new_empty_log();
+                        let event = transform.transform(event).unwrap();
+                        assert_eq!(event.as_log()[&key_atom_remap], value_bytes_remap);
+                    }
+                },
+            )
+        })
+        .with_function("lua", move |b| {
+            b.iter_with_setup(
+                || {
+                    let config = format!(
+                        r#"
+                        hooks.process = """
+                            function (event, emit)
+                                event.log['{}'] = '{}'
+                                emit(event)
+                            end
+                        """
+                        "#,
+                        key, value
+                    );
+                    transforms::lua::v2::Lua::new(&toml::from_str::<LuaConfig>(&config).unwrap())
+                        .unwrap()
+                },
+                |mut transform| {
+                    for _ in 0..num_events {
+                        let event = Event::new_empty_log();
+                        let event = transform.transform(event).unwrap();
+                        assert_eq!(event.as_log()[&key_atom_lua], value_bytes_lua);
+                    }
+                },
+            )
+        })
+        .sample_size(10),
+    );
+}
+
+fn parse_and_reshape(c: &mut Criterion) {
+    let num_events: usize = 100_000;
+    let message = r#"{"level": "INFO", "status": "503", "took": 1.5}"#;
+
+    c.bench(
+        "remap_parse_and_reshape",
+        Benchmark::new("remap", move |b| {
+            b.iter_with_setup(
+                || {
+                    Remap::new(&RemapConfig {
+                        source: r#"
+                            .parsed = parse_json(.message)
+                            .level = downcase(.parsed.level)
+                            .status = to_int(.parsed.status)
+                            .took_ms = .parsed.took * 1000
+                            .error = .status >= 500
+                            del(.message)
+                            del(.parsed)
+                        "#
+                        .to_owned(),
+                        drop_on_err: false,
+                    })
+                    .unwrap()
+                },
+                |mut transform| {
+                    for _ in 0..num_events {
+                        let event = transform.transform(Event::from(message)).unwrap();
+                        assert_eq!(event.as_log()[&"error".into()], Value::Boolean(true));
+                    }
+                },
+            )
+        })
+        .with_function("lua", move |b| {
+            b.iter_with_setup(
+                || {
+                    let config = r#"
+                        hooks.process = """
+                            function (event, emit)
+                                local level, status, took = string.match(
+                                    event.log.message,
+                                    '"level": "(%a+)", "status": "(%d+)", "took": ([%d.]+)'
+                                )
+                                event.log.level = string.lower(level)
+                                event.log.status = tonumber(status)
+                                event.log.took_ms = tonumber(took) * 1000
+                                event.log.error = event.log.status >= 500
+                                event.log.message = nil
+                                emit(event)
+                            end
+                        """
+                    "#;
+                    transforms::lua::v2::Lua::new(&toml::from_str::<LuaConfig>(config).unwrap())
+                        .unwrap()
+                },
+                |mut transform| {
+                    for _ in 0..num_events {
+                        let event = transform.transform(Event::from(message)).unwrap();
+                        assert_eq!(event.as_log()[&"error".into()], Value::Boolean(true));
+                    }
+                },
+            )
+        })
+        .sample_size(10),
+    );
+}
+
+criterion_group!(remap, add_fields, parse_and_reshape);
+criterion_main!(remap);
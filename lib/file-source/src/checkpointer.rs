@@ -0,0 +1,178 @@
+use crate::{FileFingerprint, FilePosition};
+use glob::glob;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time;
+
+/// The checkpoints of a `Checkpointer`, shared with whatever decides when a
+/// file has been processed up to a given position.
+#[derive(Debug, Default)]
+pub struct CheckpointsView {
+    checkpoints: Mutex<HashMap<FileFingerprint, FilePosition>>,
+}
+
+impl CheckpointsView {
+    pub fn update(&self, fng: FileFingerprint, pos: FilePosition) {
+        self.lock().insert(fng, pos);
+    }
+
+    pub fn get(&self, fng: FileFingerprint) -> Option<FilePosition> {
+        self.lock().get(&fng).cloned()
+    }
+
+    pub fn remove(&self, fng: FileFingerprint) -> Option<FilePosition> {
+        self.lock().remove(&fng)
+    }
+
+    /// All the checkpoints, ordered by fingerprint.
+    pub fn list(&self) -> Vec<(FileFingerprint, FilePosition)> {
+        let mut checkpoints = self
+            .lock()
+            .iter()
+            .map(|(&fng, &pos)| (fng, pos))
+            .collect::<Vec<_>>();
+        checkpoints.sort();
+        checkpoints
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<FileFingerprint, FilePosition>> {
+        self.checkpoints
+            .lock()
+            .expect("Checkpoints mutex is poisoned")
+    }
+}
+
+/// Stores the position read up to in each file, keyed by fingerprint, as
+/// empty files named `<fingerprint>.<position>` under `data_dir/checkpoints`.
+pub struct Checkpointer {
+    directory: PathBuf,
+    glob_string: String,
+    checkpoints: Arc<CheckpointsView>,
+}
+
+impl Checkpointer {
+    pub fn new(data_dir: &Path) -> Checkpointer {
+        let directory = data_dir.join("checkpoints");
+        let glob_string = directory.join("*").to_string_lossy().into_owned();
+        Checkpointer {
+            directory,
+            glob_string,
+            checkpoints: Arc::new(CheckpointsView::default()),
+        }
+    }
+
+    pub fn view(&self) -> Arc<CheckpointsView> {
+        Arc::clone(&self.checkpoints)
+    }
+
+    fn encode(&self, fng: FileFingerprint, pos: FilePosition) -> PathBuf {
+        self.directory.join(format!("{:x}.{}", fng, pos))
+    }
+
+    fn decode(&self, path: &Path) -> Option<(FileFingerprint, FilePosition)> {
+        let file_name = &path.file_name()?.to_string_lossy();
+        scan_fmt!(file_name, "{x}.{}", [hex FileFingerprint], FilePosition).ok()
+    }
+
+    pub fn set_checkpoint(&mut self, fng: FileFingerprint, pos: FilePosition) {
+        self.checkpoints.update(fng, pos);
+    }
+
+    pub fn get_checkpoint(&self, fng: FileFingerprint) -> Option<FilePosition> {
+        self.checkpoints.get(fng)
+    }
+
+    pub fn write_checkpoints(&mut self) -> Result<usize, io::Error> {
+        let checkpoints = self.checkpoints.list();
+        fs::remove_dir_all(&self.directory).ok();
+        fs::create_dir_all(&self.directory)?;
+        for &(fng, pos) in &checkpoints {
+            fs::File::create(self.encode(fng, pos))?;
+        }
+        Ok(checkpoints.len())
+    }
+
+    pub fn read_checkpoints(&mut self, ignore_before: Option<time::SystemTime>) {
+        for path in glob(&self.glob_string).unwrap().flatten() {
+            if let Some(ignore_before) = ignore_before {
+                if let Ok(Ok(modified)) = fs::metadata(&path).map(|metadata| metadata.modified()) {
+                    if modified < ignore_before {
+                        fs::remove_file(path).ok();
+                        continue;
+                    }
+                }
+            }
+            match self.decode(&path) {
+                Some((fng, pos)) => self.checkpoints.update(fng, pos),
+                None => warn!(
+                    message = "Ignoring file that is not a checkpoint.",
+                    path = ?path
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Checkpointer, FileFingerprint, FilePosition};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_checkpointer_basics() {
+        let fingerprint: FileFingerprint = 0x1234567890abcdef;
+        let position: FilePosition = 1234;
+        let data_dir = tempdir().unwrap();
+        let mut chkptr = Checkpointer::new(&data_dir.path());
+        assert_eq!(
+            chkptr.decode(&chkptr.encode(fingerprint, position)),
+            Some((fingerprint, position))
+        );
+        chkptr.set_checkpoint(fingerprint, position);
+        assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
+    }
+
+    #[test]
+    fn test_checkpointer_restart() {
+        let fingerprint: FileFingerprint = 0x1234567890abcdef;
+        let position: FilePosition = 1234;
+        let data_dir = tempdir().unwrap();
+        {
+            let mut chkptr = Checkpointer::new(&data_dir.path());
+            chkptr.set_checkpoint(fingerprint, position);
+            assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
+            chkptr.write_checkpoints().ok();
+        }
+        {
+            let mut chkptr = Checkpointer::new(&data_dir.path());
+            assert_eq!(chkptr.get_checkpoint(fingerprint), None);
+            chkptr.read_checkpoints(None);
+            assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
+        }
+    }
+
+    #[test]
+    fn test_checkpointer_view() {
+        let data_dir = tempdir().unwrap();
+        let mut chkptr = Checkpointer::new(&data_dir.path());
+        let view = chkptr.view();
+
+        view.update(2, 20);
+        view.update(1, 10);
+        assert_eq!(chkptr.get_checkpoint(2), Some(20));
+        chkptr.write_checkpoints().unwrap();
+
+        let mut chkptr = Checkpointer::new(&data_dir.path());
+        chkptr.read_checkpoints(None);
+        assert_eq!(chkptr.view().list(), vec![(1, 10), (2, 20)]);
+
+        assert_eq!(chkptr.view().remove(1), Some(10));
+        chkptr.write_checkpoints().unwrap();
+        let mut chkptr = Checkpointer::new(&data_dir.path());
+        chkptr.read_checkpoints(None);
+        assert_eq!(chkptr.view().list(), vec![(2, 20)]);
+    }
+}
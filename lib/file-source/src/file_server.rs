@@ -1,16 +1,18 @@
-use crate::{file_watcher::FileWatcher, FileFingerprint, FilePosition, FileSourceInternalEvents};
+use crate::{
+    checkpointer::Checkpointer, file_watcher::FileWatcher, FileFingerprint, FilePosition,
+    FileSourceInternalEvents,
+};
 use bytes::Bytes;
 use futures::{
     executor::block_on,
     future::{select, Either},
     stream, Future, Sink, SinkExt,
 };
-use glob::glob;
 use indexmap::IndexMap;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fs::{self, remove_file, File};
 use std::io::{self, Read, Seek, Write};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::time::{self, Duration};
 use tokio::time::delay_for;
 use tracing::field;
@@ -36,7 +38,6 @@ where
     pub start_at_beginning: bool,
     pub ignore_before: Option<time::SystemTime>,
     pub max_line_bytes: usize,
-    pub data_dir: PathBuf,
     pub glob_minimum_cooldown: Duration,
     pub fingerprinter: Fingerprinter,
     pub oldest_first: bool,
@@ -57,6 +58,10 @@ where
 ///
 /// Specific operating systems support evented interfaces that correct this
 /// problem but your intrepid authors know of no generic solution.
+///
+/// The server only resumes files from the checkpoints in `checkpointer`, and
+/// writes them out periodically. It is up to the caller to update them,
+/// through `Checkpointer::view`, as the lines it is sent are processed.
 impl<PP, E> FileServer<PP, E>
 where
     PP: PathsProvider,
@@ -66,10 +71,11 @@ where
         self,
         mut chans: C,
         mut shutdown: impl Future + Unpin,
-    ) -> Result<Shutdown, <C as Sink<(Bytes, String)>>::Error>
+        mut checkpointer: Checkpointer,
+    ) -> Result<Shutdown, <C as Sink<Line>>::Error>
     where
-        C: Sink<(Bytes, String)> + Unpin,
-        <C as Sink<(Bytes, String)>>::Error: std::error::Error,
+        C: Sink<Line> + Unpin,
+        <C as Sink<Line>>::Error: std::error::Error,
     {
         let mut line_buffer = Vec::new();
         let mut fingerprint_buffer = Vec::new();
@@ -79,7 +85,6 @@ where
         let mut backoff_cap: usize = 1;
         let mut lines = Vec::new();
 
-        let mut checkpointer = Checkpointer::new(&self.data_dir);
         checkpointer.read_checkpoints(self.ignore_before);
 
         let mut known_small_files = HashSet::new();
@@ -215,16 +220,27 @@ where
                         bytes_read += sz;
 
                         if !line_buffer.is_empty() {
-                            lines.push((
-                                line_buffer.clone().into(),
-                                watcher.path.to_str().expect("not a valid path").to_owned(),
-                            ));
+                            lines.push(Line {
+                                text: line_buffer.clone().into(),
+                                filename: watcher
+                                    .path
+                                    .to_str()
+                                    .expect("not a valid path")
+                                    .to_owned(),
+                                file_id,
+                                offset: watcher.get_file_position(),
+                            });
                             line_buffer.clear();
                         }
                     } else {
-                        // Should the file be removed
+                        // Should the file be removed. Files are only removed once
+                        // every line read from them is checkpointed, so that lines
+                        // still awaiting acknowledgement are not lost.
                         if let Some(grace_period) = self.remove_after {
-                            if watcher.last_read_success().elapsed() >= grace_period {
+                            let checkpointed = checkpointer.get_checkpoint(file_id).unwrap_or(0)
+                                == watcher.get_file_position();
+                            if checkpointed && watcher.last_read_success().elapsed() >= grace_period
+                            {
                                 // Try to remove
                                 match remove_file(&watcher.path) {
                                     Ok(()) => {
@@ -248,7 +264,6 @@ where
                 }
                 if bytes_read > 0 {
                     global_bytes_read = global_bytes_read.saturating_add(bytes_read);
-                    checkpointer.set_checkpoint(file_id, watcher.get_file_position());
                 }
                 // Do not move on to newer files if we are behind on an older file
                 if self.oldest_first && maxed_out_reading_single_file {
@@ -302,7 +317,15 @@ where
                 shutdown,
                 delay_for(Duration::from_millis(backoff as u64)),
             )) {
-                Either::Left((_, _)) => return Ok(Shutdown),
+                Either::Left((_, _)) => {
+                    // Keep what has been processed since the last periodic write.
+                    checkpointer
+                        .write_checkpoints()
+                        .map_err(|error| self.emitter.emit_file_checkpoint_write_failed(error))
+                        .map(|count| self.emitter.emit_file_checkpointed(count))
+                        .ok();
+                    return Ok(Shutdown);
+                }
                 Either::Right((_, future)) => shutdown = future,
             }
         }
@@ -336,6 +359,16 @@ where
     }
 }
 
+/// A line read from a file, along with the position to resume reading the file
+/// from once it has been processed.
+#[derive(Clone, Debug)]
+pub struct Line {
+    pub text: Bytes,
+    pub filename: String,
+    pub file_id: FileFingerprint,
+    pub offset: FilePosition,
+}
+
 /// A sentinel type to signal that file server was gracefully shut down.
 ///
 /// The purpose of this type is to clarify the semantics of the result values
@@ -344,64 +377,6 @@ where
 #[derive(Debug)]
 pub struct Shutdown;
 
-pub struct Checkpointer {
-    directory: PathBuf,
-    glob_string: String,
-    checkpoints: HashMap<FileFingerprint, FilePosition>,
-}
-
-impl Checkpointer {
-    pub fn new(data_dir: &Path) -> Checkpointer {
-        let directory = data_dir.join("checkpoints");
-        let glob_string = directory.join("*").to_string_lossy().into_owned();
-        Checkpointer {
-            directory,
-            glob_string,
-            checkpoints: HashMap::new(),
-        }
-    }
-
-    fn encode(&self, fng: FileFingerprint, pos: FilePosition) -> PathBuf {
-        self.directory.join(format!("{:x}.{}", fng, pos))
-    }
-    fn decode(&self, path: &Path) -> (FileFingerprint, FilePosition) {
-        let file_name = &path.file_name().unwrap().to_string_lossy();
-        scan_fmt!(file_name, "{x}.{}", [hex FileFingerprint], FilePosition).unwrap()
-    }
-
-    pub fn set_checkpoint(&mut self, fng: FileFingerprint, pos: FilePosition) {
-        self.checkpoints.insert(fng, pos);
-    }
-
-    pub fn get_checkpoint(&self, fng: FileFingerprint) -> Option<FilePosition> {
-        self.checkpoints.get(&fng).cloned()
-    }
-
-    pub fn write_checkpoints(&mut self) -> Result<usize, io::Error> {
-        fs::remove_dir_all(&self.directory).ok();
-        fs::create_dir_all(&self.directory)?;
-        for (&fng, &pos) in self.checkpoints.iter() {
-            fs::File::create(self.encode(fng, pos))?;
-        }
-        Ok(self.checkpoints.len())
-    }
-
-    pub fn read_checkpoints(&mut self, ignore_before: Option<time::SystemTime>) {
-        for path in glob(&self.glob_string).unwrap().flatten() {
-            if let Some(ignore_before) = ignore_before {
-                if let Ok(Ok(modified)) = fs::metadata(&path).map(|metadata| metadata.modified()) {
-                    if modified < ignore_before {
-                        fs::remove_file(path).ok();
-                        continue;
-                    }
-                }
-            }
-            let (fng, pos) = self.decode(&path);
-            self.checkpoints.insert(fng, pos);
-        }
-    }
-}
-
 #[derive(Clone)]
 pub enum Fingerprinter {
     Checksum {
@@ -495,7 +470,7 @@ fn fingerprinter_read_until(mut r: impl Read, delim: u8, mut buf: &mut [u8]) ->
 
 #[cfg(test)]
 mod test {
-    use super::{Checkpointer, FileFingerprint, FilePosition, Fingerprinter};
+    use super::Fingerprinter;
     use std::fs;
     use tempfile::tempdir;
 
@@ -637,37 +612,4 @@ mod test {
                 .unwrap()
         );
     }
-
-    #[test]
-    fn test_checkpointer_basics() {
-        let fingerprint: FileFingerprint = 0x1234567890abcdef;
-        let position: FilePosition = 1234;
-        let data_dir = tempdir().unwrap();
-        let mut chkptr = Checkpointer::new(&data_dir.path());
-        assert_eq!(
-            chkptr.decode(&chkptr.encode(fingerprint, position)),
-            (fingerprint, position)
-        );
-        chkptr.set_checkpoint(fingerprint, position);
-        assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
-    }
-
-    #[test]
-    fn test_checkpointer_restart() {
-        let fingerprint: FileFingerprint = 0x1234567890abcdef;
-        let position: FilePosition = 1234;
-        let data_dir = tempdir().unwrap();
-        {
-            let mut chkptr = Checkpointer::new(&data_dir.path());
-            chkptr.set_checkpoint(fingerprint, position);
-            assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
-            chkptr.write_checkpoints().ok();
-        }
-        {
-            let mut chkptr = Checkpointer::new(&data_dir.path());
-            assert_eq!(chkptr.get_checkpoint(fingerprint), None);
-            chkptr.read_checkpoints(None);
-            assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
-        }
-    }
 }
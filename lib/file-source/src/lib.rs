@@ -3,17 +3,19 @@ extern crate scan_fmt;
 #[macro_use]
 extern crate tracing;
 
+mod checkpointer;
 mod file_server;
 mod file_watcher;
 mod internal_events;
 mod metadata_ext;
 pub mod paths_provider;
 
-pub use self::file_server::{FileServer, Fingerprinter, Shutdown as FileServerShutdown};
+pub use self::checkpointer::{Checkpointer, CheckpointsView};
+pub use self::file_server::{FileServer, Fingerprinter, Line, Shutdown as FileServerShutdown};
 pub use self::internal_events::FileSourceInternalEvents;
 
-type FileFingerprint = u64;
-type FilePosition = u64;
+pub type FileFingerprint = u64;
+pub type FilePosition = u64;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// The outcome of passing a batch of events to a sink module's `send` export.
+///
+/// Sinks return it as the `u32` result of `send`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum Delivery {
+    /// The events were delivered, and can be acknowledged.
+    Ack = 0,
+    /// The events could not be delivered right now, and should be passed again later.
+    Retry = 1,
+    /// The events can never be delivered, and should be dropped.
+    Reject = 2,
+}
+
+impl Delivery {
+    /// Turn the result of a `send` call back into a `Delivery`.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Delivery::Ack),
+            1 => Some(Delivery::Retry),
+            2 => Some(Delivery::Reject),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// How events are passed between Vector and the module.
+///
+/// The module picks the encoding as part of its [`Registration`](crate::Registration). It applies
+/// both to the data passed to `process`/`process_batch` and the data passed to `emit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(C)]
+pub enum Encoding {
+    /// A JSON object per event, or a JSON array of objects for batches.
+    Json = 0,
+    /// Length-delimited protobuf `EventWrapper` messages, as defined in Vector's
+    /// `proto/event.proto`. Cheaper to decode than JSON, and also carries metrics.
+    Protobuf = 1,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding::Json
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Registration, WasmModuleConfig};
+use crate::{HttpRequest, HttpResponse, Registration, WasmModuleConfig};
 use anyhow::{Context, Result};
 use std::fmt::Display;
 
@@ -48,6 +48,69 @@ pub fn config() -> Result<WasmModuleConfig> {
     Ok(config)
 }
 
+/// Ask Vector to call the module's `timer_handler` export with the given `id` every
+/// `interval_seconds`.
+///
+/// Timers can only be registered during `init`.
+pub fn timer(id: u32, interval_seconds: u32) -> Result<()> {
+    unsafe { ffi::timer(id, interval_seconds) };
+    Ok(())
+}
+
+/// The level of a message passed to [`log`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+/// Write a message to Vector's log.
+pub fn log(level: Level, message: impl Display) -> Result<()> {
+    let message = format!("{}", message);
+    unsafe { ffi::log(level as u32, message.as_ptr() as u32, message.len() as u32) };
+    Ok(())
+}
+
+/// Make an HTTP request to one of the `allowed_hosts` of the module.
+pub fn http_request(request: &HttpRequest) -> Result<HttpResponse> {
+    let buffer = serde_json::to_vec(request).context("Could not turn request to JSON.")?;
+
+    let size = unsafe { ffi::http_request(buffer.as_ptr() as u32, buffer.len() as u32) };
+    if size == 0 {
+        return Err(anyhow::anyhow!("HTTP request to {} failed.", request.uri));
+    }
+    let ptr = crate::interop::allocate_buffer(size);
+
+    unsafe { ffi::response(ptr as u32, size) };
+
+    let buffer = unsafe { Vec::from_raw_parts(ptr as *mut u8, size as usize, size as usize) };
+    let response = serde_json::from_slice(&buffer)?;
+    Ok(response)
+}
+
+/// Write `data` to a TCP connection to `address`, which must be one of the `allowed_hosts` of
+/// the module. The connection is kept open between calls.
+pub fn tcp_send(address: &str, data: impl AsRef<[u8]>) -> Result<()> {
+    let data = data.as_ref();
+
+    let retval = unsafe {
+        ffi::tcp_send(
+            address.as_ptr() as u32,
+            address.len() as u32,
+            data.as_ptr() as u32,
+            data.len() as u32,
+        )
+    };
+    if retval != 0 {
+        return Err(anyhow::anyhow!("Sending to {} failed.", address));
+    }
+    Ok(())
+}
+
 pub mod ffi {
     extern "C" {
         pub(super) fn register(ptr: u32, size: u32);
@@ -56,4 +119,15 @@ pub mod ffi {
         pub(super) fn config(ptr: u32, size: u32);
         pub(super) fn config_size() -> u32;
     }
+
+    // These live in their own import module, since `log` would otherwise clash with the libc
+    // function of the same name.
+    #[link(wasm_import_module = "vector")]
+    extern "C" {
+        pub(super) fn timer(id: u32, interval_seconds: u32);
+        pub(super) fn log(level: u32, ptr: u32, size: u32);
+        pub(super) fn http_request(ptr: u32, size: u32) -> u32;
+        pub(super) fn response(ptr: u32, size: u32);
+        pub(super) fn tcp_send(address_ptr: u32, address_size: u32, ptr: u32, size: u32) -> u32;
+    }
 }
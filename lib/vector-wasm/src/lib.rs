@@ -1,5 +1,11 @@
 #![deny(improper_ctypes)]
 
+mod delivery;
+pub use delivery::Delivery;
+mod encoding;
+pub use encoding::Encoding;
+mod net;
+pub use net::{HttpRequest, HttpResponse};
 mod registration;
 pub use registration::Registration;
 mod role;
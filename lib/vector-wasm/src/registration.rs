@@ -1,4 +1,4 @@
-use super::Role;
+use super::{Encoding, Role};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -12,17 +12,41 @@ pub struct Registration {
     /// The host will also define this, and the registration will fail if they differ in types.
     /// This is a simple two-way handshake safety procedure to ensure modules get used in the right place.
     role: Role,
+    /// The encoding of the events passed to and emitted by the module.
+    #[serde(default)]
+    encoding: Encoding,
 }
 
 impl Registration {
     pub fn transform() -> Self {
         Self {
             role: Role::Transform,
+            encoding: Encoding::default(),
+        }
+    }
+    pub fn source() -> Self {
+        Self {
+            role: Role::Source,
+            encoding: Encoding::default(),
+        }
+    }
+    pub fn sink() -> Self {
+        Self {
+            role: Role::Sink,
+            encoding: Encoding::default(),
         }
     }
     pub fn role(&self) -> Role {
         self.role
     }
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+    /// Set the encoding of the events. See [`Encoding`].
+    pub fn set_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
     pub fn register(&self) -> Result<()> {
         super::hostcall::register(self)
     }
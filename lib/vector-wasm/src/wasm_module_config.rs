@@ -11,7 +11,7 @@ pub struct WasmModuleConfig {
     pub role: Role,
     /// The path to the module's `wasm` file.
     pub path: PathBuf,
-    /// The cache location where an optimized `so` file shall be placed.
+    /// The cache location where a precompiled `cwasm` file shall be placed.
     ///
     /// This folder also stores a `.fingerprints` file that is formatted as a JSON map, matching file paths
     /// to fingerprints.
@@ -20,6 +20,14 @@ pub struct WasmModuleConfig {
     // TODO: The module may also declare it's minimum heap size, and they will be compared before
     //       the module begins processing.
     pub max_heap_memory_size: usize,
+    /// The amount of fuel (roughly, WASM instructions) a single call into the module may consume
+    /// before it's interrupted. Unlimited if unset.
+    #[serde(default)]
+    pub max_fuel: Option<u64>,
+    /// The hosts (`host` or `host:port`) the module may reach through the `http_request` and
+    /// `tcp_send` hostcalls. Nothing is reachable if empty.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
     pub options: HashMap<String, serde_json::Value>,
 }
 
@@ -38,6 +46,8 @@ impl WasmModuleConfig {
             artifact_cache: artifact_cache.into(),
             // The rest should be configured via setters below...
             max_heap_memory_size,
+            max_fuel: None,
+            allowed_hosts: Vec::new(),
             options,
         }
     }
@@ -47,4 +57,16 @@ impl WasmModuleConfig {
         self.max_heap_memory_size = max_heap_memory_size;
         self
     }
+
+    /// Limit the fuel a single call into the module may consume.
+    pub fn set_max_fuel(&mut self, max_fuel: impl Into<Option<u64>>) -> &mut Self {
+        self.max_fuel = max_fuel.into();
+        self
+    }
+
+    /// Set the hosts the module may reach.
+    pub fn set_allowed_hosts(&mut self, allowed_hosts: Vec<String>) -> &mut Self {
+        self.allowed_hosts = allowed_hosts;
+        self
+    }
 }
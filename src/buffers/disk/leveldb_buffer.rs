@@ -1,4 +1,4 @@
-use crate::event::{proto, Event};
+use crate::event::{proto, Event, EventFinalizers, EventStatus};
 use bytes::Bytes;
 use futures01::{
     task::{self, AtomicTask, Task},
@@ -50,6 +50,9 @@ pub struct Writer {
     blocked_write_tasks: Arc<Mutex<Vec<Task>>>,
     writebatch: Writebatch<Key>,
     batch_size: usize,
+    // The finalizers of the events in `writebatch`, which are delivered as
+    // far as their sources are concerned once the batch is written.
+    finalizers: Vec<EventFinalizers>,
     max_size: usize,
     current_size: Arc<AtomicUsize>,
 }
@@ -66,6 +69,7 @@ impl Clone for Writer {
             blocked_write_tasks: Arc::clone(&self.blocked_write_tasks),
             writebatch: Writebatch::new(),
             batch_size: 0,
+            finalizers: Vec::new(),
             max_size: self.max_size,
             current_size: Arc::clone(&self.current_size),
         }
@@ -78,8 +82,9 @@ impl Sink for Writer {
 
     fn start_send(
         &mut self,
-        event: Self::SinkItem,
+        mut event: Self::SinkItem,
     ) -> Result<AsyncSink<Self::SinkItem>, Self::SinkError> {
+        let finalizers = event.take_finalizers();
         let mut value = vec![];
         proto::EventWrapper::from(event).encode(&mut value).unwrap(); // This will not error when writing to a Vec
         let event_size = value.len();
@@ -97,7 +102,8 @@ impl Sink for Writer {
             self.poll_complete()?;
 
             let buf = Bytes::from(value);
-            let event = proto::EventWrapper::decode(buf).unwrap().into();
+            let mut event: Event = proto::EventWrapper::decode(buf).unwrap().into();
+            event.add_finalizers(finalizers);
             return Ok(AsyncSink::NotReady(event));
         }
 
@@ -105,6 +111,9 @@ impl Sink for Writer {
 
         self.writebatch.put(Key(key), &value);
         self.batch_size += 1;
+        if !finalizers.is_empty() {
+            self.finalizers.push(finalizers);
+        }
 
         if self.batch_size >= 100 {
             self.poll_complete()?;
@@ -132,6 +141,9 @@ impl Writer {
             .unwrap();
         self.writebatch = Writebatch::new();
         self.batch_size = 0;
+        for finalizers in self.finalizers.drain(..) {
+            finalizers.update_status(EventStatus::Delivered);
+        }
         self.write_notifier.notify();
     }
 }
@@ -293,6 +305,7 @@ impl super::DiskBuffer for Buffer {
             offset: Arc::new(AtomicUsize::new(tail)),
             writebatch: Writebatch::new(),
             batch_size: 0,
+            finalizers: Vec::new(),
             max_size,
             current_size: Arc::clone(&current_size),
         };
@@ -10,7 +10,7 @@ use std::sync::{
 #[cfg(feature = "leveldb")]
 pub mod disk;
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum BufferConfig {
@@ -50,6 +50,10 @@ impl Default for WhenFull {
     }
 }
 
+/// The events read back out of a buffer.
+pub type EventStream = Box<dyn Stream<Item = Event, Error = ()> + Send>;
+
+#[derive(Clone)]
 pub enum BufferInputCloner {
     Memory(mpsc::Sender<Event>, WhenFull),
     #[cfg(feature = "leveldb")]
@@ -93,14 +97,7 @@ impl BufferConfig {
         &self,
         data_dir: &Option<PathBuf>,
         sink_name: &str,
-    ) -> Result<
-        (
-            BufferInputCloner,
-            Box<dyn Stream<Item = Event, Error = ()> + Send>,
-            Acker,
-        ),
-        String,
-    > {
+    ) -> Result<(BufferInputCloner, EventStream, Acker), String> {
         match &self {
             BufferConfig::Memory {
                 max_events,
@@ -135,6 +132,8 @@ impl BufferConfig {
 #[derive(Debug, Clone)]
 pub enum Acker {
     Disk(Arc<AtomicUsize>, Arc<AtomicTask>),
+    /// Only keeps count, for buffers that don't need acks themselves.
+    Counting(Arc<AtomicUsize>),
     Null,
 }
 
@@ -154,6 +153,9 @@ impl Acker {
                     counter.fetch_add(num, Ordering::Relaxed);
                     notifier.notify();
                 }
+                Acker::Counting(counter) => {
+                    counter.fetch_add(num, Ordering::Relaxed);
+                }
             }
         }
     }
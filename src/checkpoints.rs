@@ -0,0 +1,236 @@
+use file_source::{Checkpointer, FileFingerprint, FilePosition};
+use serde::Serialize;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Opts {
+    /// The data directory of the source, either its own `data_dir` option or the
+    /// global one.
+    #[structopt(long, default_value = "/var/lib/vector/")]
+    data_dir: PathBuf,
+
+    /// The name of the `file` or `kubernetes_logs` source whose checkpoints to use.
+    source: String,
+
+    #[structopt(subcommand)]
+    action: Action,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+enum Action {
+    /// List the fingerprints of the files read and the offsets they were
+    /// checkpointed at, then exit.
+    List {
+        /// Format the list in an encoding scheme.
+        #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+        format: Format,
+    },
+
+    /// Remove the checkpoints of the given fingerprints, so that their files are
+    /// read according to `start_at_beginning` once Vector restarts.
+    Reset {
+        /// Hexadecimal fingerprints, as listed by `list`.
+        #[structopt(required_unless = "all")]
+        fingerprints: Vec<String>,
+
+        /// Remove all the checkpoints of the source.
+        #[structopt(long, conflicts_with = "fingerprints")]
+        all: bool,
+    },
+
+    /// Checkpoint the file with the given fingerprint at the given offset.
+    Set {
+        /// Hexadecimal fingerprint, as listed by `list`.
+        fingerprint: String,
+
+        /// The offset, in bytes, to resume reading the file from.
+        offset: FilePosition,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            s => Err(format!(
+                "{} is not a valid option, expected `text` or `json`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EncodedCheckpoint {
+    fingerprint: String,
+    offset: FilePosition,
+}
+
+pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
+    let data_dir = opts.data_dir.join(&opts.source);
+    if !data_dir.is_dir() {
+        eprintln!(
+            "No data directory {:?} for source {:?}.",
+            data_dir, opts.source
+        );
+        return exitcode::NOINPUT;
+    }
+
+    let mut checkpointer = Checkpointer::new(&data_dir);
+    checkpointer.read_checkpoints(None);
+    let checkpoints = checkpointer.view();
+
+    match &opts.action {
+        Action::List { format } => {
+            let list = checkpoints.list();
+            match format {
+                Format::Text => {
+                    for (fingerprint, offset) in list {
+                        println!("{:x} {}", fingerprint, offset);
+                    }
+                }
+                Format::Json => {
+                    let list = list
+                        .into_iter()
+                        .map(|(fingerprint, offset)| EncodedCheckpoint {
+                            fingerprint: format!("{:x}", fingerprint),
+                            offset,
+                        })
+                        .collect::<Vec<_>>();
+                    println!("{}", serde_json::to_string(&list).unwrap());
+                }
+            }
+            return exitcode::OK;
+        }
+        Action::Reset { all: true, .. } => {
+            for (fingerprint, _) in checkpoints.list() {
+                checkpoints.remove(fingerprint);
+            }
+        }
+        Action::Reset { fingerprints, .. } => {
+            let fingerprints = match parse_fingerprints(fingerprints) {
+                Ok(fingerprints) => fingerprints,
+                Err(code) => return code,
+            };
+            for fingerprint in fingerprints {
+                if checkpoints.remove(fingerprint).is_none() {
+                    eprintln!("No checkpoint for fingerprint {:x}.", fingerprint);
+                    return exitcode::DATAERR;
+                }
+            }
+        }
+        Action::Set {
+            fingerprint,
+            offset,
+        } => {
+            let fingerprint = match parse_fingerprints(&[fingerprint.clone()]) {
+                Ok(fingerprints) => fingerprints[0],
+                Err(code) => return code,
+            };
+            checkpoints.update(fingerprint, *offset);
+        }
+    }
+
+    match checkpointer.write_checkpoints() {
+        Ok(count) => {
+            println!("{} checkpoints left for source {:?}.", count, opts.source);
+            exitcode::OK
+        }
+        Err(error) => {
+            eprintln!("Failed writing checkpoints: {}.", error);
+            exitcode::IOERR
+        }
+    }
+}
+
+fn parse_fingerprints(fingerprints: &[String]) -> Result<Vec<FileFingerprint>, exitcode::ExitCode> {
+    fingerprints
+        .iter()
+        .map(|fingerprint| {
+            FileFingerprint::from_str_radix(fingerprint.trim_start_matches("0x"), 16).map_err(
+                |error| {
+                    eprintln!("Invalid fingerprint {:?}: {}.", fingerprint, error);
+                    exitcode::USAGE
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn opts(data_dir: PathBuf, action: Action) -> Opts {
+        Opts {
+            data_dir,
+            source: "in".to_owned(),
+            action,
+        }
+    }
+
+    #[test]
+    fn set_and_reset_checkpoints() {
+        let dir = tempdir().unwrap();
+        let data_dir = dir.path().to_path_buf();
+        let source_dir = data_dir.join("in");
+        std::fs::create_dir(&source_dir).unwrap();
+
+        let set = |fingerprint: &str, offset| {
+            let action = Action::Set {
+                fingerprint: fingerprint.to_owned(),
+                offset,
+            };
+            cmd(&opts(data_dir.clone(), action))
+        };
+        assert_eq!(set("0xabc", 10), exitcode::OK);
+        assert_eq!(set("def", 20), exitcode::OK);
+        assert_eq!(set("xyz", 30), exitcode::USAGE);
+
+        let list = || {
+            let mut checkpointer = Checkpointer::new(&source_dir);
+            checkpointer.read_checkpoints(None);
+            checkpointer.view().list()
+        };
+        assert_eq!(list(), vec![(0xabc, 10), (0xdef, 20)]);
+
+        let reset = |fingerprints: &[&str], all| {
+            let action = Action::Reset {
+                fingerprints: fingerprints.iter().map(|&f| f.to_owned()).collect(),
+                all,
+            };
+            cmd(&opts(data_dir.clone(), action))
+        };
+        assert_eq!(reset(&["123"], false), exitcode::DATAERR);
+        assert_eq!(list(), vec![(0xabc, 10), (0xdef, 20)]);
+        assert_eq!(reset(&["abc"], false), exitcode::OK);
+        assert_eq!(list(), vec![(0xdef, 20)]);
+        assert_eq!(reset(&[], true), exitcode::OK);
+        assert!(list().is_empty());
+    }
+
+    #[test]
+    fn missing_source() {
+        let dir = tempdir().unwrap();
+        let action = Action::List {
+            format: Format::Text,
+        };
+        assert_eq!(
+            cmd(&opts(dir.path().to_path_buf(), action)),
+            exitcode::NOINPUT
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 use structopt::{clap::AppSettings, StructOpt};
-use vector::{generate, list, unit_test, validate};
+use vector::{checkpoints, generate, list, unit_test, validate};
 
 #[derive(StructOpt, Debug)]
 #[structopt(rename_all = "kebab-case")]
@@ -27,7 +27,8 @@ impl Opts {
         let (quiet_level, verbose_level) = match self.sub_command {
             Some(SubCommand::Validate(_))
             | Some(SubCommand::Generate(_))
-            | Some(SubCommand::List(_)) => {
+            | Some(SubCommand::List(_))
+            | Some(SubCommand::Checkpoints(_)) => {
                 if self.root.verbose == 0 {
                     (self.root.quiet + 1, self.root.verbose)
                 } else {
@@ -108,6 +109,10 @@ pub enum SubCommand {
     /// Run Vector config unit tests, then exit. This command is experimental and therefore subject to change.
     /// For guidance on how to write unit tests check out: https://vector.dev/docs/setup/guides/unit-testing/
     Test(unit_test::Opts),
+
+    /// List, reset or set the checkpoints of a file source, then exit.
+    /// Vector must be stopped first, as it overwrites them while running.
+    Checkpoints(checkpoints::Opts),
 }
 
 #[derive(Debug, Clone, PartialEq)]
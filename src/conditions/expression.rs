@@ -0,0 +1,194 @@
+use crate::{
+    conditions::{Condition, ConditionConfig, ConditionDescription},
+    event::{
+        metric::{Metric, MetricKind, MetricValue},
+        LogEvent, Value,
+    },
+    remap::{CompileError, Program},
+    Event,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid condition expression: {}", source))]
+    InvalidExpression { source: CompileError },
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExpressionConfig {
+    source: String,
+}
+
+inventory::submit! {
+    ConditionDescription::new::<ExpressionConfig>("expression")
+}
+
+#[typetag::serde(name = "expression")]
+impl ConditionConfig for ExpressionConfig {
+    fn build(&self) -> crate::Result<Box<dyn Condition>> {
+        let program = Program::compile_condition(&self.source).context(InvalidExpression)?;
+        Ok(Box::new(Expression { program }))
+    }
+}
+
+//------------------------------------------------------------------------------
+
+pub struct Expression {
+    program: Program,
+}
+
+impl Expression {
+    fn evaluate(&self, e: &Event) -> Result<bool, String> {
+        let result = match e {
+            Event::Log(log) => self.program.evaluate(log),
+            Event::Metric(metric) => self.program.evaluate(&metric_to_log(metric)),
+        };
+        match result {
+            Ok(Value::Boolean(value)) => Ok(value),
+            Ok(value) => Err(format!(
+                "expression resolved to {:?} instead of a boolean",
+                value
+            )),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+}
+
+impl Condition for Expression {
+    fn check(&self, e: &Event) -> bool {
+        self.evaluate(e).unwrap_or(false)
+    }
+
+    fn check_with_context(&self, e: &Event) -> Result<(), String> {
+        match self.evaluate(e)? {
+            true => Ok(()),
+            false => Err("expression evaluated to false".into()),
+        }
+    }
+}
+
+/// Expose the metric to the expression as a log with the `name`, `timestamp`,
+/// `kind`, `type`, `tags` and, for counters and gauges, `value` fields.
+fn metric_to_log(metric: &Metric) -> LogEvent {
+    let mut log = LogEvent::default();
+    log.insert("name", metric.name.clone());
+    if let Some(timestamp) = metric.timestamp {
+        log.insert("timestamp", timestamp);
+    }
+    log.insert(
+        "kind",
+        match metric.kind {
+            MetricKind::Incremental => "incremental",
+            MetricKind::Absolute => "absolute",
+        },
+    );
+    let (kind, value) = match &metric.value {
+        MetricValue::Counter { value } => ("counter", Some(*value)),
+        MetricValue::Gauge { value } => ("gauge", Some(*value)),
+        MetricValue::Set { .. } => ("set", None),
+        MetricValue::Distribution { .. } => ("distribution", None),
+        MetricValue::AggregatedHistogram { .. } => ("aggregated_histogram", None),
+        MetricValue::AggregatedSummary { .. } => ("aggregated_summary", None),
+    };
+    log.insert("type", kind);
+    if let Some(value) = value {
+        log.insert("value", value);
+    }
+    if let Some(tags) = &metric.tags {
+        let tags = tags
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+            .collect();
+        log.insert("tags", Value::Map(tags));
+    }
+    log
+}
+
+//------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn condition(source: &str) -> Box<dyn Condition> {
+        ExpressionConfig {
+            source: source.to_owned(),
+        }
+        .build()
+        .unwrap()
+    }
+
+    fn log_event(status: i64, service: &str) -> Event {
+        let mut event = Event::from("a log");
+        event.as_mut_log().insert("status", status);
+        event.as_mut_log().insert("service", service);
+        event
+    }
+
+    #[test]
+    fn check_logs() {
+        let cond = condition(r#".status >= 500 && (.service == "api" || exists(.error))"#);
+
+        assert_eq!(cond.check(&log_event(503, "api")), true);
+        assert_eq!(cond.check(&log_event(503, "web")), false);
+        assert_eq!(cond.check(&log_event(200, "api")), false);
+
+        let mut event = log_event(500, "web");
+        event.as_mut_log().insert("error", "boom");
+        assert_eq!(cond.check(&event), true);
+    }
+
+    #[test]
+    fn check_metrics() {
+        let cond = condition(r#".name == "requests" && .tags.host == "a" && .value > 10"#);
+        let metric = |name: &str, host: &str, value: f64| {
+            Event::from(Metric {
+                name: name.to_owned(),
+                timestamp: None,
+                tags: Some(
+                    vec![("host".to_owned(), host.to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                kind: MetricKind::Incremental,
+                value: MetricValue::Counter { value },
+            })
+        };
+
+        assert_eq!(cond.check(&metric("requests", "a", 11.0)), true);
+        assert_eq!(cond.check(&metric("requests", "b", 11.0)), false);
+        assert_eq!(cond.check(&metric("errors", "a", 11.0)), false);
+        assert_eq!(cond.check(&metric("requests", "a", 1.0)), false);
+    }
+
+    #[test]
+    fn check_with_context() {
+        let cond = condition(".status > 500");
+
+        assert_eq!(cond.check_with_context(&log_event(503, "api")), Ok(()));
+        assert_eq!(
+            cond.check_with_context(&log_event(200, "api")),
+            Err("expression evaluated to false".to_owned())
+        );
+        assert_eq!(
+            cond.check_with_context(&Event::from("no status")),
+            Err("can't compare null and integer at line 1, column 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let error = ExpressionConfig {
+            source: ".status = 500".to_owned(),
+        }
+        .build()
+        .err()
+        .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid condition expression: fields can't be assigned in a condition"));
+    }
+}
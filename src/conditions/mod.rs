@@ -3,6 +3,8 @@ use crate::Event;
 use serde::{Deserialize, Serialize};
 
 pub mod check_fields;
+#[cfg(feature = "remap")]
+pub mod expression;
 pub mod is_log;
 pub mod is_metric;
 
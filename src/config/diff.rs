@@ -14,9 +14,25 @@ impl ConfigDiff {
     }
 
     pub fn new(old: &Config, new: &Config) -> Self {
+        let mut transforms = Difference::new(&old.transforms, &new.transforms);
+        transforms.split_rewired(|name| {
+            json_eq(&old.transforms[name].inner, &new.transforms[name].inner)
+        });
+        // Transforms hold on to the enrichment tables they were built with, so all of them are
+        // rebuilt to pick up changed tables.
+        if old.enrichment_tables != new.enrichment_tables {
+            transforms.to_change.extend(
+                old.transforms
+                    .keys()
+                    .filter(|name| new.transforms.contains_key(*name))
+                    .cloned(),
+            );
+            transforms.to_rewire.clear();
+        }
+
         ConfigDiff {
             sources: Difference::new(&old.sources, &new.sources),
-            transforms: Difference::new(&old.transforms, &new.transforms),
+            transforms,
             sinks: Difference::new(&old.sinks, &new.sinks),
         }
     }
@@ -34,6 +50,9 @@ pub struct Difference {
     pub to_remove: HashSet<String>,
     pub to_change: HashSet<String>,
     pub to_add: HashSet<String>,
+    /// Changed only in their inputs, so they're reconnected rather than rebuilt.
+    /// Only transforms are rewired.
+    pub to_rewire: HashSet<String>,
 }
 
 impl Difference {
@@ -46,14 +65,7 @@ impl Difference {
 
         let to_change = old_names
             .intersection(&new_names)
-            .filter(|&n| {
-                // This is a hack around the issue of comparing two
-                // trait objects. Json is used here over toml since
-                // toml does not support serializing `None`.
-                let old_json = serde_json::to_vec(&old[n]).unwrap();
-                let new_json = serde_json::to_vec(&new[n]).unwrap();
-                old_json != new_json
-            })
+            .filter(|&n| !json_eq(&old[n], &new[n]))
             .cloned()
             .collect::<HashSet<_>>();
 
@@ -64,9 +76,20 @@ impl Difference {
             to_remove,
             to_change,
             to_add,
+            to_rewire: HashSet::new(),
         }
     }
 
+    /// Moves the changed components for which `only_inputs_changed` holds over to `to_rewire`.
+    fn split_rewired(&mut self, only_inputs_changed: impl Fn(&str) -> bool) {
+        let (to_rewire, to_change) = self
+            .to_change
+            .drain()
+            .partition(|name| only_inputs_changed(name));
+        self.to_rewire = to_rewire;
+        self.to_change = to_change;
+    }
+
     /// True if name is present in new config and either not in the old one or is different.
     pub fn contains_new(&self, name: &str) -> bool {
         self.to_add.contains(name) || self.to_change.contains(name)
@@ -80,3 +103,12 @@ impl Difference {
         self.to_change.iter().chain(self.to_add.iter())
     }
 }
+
+fn json_eq<C: serde::Serialize + ?Sized>(old: &C, new: &C) -> bool {
+    // This is a hack around the issue of comparing two
+    // trait objects. Json is used here over toml since
+    // toml does not support serializing `None`.
+    let old_json = serde_json::to_vec(old).unwrap();
+    let new_json = serde_json::to_vec(new).unwrap();
+    old_json == new_json
+}
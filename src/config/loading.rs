@@ -1,7 +1,10 @@
 use super::{vars, Config};
+use crate::secrets::{SecretBackendConfig, Secrets};
 use glob::glob;
+use indexmap::IndexMap;
 use lazy_static::lazy_static;
 use once_cell::sync::OnceCell;
+use serde::Deserialize;
 use std::{
     collections::HashMap,
     fs::File,
@@ -83,10 +86,48 @@ fn load_from_inputs(
     let mut config = Config::empty();
     let mut errors = Vec::new();
 
+    let mut sources = Vec::new();
     for input in inputs {
-        if let Err(errs) = load(input).and_then(|n| config.append(n)) {
+        match read(input) {
+            Ok(source) => sources.push(source),
+            Err(errs) => errors.extend(errs),
+        }
+    }
+
+    // Secret backends may be defined in any of the files, so they're all gathered before
+    // references are resolved. References are only resolved in string values once the files
+    // are parsed, so that those in comments are left alone and values need no escaping.
+    let mut values = Vec::new();
+    let mut backends = IndexMap::new();
+    for source in &sources {
+        match toml::from_str::<toml::Value>(source) {
+            Ok(value) => match value.clone().try_into::<SecretBackends>() {
+                Ok(secret) => {
+                    backends.extend(secret.secret);
+                    values.push(value);
+                }
+                Err(error) => errors.push(error.to_string()),
+            },
+            Err(error) => errors.push(error.to_string()),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let secrets = Secrets::resolve(&backends, &values)?;
+    let redactor = secrets.redactor();
+
+    for (source, mut value) in sources.iter().zip(values) {
+        // Files without references are loaded from their text, so that errors point at lines.
+        let loaded = if secrets.interpolate(&mut value) {
+            value.try_into().map_err(|e| vec![e.to_string()])
+        } else {
+            load(source)
+        };
+        if let Err(errs) = loaded.and_then(|n| config.append(n)) {
             // TODO: add back paths
-            errors.extend(errs.iter().map(|e| e.to_string()));
+            errors.extend(errs.iter().map(|e| redactor.redact(e)));
         }
     }
 
@@ -95,12 +136,20 @@ fn load_from_inputs(
     }
 
     if errors.is_empty() {
+        config.redactor = redactor;
         Ok(config)
     } else {
         Err(errors)
     }
 }
 
+/// Just the secret backends of a config file, read before the rest of it.
+#[derive(Deserialize)]
+struct SecretBackends {
+    #[serde(default)]
+    secret: IndexMap<String, SecretBackendConfig>,
+}
+
 fn open_config(path: &Path) -> Option<File> {
     match File::open(path) {
         Ok(f) => Some(f),
@@ -116,7 +165,7 @@ fn open_config(path: &Path) -> Option<File> {
     }
 }
 
-fn load(mut input: impl std::io::Read) -> Result<Config, Vec<String>> {
+fn read(mut input: impl std::io::Read) -> Result<String, Vec<String>> {
     let mut source_string = String::new();
     input
         .read_to_string(&mut source_string)
@@ -128,7 +177,9 @@ fn load(mut input: impl std::io::Read) -> Result<Config, Vec<String>> {
             vars.insert("HOSTNAME".into(), hostname);
         }
     }
-    let with_vars = vars::interpolate(&source_string, &vars);
+    Ok(vars::interpolate(&source_string, &vars))
+}
 
-    toml::from_str(&with_vars).map_err(|e| vec![e.to_string()])
+fn load(source: &str) -> Result<Config, Vec<String>> {
+    toml::from_str(source).map_err(|e| vec![e.to_string()])
 }
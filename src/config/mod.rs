@@ -2,7 +2,9 @@ use crate::{
     buffers::Acker,
     conditions,
     dns::Resolver,
-    event::{self, Metric},
+    enrichment_tables::{EnrichmentTableConfig, EnrichmentTables},
+    event::{self, metric::MetricKind, Metric},
+    secrets::{Redactor, SecretBackendConfig},
     shutdown::ShutdownSignal,
     sinks, sources, transforms, Pipeline,
 };
@@ -36,9 +38,15 @@ pub struct Config {
     #[serde(default)]
     pub transforms: IndexMap<String, TransformOuter>,
     #[serde(default)]
+    pub enrichment_tables: IndexMap<String, EnrichmentTableConfig>,
+    #[serde(default)]
     pub tests: Vec<TestDefinition>,
+    #[serde(default)]
+    pub secret: IndexMap<String, SecretBackendConfig>,
     #[serde(skip)]
     expansions: IndexMap<String, Vec<String>>,
+    #[serde(skip)]
+    redactor: Redactor,
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
@@ -165,6 +173,9 @@ pub struct SinkOuter {
     pub buffer: crate::buffers::BufferConfig,
     #[serde(default = "healthcheck_default")]
     pub healthcheck: bool,
+    /// How long the sink is given to flush on shutdown, counted from when shutdown begins.
+    #[serde(default = "shutdown_timeout_secs_default")]
+    pub shutdown_timeout_secs: u64,
     pub inputs: Vec<String>,
     #[serde(flatten)]
     pub inner: Box<dyn SinkConfig>,
@@ -185,6 +196,13 @@ pub trait SinkConfig: core::fmt::Debug + Send + Sync {
     fn input_type(&self) -> DataType;
 
     fn sink_type(&self) -> &'static str;
+
+    /// Builds an encoder that runs this sink's encoding against a null
+    /// transport, allowing `vector test` to assert on its output. Sinks
+    /// that do not support this return `None`.
+    fn build_test_encoder(&self) -> crate::Result<Option<Box<dyn sinks::util::TestEncoder>>> {
+        Ok(None)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -251,16 +269,30 @@ pub trait TransformConfig: core::fmt::Debug + Send + Sync {
 #[derive(Debug, Clone)]
 pub struct TransformContext {
     pub(super) resolver: Resolver,
+    pub(super) enrichment_tables: EnrichmentTables,
 }
 
 impl TransformContext {
     pub fn new_test() -> Self {
-        Self { resolver: Resolver }
+        Self {
+            resolver: Resolver,
+            enrichment_tables: EnrichmentTables::default(),
+        }
+    }
+
+    /// Allow tests to provide the tables that would be configured globally.
+    pub fn with_enrichment_tables(mut self, enrichment_tables: EnrichmentTables) -> Self {
+        self.enrichment_tables = enrichment_tables;
+        self
     }
 
     pub fn resolver(&self) -> Resolver {
         self.resolver
     }
+
+    pub fn enrichment_tables(&self) -> &EnrichmentTables {
+        &self.enrichment_tables
+    }
 }
 
 pub type TransformDescription = ComponentDescription<Box<dyn TransformConfig>>;
@@ -278,9 +310,13 @@ pub struct TestDefinition {
     pub outputs: Vec<TestOutput>,
     #[serde(default)]
     pub no_outputs_from: Vec<String>,
+    /// An RFC 3339 timestamp given to every input event, so that templates
+    /// and strftime specifiers render deterministically. Events created by
+    /// transforms without a timestamp still fall back to the current time.
+    pub frozen_timestamp: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum TestInputValue {
     String(String),
@@ -309,6 +345,32 @@ fn default_test_input_type() -> String {
 pub struct TestOutput {
     pub extract_from: String,
     pub conditions: Option<Vec<TestCondition>>,
+    /// Metric assertions, each of which must hold for at least one output.
+    #[serde(default)]
+    pub metrics: Vec<TestMetric>,
+    /// The exact sequence of events expected from the transform.
+    pub events: Option<Vec<TestEvent>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TestMetric {
+    pub name: Option<String>,
+    pub kind: Option<MetricKind>,
+    /// Tags that must be present with exactly these values.
+    #[serde(default)]
+    pub tags: IndexMap<String, String>,
+    /// The value of a counter or gauge.
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub tolerance: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TestEvent {
+    pub log_fields: Option<IndexMap<String, TestInputValue>>,
+    pub metric: Option<TestMetric>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -330,11 +392,19 @@ impl Config {
             sources: IndexMap::new(),
             sinks: IndexMap::new(),
             transforms: IndexMap::new(),
+            enrichment_tables: IndexMap::new(),
             tests: Vec::new(),
+            secret: IndexMap::new(),
             expansions: IndexMap::new(),
+            redactor: Redactor::default(),
         }
     }
 
+    /// Replaces any secret values in `message` so it can be shown to users.
+    pub fn redact(&self, message: &str) -> String {
+        self.redactor.redact(message)
+    }
+
     pub fn add_source<S: SourceConfig + 'static>(&mut self, name: &str, source: S) {
         self.sources.insert(name.to_string(), Box::new(source));
     }
@@ -344,6 +414,7 @@ impl Config {
         let sink = SinkOuter {
             buffer: Default::default(),
             healthcheck: true,
+            shutdown_timeout_secs: shutdown_timeout_secs_default(),
             inner: Box::new(sink),
             inputs,
         };
@@ -481,11 +552,21 @@ impl Config {
                 errors.push(format!("duplicate transform name found: {}", k));
             }
         });
+        with.enrichment_tables.keys().for_each(|k| {
+            if self.enrichment_tables.contains_key(k) {
+                errors.push(format!("duplicate enrichment table name found: {}", k));
+            }
+        });
         with.tests.iter().for_each(|wt| {
             if self.tests.iter().any(|t| t.name == wt.name) {
                 errors.push(format!("duplicate test name found: {}", wt.name));
             }
         });
+        with.secret.keys().for_each(|k| {
+            if self.secret.contains_key(k) {
+                errors.push(format!("duplicate secret backend name found: {}", k));
+            }
+        });
         if !errors.is_empty() {
             return Err(errors);
         }
@@ -493,7 +574,10 @@ impl Config {
         self.sources.extend(with.sources);
         self.sinks.extend(with.sinks);
         self.transforms.extend(with.transforms);
+        self.enrichment_tables.extend(with.enrichment_tables);
         self.tests.extend(with.tests);
+        self.secret.extend(with.secret);
+        self.redactor.extend(with.redactor);
 
         Ok(())
     }
@@ -519,6 +603,10 @@ fn healthcheck_default() -> bool {
     true
 }
 
+fn shutdown_timeout_secs_default() -> u64 {
+    60
+}
+
 #[cfg(all(
     test,
     feature = "sources-file",
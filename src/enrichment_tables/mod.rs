@@ -0,0 +1,621 @@
+//! Tables of rows loaded from CSV or JSON files, configured in the global `enrichment_tables`
+//! section and shared by the transforms that look events up in them.
+use crate::{
+    emit,
+    event::Value,
+    internal_events::{EnrichmentTableReloadFailed, EnrichmentTableReloaded},
+};
+use cidr_utils::cidr::IpCidr;
+use indexmap::IndexMap;
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        mpsc::{channel, Receiver},
+        Arc, Mutex, RwLock, Weak,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How long the file has to be left alone before it's reloaded, so that a table isn't loaded
+/// while it's still being written.
+const RELOAD_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EnrichmentTableConfig {
+    pub path: PathBuf,
+    /// Inferred from the extension of `path` if unset.
+    #[serde(default)]
+    pub format: Option<Format>,
+    #[serde(default = "crate::serde::default_true")]
+    pub reload: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Self, LoadError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Ok(Format::Csv),
+            Some("json") => Ok(Format::Json),
+            _ => Err(LoadError::UnknownFormat {
+                path: path.to_owned(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum LoadError {
+    #[snafu(display("Could not read {:?}: {}", path, source))]
+    Read { path: PathBuf, source: io::Error },
+    #[snafu(display("Invalid CSV in {:?}: {}", path, source))]
+    InvalidCsv { path: PathBuf, source: csv::Error },
+    #[snafu(display("Invalid JSON in {:?}: {}", path, source))]
+    InvalidJson {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Unknown format of {:?}, expected a \".csv\" or \".json\" file", path))]
+    UnknownFormat { path: PathBuf },
+    #[snafu(display("Could not create a file watcher: {}", source))]
+    CreateWatcher { source: notify::Error },
+    #[snafu(display("Could not watch {:?}: {}", path, source))]
+    InvalidWatch {
+        path: PathBuf,
+        source: notify::Error,
+    },
+}
+
+pub type Row = BTreeMap<String, Value>;
+
+/// Read the rows of a CSV file, with the column names taken from the header, or of a JSON
+/// file holding an array of objects.
+pub fn read_rows(path: &Path, format: Format) -> Result<Vec<Row>, LoadError> {
+    match format {
+        Format::Csv => {
+            let mut reader = csv::Reader::from_path(path).context(InvalidCsv { path })?;
+            let headers = reader.headers().context(InvalidCsv { path })?.clone();
+            reader
+                .records()
+                .map(|record| {
+                    let record = record.context(InvalidCsv { path })?;
+                    Ok(headers
+                        .iter()
+                        .zip(record.iter())
+                        .map(|(name, value)| (name.to_owned(), Value::from(value)))
+                        .collect())
+                })
+                .collect()
+        }
+        Format::Json => {
+            let data = fs::read(path).context(Read { path })?;
+            let rows: Vec<serde_json::Map<String, serde_json::Value>> =
+                serde_json::from_slice(&data).context(InvalidJson { path })?;
+            Ok(rows
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|(name, value)| (name, Value::from(value)))
+                        .collect()
+                })
+                .collect())
+        }
+    }
+}
+
+/// How the value of an event field is compared with a column.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Match {
+    Exact,
+    CaseInsensitive,
+    /// The value is an IP address, and the column holds the networks containing it.
+    Cidr,
+}
+
+impl Default for Match {
+    fn default() -> Self {
+        Match::Exact
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Condition<'a> {
+    pub column: &'a str,
+    pub value: &'a str,
+    pub mode: Match,
+}
+
+/// Positions of the rows by the value of a column, in order.
+type Index = HashMap<String, Vec<usize>>;
+
+/// The indexes of a column. Each is built on first use, as only the columns that are matched
+/// on need one, and only in the ways they are matched.
+#[derive(Debug, Default)]
+struct Column {
+    exact: OnceCell<Index>,
+    /// Keyed by the lowercased values.
+    case_insensitive: OnceCell<Index>,
+    networks: OnceCell<Networks>,
+}
+
+/// Positions of the rows by the network they hold in a column, grouped by prefix length, so
+/// that finding the networks containing an address takes a lookup per prefix length in use.
+#[derive(Debug, Default)]
+struct Networks {
+    v4: BTreeMap<u8, HashMap<u32, Vec<usize>>>,
+    v6: BTreeMap<u8, HashMap<u128, Vec<usize>>>,
+}
+
+impl Networks {
+    fn new(rows: &[Row], column: &str) -> Self {
+        let mut networks = Self::default();
+        for (position, row) in rows.iter().enumerate() {
+            let network = match row.get(column) {
+                Some(value) => IpCidr::from_str(&value.to_string_lossy()),
+                None => continue,
+            };
+            match network {
+                Ok(IpCidr::V4(network)) => {
+                    let bits = network.get_bits();
+                    let prefix = mask_v4(network.get_prefix(), bits);
+                    let by_prefix = networks.v4.entry(bits).or_default();
+                    by_prefix.entry(prefix).or_default().push(position);
+                }
+                Ok(IpCidr::V6(network)) => {
+                    let bits = network.get_bits();
+                    let prefix = mask_v6(network.get_prefix(), bits);
+                    let by_prefix = networks.v6.entry(bits).or_default();
+                    by_prefix.entry(prefix).or_default().push(position);
+                }
+                Err(_) => (),
+            }
+        }
+        networks
+    }
+
+    /// The positions of the rows whose network contains `ip`, in order.
+    fn find(&self, ip: IpAddr) -> Vec<usize> {
+        let mut positions: Vec<usize> = match ip {
+            IpAddr::V4(ip) => self
+                .v4
+                .iter()
+                .filter_map(|(&bits, by_prefix)| by_prefix.get(&mask_v4(ip.into(), bits)))
+                .flatten()
+                .copied()
+                .collect(),
+            IpAddr::V6(ip) => self
+                .v6
+                .iter()
+                .filter_map(|(&bits, by_prefix)| by_prefix.get(&mask_v6(ip.into(), bits)))
+                .flatten()
+                .copied()
+                .collect(),
+        };
+        positions.sort_unstable();
+        positions
+    }
+}
+
+/// The network of `address` with a prefix of `bits` bits.
+fn mask_v4(address: u32, bits: u8) -> u32 {
+    match bits {
+        0 => 0,
+        bits => address & (u32::MAX << (32 - u32::from(bits.min(32)))),
+    }
+}
+
+fn mask_v6(address: u128, bits: u8) -> u128 {
+    match bits {
+        0 => 0,
+        bits => address & (u128::MAX << (128 - u32::from(bits.min(128)))),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Table {
+    rows: Vec<Row>,
+    columns: HashMap<String, Column>,
+}
+
+impl Table {
+    pub fn new(rows: Vec<Row>) -> Self {
+        let columns = rows
+            .iter()
+            .flat_map(|row| row.keys())
+            .map(|column| (column.clone(), Column::default()))
+            .collect();
+        Self { rows, columns }
+    }
+
+    pub fn load(config: &EnrichmentTableConfig) -> Result<Self, LoadError> {
+        let format = match config.format {
+            Some(format) => format,
+            None => Format::from_path(&config.path)?,
+        };
+        read_rows(&config.path, format).map(Self::new)
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The first row matching all of the `conditions`, if there are any.
+    pub fn find(&self, conditions: &[Condition<'_>]) -> Option<&Row> {
+        let (first, rest) = conditions.split_first()?;
+        let mut candidates = self.positions(first).into_owned();
+        for condition in rest {
+            if candidates.is_empty() {
+                break;
+            }
+            let positions = self.positions(condition);
+            candidates.retain(|position| positions.binary_search(position).is_ok());
+        }
+        candidates.first().map(|&position| &self.rows[position])
+    }
+
+    /// The positions of the rows matching `condition`, in order.
+    fn positions(&self, condition: &Condition<'_>) -> Cow<'_, [usize]> {
+        let column = match self.columns.get(condition.column) {
+            Some(column) => column,
+            None => return Cow::Borrowed(&[]),
+        };
+        let positions = match condition.mode {
+            Match::Exact => column
+                .exact
+                .get_or_init(|| self.index(condition.column, false))
+                .get(condition.value),
+            Match::CaseInsensitive => column
+                .case_insensitive
+                .get_or_init(|| self.index(condition.column, true))
+                .get(&condition.value.to_lowercase()),
+            Match::Cidr => {
+                return match condition.value.parse::<IpAddr>() {
+                    Ok(ip) => Cow::Owned(
+                        column
+                            .networks
+                            .get_or_init(|| Networks::new(&self.rows, condition.column))
+                            .find(ip),
+                    ),
+                    Err(_) => Cow::Borrowed(&[]),
+                }
+            }
+        };
+        Cow::Borrowed(positions.map_or(&[][..], Vec::as_slice))
+    }
+
+    fn index(&self, column: &str, case_insensitive: bool) -> Index {
+        let mut index = Index::new();
+        for (position, row) in self.rows.iter().enumerate() {
+            if let Some(value) = row.get(column) {
+                let value = value.to_string_lossy();
+                let value = if case_insensitive {
+                    value.to_lowercase()
+                } else {
+                    value
+                };
+                index.entry(value).or_default().push(position);
+            }
+        }
+        index
+    }
+}
+
+/// A configured table. Transforms hold on to it, and use the `current` version of the table
+/// for each event, so that they see the changes of the file.
+#[derive(Debug, Clone)]
+pub struct EnrichmentTable {
+    config: EnrichmentTableConfig,
+    table: Arc<RwLock<Arc<Table>>>,
+}
+
+impl EnrichmentTable {
+    pub fn new(config: EnrichmentTableConfig, table: Table) -> Self {
+        Self {
+            config,
+            table: Arc::new(RwLock::new(Arc::new(table))),
+        }
+    }
+
+    pub fn current(&self) -> Arc<Table> {
+        Arc::clone(&self.table.read().expect("enrichment table lock poisoned"))
+    }
+
+    /// Load the file again, keeping the current table if that fails.
+    fn reload(&self, name: &str) {
+        match Table::load(&self.config) {
+            Ok(table) => {
+                *self.table.write().expect("enrichment table lock poisoned") = Arc::new(table);
+                emit!(EnrichmentTableReloaded { name });
+            }
+            Err(error) => emit!(EnrichmentTableReloadFailed { name, error }),
+        }
+    }
+}
+
+/// All the configured tables, by name.
+#[derive(Clone, Default)]
+pub struct EnrichmentTables {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    tables: HashMap<String, EnrichmentTable>,
+    /// Dropping the watcher stops the reloading thread.
+    _watcher: Option<Mutex<RecommendedWatcher>>,
+}
+
+impl fmt::Debug for EnrichmentTables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.inner.tables.iter()).finish()
+    }
+}
+
+impl EnrichmentTables {
+    /// Load all the tables, and watch the files of those to be reloaded.
+    pub fn load(configs: &IndexMap<String, EnrichmentTableConfig>) -> Result<Self, Vec<String>> {
+        let mut tables = HashMap::new();
+        let mut errors = Vec::new();
+        for (name, config) in configs {
+            match Table::load(config) {
+                Ok(table) => {
+                    tables.insert(name.clone(), EnrichmentTable::new(config.clone(), table));
+                }
+                Err(error) => errors.push(format!("Enrichment table \"{}\": {}", name, error)),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut inner = Inner {
+            tables,
+            _watcher: None,
+        };
+        if configs.values().any(|config| config.reload) {
+            let (watcher, receiver, watched) =
+                watch(&inner.tables).map_err(|error| vec![error.to_string()])?;
+            inner._watcher = Some(Mutex::new(watcher));
+            let inner = Arc::new(inner);
+            spawn_reloader(Arc::downgrade(&inner), receiver, watched);
+            Ok(Self { inner })
+        } else {
+            Ok(Self {
+                inner: Arc::new(inner),
+            })
+        }
+    }
+
+    /// Tables that are never reloaded, mostly for tests.
+    pub fn from_tables(tables: impl IntoIterator<Item = (String, EnrichmentTable)>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                tables: tables.into_iter().collect(),
+                _watcher: None,
+            }),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<EnrichmentTable> {
+        self.inner.tables.get(name).cloned()
+    }
+}
+
+/// The tables to be reloaded, by their canonical path.
+type Watched = HashMap<PathBuf, Vec<String>>;
+
+/// Watch the directories holding the files, as files that are replaced rather than written to
+/// would otherwise go unnoticed.
+fn watch(
+    tables: &HashMap<String, EnrichmentTable>,
+) -> Result<(RecommendedWatcher, Receiver<DebouncedEvent>, Watched), LoadError> {
+    let (sender, receiver) = channel();
+    let mut watcher = watcher(sender, RELOAD_DELAY).context(CreateWatcher)?;
+    let mut watched = Watched::new();
+    for (name, table) in tables.iter().filter(|(_, table)| table.config.reload) {
+        let path = &table.config.path;
+        let path = fs::canonicalize(path).context(Read { path })?;
+        let dir = path.parent().unwrap_or(&path).to_owned();
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .context(InvalidWatch { path: dir })?;
+        watched.entry(path).or_default().push(name.clone());
+    }
+    Ok((watcher, receiver, watched))
+}
+
+fn spawn_reloader(inner: Weak<Inner>, receiver: Receiver<DebouncedEvent>, watched: Watched) {
+    thread::spawn(move || {
+        // Ends once the watcher, and with it the sending half of the channel, is dropped.
+        for event in receiver {
+            let path = match event {
+                DebouncedEvent::Create(path)
+                | DebouncedEvent::Write(path)
+                | DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+            let inner = match inner.upgrade() {
+                Some(inner) => inner,
+                None => break,
+            };
+            for name in watched.get(&path).into_iter().flatten() {
+                if let Some(table) = inner.tables.get(name) {
+                    table.reload(name);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn config(path: PathBuf) -> EnrichmentTableConfig {
+        EnrichmentTableConfig {
+            path,
+            format: None,
+            reload: false,
+        }
+    }
+
+    fn condition<'a>(column: &'a str, value: &'a str, mode: Match) -> Condition<'a> {
+        Condition {
+            column,
+            value,
+            mode,
+        }
+    }
+
+    #[test]
+    fn loads_csv_and_json() {
+        let table = Table::load(&config(write(
+            "owners.csv",
+            "service,owner\napi,core\nweb,frontend\n",
+        )))
+        .unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.rows()[1]["owner"], Value::from("frontend"));
+
+        let table = Table::load(&config(write(
+            "tiers.json",
+            r#"[{"customer": 42, "tier": "gold"}]"#,
+        )))
+        .unwrap();
+        assert_eq!(table.rows()[0]["tier"], Value::from("gold"));
+
+        let error = Table::load(&config(write("tiers.txt", ""))).unwrap_err();
+        assert!(matches!(error, LoadError::UnknownFormat { .. }));
+    }
+
+    #[test]
+    fn finds_rows() {
+        let table = Table::load(&config(write(
+            "networks.csv",
+            "network,region,owner\n10.0.0.0/8,eu,Core\n10.1.0.0/16,us,Web\n192.168.0.0/16,us,Core\n",
+        )))
+        .unwrap();
+
+        let owner = |conditions: &[Condition<'_>]| {
+            table
+                .find(conditions)
+                .map(|row| row["region"].to_string_lossy())
+        };
+
+        assert_eq!(
+            owner(&[condition("owner", "Web", Match::Exact)]),
+            Some("us".into())
+        );
+        assert_eq!(owner(&[condition("owner", "web", Match::Exact)]), None);
+        assert_eq!(
+            owner(&[condition("owner", "web", Match::CaseInsensitive)]),
+            Some("us".into())
+        );
+        assert_eq!(
+            owner(&[condition("network", "10.1.2.3", Match::Cidr)]),
+            Some("eu".into())
+        );
+        assert_eq!(
+            owner(&[
+                condition("owner", "core", Match::CaseInsensitive),
+                condition("network", "192.168.1.1", Match::Cidr),
+            ]),
+            Some("us".into())
+        );
+        assert_eq!(
+            owner(&[condition("network", "not an address", Match::Cidr)]),
+            None
+        );
+        assert_eq!(owner(&[condition("missing", "Web", Match::Exact)]), None);
+        assert_eq!(owner(&[]), None);
+    }
+
+    #[test]
+    fn finds_first_network_containing_address() {
+        let table = Table::load(&config(write(
+            "prefixes.csv",
+            "network,name\n2001:db8::/32,docs\n10.1.0.0/16,web\n10.0.0.0/8,internal\n0.0.0.0/0,any\nbogus,none\n",
+        )))
+        .unwrap();
+
+        let name = |address: &str| {
+            table
+                .find(&[condition("network", address, Match::Cidr)])
+                .map(|row| row["name"].to_string_lossy())
+        };
+
+        assert_eq!(name("10.1.2.3"), Some("web".into()));
+        assert_eq!(name("10.2.3.4"), Some("internal".into()));
+        assert_eq!(name("8.8.8.8"), Some("any".into()));
+        assert_eq!(name("2001:db8::1"), Some("docs".into()));
+        assert_eq!(name("2001:db9::1"), None);
+    }
+
+    #[test]
+    fn reloads_changed_files() {
+        let path = write("reloaded.csv", "key,value\na,1\n");
+        let mut configs = IndexMap::new();
+        configs.insert(
+            "reloaded".to_owned(),
+            EnrichmentTableConfig {
+                reload: true,
+                ..config(path.clone())
+            },
+        );
+        let tables = EnrichmentTables::load(&configs).unwrap();
+        let table = tables.get("reloaded").unwrap();
+        assert_eq!(table.current().rows()[0]["value"], Value::from("1"));
+
+        fs::write(&path, "key,value\na,2\n").unwrap();
+        for _ in 0..50 {
+            if table.current().rows()[0]["value"] == Value::from("2") {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        panic!("table was not reloaded");
+    }
+
+    #[test]
+    fn reports_invalid_tables() {
+        let mut configs = IndexMap::new();
+        configs.insert("missing".to_owned(), config(temp_dir().join("missing.csv")));
+        let errors = EnrichmentTables::load(&configs).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Enrichment table \"missing\":"));
+    }
+}
@@ -0,0 +1,240 @@
+//! End-to-end acknowledgement of events.
+//!
+//! A source that wants to know when its events have been processed
+//! creates a `BatchNotifier` for each batch it reads and attaches it to
+//! every event of that batch. The resulting finalizer is shared by all the
+//! copies of an event made as it fans out to several sinks, and each sink
+//! records its outcome on it as it delivers or rejects its copy. Once every
+//! event of a batch has been finalized, the source is told the combined
+//! status of the batch.
+
+use futures::channel::oneshot;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
+/// The outcome of processing an event, or a batch of events.
+///
+/// Statuses are ordered so that combining them keeps the most significant
+/// one: a batch is `Rejected` if any sink rejected any of its events, and
+/// `Delivered` if any sink delivered any of them.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum EventStatus {
+    /// No sink reported on the event: it was filtered out by a transform
+    /// or discarded during a forced shutdown.
+    Dropped = 0,
+    /// A sink delivered the event, or persisted it in a disk buffer.
+    Delivered = 1,
+    /// A sink gave up on the event.
+    Rejected = 2,
+}
+
+impl EventStatus {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Dropped,
+            1 => Self::Delivered,
+            _ => Self::Rejected,
+        }
+    }
+}
+
+/// Combines statuses recorded from several places, keeping the most
+/// significant one.
+#[derive(Debug)]
+struct AtomicStatus(AtomicU8);
+
+impl AtomicStatus {
+    fn new() -> Self {
+        Self(AtomicU8::new(EventStatus::Dropped as u8))
+    }
+
+    fn update(&self, status: EventStatus) {
+        let status = status as u8;
+        let mut current = self.0.load(Ordering::Acquire);
+        while current < status {
+            match self
+                .0
+                .compare_exchange(current, status, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    fn get(&self) -> EventStatus {
+        EventStatus::from_u8(self.0.load(Ordering::Acquire))
+    }
+}
+
+/// Tells a source the status of a batch once every event in it has been
+/// finalized.
+pub struct BatchNotifier {
+    status: AtomicStatus,
+    tx: Mutex<Option<oneshot::Sender<EventStatus>>>,
+}
+
+impl BatchNotifier {
+    pub fn new_with_receiver() -> (Arc<Self>, BatchStatusReceiver) {
+        let (tx, rx) = oneshot::channel();
+        let notifier = Self {
+            status: AtomicStatus::new(),
+            tx: Mutex::new(Some(tx)),
+        };
+        (Arc::new(notifier), BatchStatusReceiver(rx))
+    }
+}
+
+impl Drop for BatchNotifier {
+    fn drop(&mut self) {
+        let tx = self
+            .tx
+            .lock()
+            .expect("Batch notifier mutex is poisoned")
+            .take();
+        if let Some(tx) = tx {
+            // The source may have stopped waiting, which is fine.
+            let _ = tx.send(self.status.get());
+        }
+    }
+}
+
+impl fmt::Debug for BatchNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchNotifier")
+            .field("status", &self.status.get())
+            .finish()
+    }
+}
+
+/// Resolves to the status of a batch once all of its events have been
+/// finalized.
+#[derive(Debug)]
+pub struct BatchStatusReceiver(oneshot::Receiver<EventStatus>);
+
+impl Future for BatchStatusReceiver {
+    type Output = EventStatus;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|status| status.unwrap_or(EventStatus::Dropped))
+    }
+}
+
+/// Records the outcome of a single event for its batch. It is shared by
+/// every copy of the event and reports to the batch when the last copy is
+/// finalized.
+#[derive(Debug)]
+pub struct EventFinalizer {
+    status: AtomicStatus,
+    batch: Arc<BatchNotifier>,
+}
+
+impl EventFinalizer {
+    pub fn new(batch: Arc<BatchNotifier>) -> Self {
+        Self {
+            status: AtomicStatus::new(),
+            batch,
+        }
+    }
+}
+
+impl Drop for EventFinalizer {
+    fn drop(&mut self) {
+        self.batch.status.update(self.status.get());
+    }
+}
+
+/// The finalizers attached to an event. Events merged from several
+/// sources of data may carry more than one.
+#[derive(Clone, Debug, Default)]
+pub struct EventFinalizers(Vec<Arc<EventFinalizer>>);
+
+impl EventFinalizers {
+    pub fn new(finalizer: EventFinalizer) -> Self {
+        Self(vec![Arc::new(finalizer)])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    /// Records the outcome of this copy of the event. The finalizers are
+    /// resolved once they, and those of every other copy, are dropped.
+    pub fn update_status(&self, status: EventStatus) {
+        for finalizer in &self.0 {
+            finalizer.status.update(status);
+        }
+    }
+}
+
+// Finalizers track what happens to an event, they are not part of it.
+impl PartialEq for EventFinalizers {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    fn finalizers() -> (EventFinalizers, BatchStatusReceiver) {
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        (EventFinalizers::new(EventFinalizer::new(batch)), receiver)
+    }
+
+    #[test]
+    fn resolves_when_all_copies_are_finalized() {
+        let (finalizers, mut receiver) = finalizers();
+        let copy = finalizers.clone();
+
+        finalizers.update_status(EventStatus::Delivered);
+        drop(finalizers);
+        assert_eq!((&mut receiver).now_or_never(), None);
+
+        drop(copy);
+        assert_eq!(receiver.now_or_never(), Some(EventStatus::Delivered));
+    }
+
+    #[test]
+    fn keeps_the_most_significant_status() {
+        let (finalizers, receiver) = finalizers();
+        let copy = finalizers.clone();
+
+        copy.update_status(EventStatus::Rejected);
+        finalizers.update_status(EventStatus::Delivered);
+        drop((finalizers, copy));
+        assert_eq!(receiver.now_or_never(), Some(EventStatus::Rejected));
+    }
+
+    #[test]
+    fn batches_wait_for_every_event() {
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let first = EventFinalizers::new(EventFinalizer::new(Arc::clone(&batch)));
+        let second = EventFinalizers::new(EventFinalizer::new(batch));
+
+        second.update_status(EventStatus::Delivered);
+        drop(second);
+        assert_eq!((&mut receiver).now_or_never(), None);
+
+        // Dropping the first event without a status, as a filtering
+        // transform would, still resolves the batch.
+        drop(first);
+        assert_eq!(receiver.now_or_never(), Some(EventStatus::Delivered));
+    }
+}
@@ -16,7 +16,7 @@ pub struct Metric {
     pub value: MetricValue,
 }
 
-#[derive(Debug, Hash, Clone, PartialEq, Deserialize, Serialize, is_enum_variant)]
+#[derive(Debug, Hash, Clone, PartialEq, Eq, Deserialize, Serialize, is_enum_variant)]
 #[serde(rename_all = "snake_case")]
 pub enum MetricKind {
     Incremental,
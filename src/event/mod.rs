@@ -7,15 +7,19 @@ use metric::{MetricKind, MetricValue};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value as JsonValue;
-use std::{collections::BTreeMap, iter::FromIterator};
+use std::{collections::BTreeMap, iter::FromIterator, sync::Arc};
 use string_cache::DefaultAtom as Atom;
 
 pub mod discriminant;
+pub mod finalization;
 pub mod merge;
 pub mod merge_state;
 pub mod metric;
 mod util;
 
+pub use finalization::{
+    BatchNotifier, BatchStatusReceiver, EventFinalizer, EventFinalizers, EventStatus,
+};
 pub use metric::{Metric, StatisticKind};
 pub(crate) use util::log::PathComponent;
 pub(crate) use util::log::PathIter;
@@ -47,6 +51,7 @@ pub enum Event {
 #[derive(PartialEq, Debug, Clone, Default)]
 pub struct LogEvent {
     fields: BTreeMap<String, Value>,
+    finalizers: EventFinalizers,
 }
 
 impl Event {
@@ -54,6 +59,28 @@ impl Event {
         Event::Log(LogEvent::default())
     }
 
+    /// Reports the outcome of this event to `batch` once it has been
+    /// processed. Only log events support acknowledgements; metrics are
+    /// treated as dropped.
+    pub fn add_batch_notifier(&mut self, batch: Arc<BatchNotifier>) {
+        if let Event::Log(log) = self {
+            log.add_finalizers(EventFinalizers::new(EventFinalizer::new(batch)));
+        }
+    }
+
+    pub fn add_finalizers(&mut self, finalizers: EventFinalizers) {
+        if let Event::Log(log) = self {
+            log.add_finalizers(finalizers);
+        }
+    }
+
+    pub fn take_finalizers(&mut self) -> EventFinalizers {
+        match self {
+            Event::Log(log) => log.take_finalizers(),
+            Event::Metric(_) => EventFinalizers::default(),
+        }
+    }
+
     pub fn as_log(&self) -> &LogEvent {
         match self {
             Event::Log(log) => log,
@@ -102,6 +129,10 @@ impl LogEvent {
         util::log::get(&self.fields, key)
     }
 
+    pub fn get_path(&self, path: &[PathComponent]) -> Option<&Value> {
+        util::log::get_path(&self.fields, path)
+    }
+
     pub fn get_flat(&self, key: impl AsRef<str>) -> Option<&Value> {
         self.fields.get(key.as_ref())
     }
@@ -154,6 +185,10 @@ impl LogEvent {
         util::log::remove(&mut self.fields, &key, prune)
     }
 
+    pub fn remove_path(&mut self, path: &[PathComponent], prune: bool) -> Option<Value> {
+        util::log::remove_path(&mut self.fields, path, prune)
+    }
+
     pub fn keys<'a>(&'a self) -> impl Iterator<Item = String> + 'a {
         util::log::keys(&self.fields)
     }
@@ -165,6 +200,14 @@ impl LogEvent {
     pub fn is_empty(&self) -> bool {
         self.fields.is_empty()
     }
+
+    pub fn add_finalizers(&mut self, finalizers: EventFinalizers) {
+        self.finalizers.merge(finalizers);
+    }
+
+    pub fn take_finalizers(&mut self) -> EventFinalizers {
+        std::mem::take(&mut self.finalizers)
+    }
 }
 
 impl std::ops::Index<&Atom> for LogEvent {
@@ -495,7 +538,10 @@ impl From<proto::EventWrapper> for Event {
                     .filter_map(|(k, v)| decode_value(v).map(|value| (k, value)))
                     .collect::<BTreeMap<_, _>>();
 
-                Event::Log(LogEvent { fields })
+                Event::Log(LogEvent {
+                    fields,
+                    finalizers: Default::default(),
+                })
             }
             EventProto::Metric(proto) => {
                 let kind = match proto.kind() {
@@ -595,7 +641,7 @@ fn encode_array(items: Vec<Value>) -> proto::ValueArray {
 impl From<Event> for proto::EventWrapper {
     fn from(event: Event) -> Self {
         match event {
-            Event::Log(LogEvent { fields }) => {
+            Event::Log(LogEvent { fields, .. }) => {
                 let fields = fields
                     .into_iter()
                     .map(|(k, v)| (k, encode_value(v)))
@@ -700,9 +746,7 @@ impl From<Event> for Vec<u8> {
 
 impl From<Bytes> for Event {
     fn from(message: Bytes) -> Self {
-        let mut event = Event::Log(LogEvent {
-            fields: BTreeMap::new(),
-        });
+        let mut event = Event::Log(LogEvent::default());
 
         event
             .as_mut_log()
@@ -14,6 +14,21 @@ pub fn get<'a>(fields: &'a BTreeMap<String, Value>, path: &str) -> Option<&'a Va
     }
 }
 
+/// Returns a reference to a field value specified by the given pre-parsed
+/// path components.
+pub fn get_path<'a>(
+    fields: &'a BTreeMap<String, Value>,
+    path: &[PathComponent],
+) -> Option<&'a Value> {
+    match path.split_first() {
+        Some((PathComponent::Key(key), rest)) => match fields.get(key) {
+            None => None,
+            Some(value) => get_value(value, rest.iter().cloned()),
+        },
+        _ => None,
+    }
+}
+
 fn get_value<I>(mut value: &Value, mut path_iter: I) -> Option<&Value>
 where
     I: Iterator<Item = PathComponent>,
@@ -12,11 +12,11 @@ pub(crate) use path_iter::{PathComponent, PathIter};
 
 pub use all_fields::all_fields;
 pub use contains::contains;
-pub use get::get;
+pub use get::{get, get_path};
 pub use get_mut::get_mut;
 pub use insert::{insert, insert_path};
 pub use keys::keys;
-pub use remove::remove;
+pub use remove::{remove, remove_path};
 
 #[cfg(test)]
 pub(self) mod test {
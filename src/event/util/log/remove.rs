@@ -9,10 +9,23 @@ pub fn remove(fields: &mut BTreeMap<String, Value>, path: &str, prune: bool) ->
     remove_map(fields, PathIter::new(path).peekable(), prune).map(|(value, _)| value)
 }
 
+/// Removes field value specified by the given pre-parsed path components
+/// and return its value.
+pub fn remove_path(
+    fields: &mut BTreeMap<String, Value>,
+    path: &[PathComponent],
+    prune: bool,
+) -> Option<Value> {
+    remove_map(fields, path.iter().cloned().peekable(), prune).map(|(value, _)| value)
+}
+
 /// Recursively iterate through the path, and remove the last path
 /// element. This is the top-level function which can remove from any
 /// type of `Value`.
-fn remove_rec(value: &mut Value, path: Peekable<PathIter>, prune: bool) -> Option<(Value, bool)> {
+fn remove_rec<I>(value: &mut Value, path: Peekable<I>, prune: bool) -> Option<(Value, bool)>
+where
+    I: Iterator<Item = PathComponent>,
+{
     match value {
         Value::Map(map) => remove_map(map, path, prune),
         Value::Array(map) => remove_array(map, path, prune),
@@ -20,11 +33,14 @@ fn remove_rec(value: &mut Value, path: Peekable<PathIter>, prune: bool) -> Optio
     }
 }
 
-fn remove_array(
+fn remove_array<I>(
     array: &mut Vec<Value>,
-    mut path: Peekable<PathIter>,
+    mut path: Peekable<I>,
     prune: bool,
-) -> Option<(Value, bool)> {
+) -> Option<(Value, bool)>
+where
+    I: Iterator<Item = PathComponent>,
+{
     match path.next()? {
         PathComponent::Index(index) => match path.peek() {
             None => array_remove(array, index).map(|v| (v, false)),
@@ -36,11 +52,14 @@ fn remove_array(
     }
 }
 
-fn remove_map(
+fn remove_map<I>(
     fields: &mut BTreeMap<String, Value>,
-    mut path: Peekable<PathIter>,
+    mut path: Peekable<I>,
     prune: bool,
-) -> Option<(Value, bool)> {
+) -> Option<(Value, bool)>
+where
+    I: Iterator<Item = PathComponent>,
+{
     match path.next()? {
         PathComponent::Key(key) => match path.peek() {
             None => fields.remove(&key).map(|v| (v, fields.is_empty())),
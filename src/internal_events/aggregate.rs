@@ -0,0 +1,36 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct AggregateEventRecorded;
+
+impl InternalEvent for AggregateEventRecorded {
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "aggregate",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct AggregateFlushed {
+    pub series: usize,
+}
+
+impl InternalEvent for AggregateFlushed {
+    fn emit_logs(&self) {
+        trace!(message = "Flushed aggregated metrics.", series = %self.series);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("flushes", 1,
+            "component_kind" => "transform",
+            "component_type" => "aggregate",
+        );
+        counter!("aggregated_series", self.series as u64,
+            "component_kind" => "transform",
+            "component_type" => "aggregate",
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use super::InternalEvent;
-use metrics::value;
+use metrics::{gauge, value};
 use std::time::Duration;
 
 #[derive(Debug)]
@@ -25,6 +25,7 @@ impl InternalEvent for AutoConcurrencyLimit {
 
     fn emit_metrics(&self) {
         value!("auto_concurrency_limit", self.concurrency);
+        gauge!("auto_concurrency_current_limit", self.concurrency as i64);
     }
 }
 
@@ -47,6 +48,10 @@ pub struct AutoConcurrencyObservedRtt {
 impl InternalEvent for AutoConcurrencyObservedRtt {
     fn emit_metrics(&self) {
         value!("auto_concurrency_observed_rtt", self.rtt);
+        gauge!(
+            "auto_concurrency_current_rtt_nanoseconds",
+            self.rtt.as_nanos() as i64
+        );
     }
 }
 
@@ -60,3 +65,14 @@ impl InternalEvent for AutoConcurrencyAveragedRtt {
         value!("auto_concurrency_averaged_rtt", self.rtt);
     }
 }
+
+#[derive(Debug)]
+pub struct AutoConcurrencyBackPressure {
+    pub count: u64,
+}
+
+impl InternalEvent for AutoConcurrencyBackPressure {
+    fn emit_metrics(&self) {
+        gauge!("auto_concurrency_back_pressure", self.count as i64);
+    }
+}
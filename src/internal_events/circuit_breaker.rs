@@ -0,0 +1,51 @@
+use super::InternalEvent;
+use metrics::{counter, gauge};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct CircuitBreakerOpened {
+    pub consecutive_failures: usize,
+    pub reset_timeout: Duration,
+}
+
+impl InternalEvent for CircuitBreakerOpened {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Circuit breaker opened; holding requests until the service recovers.",
+            consecutive_failures = %self.consecutive_failures,
+            reset_timeout_secs = %self.reset_timeout.as_secs(),
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("circuit_breaker_transitions", 1, "state" => "open");
+        gauge!("circuit_breaker_open", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitBreakerHalfOpened;
+
+impl InternalEvent for CircuitBreakerHalfOpened {
+    fn emit_logs(&self) {
+        debug!(message = "Circuit breaker half-open; probing the service.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("circuit_breaker_transitions", 1, "state" => "half_open");
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitBreakerClosed;
+
+impl InternalEvent for CircuitBreakerClosed {
+    fn emit_logs(&self) {
+        info!(message = "Circuit breaker closed; the service has recovered.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("circuit_breaker_transitions", 1, "state" => "closed");
+        gauge!("circuit_breaker_open", 0);
+    }
+}
@@ -0,0 +1,43 @@
+use super::InternalEvent;
+use crate::enrichment_tables::LoadError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct EnrichmentTableReloaded<'a> {
+    pub name: &'a str,
+}
+
+impl<'a> InternalEvent for EnrichmentTableReloaded<'a> {
+    fn emit_logs(&self) {
+        info!(message = "Enrichment table reloaded.", name = %self.name);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("enrichment_table_reloads_total", 1,
+            "name" => self.name.to_owned(),
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct EnrichmentTableReloadFailed<'a> {
+    pub name: &'a str,
+    pub error: LoadError,
+}
+
+impl<'a> InternalEvent for EnrichmentTableReloadFailed<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to reload enrichment table; keeping the previous version.",
+            name = %self.name,
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("enrichment_table_reload_errors", 1,
+            "name" => self.name.to_owned(),
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct GeoipDatabaseReloaded<'a> {
+    pub database: &'a str,
+}
+
+impl<'a> InternalEvent for GeoipDatabaseReloaded<'a> {
+    fn emit_logs(&self) {
+        info!(message = "GeoIP database reloaded.", database = %self.database);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("reloaded_total", 1,
+            "component_kind" => "transform",
+            "component_type" => "geoip",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct GeoipDatabaseReloadFailed<'a> {
+    pub database: &'a str,
+    pub error: String,
+}
+
+impl<'a> InternalEvent for GeoipDatabaseReloadFailed<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to reload GeoIP database; keeping the previous version.",
+            database = %self.database,
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("reload_errors", 1,
+            "component_kind" => "transform",
+            "component_type" => "geoip",
+        );
+    }
+}
@@ -73,3 +73,71 @@ impl InternalEvent for KafkaKeyExtractionFailed<'_> {
         error!(message = "failed to extract key.", key_field = %self.key_field);
     }
 }
+
+#[derive(Debug)]
+pub struct KafkaOffsetCommitFailed {
+    pub error: rdkafka::error::KafkaError,
+}
+
+impl InternalEvent for KafkaOffsetCommitFailed {
+    fn emit_logs(&self) {
+        error!(message = "Unable to commit consumer offsets.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "consumer_offset_commits_failed", 1,
+            "component_kind" => "source",
+            "component_type" => "kafka",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub enum KafkaRebalanceKind {
+    Assign { partitions: usize },
+    Revoke,
+    Error { error: String },
+}
+
+#[derive(Debug)]
+pub struct KafkaRebalance {
+    pub kind: KafkaRebalanceKind,
+    pub stage: &'static str,
+}
+
+impl InternalEvent for KafkaRebalance {
+    fn emit_logs(&self) {
+        match &self.kind {
+            KafkaRebalanceKind::Assign { partitions } => info!(
+                message = "Consumer group rebalance: partitions assigned.",
+                stage = self.stage,
+                partitions = %partitions,
+            ),
+            KafkaRebalanceKind::Revoke => info!(
+                message = "Consumer group rebalance: partitions revoked.",
+                stage = self.stage,
+            ),
+            KafkaRebalanceKind::Error { error } => error!(
+                message = "Consumer group rebalance failed.",
+                stage = self.stage,
+                error = %error,
+            ),
+        }
+    }
+
+    fn emit_metrics(&self) {
+        let kind = match self.kind {
+            KafkaRebalanceKind::Assign { .. } => "assign",
+            KafkaRebalanceKind::Revoke => "revoke",
+            KafkaRebalanceKind::Error { .. } => "error",
+        };
+        counter!(
+            "consumer_rebalances", 1,
+            "component_kind" => "source",
+            "component_type" => "kafka",
+            "kind" => kind,
+            "stage" => self.stage,
+        );
+    }
+}
@@ -53,6 +53,50 @@ impl InternalEvent for KubernetesLogsEventAnnotationFailed<'_> {
     }
 }
 
+#[derive(Debug)]
+pub struct KubernetesLogsEventNamespaceAnnotationFailed<'a> {
+    pub event: &'a Event,
+}
+
+impl InternalEvent for KubernetesLogsEventNamespaceAnnotationFailed<'_> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to annotate event with namespace metadata",
+            event = ?self.event
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "k8s_event_namespace_annotation_failures", 1,
+            "component_kind" => "source",
+            "component_type" => "kubernetes_logs",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct KubernetesLogsEventNodeAnnotationFailed<'a> {
+    pub event: &'a Event,
+}
+
+impl InternalEvent for KubernetesLogsEventNodeAnnotationFailed<'_> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to annotate event with node metadata",
+            event = ?self.event
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "k8s_event_node_annotation_failures", 1,
+            "component_kind" => "source",
+            "component_type" => "kubernetes_logs",
+        );
+    }
+}
+
 #[derive(Debug)]
 pub struct KubernetesLogsDockerFormatParseFailed<'a> {
     pub message: &'a Bytes,
@@ -0,0 +1,47 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct LookupEventProcessed;
+
+impl InternalEvent for LookupEventProcessed {
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupNoMatch<'a> {
+    pub table: &'a str,
+}
+
+impl<'a> InternalEvent for LookupNoMatch<'a> {
+    fn emit_logs(&self) {
+        debug!(message = "No matching row found.", table = %self.table, rate_limit_secs = 30);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("lookup_misses", 1,
+            "component_kind" => "transform",
+            "component_type" => "lookup",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupTargetOverwritten<'a> {
+    pub field: &'a str,
+}
+
+impl<'a> InternalEvent for LookupTargetOverwritten<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Target field is not a map; overwriting it.",
+            field = %self.field,
+            rate_limit_secs = 30,
+        );
+    }
+}
@@ -46,3 +46,41 @@ impl InternalEvent for LuaScriptError {
         );
     }
 }
+
+#[derive(Debug)]
+pub struct LuaScriptReloaded;
+
+impl InternalEvent for LuaScriptReloaded {
+    fn emit_logs(&self) {
+        info!(message = "Lua scripts reloaded.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("reloaded_total", 1,
+            "component_kind" => "transform",
+            "component_type" => "lua",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct LuaScriptReloadFailed {
+    pub error: String,
+}
+
+impl InternalEvent for LuaScriptReloadFailed {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to reload Lua scripts; keeping the previous version.",
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("reload_errors", 1,
+            "component_kind" => "transform",
+            "component_type" => "lua",
+        );
+    }
+}
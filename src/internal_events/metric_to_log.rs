@@ -0,0 +1,42 @@
+use super::InternalEvent;
+use metrics::counter;
+use serde_json::Error;
+
+#[derive(Debug)]
+pub struct MetricToLogEventProcessed;
+
+impl InternalEvent for MetricToLogEventProcessed {
+    fn emit_logs(&self) {
+        trace!(message = "Processed one event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "metric_to_log",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct MetricToLogFailedSerialize {
+    pub error: Error,
+}
+
+impl InternalEvent for MetricToLogFailedSerialize {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Metric failed to serialize as JSON.",
+            error = %self.error,
+            rate_limit_secs = 30
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_errors", 1,
+            "component_kind" => "transform",
+            "component_type" => "metric_to_log",
+            "error_type" => "failed_serialize",
+        );
+    }
+}
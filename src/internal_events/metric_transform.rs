@@ -0,0 +1,40 @@
+use super::InternalEvent;
+use metrics::counter;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug)]
+pub struct MetricTransformEventProcessed;
+
+impl InternalEvent for MetricTransformEventProcessed {
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "metric_transform",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct MetricTransformTemplateRenderingError<'a> {
+    pub tag: &'a str,
+    pub missing_keys: Vec<Atom>,
+}
+
+impl<'a> InternalEvent for MetricTransformTemplateRenderingError<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Failed to render tag template; leaving tag unset.",
+            tag = %self.tag,
+            missing_keys = ?self.missing_keys,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_errors", 1,
+            "component_kind" => "transform",
+            "component_type" => "metric_transform",
+            "error_type" => "template_error",
+        );
+    }
+}
@@ -2,10 +2,13 @@ use std::borrow::Cow;
 
 mod add_fields;
 mod add_tags;
+#[cfg(feature = "transforms-aggregate")]
+mod aggregate;
 mod ansi_stripper;
 mod auto_concurrency;
 mod aws_kinesis_streams;
 mod blackhole;
+mod circuit_breaker;
 #[cfg(feature = "transforms-coercer")]
 mod coercer;
 #[cfg(feature = "transforms-concat")]
@@ -13,6 +16,9 @@ mod concat;
 #[cfg(feature = "sources-docker")]
 mod docker;
 mod elasticsearch;
+mod enrichment_tables;
+#[cfg(feature = "transforms-geoip")]
+mod geoip;
 #[cfg(feature = "transforms-grok_parser")]
 mod grok_parser;
 mod heartbeat;
@@ -28,6 +34,12 @@ mod kubernetes_logs;
 #[cfg(feature = "transforms-log_to_metric")]
 mod log_to_metric;
 mod logplex;
+#[cfg(feature = "transforms-metric_to_log")]
+mod metric_to_log;
+#[cfg(feature = "transforms-metric_transform")]
+mod metric_transform;
+#[cfg(feature = "transforms-lookup")]
+mod lookup;
 #[cfg(feature = "transforms-lua")]
 mod lua;
 mod process;
@@ -35,6 +47,8 @@ mod process;
 mod prometheus;
 #[cfg(feature = "transforms-regex_parser")]
 mod regex_parser;
+#[cfg(feature = "transforms-remap")]
+mod remap;
 #[cfg(feature = "transforms-remove_fields")]
 mod remove_fields;
 #[cfg(feature = "transforms-remove_tags")]
@@ -58,6 +72,8 @@ mod syslog;
 #[cfg(feature = "transforms-tag_cardinality_limit")]
 mod tag_cardinality_limit;
 mod tcp;
+mod template;
+mod topology;
 mod unix;
 mod vector;
 #[cfg(feature = "wasm")]
@@ -67,10 +83,13 @@ pub mod kubernetes;
 
 pub use self::add_fields::*;
 pub use self::add_tags::*;
+#[cfg(feature = "transforms-aggregate")]
+pub use self::aggregate::*;
 pub use self::ansi_stripper::*;
 pub use self::auto_concurrency::*;
 pub use self::aws_kinesis_streams::*;
 pub use self::blackhole::*;
+pub use self::circuit_breaker::*;
 #[cfg(feature = "transforms-coercer")]
 pub(crate) use self::coercer::*;
 #[cfg(feature = "transforms-concat")]
@@ -78,7 +97,10 @@ pub use self::concat::*;
 #[cfg(feature = "sources-docker")]
 pub use self::docker::*;
 pub use self::elasticsearch::*;
+pub use self::enrichment_tables::*;
 pub use self::file::*;
+#[cfg(feature = "transforms-geoip")]
+pub use self::geoip::*;
 #[cfg(feature = "transforms-grok_parser")]
 pub(crate) use self::grok_parser::*;
 pub use self::heartbeat::*;
@@ -94,6 +116,12 @@ pub use self::kubernetes_logs::*;
 #[cfg(feature = "transforms-log_to_metric")]
 pub(crate) use self::log_to_metric::*;
 pub use self::logplex::*;
+#[cfg(feature = "transforms-metric_to_log")]
+pub use self::metric_to_log::*;
+#[cfg(feature = "transforms-metric_transform")]
+pub use self::metric_transform::*;
+#[cfg(feature = "transforms-lookup")]
+pub use self::lookup::*;
 #[cfg(feature = "transforms-lua")]
 pub use self::lua::*;
 pub use self::process::*;
@@ -101,6 +129,8 @@ pub use self::process::*;
 pub use self::prometheus::*;
 #[cfg(feature = "transforms-regex_parser")]
 pub(crate) use self::regex_parser::*;
+#[cfg(feature = "transforms-remap")]
+pub(crate) use self::remap::*;
 #[cfg(feature = "transforms-remove_fields")]
 pub use self::remove_fields::*;
 #[cfg(feature = "transforms-remove_tags")]
@@ -124,6 +154,8 @@ pub use self::syslog::*;
 #[cfg(feature = "transforms-tag_cardinality_limit")]
 pub(crate) use self::tag_cardinality_limit::*;
 pub use self::tcp::*;
+pub use self::template::*;
+pub use self::topology::*;
 pub use self::unix::*;
 pub use self::vector::*;
 #[cfg(feature = "wasm")]
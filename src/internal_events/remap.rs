@@ -0,0 +1,52 @@
+use super::InternalEvent;
+use crate::remap::RuntimeError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub(crate) struct RemapEventProcessed;
+
+impl InternalEvent for RemapEventProcessed {
+    fn emit_logs(&self) {
+        trace!(message = "Processed one event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed", 1,
+            "component_kind" => "transform",
+            "component_type" => "remap",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RemapFailedMapping<'a> {
+    /// Whether the event was dropped because of the error.
+    pub event_dropped: bool,
+    pub error: &'a RuntimeError,
+}
+
+impl InternalEvent for RemapFailedMapping<'_> {
+    fn emit_logs(&self) {
+        if self.event_dropped {
+            warn!(
+                message = "Mapping failed with event; discarding event.",
+                error = %self.error,
+                rate_limit_secs = 30
+            );
+        } else {
+            warn!(
+                message = "Mapping failed with event.",
+                error = %self.error,
+                rate_limit_secs = 30
+            );
+        }
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_errors", 1,
+            "component_kind" => "transform",
+            "component_type" => "remap",
+            "error_type" => "failed_mapping",
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use super::InternalEvent;
+use metrics::counter;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug)]
+pub struct TemplateRenderingError<'a> {
+    pub template: &'a str,
+    pub missing_keys: &'a [Atom],
+}
+
+impl<'a> InternalEvent for TemplateRenderingError<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to render template; dropping event.",
+            template = %self.template,
+            missing_keys = ?self.missing_keys,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_errors", 1,
+            "error_type" => "template_error",
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use super::InternalEvent;
+use metrics::counter;
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub struct TopologyShutdownSummary<'a> {
+    pub unfinished: &'a [String],
+    pub undelivered: &'a BTreeMap<String, usize>,
+}
+
+impl InternalEvent for TopologyShutdownSummary<'_> {
+    fn emit_logs(&self) {
+        if self.undelivered.is_empty() {
+            info!(
+                message = "All components drained.",
+                unfinished = ?self.unfinished,
+            );
+        } else {
+            error!(
+                message = "Events were dropped on shutdown.",
+                undelivered = ?self.undelivered,
+                unfinished = ?self.unfinished,
+            );
+        }
+    }
+
+    fn emit_metrics(&self) {
+        for (component, count) in self.undelivered {
+            counter!("shutdown_undelivered_events", *count as u64,
+                "component_name" => component.clone(),
+            );
+        }
+    }
+}
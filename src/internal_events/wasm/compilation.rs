@@ -60,7 +60,7 @@ impl InternalEvent for WasmCompilationProgress {
                 state = self.state.as_const_str(),
                 role = self.role.as_const_str(),
                 elapsed_micros = self.elapsed.as_micros() as u64,
-                "WASM Compilation via `lucet`.",
+                "WASM Compilation via `wasmtime`.",
             ),
             State::Errored => error!(
                 state = self.state.as_const_str(),
@@ -68,7 +68,7 @@ impl InternalEvent for WasmCompilationProgress {
                 error = tracing::field::display(self.error.as_ref().unwrap_or(&String::from(""))),
                 elapsed_micros = self.elapsed.as_micros() as u64,
                 // We do not rate limit this since it should never spam, it's a oneshot at startup.
-                "WASM Compilation via `lucet`.",
+                "WASM Compilation via `wasmtime`.",
             ),
         }
     }
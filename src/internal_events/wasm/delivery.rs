@@ -0,0 +1,50 @@
+use crate::internal_events::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct WasmSinkDeliveryRetrying {
+    pub count: usize,
+    pub attempt: usize,
+}
+
+impl InternalEvent for WasmSinkDeliveryRetrying {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Module asked to retry delivery.",
+            count = self.count,
+            attempt = self.attempt,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("delivery_retries", 1,
+            "component_kind" => "sink",
+            "component_type" => "wasm",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct WasmSinkEventsDropped {
+    pub count: usize,
+    pub reason: String,
+}
+
+impl InternalEvent for WasmSinkEventsDropped {
+    fn emit_logs(&self) {
+        error!(
+            message = "Events dropped.",
+            count = self.count,
+            reason = %self.reason,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_discarded", self.count as u64,
+            "component_kind" => "sink",
+            "component_type" => "wasm",
+        );
+    }
+}
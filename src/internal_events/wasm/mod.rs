@@ -7,6 +7,9 @@ pub use hostcall::WasmHostcallProgress;
 mod event_processing;
 pub use event_processing::EventProcessingProgress;
 
+mod delivery;
+pub use delivery::{WasmSinkDeliveryRetrying, WasmSinkEventsDropped};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum State {
     Beginning,
@@ -16,6 +16,17 @@ where
 {
     inner: WriteHandle<String, Value<T>>,
     debounced_flush: Option<Debounce>,
+    key: Key,
+}
+
+/// What the objects in the state are keyed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// The object `uid`.
+    Uid,
+    /// The object `name`, for cluster-global objects that are looked up by
+    /// name, such as namespaces.
+    Name,
 }
 
 impl<T> Writer<T>
@@ -38,9 +49,16 @@ where
         Self {
             inner,
             debounced_flush,
+            key: Key::Uid,
         }
     }
 
+    /// Key the objects by `key` rather than by their `uid`.
+    pub fn keyed_by(mut self, key: Key) -> Self {
+        self.key = key;
+        self
+    }
+
     /// Debounced `flush`.
     /// When a number of flush events arrive un a row, we buffer them such that
     /// only the last one in the chain is propagated.
@@ -65,22 +83,27 @@ where
     type Item = T;
 
     async fn add(&mut self, item: Self::Item) {
-        if let Some((key, value)) = kv(item) {
+        if let Some((key, value)) = kv(item, self.key) {
             self.inner.insert(key, value);
             self.debounced_flush();
         }
     }
 
     async fn update(&mut self, item: Self::Item) {
-        if let Some((key, value)) = kv(item) {
+        if let Some((key, value)) = kv(item, self.key) {
             self.inner.update(key, value);
             self.debounced_flush();
         }
     }
 
     async fn delete(&mut self, item: Self::Item) {
-        if let Some((key, _value)) = kv(item) {
-            self.inner.empty(key);
+        if let Some((key, value)) = kv(item, self.key) {
+            match self.key {
+                Key::Uid => self.inner.empty(key),
+                // An object recreated under the same name may already be
+                // there, so only the deleted one is removed.
+                Key::Name => self.inner.remove(key, value),
+            };
             self.debounced_flush();
         }
     }
@@ -119,9 +142,13 @@ where
 pub type Value<T> = Box<HashValue<T>>;
 
 /// Build a key value pair for using in [`evmap`].
-fn kv<T: Metadata<Ty = ObjectMeta>>(object: T) -> Option<(String, Value<T>)> {
+fn kv<T: Metadata<Ty = ObjectMeta>>(object: T, key: Key) -> Option<(String, Value<T>)> {
     let value = Box::new(HashValue::new(object));
-    let key = value.uid()?.to_owned();
+    let key = match key {
+        Key::Uid => value.uid()?,
+        Key::Name => value.metadata().name.as_deref()?,
+    }
+    .to_owned();
     Some((key, value))
 }
 
@@ -141,10 +168,16 @@ mod tests {
         }
     }
 
+    fn make_named_pod(uid: &str, name: &str) -> Pod {
+        let mut pod = make_pod(uid);
+        pod.metadata.name = Some(name.to_owned());
+        pod
+    }
+
     #[test]
     fn test_kv() {
         let pod = make_pod("uid");
-        let (key, val) = kv(pod.clone()).unwrap();
+        let (key, val) = kv(pod.clone(), Key::Uid).unwrap();
         assert_eq!(key, "uid");
         assert_eq!(val, Box::new(HashValue::new(pod)));
     }
@@ -165,6 +198,29 @@ mod tests {
         drop(state_writer);
     }
 
+    #[tokio::test]
+    async fn test_keyed_by_name() {
+        let (state_reader, state_writer) = evmap::new();
+        let mut state_writer = Writer::new(state_writer, None).keyed_by(Key::Name);
+
+        // The object is recreated before the old one is deleted.
+        state_writer.add(make_named_pod("uid0", "name")).await;
+        state_writer.add(make_named_pod("uid1", "name")).await;
+        state_writer.delete(make_named_pod("uid0", "name")).await;
+
+        let values = state_reader.get("name").unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values.get_one().unwrap().uid(), Some("uid1"));
+        drop(values);
+
+        state_writer.delete(make_named_pod("uid1", "name")).await;
+        assert!(state_reader
+            .get("name")
+            .map_or(true, |values| values.is_empty()));
+
+        drop(state_writer);
+    }
+
     #[tokio::test]
     async fn test_with_debounce() {
         // Due to https://github.com/tokio-rs/tokio/issues/2090 we're not
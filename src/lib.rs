@@ -20,9 +20,11 @@ extern crate derivative;
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 pub mod buffers;
+pub mod checkpoints;
 pub mod conditions;
 pub mod config;
 pub mod dns;
+pub mod enrichment_tables;
 pub mod event;
 pub mod expiring_hash_map;
 pub mod generate;
@@ -40,6 +42,9 @@ pub mod list;
 pub mod metrics;
 pub(crate) mod pipeline;
 pub mod region;
+#[cfg(feature = "remap")]
+pub mod remap;
+pub mod secrets;
 pub mod serde;
 pub mod shutdown;
 pub mod signal;
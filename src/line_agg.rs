@@ -122,17 +122,30 @@ pub struct Logic<K, C> {
 
     /// A queue of key timeouts.
     timeouts: DelayQueue<K>,
+
+    /// Combines the context of a line with that of the aggregate it's
+    /// added to.
+    merge_context: fn(&mut C, C),
 }
 
 impl<K, C> Logic<K, C> {
-    /// Create a new `Logic` using the specified `Config`.
+    /// Create a new `Logic` using the specified `Config`. Aggregates keep the
+    /// context of their first line.
     pub fn new(config: Config) -> Self {
         Self {
             config,
             buffers: HashMap::new(),
             timeouts: DelayQueue::new(),
+            merge_context: |_, _| (),
         }
     }
+
+    /// Use `merge_context` to combine the context of each line added to an
+    /// aggregate with the aggregate's.
+    pub fn with_merge_context(mut self, merge_context: fn(&mut C, C)) -> Self {
+        self.merge_context = merge_context;
+        self
+    }
 }
 
 impl<T, K, C> LineAgg<T, K, C> {
@@ -295,7 +308,7 @@ where
                     Mode::ContinueThrough => {
                         if condition_matched {
                             let buffered = entry.get_mut();
-                            buffered.add_next_line(line);
+                            buffered.add_next_line(line, context, self.merge_context);
                             None
                         } else {
                             let (src, buffered) = entry.remove_entry();
@@ -307,11 +320,11 @@ where
                     Mode::ContinuePast => {
                         if condition_matched {
                             let buffered = entry.get_mut();
-                            buffered.add_next_line(line);
+                            buffered.add_next_line(line, context, self.merge_context);
                             None
                         } else {
                             let (src, mut buffered) = entry.remove_entry();
-                            buffered.add_next_line(line);
+                            buffered.add_next_line(line, context, self.merge_context);
                             Some((src, Emit::One(buffered.merge())))
                         }
                     }
@@ -323,7 +336,7 @@ where
                             Some((src, Emit::Two(buffered.merge(), (line, context))))
                         } else {
                             let buffered = entry.get_mut();
-                            buffered.add_next_line(line);
+                            buffered.add_next_line(line, context, self.merge_context);
                             None
                         }
                     }
@@ -332,11 +345,11 @@ where
                     Mode::HaltWith => {
                         if condition_matched {
                             let (src, mut buffered) = entry.remove_entry();
-                            buffered.add_next_line(line);
+                            buffered.add_next_line(line, context, self.merge_context);
                             Some((src, Emit::One(buffered.merge())))
                         } else {
                             let buffered = entry.get_mut();
-                            buffered.add_next_line(line);
+                            buffered.add_next_line(line, context, self.merge_context);
                             None
                         }
                     }
@@ -373,8 +386,9 @@ impl<C> Aggregate<C> {
         }
     }
 
-    fn add_next_line(&mut self, line: Bytes) {
+    fn add_next_line(&mut self, line: Bytes, context: C, merge_context: fn(&mut C, C)) {
         self.lines.push(line);
+        merge_context(&mut self.context, context);
     }
 
     fn merge(self) -> (Bytes, C) {
@@ -10,9 +10,10 @@ use futures::{
     compat::{Future01CompatExt, Stream01CompatExt},
     StreamExt,
 };
-use std::cmp::max;
+use std::{cmp::max, collections::HashMap};
 use tokio::{runtime, select};
 use vector::{
+    checkpoints,
     config::{self, ConfigDiff},
     generate, heartbeat,
     internal_events::{
@@ -92,6 +93,7 @@ fn main() {
                 SubCommand::List(l) => list::cmd(&l),
                 SubCommand::Test(t) => unit_test::cmd(&t),
                 SubCommand::Generate(g) => generate::cmd(&g),
+                SubCommand::Checkpoints(c) => checkpoints::cmd(&c),
             })
         };
 
@@ -123,7 +125,7 @@ fn main() {
             .expect("Couldn't set schema");
 
         let diff = ConfigDiff::initial(&config);
-        let pieces = topology::validate(&config, &diff).await.unwrap_or_else(|| {
+        let pieces = topology::validate(&config, &diff, HashMap::new()).await.unwrap_or_else(|| {
             std::process::exit(exitcode::CONFIG);
         });
 
@@ -179,7 +181,12 @@ fn main() {
             SignalTo::Shutdown => {
                 emit!(VectorStopped);
                 select! {
-                    _ = topology.stop().compat() => (), // Graceful shutdown finished
+                    // Graceful shutdown finished
+                    report = topology.stop().compat() => {
+                        if report.map_or(false, |report| report.dropped_events()) {
+                            std::process::exit(exitcode::SOFTWARE);
+                        }
+                    }
                     _ = signals.next() => {
                         // It is highly unlikely that this event will exit from topology.
                         emit!(VectorQuit);
@@ -0,0 +1,168 @@
+use std::fmt;
+
+/// A byte range in the program source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// The offset of the first byte.
+    pub start: usize,
+    /// The offset past the last byte.
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new [`Span`].
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Create a [`Span`] covering both `self` and `other`.
+    pub fn merge(self, other: Span) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// A human-friendly position in the program source, both components are
+/// one-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The line number.
+    pub line: usize,
+    /// The column number, in characters.
+    pub column: usize,
+}
+
+impl Location {
+    pub(super) fn from_offset(source: &str, offset: usize) -> Self {
+        let offset = offset.min(source.len());
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = source[line_start..offset].chars().count() + 1;
+        Self { line, column }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// An error detected while compiling a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    message: String,
+    location: Location,
+    source_line: String,
+    marker: (usize, usize),
+}
+
+impl CompileError {
+    pub(super) fn new(message: impl Into<String>, span: Span, source: &str) -> Self {
+        let start = span.start.min(source.len());
+        let location = Location::from_offset(source, start);
+        let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = source[start..]
+            .find('\n')
+            .map(|i| start + i)
+            .unwrap_or_else(|| source.len());
+        let end = span.end.max(start).min(line_end);
+        let width = source[start..end].chars().count().max(1);
+        Self {
+            message: message.into(),
+            location,
+            source_line: source[line_start..line_end].to_owned(),
+            marker: (location.column - 1, width),
+        }
+    }
+
+    /// The error message, without the location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where in the source the error was found.
+    pub fn location(&self) -> Location {
+        self.location
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number = self.location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        writeln!(f, "{} at {}", self.message, self.location)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, self.source_line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.marker.0),
+            "^".repeat(self.marker.1)
+        )
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// An error that occurred while executing a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    message: String,
+    location: Option<Location>,
+}
+
+impl RuntimeError {
+    pub(super) fn new(message: impl Into<String>, location: Location) -> Self {
+        Self {
+            message: message.into(),
+            location: Some(location),
+        }
+    }
+
+    /// Set the location, unless the error already has a more precise one.
+    pub(super) fn or_location(mut self, location: Location) -> Self {
+        self.location = self.location.or(Some(location));
+        self
+    }
+
+    /// The error message, without the location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where in the source the error happened, if known.
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+}
+
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            location: None,
+        }
+    }
+}
+
+impl From<&str> for RuntimeError {
+    fn from(message: &str) -> Self {
+        message.to_owned().into()
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{} at {}", self.message, location),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
@@ -0,0 +1,441 @@
+use super::{
+    error::{Location, RuntimeError},
+    functions::Call,
+    kind::Kind,
+};
+use crate::event::{LogEvent, PathComponent, Value};
+use bytes::{BufMut, BytesMut};
+use std::cmp::Ordering;
+
+/// The event a program is executed against.
+enum Target<'a> {
+    Mutable(&'a mut LogEvent),
+    ReadOnly(&'a LogEvent),
+}
+
+/// The state of a single program execution.
+pub struct Context<'a> {
+    target: Target<'a>,
+    variables: Vec<Value>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(log: &'a mut LogEvent, variables: usize) -> Self {
+        Self {
+            target: Target::Mutable(log),
+            variables: vec![Value::Null; variables],
+        }
+    }
+
+    /// A context that doesn't allow modifying the event, for the programs
+    /// compiled as conditions.
+    pub fn read_only(log: &'a LogEvent, variables: usize) -> Self {
+        Self {
+            target: Target::ReadOnly(log),
+            variables: vec![Value::Null; variables],
+        }
+    }
+
+    /// The event being processed.
+    pub fn log(&self) -> &LogEvent {
+        match &self.target {
+            Target::Mutable(log) => &**log,
+            Target::ReadOnly(log) => *log,
+        }
+    }
+
+    /// The event being processed, for modification.
+    pub fn log_mut(&mut self) -> Result<&mut LogEvent, RuntimeError> {
+        match &mut self.target {
+            Target::Mutable(log) => Ok(&mut **log),
+            Target::ReadOnly(_) => Err("the event can't be modified here".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// A compiled expression.
+pub enum Expr {
+    Literal(Value),
+    Path(Vec<PathComponent>),
+    Variable(usize, Kind),
+    Array(Vec<Expr>),
+    Map(Vec<(String, Expr)>),
+    Not(Box<Expr>, Location),
+    Negate(Box<Expr>, Location),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        location: Location,
+    },
+    Coalesce(Box<Expr>, Box<Expr>),
+    Call {
+        call: Box<dyn Call>,
+        kind: Kind,
+        location: Location,
+    },
+    If {
+        condition: Box<Expr>,
+        consequent: Vec<Expr>,
+        alternative: Option<Vec<Expr>>,
+        location: Location,
+    },
+    AssignPath(Vec<PathComponent>, Box<Expr>),
+    AssignVariable(usize, Box<Expr>),
+}
+
+impl Expr {
+    /// The kinds the expression may resolve to.
+    pub fn kind(&self) -> Kind {
+        match self {
+            Expr::Literal(value) => Kind::of(value),
+            Expr::Path(_) => Kind::ANY,
+            Expr::Variable(_, kind) => *kind,
+            Expr::Array(_) => Kind::ARRAY,
+            Expr::Map(_) => Kind::MAP,
+            Expr::Not(..) => Kind::BOOLEAN,
+            Expr::Negate(expr, _) => expr.kind(),
+            Expr::Binary { op, lhs, rhs, .. } => binary_kind(*op, lhs.kind(), rhs.kind()),
+            Expr::Coalesce(lhs, rhs) => lhs.kind() | rhs.kind(),
+            Expr::Call { kind, .. } => *kind,
+            Expr::If {
+                consequent,
+                alternative,
+                ..
+            } => {
+                let alternative = alternative
+                    .as_ref()
+                    .map(|block| block_kind(block))
+                    .unwrap_or(Kind::NULL);
+                block_kind(consequent) | alternative
+            }
+            Expr::AssignPath(_, value) | Expr::AssignVariable(_, value) => value.kind(),
+        }
+    }
+
+    /// Whether the expression is a plain path.
+    pub fn as_path(&self) -> Option<&[PathComponent]> {
+        match self {
+            Expr::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The value of the expression, if it's a literal.
+    pub fn as_literal(&self) -> Option<&Value> {
+        match self {
+            Expr::Literal(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Evaluate the expression.
+    pub fn eval(&self, ctx: &mut Context) -> Result<Value, RuntimeError> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Path(path) => Ok(ctx.log().get_path(path).cloned().unwrap_or(Value::Null)),
+            Expr::Variable(index, _) => Ok(ctx.variables[*index].clone()),
+            Expr::Array(items) => items
+                .iter()
+                .map(|item| item.eval(ctx))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            Expr::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), value.eval(ctx)?)))
+                .collect::<Result<_, _>>()
+                .map(Value::Map),
+            Expr::Not(expr, location) => match expr.eval(ctx)? {
+                Value::Boolean(value) => Ok(Value::Boolean(!value)),
+                value => Err(RuntimeError::new(
+                    format!("can't negate {}", Kind::of(&value)),
+                    *location,
+                )),
+            },
+            Expr::Negate(expr, location) => match expr.eval(ctx)? {
+                Value::Integer(value) => value
+                    .checked_neg()
+                    .map(Value::Integer)
+                    .ok_or_else(|| RuntimeError::new("integer overflow", *location)),
+                Value::Float(value) => Ok(Value::Float(-value)),
+                value => Err(RuntimeError::new(
+                    format!("can't negate {}", Kind::of(&value)),
+                    *location,
+                )),
+            },
+            Expr::Binary {
+                op,
+                lhs,
+                rhs,
+                location,
+            } => eval_binary(*op, lhs, rhs, *location, ctx),
+            Expr::Coalesce(lhs, rhs) => match lhs.eval(ctx) {
+                Ok(Value::Null) | Err(_) => rhs.eval(ctx),
+                Ok(value) => Ok(value),
+            },
+            Expr::Call { call, location, .. } => {
+                call.call(ctx).map_err(|error| error.or_location(*location))
+            }
+            Expr::If {
+                condition,
+                consequent,
+                alternative,
+                location,
+            } => match condition.eval(ctx)? {
+                Value::Boolean(true) => eval_block(consequent, ctx),
+                Value::Boolean(false) => match alternative {
+                    Some(alternative) => eval_block(alternative, ctx),
+                    None => Ok(Value::Null),
+                },
+                value => Err(RuntimeError::new(
+                    format!("expected boolean condition, got {}", Kind::of(&value)),
+                    *location,
+                )),
+            },
+            Expr::AssignPath(path, value) => {
+                let value = value.eval(ctx)?;
+                ctx.log_mut()?.insert_path(path.clone(), value.clone());
+                Ok(value)
+            }
+            Expr::AssignVariable(index, value) => {
+                let value = value.eval(ctx)?;
+                ctx.variables[*index] = value.clone();
+                Ok(value)
+            }
+        }
+    }
+}
+
+fn eval_block(block: &[Expr], ctx: &mut Context) -> Result<Value, RuntimeError> {
+    let mut result = Value::Null;
+    for expr in block {
+        result = expr.eval(ctx)?;
+    }
+    Ok(result)
+}
+
+fn block_kind(block: &[Expr]) -> Kind {
+    block.last().map(Expr::kind).unwrap_or(Kind::NULL)
+}
+
+/// The kinds of the binary operation result, given the kinds of the operands.
+pub fn binary_kind(op: BinaryOp, lhs: Kind, rhs: Kind) -> Kind {
+    match op {
+        BinaryOp::Or
+        | BinaryOp::And
+        | BinaryOp::Eq
+        | BinaryOp::Ne
+        | BinaryOp::Lt
+        | BinaryOp::Le
+        | BinaryOp::Gt
+        | BinaryOp::Ge => Kind::BOOLEAN,
+        BinaryOp::Div => Kind::FLOAT,
+        BinaryOp::Add if lhs == Kind::BYTES || rhs == Kind::BYTES => Kind::BYTES,
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Rem => {
+            if lhs == Kind::INTEGER && rhs == Kind::INTEGER {
+                Kind::INTEGER
+            } else if lhs == Kind::FLOAT || rhs == Kind::FLOAT {
+                Kind::FLOAT
+            } else if op == BinaryOp::Add {
+                Kind::NUMBER | Kind::BYTES
+            } else {
+                Kind::NUMBER
+            }
+        }
+    }
+}
+
+/// Check whether the binary operation can succeed for the operands of the
+/// given kinds. Returns the error message if it can never succeed.
+pub fn check_binary(op: BinaryOp, lhs: Kind, rhs: Kind) -> Result<(), String> {
+    let accepted = match op {
+        BinaryOp::Or | BinaryOp::And => Kind::BOOLEAN,
+        BinaryOp::Eq | BinaryOp::Ne => Kind::ANY,
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            Kind::NUMBER | Kind::BYTES | Kind::TIMESTAMP
+        }
+        BinaryOp::Add => Kind::NUMBER | Kind::BYTES,
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => Kind::NUMBER,
+    };
+    for kind in &[lhs, rhs] {
+        if !kind.intersects(accepted) {
+            return Err(format!("expected {}, got {}", accepted, kind));
+        }
+    }
+
+    // Both operands are known exactly, so we can check them together.
+    if lhs.is_exact() && rhs.is_exact() {
+        let lhs = sample(lhs);
+        let rhs = sample(rhs);
+        if let Some(lhs) = lhs {
+            if let Some(rhs) = rhs {
+                apply_binary(op, lhs, rhs)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A representative value of an exact kind, used for compile time checks.
+fn sample(kind: Kind) -> Option<Value> {
+    Some(match kind {
+        Kind::BYTES => Value::from("a"),
+        Kind::INTEGER => Value::Integer(1),
+        Kind::FLOAT => Value::Float(1.0),
+        Kind::BOOLEAN => Value::Boolean(true),
+        Kind::TIMESTAMP => Value::Timestamp(chrono::Utc::now()),
+        Kind::NULL => Value::Null,
+        _ => return None,
+    })
+}
+
+fn eval_binary(
+    op: BinaryOp,
+    lhs: &Expr,
+    rhs: &Expr,
+    location: Location,
+    ctx: &mut Context,
+) -> Result<Value, RuntimeError> {
+    let as_bool = |value: Value, side: &str| match value {
+        Value::Boolean(value) => Ok(value),
+        value => Err(RuntimeError::new(
+            format!(
+                "expected boolean on the {} side, got {}",
+                side,
+                Kind::of(&value)
+            ),
+            location,
+        )),
+    };
+
+    // Logical operators short-circuit.
+    match op {
+        BinaryOp::Or => {
+            if as_bool(lhs.eval(ctx)?, "left")? {
+                return Ok(Value::Boolean(true));
+            }
+            Ok(Value::Boolean(as_bool(rhs.eval(ctx)?, "right")?))
+        }
+        BinaryOp::And => {
+            if !as_bool(lhs.eval(ctx)?, "left")? {
+                return Ok(Value::Boolean(false));
+            }
+            Ok(Value::Boolean(as_bool(rhs.eval(ctx)?, "right")?))
+        }
+        _ => {
+            let lhs = lhs.eval(ctx)?;
+            let rhs = rhs.eval(ctx)?;
+            apply_binary(op, lhs, rhs).map_err(|message| RuntimeError::new(message, location))
+        }
+    }
+}
+
+fn apply_binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, String> {
+    use Value::*;
+
+    let verb = match op {
+        BinaryOp::Add => "add",
+        BinaryOp::Sub => "subtract",
+        BinaryOp::Mul => "multiply",
+        BinaryOp::Div => "divide",
+        BinaryOp::Rem => "take the remainder of",
+        _ => "compare",
+    };
+    let mismatch = |lhs: &Value, rhs: &Value| match op {
+        BinaryOp::Add => format!("can't add {} to {}", Kind::of(rhs), Kind::of(lhs)),
+        BinaryOp::Sub => format!("can't subtract {} from {}", Kind::of(rhs), Kind::of(lhs)),
+        _ => format!("can't {} {} and {}", verb, Kind::of(lhs), Kind::of(rhs)),
+    };
+
+    Ok(match op {
+        BinaryOp::Eq => Boolean(lhs == rhs),
+        BinaryOp::Ne => Boolean(lhs != rhs),
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (&lhs, &rhs) {
+                (Integer(a), Integer(b)) => a.partial_cmp(b),
+                (Integer(a), Float(b)) => (*a as f64).partial_cmp(b),
+                (Float(a), Integer(b)) => a.partial_cmp(&(*b as f64)),
+                (Float(a), Float(b)) => a.partial_cmp(b),
+                (Bytes(a), Bytes(b)) => a.partial_cmp(b),
+                (Timestamp(a), Timestamp(b)) => a.partial_cmp(b),
+                _ => return Err(mismatch(&lhs, &rhs)),
+            };
+            let ordering = ordering.ok_or_else(|| mismatch(&lhs, &rhs))?;
+            Boolean(match op {
+                BinaryOp::Lt => ordering == Ordering::Less,
+                BinaryOp::Le => ordering != Ordering::Greater,
+                BinaryOp::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            })
+        }
+        BinaryOp::Add => match (lhs, rhs) {
+            (Bytes(a), Bytes(b)) => {
+                let mut bytes = BytesMut::with_capacity(a.len() + b.len());
+                bytes.put(a);
+                bytes.put(b);
+                Bytes(bytes.freeze())
+            }
+            (Integer(a), Integer(b)) => Integer(
+                a.checked_add(b)
+                    .ok_or_else(|| "integer overflow".to_owned())?,
+            ),
+            (lhs, rhs) => float_op(&lhs, &rhs, |a, b| a + b).ok_or_else(|| mismatch(&lhs, &rhs))?,
+        },
+        BinaryOp::Sub => match (lhs, rhs) {
+            (Integer(a), Integer(b)) => Integer(
+                a.checked_sub(b)
+                    .ok_or_else(|| "integer overflow".to_owned())?,
+            ),
+            (lhs, rhs) => float_op(&lhs, &rhs, |a, b| a - b).ok_or_else(|| mismatch(&lhs, &rhs))?,
+        },
+        BinaryOp::Mul => match (lhs, rhs) {
+            (Integer(a), Integer(b)) => Integer(
+                a.checked_mul(b)
+                    .ok_or_else(|| "integer overflow".to_owned())?,
+            ),
+            (lhs, rhs) => float_op(&lhs, &rhs, |a, b| a * b).ok_or_else(|| mismatch(&lhs, &rhs))?,
+        },
+        BinaryOp::Div => {
+            let value = float_op(&lhs, &rhs, |a, b| a / b).ok_or_else(|| mismatch(&lhs, &rhs))?;
+            if rhs == Integer(0) || rhs == Float(0.0) {
+                return Err("division by zero".to_owned());
+            }
+            value
+        }
+        BinaryOp::Rem => match (lhs, rhs) {
+            (Integer(_), Integer(0)) => return Err("division by zero".to_owned()),
+            (Integer(a), Integer(b)) => Integer(
+                a.checked_rem(b)
+                    .ok_or_else(|| "integer overflow".to_owned())?,
+            ),
+            (lhs, rhs) => float_op(&lhs, &rhs, |a, b| a % b).ok_or_else(|| mismatch(&lhs, &rhs))?,
+        },
+        BinaryOp::Or | BinaryOp::And => unreachable!("logical operators are evaluated lazily"),
+    })
+}
+
+fn float_op(lhs: &Value, rhs: &Value, f: impl Fn(f64, f64) -> f64) -> Option<Value> {
+    let as_float = |value: &Value| match value {
+        Value::Integer(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        _ => None,
+    };
+    Some(Value::Float(f(as_float(lhs)?, as_float(rhs)?)))
+}
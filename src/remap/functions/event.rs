@@ -0,0 +1,82 @@
+use super::{Arguments, Call, Function, FunctionError, Parameter};
+use crate::{
+    event::{PathComponent, Value},
+    remap::{expression::Context, Kind, RuntimeError},
+};
+
+const PATH: &[Parameter] = &[Parameter {
+    keyword: "path",
+    kind: Kind::ANY,
+    required: true,
+}];
+
+pub struct Del;
+
+impl Function for Del {
+    fn identifier(&self) -> &'static str {
+        "del"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        PATH
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::ANY
+    }
+
+    fn modifies_event(&self) -> bool {
+        true
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Ok(Box::new(DelCall {
+            path: arguments.required_path("path")?,
+        }))
+    }
+}
+
+struct DelCall {
+    path: Vec<PathComponent>,
+}
+
+impl Call for DelCall {
+    fn call(&self, ctx: &mut Context) -> Result<Value, RuntimeError> {
+        Ok(ctx
+            .log_mut()?
+            .remove_path(&self.path, false)
+            .unwrap_or(Value::Null))
+    }
+}
+
+pub struct Exists;
+
+impl Function for Exists {
+    fn identifier(&self) -> &'static str {
+        "exists"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        PATH
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BOOLEAN
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Ok(Box::new(ExistsCall {
+            path: arguments.required_path("path")?,
+        }))
+    }
+}
+
+struct ExistsCall {
+    path: Vec<PathComponent>,
+}
+
+impl Call for ExistsCall {
+    fn call(&self, ctx: &mut Context) -> Result<Value, RuntimeError> {
+        Ok(Value::Boolean(ctx.log().get_path(&self.path).is_some()))
+    }
+}
@@ -0,0 +1,255 @@
+//! The functions available to the programs.
+//!
+//! | Category | Functions |
+//! |----------|-----------|
+//! | String   | `upcase`, `downcase`, `strip_whitespace`, `truncate`, `contains`, `starts_with`, `ends_with`, `replace`, `slice`, `split`, `length`, `to_string` |
+//! | Number   | `to_int`, `to_float`, `to_bool`, `round`, `ceil`, `floor`, `abs` |
+//! | Time     | `now`, `to_timestamp`, `parse_timestamp`, `format_timestamp` |
+//! | Parsing  | `parse_json`, `parse_logfmt`, `parse_regex`, `parse_grok` |
+//! | Event    | `del`, `exists` |
+
+use super::{
+    error::{RuntimeError, Span},
+    expression::{Context, Expr},
+    kind::Kind,
+};
+use crate::event::{PathComponent, Value};
+use chrono::{DateTime, Utc};
+
+mod event;
+mod number;
+mod parse;
+mod string;
+mod time;
+
+/// A function definition.
+pub trait Function: Sync {
+    /// The name the function is called by.
+    fn identifier(&self) -> &'static str;
+
+    /// The parameters the function accepts, in positional order.
+    fn parameters(&self) -> &'static [Parameter];
+
+    /// The kinds of the values the function may return.
+    fn return_kind(&self) -> Kind;
+
+    /// Whether the function modifies the event, which isn't allowed in
+    /// conditions.
+    fn modifies_event(&self) -> bool {
+        false
+    }
+
+    /// Prepare the function call with the given arguments.
+    ///
+    /// The arguments are already checked against the [`Parameter`]
+    /// requirements, so this is where the function-specific validation and
+    /// pre-computation (i.e. compiling regular expressions) takes place.
+    fn compile(&self, arguments: Arguments) -> Result<Box<dyn Call>, FunctionError>;
+}
+
+/// A prepared function call.
+pub trait Call: Send + Sync {
+    /// Evaluate the call.
+    fn call(&self, ctx: &mut Context) -> Result<Value, RuntimeError>;
+}
+
+/// A function parameter.
+#[derive(Debug, Clone, Copy)]
+pub struct Parameter {
+    /// The name of the parameter, for passing the argument by name.
+    pub keyword: &'static str,
+    /// The kinds of the values the parameter accepts.
+    pub kind: Kind,
+    /// Whether the argument must be passed.
+    pub required: bool,
+}
+
+/// An error returned by [`Function::compile`].
+#[derive(Debug)]
+pub struct FunctionError {
+    /// What went wrong.
+    pub message: String,
+    /// The span of the offending argument, if any.
+    pub span: Option<Span>,
+}
+
+impl FunctionError {
+    fn at(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+}
+
+/// The arguments of a function call, ordered by the function parameters.
+pub struct Arguments {
+    parameters: &'static [Parameter],
+    values: Vec<Option<(Expr, Span)>>,
+}
+
+impl Arguments {
+    pub(super) fn new(parameters: &'static [Parameter], values: Vec<Option<(Expr, Span)>>) -> Self {
+        Self { parameters, values }
+    }
+
+    fn position(&self, keyword: &str) -> usize {
+        self.parameters
+            .iter()
+            .position(|parameter| parameter.keyword == keyword)
+            .unwrap_or_else(|| panic!("unknown parameter `{}`", keyword))
+    }
+
+    /// Take an optional argument.
+    pub fn optional(&mut self, keyword: &str) -> Option<Expr> {
+        self.optional_with_span(keyword).map(|(expr, _)| expr)
+    }
+
+    fn optional_with_span(&mut self, keyword: &str) -> Option<(Expr, Span)> {
+        let position = self.position(keyword);
+        self.values[position].take()
+    }
+
+    /// Take a required argument.
+    pub fn required(&mut self, keyword: &str) -> Expr {
+        self.optional(keyword)
+            .unwrap_or_else(|| panic!("required argument `{}` is checked by the parser", keyword))
+    }
+
+    /// Take a required argument that has to be a string literal.
+    pub fn required_literal_string(
+        &mut self,
+        keyword: &str,
+    ) -> Result<(String, Span), FunctionError> {
+        let (expr, span) = self
+            .optional_with_span(keyword)
+            .unwrap_or_else(|| panic!("required argument `{}` is checked by the parser", keyword));
+        match expr.as_literal() {
+            Some(Value::Bytes(bytes)) => Ok((String::from_utf8_lossy(bytes).into_owned(), span)),
+            _ => Err(FunctionError::at(
+                format!("`{}` must be a string literal", keyword),
+                span,
+            )),
+        }
+    }
+
+    /// Take a required argument that has to be a path.
+    pub fn required_path(&mut self, keyword: &str) -> Result<Vec<PathComponent>, FunctionError> {
+        let (expr, span) = self
+            .optional_with_span(keyword)
+            .unwrap_or_else(|| panic!("required argument `{}` is checked by the parser", keyword));
+        match expr.as_path() {
+            Some(path) => Ok(path.to_vec()),
+            None => Err(FunctionError::at("expected a path", span)),
+        }
+    }
+}
+
+/// All the available functions.
+static FUNCTIONS: &[&dyn Function] = &[
+    &string::Upcase,
+    &string::Downcase,
+    &string::StripWhitespace,
+    &string::Truncate,
+    &string::Contains,
+    &string::StartsWith,
+    &string::EndsWith,
+    &string::Replace,
+    &string::Slice,
+    &string::Split,
+    &string::Length,
+    &string::ToString,
+    &number::ToInt,
+    &number::ToFloat,
+    &number::ToBool,
+    &number::Round,
+    &number::Ceil,
+    &number::Floor,
+    &number::Abs,
+    &time::Now,
+    &time::ToTimestamp,
+    &time::ParseTimestamp,
+    &time::FormatTimestamp,
+    &parse::ParseJson,
+    &parse::ParseLogfmt,
+    &parse::ParseRegex,
+    &parse::ParseGrok,
+    &event::Del,
+    &event::Exists,
+];
+
+/// Look up a function by its identifier.
+pub fn find(identifier: &str) -> Option<&'static dyn Function> {
+    FUNCTIONS
+        .iter()
+        .find(|function| function.identifier() == identifier)
+        .copied()
+}
+
+/// A [`Call`] that evaluates all the arguments and applies `F` to the
+/// resulting values. Suitable for the majority of the functions.
+struct Apply<F> {
+    arguments: Vec<Option<Expr>>,
+    f: F,
+}
+
+impl<F> Apply<F>
+where
+    F: Fn(Vec<Option<Value>>) -> Result<Value, String> + Send + Sync + 'static,
+{
+    fn boxed(arguments: Vec<Option<Expr>>, f: F) -> Result<Box<dyn Call>, FunctionError> {
+        Ok(Box::new(Self { arguments, f }))
+    }
+}
+
+impl<F> Call for Apply<F>
+where
+    F: Fn(Vec<Option<Value>>) -> Result<Value, String> + Send + Sync,
+{
+    fn call(&self, ctx: &mut Context) -> Result<Value, RuntimeError> {
+        let values = self
+            .arguments
+            .iter()
+            .map(|argument| argument.as_ref().map(|expr| expr.eval(ctx)).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        (self.f)(values).map_err(Into::into)
+    }
+}
+
+fn unexpected(expected: Kind, value: &Value) -> String {
+    format!("expected {}, got {}", expected, Kind::of(value))
+}
+
+fn string(value: Value) -> Result<String, String> {
+    match value {
+        Value::Bytes(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        value => Err(unexpected(Kind::BYTES, &value)),
+    }
+}
+
+fn integer(value: Value) -> Result<i64, String> {
+    match value {
+        Value::Integer(value) => Ok(value),
+        value => Err(unexpected(Kind::INTEGER, &value)),
+    }
+}
+
+fn boolean(value: Value) -> Result<bool, String> {
+    match value {
+        Value::Boolean(value) => Ok(value),
+        value => Err(unexpected(Kind::BOOLEAN, &value)),
+    }
+}
+
+fn timestamp(value: Value) -> Result<DateTime<Utc>, String> {
+    match value {
+        Value::Timestamp(value) => Ok(value),
+        value => Err(unexpected(Kind::TIMESTAMP, &value)),
+    }
+}
+
+/// Take the next argument value. Arguments are consumed in the parameter
+/// order.
+fn next(values: &mut std::vec::IntoIter<Option<Value>>) -> Option<Value> {
+    values.next().flatten()
+}
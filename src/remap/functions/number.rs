@@ -0,0 +1,265 @@
+use super::{
+    integer, next, unexpected, Apply, Arguments, Call, Function, FunctionError, Parameter,
+};
+use crate::{event::Value, remap::Kind, types::Conversion};
+
+const ANY_VALUE: Parameter = Parameter {
+    keyword: "value",
+    kind: Kind::ANY,
+    required: true,
+};
+
+const NUMBER_VALUE: &[Parameter] = &[Parameter {
+    keyword: "value",
+    kind: Kind::NUMBER,
+    required: true,
+}];
+
+/// Convert the string `value` with the given [`Conversion`], the same way the
+/// `coercer` transform does.
+fn convert(conversion: Conversion, value: Value) -> Result<Value, String> {
+    conversion.convert(value).map_err(|error| error.to_string())
+}
+
+/// Convert a float into an integer, towards zero. Casting a float that doesn't
+/// fit into the integer type is undefined behaviour, so the range is checked first.
+fn float_to_integer(value: f64) -> Result<Value, String> {
+    let value = value.trunc();
+    if value >= i64::MIN as f64 && value < i64::MAX as f64 {
+        Ok(Value::Integer(value as i64))
+    } else {
+        Err(format!("{} is out of the range of integers", value))
+    }
+}
+
+pub struct ToInt;
+
+impl Function for ToInt {
+    fn identifier(&self) -> &'static str {
+        "to_int"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[ANY_VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::INTEGER
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+                value @ Value::Integer(_) => Ok(value),
+                Value::Float(value) => float_to_integer(value),
+                Value::Boolean(value) => Ok(Value::Integer(value as i64)),
+                Value::Timestamp(value) => Ok(Value::Integer(value.timestamp())),
+                value @ Value::Bytes(_) => convert(Conversion::Integer, value),
+                value => Err(unexpected(
+                    Kind::BYTES | Kind::NUMBER | Kind::BOOLEAN | Kind::TIMESTAMP,
+                    &value,
+                )),
+            },
+        )
+    }
+}
+
+pub struct ToFloat;
+
+impl Function for ToFloat {
+    fn identifier(&self) -> &'static str {
+        "to_float"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[ANY_VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::FLOAT
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+                value @ Value::Float(_) => Ok(value),
+                Value::Integer(value) => Ok(Value::Float(value as f64)),
+                Value::Boolean(value) => Ok(Value::Float(if value { 1.0 } else { 0.0 })),
+                Value::Timestamp(value) => Ok(Value::Float(
+                    value.timestamp() as f64 + f64::from(value.timestamp_subsec_nanos()) / 1e9,
+                )),
+                value @ Value::Bytes(_) => convert(Conversion::Float, value),
+                value => Err(unexpected(
+                    Kind::BYTES | Kind::NUMBER | Kind::BOOLEAN | Kind::TIMESTAMP,
+                    &value,
+                )),
+            },
+        )
+    }
+}
+
+pub struct ToBool;
+
+impl Function for ToBool {
+    fn identifier(&self) -> &'static str {
+        "to_bool"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[ANY_VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BOOLEAN
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+                value @ Value::Boolean(_) => Ok(value),
+                Value::Integer(value) => Ok(Value::Boolean(value != 0)),
+                Value::Float(value) => Ok(Value::Boolean(value != 0.0)),
+                Value::Null => Ok(Value::Boolean(false)),
+                value @ Value::Bytes(_) => convert(Conversion::Boolean, value),
+                value => Err(unexpected(
+                    Kind::BYTES | Kind::NUMBER | Kind::BOOLEAN | Kind::NULL,
+                    &value,
+                )),
+            },
+        )
+    }
+}
+
+pub struct Round;
+
+impl Function for Round {
+    fn identifier(&self) -> &'static str {
+        "round"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[
+            Parameter {
+                keyword: "value",
+                kind: Kind::NUMBER,
+                required: true,
+            },
+            Parameter {
+                keyword: "precision",
+                kind: Kind::INTEGER,
+                required: false,
+            },
+        ]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::NUMBER
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let args = vec![
+            Some(arguments.required("value")),
+            arguments.optional("precision"),
+        ];
+        Apply::boxed(args, |values| {
+            let mut values = values.into_iter();
+            let value = next(&mut values).unwrap_or(Value::Null);
+            let precision = next(&mut values).map(integer).transpose()?.unwrap_or(0);
+
+            match value {
+                value @ Value::Integer(_) => Ok(value),
+                Value::Float(value) if precision <= 0 => float_to_integer(value.round()),
+                Value::Float(value) => {
+                    let multiplier = 10_f64.powi(precision.min(i32::MAX as i64) as i32);
+                    Ok(Value::Float((value * multiplier).round() / multiplier))
+                }
+                value => Err(unexpected(Kind::NUMBER, &value)),
+            }
+        })
+    }
+}
+
+/// Shared implementation of the integer rounding functions.
+fn rounding(mut arguments: Arguments, f: fn(f64) -> f64) -> Result<Box<dyn Call>, FunctionError> {
+    Apply::boxed(
+        vec![Some(arguments.required("value"))],
+        move |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+            value @ Value::Integer(_) => Ok(value),
+            Value::Float(value) => float_to_integer(f(value)),
+            value => Err(unexpected(Kind::NUMBER, &value)),
+        },
+    )
+}
+
+pub struct Ceil;
+
+impl Function for Ceil {
+    fn identifier(&self) -> &'static str {
+        "ceil"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        NUMBER_VALUE
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::INTEGER
+    }
+
+    fn compile(&self, arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        rounding(arguments, f64::ceil)
+    }
+}
+
+pub struct Floor;
+
+impl Function for Floor {
+    fn identifier(&self) -> &'static str {
+        "floor"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        NUMBER_VALUE
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::INTEGER
+    }
+
+    fn compile(&self, arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        rounding(arguments, f64::floor)
+    }
+}
+
+pub struct Abs;
+
+impl Function for Abs {
+    fn identifier(&self) -> &'static str {
+        "abs"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        NUMBER_VALUE
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::NUMBER
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+                Value::Integer(value) => value
+                    .checked_abs()
+                    .map(Value::Integer)
+                    .ok_or_else(|| "integer overflow".to_owned()),
+                Value::Float(value) => Ok(Value::Float(value.abs())),
+                value => Err(unexpected(Kind::NUMBER, &value)),
+            },
+        )
+    }
+}
@@ -0,0 +1,156 @@
+use super::{next, string, unexpected, Apply, Arguments, Call, Function, FunctionError, Parameter};
+use crate::{event::Value, remap::Kind};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+const VALUE: Parameter = Parameter {
+    keyword: "value",
+    kind: Kind::BYTES,
+    required: true,
+};
+
+const PATTERN_PARAMETERS: &[Parameter] = &[
+    VALUE,
+    Parameter {
+        keyword: "pattern",
+        kind: Kind::BYTES,
+        required: true,
+    },
+];
+
+pub struct ParseJson;
+
+impl Function for ParseJson {
+    fn identifier(&self) -> &'static str {
+        "parse_json"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::ANY
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+                Value::Bytes(bytes) => serde_json::from_slice::<serde_json::Value>(&bytes)
+                    .map(Value::from)
+                    .map_err(|error| format!("unable to parse json: {}", error)),
+                value => Err(unexpected(Kind::BYTES, &value)),
+            },
+        )
+    }
+}
+
+pub struct ParseLogfmt;
+
+impl Function for ParseLogfmt {
+    fn identifier(&self) -> &'static str {
+        "parse_logfmt"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::MAP
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(vec![Some(arguments.required("value"))], |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            // Same as the `logfmt_parser` transform: the keys without values
+            // are not logfmt data, so we skip them.
+            let map = logfmt::parse(&value)
+                .into_iter()
+                .filter_map(|logfmt::Pair { key, val }| val.map(|val| (key, Value::from(val))))
+                .collect::<BTreeMap<_, _>>();
+            Ok(Value::Map(map))
+        })
+    }
+}
+
+pub struct ParseRegex;
+
+impl Function for ParseRegex {
+    fn identifier(&self) -> &'static str {
+        "parse_regex"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        PATTERN_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::MAP
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let value = arguments.required("value");
+        let (pattern, span) = arguments.required_literal_string("pattern")?;
+        let regex = Regex::new(&pattern).map_err(|error| {
+            FunctionError::at(format!("invalid regular expression: {}", error), span)
+        })?;
+
+        Apply::boxed(vec![Some(value)], move |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            let captures = regex
+                .captures(&value)
+                .ok_or_else(|| "value did not match the pattern".to_owned())?;
+
+            // Only the named groups end up in the result, just like with the
+            // `regex_parser` transform.
+            let map = regex
+                .capture_names()
+                .filter_map(|name| name)
+                .filter_map(|name| {
+                    captures
+                        .name(name)
+                        .map(|capture| (name.to_owned(), Value::from(capture.as_str())))
+                })
+                .collect::<BTreeMap<_, _>>();
+            Ok(Value::Map(map))
+        })
+    }
+}
+
+pub struct ParseGrok;
+
+impl Function for ParseGrok {
+    fn identifier(&self) -> &'static str {
+        "parse_grok"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        PATTERN_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::MAP
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let value = arguments.required("value");
+        let (pattern, span) = arguments.required_literal_string("pattern")?;
+        let pattern = grok::Grok::with_patterns()
+            .compile(&pattern, true)
+            .map_err(|error| FunctionError::at(format!("invalid grok pattern: {}", error), span))?;
+
+        Apply::boxed(vec![Some(value)], move |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            let matches = pattern
+                .match_against(&value)
+                .ok_or_else(|| "value did not match the pattern".to_owned())?;
+            let map = matches
+                .iter()
+                .map(|(name, value)| (name.to_owned(), Value::from(value)))
+                .collect::<BTreeMap<_, _>>();
+            Ok(Value::Map(map))
+        })
+    }
+}
@@ -0,0 +1,486 @@
+use super::{
+    boolean, integer, next, string, unexpected, Apply, Arguments, Call, Function, FunctionError,
+    Parameter,
+};
+use crate::{event::Value, remap::Kind};
+
+const VALUE: Parameter = Parameter {
+    keyword: "value",
+    kind: Kind::BYTES,
+    required: true,
+};
+
+pub struct Upcase;
+
+impl Function for Upcase {
+    fn identifier(&self) -> &'static str {
+        "upcase"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(vec![Some(arguments.required("value"))], |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            Ok(value.to_uppercase().into())
+        })
+    }
+}
+
+pub struct Downcase;
+
+impl Function for Downcase {
+    fn identifier(&self) -> &'static str {
+        "downcase"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(vec![Some(arguments.required("value"))], |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            Ok(value.to_lowercase().into())
+        })
+    }
+}
+
+pub struct StripWhitespace;
+
+impl Function for StripWhitespace {
+    fn identifier(&self) -> &'static str {
+        "strip_whitespace"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[VALUE]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(vec![Some(arguments.required("value"))], |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            Ok(value.trim().into())
+        })
+    }
+}
+
+pub struct Truncate;
+
+impl Function for Truncate {
+    fn identifier(&self) -> &'static str {
+        "truncate"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[
+            VALUE,
+            Parameter {
+                keyword: "limit",
+                kind: Kind::INTEGER,
+                required: true,
+            },
+            Parameter {
+                keyword: "ellipsis",
+                kind: Kind::BOOLEAN,
+                required: false,
+            },
+        ]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let args = vec![
+            Some(arguments.required("value")),
+            Some(arguments.required("limit")),
+            arguments.optional("ellipsis"),
+        ];
+        Apply::boxed(args, |values| {
+            let mut values = values.into_iter();
+            let value = string(next(&mut values).unwrap_or(Value::Null))?;
+            let limit = integer(next(&mut values).unwrap_or(Value::Null))?;
+            let ellipsis = next(&mut values).map(boolean).transpose()?.unwrap_or(false);
+
+            if limit < 0 {
+                return Err("`limit` must not be negative".to_owned());
+            }
+            let limit = limit as usize;
+            if value.chars().count() <= limit {
+                return Ok(value.into());
+            }
+            let mut truncated: String = value.chars().take(limit).collect();
+            if ellipsis {
+                truncated.push_str("...");
+            }
+            Ok(truncated.into())
+        })
+    }
+}
+
+/// Shared implementation of the substring tests.
+fn substring_test(
+    mut arguments: Arguments,
+    test: fn(&str, &str) -> bool,
+) -> Result<Box<dyn Call>, FunctionError> {
+    let args = vec![
+        Some(arguments.required("value")),
+        Some(arguments.required("substring")),
+        arguments.optional("case_sensitive"),
+    ];
+    Apply::boxed(args, move |values| {
+        let mut values = values.into_iter();
+        let value = string(next(&mut values).unwrap_or(Value::Null))?;
+        let substring = string(next(&mut values).unwrap_or(Value::Null))?;
+        let case_sensitive = next(&mut values).map(boolean).transpose()?.unwrap_or(true);
+
+        Ok(if case_sensitive {
+            test(&value, &substring)
+        } else {
+            test(&value.to_lowercase(), &substring.to_lowercase())
+        }
+        .into())
+    })
+}
+
+const SUBSTRING_PARAMETERS: &[Parameter] = &[
+    VALUE,
+    Parameter {
+        keyword: "substring",
+        kind: Kind::BYTES,
+        required: true,
+    },
+    Parameter {
+        keyword: "case_sensitive",
+        kind: Kind::BOOLEAN,
+        required: false,
+    },
+];
+
+pub struct Contains;
+
+impl Function for Contains {
+    fn identifier(&self) -> &'static str {
+        "contains"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        SUBSTRING_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BOOLEAN
+    }
+
+    fn compile(&self, arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        substring_test(arguments, |value, substring| value.contains(substring))
+    }
+}
+
+pub struct StartsWith;
+
+impl Function for StartsWith {
+    fn identifier(&self) -> &'static str {
+        "starts_with"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        SUBSTRING_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BOOLEAN
+    }
+
+    fn compile(&self, arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        substring_test(arguments, |value, substring| value.starts_with(substring))
+    }
+}
+
+pub struct EndsWith;
+
+impl Function for EndsWith {
+    fn identifier(&self) -> &'static str {
+        "ends_with"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        SUBSTRING_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BOOLEAN
+    }
+
+    fn compile(&self, arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        substring_test(arguments, |value, substring| value.ends_with(substring))
+    }
+}
+
+pub struct Replace;
+
+impl Function for Replace {
+    fn identifier(&self) -> &'static str {
+        "replace"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[
+            VALUE,
+            Parameter {
+                keyword: "pattern",
+                kind: Kind::BYTES,
+                required: true,
+            },
+            Parameter {
+                keyword: "with",
+                kind: Kind::BYTES,
+                required: true,
+            },
+            Parameter {
+                keyword: "count",
+                kind: Kind::INTEGER,
+                required: false,
+            },
+        ]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let args = vec![
+            Some(arguments.required("value")),
+            Some(arguments.required("pattern")),
+            Some(arguments.required("with")),
+            arguments.optional("count"),
+        ];
+        Apply::boxed(args, |values| {
+            let mut values = values.into_iter();
+            let value = string(next(&mut values).unwrap_or(Value::Null))?;
+            let pattern = string(next(&mut values).unwrap_or(Value::Null))?;
+            let with = string(next(&mut values).unwrap_or(Value::Null))?;
+            let count = next(&mut values).map(integer).transpose()?;
+
+            Ok(match count {
+                Some(count) if count >= 0 => value.replacen(&pattern, &with, count as usize),
+                _ => value.replace(&pattern, &with),
+            }
+            .into())
+        })
+    }
+}
+
+const SLICE_PARAMETERS: &[Parameter] = &[
+    Parameter {
+        keyword: "value",
+        kind: Kind::BYTES.or(Kind::ARRAY),
+        required: true,
+    },
+    Parameter {
+        keyword: "start",
+        kind: Kind::INTEGER,
+        required: true,
+    },
+    Parameter {
+        keyword: "end",
+        kind: Kind::INTEGER,
+        required: false,
+    },
+];
+
+pub struct Slice;
+
+impl Function for Slice {
+    fn identifier(&self) -> &'static str {
+        "slice"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        SLICE_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES | Kind::ARRAY
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let args = vec![
+            Some(arguments.required("value")),
+            Some(arguments.required("start")),
+            arguments.optional("end"),
+        ];
+        Apply::boxed(args, |values| {
+            let mut values = values.into_iter();
+            let value = next(&mut values).unwrap_or(Value::Null);
+            let start = integer(next(&mut values).unwrap_or(Value::Null))?;
+            let end = next(&mut values).map(integer).transpose()?;
+
+            // Negative offsets are counted from the end.
+            let range = |len: usize| {
+                let resolve = |offset: i64| {
+                    if offset < 0 {
+                        // `i64::MIN` has no positive counterpart, and is past the start anyway.
+                        len.saturating_sub(offset.checked_neg().map_or(usize::MAX, |n| n as usize))
+                    } else {
+                        (offset as usize).min(len)
+                    }
+                };
+                let start = resolve(start);
+                let end = end.map(resolve).unwrap_or(len);
+                if start > end {
+                    Err(format!("`start` ({}) is past `end` ({})", start, end))
+                } else {
+                    Ok((start, end))
+                }
+            };
+
+            match value {
+                Value::Bytes(_) => {
+                    let chars = string(value)?.chars().collect::<Vec<_>>();
+                    let (start, end) = range(chars.len())?;
+                    Ok(chars[start..end].iter().collect::<String>().into())
+                }
+                Value::Array(array) => {
+                    let (start, end) = range(array.len())?;
+                    Ok(Value::Array(array[start..end].to_vec()))
+                }
+                value => Err(unexpected(Kind::BYTES | Kind::ARRAY, &value)),
+            }
+        })
+    }
+}
+
+pub struct Split;
+
+impl Function for Split {
+    fn identifier(&self) -> &'static str {
+        "split"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[
+            VALUE,
+            Parameter {
+                keyword: "pattern",
+                kind: Kind::BYTES,
+                required: true,
+            },
+            Parameter {
+                keyword: "limit",
+                kind: Kind::INTEGER,
+                required: false,
+            },
+        ]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::ARRAY
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let args = vec![
+            Some(arguments.required("value")),
+            Some(arguments.required("pattern")),
+            arguments.optional("limit"),
+        ];
+        Apply::boxed(args, |values| {
+            let mut values = values.into_iter();
+            let value = string(next(&mut values).unwrap_or(Value::Null))?;
+            let pattern = string(next(&mut values).unwrap_or(Value::Null))?;
+            let limit = next(&mut values).map(integer).transpose()?;
+
+            let parts: Vec<Value> = match limit {
+                Some(limit) if limit > 0 => value
+                    .splitn(limit as usize, pattern.as_str())
+                    .map(Value::from)
+                    .collect(),
+                _ => value.split(pattern.as_str()).map(Value::from).collect(),
+            };
+            Ok(Value::Array(parts))
+        })
+    }
+}
+
+const LENGTH_PARAMETERS: &[Parameter] = &[Parameter {
+    keyword: "value",
+    kind: Kind::BYTES.or(Kind::ARRAY).or(Kind::MAP),
+    required: true,
+}];
+
+pub struct Length;
+
+impl Function for Length {
+    fn identifier(&self) -> &'static str {
+        "length"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        LENGTH_PARAMETERS
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::INTEGER
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(vec![Some(arguments.required("value"))], |values| {
+            let value = next(&mut values.into_iter()).unwrap_or(Value::Null);
+            let length = match value {
+                Value::Bytes(_) => string(value)?.chars().count(),
+                Value::Array(array) => array.len(),
+                Value::Map(map) => map.len(),
+                value => return Err(unexpected(Kind::BYTES | Kind::ARRAY | Kind::MAP, &value)),
+            };
+            Ok(Value::Integer(length as i64))
+        })
+    }
+}
+
+pub struct ToString;
+
+impl Function for ToString {
+    fn identifier(&self) -> &'static str {
+        "to_string"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[Parameter {
+            keyword: "value",
+            kind: Kind::ANY,
+            required: true,
+        }]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()) {
+                Some(value @ Value::Bytes(_)) => Ok(value),
+                Some(Value::Null) | None => Ok("".into()),
+                Some(value) => Ok(value.to_string_lossy().into()),
+            },
+        )
+    }
+}
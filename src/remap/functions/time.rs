@@ -0,0 +1,176 @@
+use super::{
+    next, string, timestamp, unexpected, Apply, Arguments, Call, Function, FunctionError, Parameter,
+};
+use crate::{event::Value, remap::Kind, types::Conversion};
+use chrono::{TimeZone, Utc};
+
+pub struct Now;
+
+impl Function for Now {
+    fn identifier(&self) -> &'static str {
+        "now"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::TIMESTAMP
+    }
+
+    fn compile(&self, _arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(vec![], |_| Ok(Value::Timestamp(Utc::now())))
+    }
+}
+
+pub struct ToTimestamp;
+
+impl Function for ToTimestamp {
+    fn identifier(&self) -> &'static str {
+        "to_timestamp"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[Parameter {
+            keyword: "value",
+            kind: Kind::ANY,
+            required: true,
+        }]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::TIMESTAMP
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        Apply::boxed(
+            vec![Some(arguments.required("value"))],
+            |values| match next(&mut values.into_iter()).unwrap_or(Value::Null) {
+                value @ Value::Timestamp(_) => Ok(value),
+                Value::Integer(seconds) => timestamp(seconds, 0),
+                Value::Float(seconds) => {
+                    // Casting a float that doesn't fit into the integer type is
+                    // undefined behaviour, so the range is checked first.
+                    let whole = seconds.floor();
+                    if !(whole >= i64::MIN as f64 && whole < i64::MAX as f64) {
+                        return Err(format!("timestamp out of range: {} seconds", seconds));
+                    }
+                    let nanos = ((seconds - whole) * 1e9).min(999_999_999.0);
+                    timestamp(whole as i64, nanos as u32)
+                }
+                value @ Value::Bytes(_) => Conversion::Timestamp
+                    .convert(value)
+                    .map_err(|error| error.to_string()),
+                value => Err(unexpected(
+                    Kind::BYTES | Kind::NUMBER | Kind::TIMESTAMP,
+                    &value,
+                )),
+            },
+        )
+    }
+}
+
+fn timestamp(seconds: i64, nanos: u32) -> Result<Value, String> {
+    Utc.timestamp_opt(seconds, nanos)
+        .single()
+        .map(Value::Timestamp)
+        .ok_or_else(|| format!("timestamp out of range: {} seconds", seconds))
+}
+
+pub struct ParseTimestamp;
+
+impl Function for ParseTimestamp {
+    fn identifier(&self) -> &'static str {
+        "parse_timestamp"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[
+            Parameter {
+                keyword: "value",
+                kind: Kind::BYTES,
+                required: true,
+            },
+            Parameter {
+                keyword: "format",
+                kind: Kind::BYTES,
+                required: true,
+            },
+        ]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::TIMESTAMP
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let value = arguments.required("value");
+        let (format, _) = arguments.required_literal_string("format")?;
+        // Reuse the `timestamp|<format>` conversion, so the time zone handling
+        // is the same as in the other transforms.
+        let conversion: Conversion = format!("timestamp|{}", format)
+            .parse()
+            .expect("timestamp conversions with a format always parse");
+
+        Apply::boxed(vec![Some(value)], move |values| {
+            let value = string(next(&mut values.into_iter()).unwrap_or(Value::Null))?;
+            conversion
+                .convert(value.into())
+                .map_err(|error| error.to_string())
+        })
+    }
+}
+
+pub struct FormatTimestamp;
+
+impl Function for FormatTimestamp {
+    fn identifier(&self) -> &'static str {
+        "format_timestamp"
+    }
+
+    fn parameters(&self) -> &'static [Parameter] {
+        &[
+            Parameter {
+                keyword: "value",
+                kind: Kind::TIMESTAMP,
+                required: true,
+            },
+            Parameter {
+                keyword: "format",
+                kind: Kind::BYTES,
+                required: true,
+            },
+        ]
+    }
+
+    fn return_kind(&self) -> Kind {
+        Kind::BYTES
+    }
+
+    fn compile(&self, mut arguments: Arguments) -> Result<Box<dyn Call>, FunctionError> {
+        let args = vec![
+            Some(arguments.required("value")),
+            Some(arguments.required("format")),
+        ];
+        Apply::boxed(args, |values| {
+            let mut values = values.into_iter();
+            let value = timestamp(next(&mut values).unwrap_or(Value::Null))?;
+            let format = string(next(&mut values).unwrap_or(Value::Null))?;
+
+            // Invalid format specifiers make `Display` fail, so we check them
+            // up front instead of panicking on `to_string`.
+            let items = chrono::format::StrftimeItems::new(&format).collect::<Vec<_>>();
+            if items
+                .iter()
+                .any(|item| matches!(item, chrono::format::Item::Error))
+            {
+                return Err(format!("invalid timestamp format `{}`", format));
+            }
+            Ok(value
+                .format_with_items(items.into_iter())
+                .to_string()
+                .into())
+        })
+    }
+}
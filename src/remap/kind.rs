@@ -0,0 +1,135 @@
+use crate::event::Value;
+use std::fmt;
+use std::ops::BitOr;
+
+/// A set of value kinds an expression can resolve to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Kind(u8);
+
+impl Kind {
+    /// A string.
+    pub const BYTES: Kind = Kind(1);
+    /// An integer.
+    pub const INTEGER: Kind = Kind(1 << 1);
+    /// A float.
+    pub const FLOAT: Kind = Kind(1 << 2);
+    /// A boolean.
+    pub const BOOLEAN: Kind = Kind(1 << 3);
+    /// A timestamp.
+    pub const TIMESTAMP: Kind = Kind(1 << 4);
+    /// A map.
+    pub const MAP: Kind = Kind(1 << 5);
+    /// An array.
+    pub const ARRAY: Kind = Kind(1 << 6);
+    /// The `null` value.
+    pub const NULL: Kind = Kind(1 << 7);
+    /// Either an integer or a float.
+    pub const NUMBER: Kind = Self::INTEGER.or(Self::FLOAT);
+    /// Any kind at all.
+    pub const ANY: Kind = Kind(u8::MAX);
+
+    /// The kind of the `value`.
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Bytes(_) => Self::BYTES,
+            Value::Integer(_) => Self::INTEGER,
+            Value::Float(_) => Self::FLOAT,
+            Value::Boolean(_) => Self::BOOLEAN,
+            Value::Timestamp(_) => Self::TIMESTAMP,
+            Value::Map(_) => Self::MAP,
+            Value::Array(_) => Self::ARRAY,
+            Value::Null => Self::NULL,
+        }
+    }
+
+    /// The union of `self` and `other`, usable in constants.
+    pub const fn or(self, other: Kind) -> Kind {
+        Kind(self.0 | other.0)
+    }
+
+    /// Whether all the kinds of `other` are included in `self`.
+    pub fn contains(self, other: Kind) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether `self` and `other` have any kinds in common.
+    pub fn intersects(self, other: Kind) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Whether `self` is exactly one kind.
+    pub fn is_exact(self) -> bool {
+        self.0.count_ones() == 1
+    }
+
+    fn names(self) -> Vec<&'static str> {
+        [
+            (Self::BYTES, "string"),
+            (Self::INTEGER, "integer"),
+            (Self::FLOAT, "float"),
+            (Self::BOOLEAN, "boolean"),
+            (Self::TIMESTAMP, "timestamp"),
+            (Self::MAP, "map"),
+            (Self::ARRAY, "array"),
+            (Self::NULL, "null"),
+        ]
+        .iter()
+        .filter(|(kind, _)| self.contains(*kind))
+        .map(|(_, name)| *name)
+        .collect()
+    }
+}
+
+impl BitOr for Kind {
+    type Output = Kind;
+
+    fn bitor(self, other: Kind) -> Kind {
+        self.or(other)
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Self::ANY {
+            return write!(f, "any");
+        }
+        let names = self.names();
+        match names.split_last() {
+            None => write!(f, "nothing"),
+            Some((last, [])) => write!(f, "{}", last),
+            Some((last, rest)) => write!(f, "{} or {}", rest.join(", "), last),
+        }
+    }
+}
+
+impl fmt::Debug for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Kind({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(Kind::BYTES.to_string(), "string");
+        assert_eq!(Kind::NUMBER.to_string(), "integer or float");
+        assert_eq!(
+            (Kind::BYTES | Kind::NUMBER).to_string(),
+            "string, integer or float"
+        );
+        assert_eq!(Kind::ANY.to_string(), "any");
+    }
+
+    #[test]
+    fn set_operations() {
+        assert!(Kind::NUMBER.contains(Kind::FLOAT));
+        assert!(!Kind::FLOAT.contains(Kind::NUMBER));
+        assert!(Kind::ANY.intersects(Kind::NULL));
+        assert!(!Kind::BYTES.intersects(Kind::NUMBER));
+        assert!(Kind::INTEGER.is_exact());
+        assert!(!Kind::NUMBER.is_exact());
+    }
+}
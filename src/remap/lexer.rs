@@ -0,0 +1,451 @@
+use super::error::Span;
+use crate::event::PathComponent;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Path(Vec<PathComponent>),
+    Variable(String),
+    Identifier(String),
+    String(String),
+    Integer(i64),
+    Float(f64),
+
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    Semicolon,
+    Newline,
+
+    Assign,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Bang,
+    And,
+    Or,
+    Coalesce,
+
+    Eof,
+}
+
+impl Token {
+    /// A short description for the error messages.
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Path(_) => "path".to_owned(),
+            Token::Variable(name) => format!("`${}`", name),
+            Token::Identifier(name) => format!("`{}`", name),
+            Token::String(_) => "string".to_owned(),
+            Token::Integer(_) | Token::Float(_) => "number".to_owned(),
+            Token::Newline => "end of line".to_owned(),
+            Token::Eof => "end of program".to_owned(),
+            other => format!("`{}`", other.symbol()),
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::Comma => ",",
+            Token::Colon => ":",
+            Token::Semicolon => ";",
+            Token::Assign => "=",
+            Token::Eq => "==",
+            Token::Ne => "!=",
+            Token::Lt => "<",
+            Token::Le => "<=",
+            Token::Gt => ">",
+            Token::Ge => ">=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Bang => "!",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Coalesce => "??",
+            _ => "",
+        }
+    }
+}
+
+/// Split the `source` into tokens.
+pub fn tokenize(source: &str) -> Result<Vec<(Token, Span)>, (String, Span)> {
+    let mut lexer = Lexer {
+        source,
+        chars: source.char_indices().peekable(),
+    };
+    let mut tokens = Vec::new();
+    loop {
+        let (token, span) = lexer.next_token()?;
+        let done = token == Token::Eof;
+        tokens.push((token, span));
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '@'
+}
+
+impl<'a> Lexer<'a> {
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|(i, _)| *i)
+            .unwrap_or_else(|| self.source.len())
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next().map(|(_, c)| c)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next_token(&mut self) -> Result<(Token, Span), (String, Span)> {
+        // Skip whitespace and comments.
+        loop {
+            match self.peek() {
+                Some(' ') | Some('\t') | Some('\r') => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), Some('\n') | None) {
+                        self.bump();
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let start = self.offset();
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return Ok((Token::Eof, Span::new(start, start))),
+        };
+
+        let token = match c {
+            '\n' => Token::Newline,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            ';' => Token::Semicolon,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '=' if self.eat('=') => Token::Eq,
+            '=' => Token::Assign,
+            '!' if self.eat('=') => Token::Ne,
+            '!' => Token::Bang,
+            '<' if self.eat('=') => Token::Le,
+            '<' => Token::Lt,
+            '>' if self.eat('=') => Token::Ge,
+            '>' => Token::Gt,
+            '&' if self.eat('&') => Token::And,
+            '|' if self.eat('|') => Token::Or,
+            '?' if self.eat('?') => Token::Coalesce,
+            '"' => Token::String(self.string(start)?),
+            '\'' => Token::String(self.raw_string(start)?),
+            '.' => Token::Path(self.path(start)?),
+            '$' => {
+                let name = self.identifier();
+                if name.is_empty() {
+                    return Err((
+                        "expected a variable name after `$`".to_owned(),
+                        Span::new(start, self.offset()),
+                    ));
+                }
+                Token::Variable(name)
+            }
+            c if c.is_ascii_digit() => self.number(start)?,
+            c if is_identifier_char(c) => {
+                let mut name = c.to_string();
+                name.push_str(&self.identifier());
+                Token::Identifier(name)
+            }
+            c => {
+                return Err((
+                    format!("unexpected character `{}`", c),
+                    Span::new(start, self.offset()),
+                ))
+            }
+        };
+
+        Ok((token, Span::new(start, self.offset())))
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if !is_identifier_char(c) {
+                break;
+            }
+            name.push(c);
+            self.bump();
+        }
+        name
+    }
+
+    fn number(&mut self, start: usize) -> Result<Token, (String, Span)> {
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '_') {
+            self.bump();
+        }
+        let is_float =
+            self.peek() == Some('.') && matches!(self.peek_second(), Some(c) if c.is_ascii_digit());
+        if is_float {
+            self.bump();
+            while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '_') {
+                self.bump();
+            }
+        }
+
+        let end = self.offset();
+        let text = self.source[start..end].replace('_', "");
+        let span = Span::new(start, end);
+        if is_float {
+            text.parse()
+                .map(Token::Float)
+                .map_err(|_| ("invalid float literal".to_owned(), span))
+        } else {
+            text.parse()
+                .map(Token::Integer)
+                .map_err(|_| ("integer literal is out of range".to_owned(), span))
+        }
+    }
+
+    fn string(&mut self, start: usize) -> Result<String, (String, Span)> {
+        let mut value = String::new();
+        loop {
+            let escape_start = self.offset();
+            match self.bump() {
+                None | Some('\n') => {
+                    return Err((
+                        "unterminated string literal".to_owned(),
+                        Span::new(start, self.offset()),
+                    ))
+                }
+                Some('"') => return Ok(value),
+                Some('\\') => match self.bump() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('0') => value.push('\0'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    _ => {
+                        return Err((
+                            "unknown escape sequence".to_owned(),
+                            Span::new(escape_start, self.offset()),
+                        ))
+                    }
+                },
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn raw_string(&mut self, start: usize) -> Result<String, (String, Span)> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => {
+                    return Err((
+                        "unterminated string literal".to_owned(),
+                        Span::new(start, self.offset()),
+                    ))
+                }
+                Some('\'') => return Ok(value),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    /// Lex the rest of the path after the leading `.`.
+    fn path(&mut self, start: usize) -> Result<Vec<PathComponent>, (String, Span)> {
+        let mut components = Vec::new();
+        loop {
+            // A key segment.
+            let key_start = self.offset();
+            let key = match self.peek() {
+                Some('"') => {
+                    self.bump();
+                    self.string(key_start)?
+                }
+                Some(c) if is_identifier_char(c) => self.identifier(),
+                _ => {
+                    return Err((
+                        "expected a field name after `.`".to_owned(),
+                        Span::new(start, self.offset()),
+                    ))
+                }
+            };
+            components.push(PathComponent::Key(key));
+
+            // Any number of indexes.
+            while self.peek() == Some('[') {
+                let index_start = self.offset();
+                self.bump();
+                let mut digits = String::new();
+                while let Some(c) = self.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    digits.push(c);
+                    self.bump();
+                }
+                if digits.is_empty() || !self.eat(']') {
+                    return Err((
+                        "expected a non-negative array index".to_owned(),
+                        Span::new(index_start, self.offset()),
+                    ));
+                }
+                let index = digits.parse().map_err(|_| {
+                    (
+                        "array index is out of range".to_owned(),
+                        Span::new(index_start, self.offset()),
+                    )
+                })?;
+                components.push(PathComponent::Index(index));
+            }
+
+            // Continue only if the next segment follows immediately.
+            let continues = self.peek() == Some('.')
+                && matches!(self.peek_second(), Some(c) if c == '"' || is_identifier_char(c));
+            if !continues {
+                return Ok(components);
+            }
+            self.bump();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    fn key(name: &str) -> PathComponent {
+        PathComponent::Key(name.to_owned())
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            tokens(r#".foo.bar[1][2]."baz qux" .a"#),
+            vec![
+                Token::Path(vec![
+                    key("foo"),
+                    key("bar"),
+                    PathComponent::Index(1),
+                    PathComponent::Index(2),
+                    key("baz qux"),
+                ]),
+                Token::Path(vec![key("a")]),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_and_literals() {
+        assert_eq!(
+            tokens("$x = 1.5 + 2_000 ?? \"a\\tb\" # comment\n!= 'c\\d'"),
+            vec![
+                Token::Variable("x".to_owned()),
+                Token::Assign,
+                Token::Float(1.5),
+                Token::Plus,
+                Token::Integer(2000),
+                Token::Coalesce,
+                Token::String("a\tb".to_owned()),
+                Token::Newline,
+                Token::Ne,
+                Token::String("c\\d".to_owned()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = vec![
+            (". foo", "expected a field name after `.`", Span::new(0, 1)),
+            (
+                ".foo[x]",
+                "expected a non-negative array index",
+                Span::new(4, 5),
+            ),
+            ("\"abc", "unterminated string literal", Span::new(0, 4)),
+            ("\"\\q\"", "unknown escape sequence", Span::new(1, 3)),
+            ("1 ^ 2", "unexpected character `^`", Span::new(2, 3)),
+        ];
+
+        for (source, message, span) in cases {
+            assert_eq!(
+                tokenize(source),
+                Err((message.to_owned(), span)),
+                "{}",
+                source
+            );
+        }
+    }
+}
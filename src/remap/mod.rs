@@ -0,0 +1,476 @@
+//! A small expression language for reshaping log events.
+//!
+//! Programs are compiled once at config load time into a tree of
+//! expressions, and then evaluated natively against the [`LogEvent`] paths of
+//! each event, without any intermediate runtime.
+//!
+//! ```text
+//! # Parse the message and tidy up the result.
+//! .parsed = parse_json(.message) ?? {}
+//! .level = downcase(.parsed.level ?? "info")
+//! if .parsed.status >= 500 {
+//!     .error = true
+//! }
+//! del(.message)
+//! ```
+//!
+//! The supported constructs are:
+//!
+//! - paths (`.foo.bar[0]`, `."key with spaces"`) and variables (`$foo`);
+//! - literals: strings (`"escaped\n"`, `'raw'`), integers, floats, booleans,
+//!   `null`, arrays and maps;
+//! - assignment to paths and variables, `del(...)` for deletion;
+//! - arithmetic (`+ - * / %`), comparison (`== != < <= > >=`), logical
+//!   (`! && ||`) and error coalescing (`??`) operators;
+//! - `if`/`else if`/`else` conditionals;
+//! - calls of the built-in functions (string, number, time, parsing and event
+//!   manipulation helpers).
+//!
+//! The program is type-checked at compile time: wherever the kind of a value
+//! is statically known, it's validated against what the operator or function
+//! expects. Compile errors point at the offending span of the source.
+//!
+//! The same language is used for conditions, see
+//! [`Program::compile_condition`].
+
+#![deny(missing_docs)]
+
+mod error;
+mod expression;
+mod functions;
+mod kind;
+mod lexer;
+mod parser;
+
+pub use error::{CompileError, Location, RuntimeError, Span};
+pub use kind::Kind;
+
+use crate::event::{LogEvent, Value};
+use expression::{Context, Expr};
+
+/// A compiled program.
+pub struct Program {
+    expressions: Vec<Expr>,
+    variables: usize,
+}
+
+impl std::fmt::Debug for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Program")
+            .field("expressions", &self.expressions.len())
+            .field("variables", &self.variables)
+            .finish()
+    }
+}
+
+impl Program {
+    /// Compile the `source` into a [`Program`].
+    pub fn compile(source: &str) -> Result<Self, CompileError> {
+        Self::compile_with_mode(source, parser::Mode::Program)
+    }
+
+    /// Compile the `source` into a [`Program`] used as a condition.
+    ///
+    /// Conditions can't modify the event, so field assignment and functions
+    /// like `del` are rejected. The last expression has to resolve to a
+    /// boolean.
+    pub fn compile_condition(source: &str) -> Result<Self, CompileError> {
+        Self::compile_with_mode(source, parser::Mode::Condition)
+    }
+
+    fn compile_with_mode(source: &str, mode: parser::Mode) -> Result<Self, CompileError> {
+        let (expressions, variables) = parser::parse(source, mode)?;
+        Ok(Self {
+            expressions,
+            variables,
+        })
+    }
+
+    /// Execute the program against the `log`, returning the value of the
+    /// last expression.
+    ///
+    /// Execution stops at the first error. The changes made to the `log`
+    /// before the error was encountered are kept.
+    pub fn execute(&self, log: &mut LogEvent) -> Result<Value, RuntimeError> {
+        self.run(Context::new(log, self.variables))
+    }
+
+    /// Execute the program against the `log` without modifying it. Fails if
+    /// the program tries to modify the event, which can't happen for the
+    /// programs compiled with [`Program::compile_condition`].
+    pub fn evaluate(&self, log: &LogEvent) -> Result<Value, RuntimeError> {
+        self.run(Context::read_only(log, self.variables))
+    }
+
+    fn run(&self, mut ctx: Context) -> Result<Value, RuntimeError> {
+        let mut result = Value::Null;
+        for expression in &self.expressions {
+            result = expression.eval(&mut ctx)?;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+    use chrono::{TimeZone, Utc};
+
+    fn run(source: &str, event: Event) -> (Result<Value, RuntimeError>, LogEvent) {
+        let program = Program::compile(source).unwrap_or_else(|error| panic!("{}", error));
+        let mut log = event.into_log();
+        let result = program.execute(&mut log);
+        (result, log)
+    }
+
+    fn log(fields: &[(&str, Value)]) -> Event {
+        let mut event = Event::new_empty_log();
+        for (key, value) in fields {
+            event.as_mut_log().insert(key, value.clone());
+        }
+        event
+    }
+
+    #[test]
+    fn assignment_and_deletion() {
+        let (result, log) = run(
+            r#"
+                .foo = "bar"
+                .nested.value = 1 + 2 * 3
+                ."with space" = true
+                $tmp = .message
+                .copy = $tmp
+                del(.message)
+            "#,
+            Event::from("hello"),
+        );
+        result.unwrap();
+        assert_eq!(log[&"foo".into()], "bar".into());
+        assert_eq!(log[&"nested.value".into()], Value::Integer(7));
+        assert_eq!(log.get_flat("with space"), Some(&Value::Boolean(true)));
+        assert_eq!(log[&"copy".into()], "hello".into());
+        assert!(log.get(&"message".into()).is_none());
+    }
+
+    #[test]
+    fn conditionals() {
+        let source = r#"
+            if .status >= 500 {
+                .level = "error"
+            } else if .status >= 400 {
+                .level = "warn"
+            } else {
+                .level = "info"
+            }
+        "#;
+
+        for (status, expected) in &[(503, "error"), (404, "warn"), (200, "info")] {
+            let (result, log) = run(source, log(&[("status", Value::Integer(*status))]));
+            result.unwrap();
+            assert_eq!(log[&"level".into()], (*expected).into());
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let cases = vec![
+            ("1 + 2", Value::Integer(3)),
+            ("1 + 2.5", Value::Float(3.5)),
+            ("7 / 2", Value::Float(3.5)),
+            ("7 % 4", Value::Integer(3)),
+            ("-(2 - 5)", Value::Integer(3)),
+            (r#""foo" + "bar""#, "foobar".into()),
+            ("1 < 2 && 2 <= 2", Value::Boolean(true)),
+            (r#""a" != "b" || false"#, Value::Boolean(true)),
+            ("!true", Value::Boolean(false)),
+            ("null == .missing", Value::Boolean(true)),
+            (".missing ?? 42", Value::Integer(42)),
+            (r#"parse_json("{") ?? "fallback""#, "fallback".into()),
+            ("[1, 2] == [1, 2]", Value::Boolean(true)),
+        ];
+
+        for (source, expected) in cases {
+            let (result, _) = run(source, Event::new_empty_log());
+            assert_eq!(result.unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn literals() {
+        let (result, _) = run(
+            r#"{"a": [1, 2.5, "three\n", 'raw\n', true, null]}"#,
+            Event::new_empty_log(),
+        );
+        let expected = Value::Map(
+            vec![(
+                "a".to_owned(),
+                Value::Array(vec![
+                    Value::Integer(1),
+                    Value::Float(2.5),
+                    "three\n".into(),
+                    "raw\\n".into(),
+                    Value::Boolean(true),
+                    Value::Null,
+                ]),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let cases = vec![
+            (".a = 1 / 0", "division by zero"),
+            (".a = upcase(.missing)", "expected string, got null"),
+            (".a = .s - 1", "can't subtract integer from string"),
+            (".a = .min % -1", "integer overflow"),
+            (".a = -.min", "integer overflow"),
+            (".a = to_timestamp(.min)", "timestamp out of range"),
+            (".a = to_timestamp(.huge)", "timestamp out of range"),
+            (".a = to_int(.huge)", "out of the range of integers"),
+            (".a = floor(-.huge)", "out of the range of integers"),
+        ];
+
+        for (source, expected) in cases {
+            let (result, _) = run(
+                source,
+                log(&[
+                    ("s", "foo".into()),
+                    ("min", Value::Integer(i64::MIN)),
+                    ("huge", Value::Float(1e300)),
+                ]),
+            );
+            let error = result.unwrap_err();
+            assert!(
+                error.to_string().contains(expected),
+                "{}: {}",
+                source,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn errors_keep_previous_changes() {
+        let (result, log) = run(".a = 1\n.b = 1 / 0\n.c = 3", Event::new_empty_log());
+        assert!(result.is_err());
+        assert_eq!(log[&"a".into()], Value::Integer(1));
+        assert!(log.get(&"c".into()).is_none());
+    }
+
+    #[test]
+    fn compile_errors() {
+        let cases = vec![
+            (".foo = upcse(.bar)", "unknown function `upcse`", (1, 8)),
+            (".foo = upcase(1)", "expected string, got integer", (1, 15)),
+            ("if 1 { .a = 1 }", "expected boolean, got integer", (1, 4)),
+            (".foo = \"bar\" + 1", "can't add integer to string", (1, 8)),
+            (".a = $undefined", "undefined variable `$undefined`", (1, 6)),
+            (".a = (1 + 2", "expected `)`", (1, 12)),
+            (
+                ".a = parse_regex(.message, \"(\")",
+                "invalid regular expression",
+                (1, 28),
+            ),
+            (
+                ".a = truncate(.message)",
+                "missing required argument `limit`",
+                (1, 6),
+            ),
+            (".a = 1\n.b = del(1)", "expected a path", (2, 10)),
+        ];
+
+        for (source, expected, (line, column)) in cases {
+            let error = Program::compile(source).unwrap_err();
+            assert!(error.message().contains(expected), "{}: {}", source, error);
+            assert_eq!(
+                error.location(),
+                Location { line, column },
+                "{}: {}",
+                source,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn compile_error_rendering() {
+        let error = Program::compile(".foo = \"ok\"\n.bar = upcse(.baz)").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            [
+                "unknown function `upcse` at line 2, column 8",
+                "  |",
+                "2 | .bar = upcse(.baz)",
+                "  |        ^^^^^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn functions() {
+        let event = log(&[
+            ("message", " Hello, World! ".into()),
+            ("number", "42".into()),
+            ("float", Value::Float(2.567)),
+            ("min", Value::Integer(i64::MIN)),
+            (
+                "timestamp",
+                Value::Timestamp(Utc.ymd(2020, 10, 21).and_hms(16, 0, 0)),
+            ),
+        ]);
+
+        let cases = vec![
+            ("upcase(.message)", " HELLO, WORLD! ".into()),
+            ("downcase(.message)", " hello, world! ".into()),
+            ("strip_whitespace(.message)", "Hello, World!".into()),
+            ("truncate(strip_whitespace(.message), 5)", "Hello".into()),
+            (
+                "truncate(strip_whitespace(.message), 5, ellipsis: true)",
+                "Hello...".into(),
+            ),
+            (r#"contains(.message, "world")"#, Value::Boolean(false)),
+            (
+                r#"contains(.message, "world", case_sensitive: false)"#,
+                Value::Boolean(true),
+            ),
+            (r#"starts_with(.message, " Hello")"#, Value::Boolean(true)),
+            (r#"ends_with(.message, "! ")"#, Value::Boolean(true)),
+            (r#"replace(.message, "o", "0")"#, " Hell0, W0rld! ".into()),
+            (r#"slice(.message, 1, 6)"#, "Hello".into()),
+            (r#"slice(.message, -7)"#, "World! ".into()),
+            (r#"slice(.message, .min)"#, " Hello, World! ".into()),
+            (
+                r#"split(strip_whitespace(.message), ", ")"#,
+                Value::Array(vec!["Hello".into(), "World!".into()]),
+            ),
+            ("length(.message)", Value::Integer(15)),
+            ("length([1, 2, 3])", Value::Integer(3)),
+            ("to_string(.float)", "2.567".into()),
+            ("to_int(.number) + 1", Value::Integer(43)),
+            ("to_float(.number)", Value::Float(42.0)),
+            (r#"to_bool("yes")"#, Value::Boolean(true)),
+            ("round(.float)", Value::Integer(3)),
+            ("round(.float, precision: 2)", Value::Float(2.57)),
+            ("ceil(.float)", Value::Integer(3)),
+            ("floor(.float)", Value::Integer(2)),
+            ("abs(-3)", Value::Integer(3)),
+            (
+                r#"format_timestamp(.timestamp, "%Y-%m-%d")"#,
+                "2020-10-21".into(),
+            ),
+            (
+                r#"parse_timestamp("2020-10-21T16:00:00Z", "%+")"#,
+                Value::Timestamp(Utc.ymd(2020, 10, 21).and_hms(16, 0, 0)),
+            ),
+            (
+                r#"to_timestamp(1603296000)"#,
+                Value::Timestamp(Utc.ymd(2020, 10, 21).and_hms(16, 0, 0)),
+            ),
+            (
+                r#"to_timestamp(-1.5)"#,
+                Value::Timestamp(Utc.ymd(1969, 12, 31).and_hms_milli(23, 59, 58, 500)),
+            ),
+            ("exists(.message)", Value::Boolean(true)),
+            ("exists(.nope)", Value::Boolean(false)),
+        ];
+
+        for (source, expected) in cases {
+            let (result, _) = run(source, event.clone());
+            assert_eq!(result.unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn parser_functions() {
+        let event = log(&[
+            ("json", r#"{"a": {"b": [1, true]}}"#.into()),
+            ("logfmt", "level=info msg=\"hello world\"".into()),
+            ("line", "GET /index.html 200".into()),
+        ]);
+
+        let map = |pairs: Vec<(&str, Value)>| {
+            Value::Map(pairs.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+        };
+
+        let cases = vec![
+            (
+                "parse_json(.json)",
+                map(vec![(
+                    "a",
+                    map(vec![(
+                        "b",
+                        Value::Array(vec![Value::Integer(1), Value::Boolean(true)]),
+                    )]),
+                )]),
+            ),
+            (
+                "parse_logfmt(.logfmt)",
+                map(vec![
+                    ("level", "info".into()),
+                    ("msg", "hello world".into()),
+                ]),
+            ),
+            (
+                r#"parse_regex(.line, "^(?P<method>\\w+) (?P<path>\\S+) (?P<status>\\d+)$")"#,
+                map(vec![
+                    ("method", "GET".into()),
+                    ("path", "/index.html".into()),
+                    ("status", "200".into()),
+                ]),
+            ),
+            (
+                r#"parse_grok(.line, "%{WORD:method} %{URIPATH:path} %{NUMBER:status}")"#,
+                map(vec![
+                    ("method", "GET".into()),
+                    ("path", "/index.html".into()),
+                    ("status", "200".into()),
+                ]),
+            ),
+        ];
+
+        for (source, expected) in cases {
+            let (result, _) = run(source, event.clone());
+            assert_eq!(result.unwrap(), expected, "{}", source);
+        }
+
+        let (result, _) = run(r#"parse_regex(.line, "^POST")"#, event);
+        assert!(result.unwrap_err().to_string().contains("did not match"));
+    }
+
+    #[test]
+    fn conditions() {
+        let program = Program::compile_condition(
+            r#".status >= 500 && (.service == "api" || exists(.error))"#,
+        )
+        .unwrap();
+
+        let matching = log(&[("status", Value::Integer(503)), ("service", "api".into())]);
+        assert_eq!(
+            program.evaluate(matching.as_log()).unwrap(),
+            Value::Boolean(true)
+        );
+
+        let other = log(&[("status", Value::Integer(503)), ("service", "web".into())]);
+        assert_eq!(
+            program.evaluate(other.as_log()).unwrap(),
+            Value::Boolean(false)
+        );
+
+        let error = Program::compile_condition(".a = 1").unwrap_err();
+        assert_eq!(error.location(), Location { line: 1, column: 1 });
+    }
+
+    #[test]
+    fn evaluate_does_not_modify() {
+        let program = Program::compile(".a = 1").unwrap();
+        let error = program
+            .evaluate(Event::new_empty_log().as_log())
+            .unwrap_err();
+        assert!(error.message().contains("can't be modified"));
+    }
+}
@@ -0,0 +1,611 @@
+use super::{
+    error::{CompileError, Location, Span},
+    expression::{check_binary, BinaryOp, Expr},
+    functions::{self, Arguments},
+    kind::Kind,
+    lexer::{tokenize, Token},
+};
+use crate::event::Value;
+
+/// What the program is compiled for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// A program that reshapes the event.
+    Program,
+    /// A condition, which can't modify the event and has to resolve to a
+    /// boolean.
+    Condition,
+}
+
+/// Parse and type-check the `source`, returning the compiled expressions and
+/// the number of variable slots they need.
+pub fn parse(source: &str, mode: Mode) -> Result<(Vec<Expr>, usize), CompileError> {
+    let tokens =
+        tokenize(source).map_err(|(message, span)| CompileError::new(message, span, source))?;
+    let mut parser = Parser {
+        source,
+        tokens,
+        position: 0,
+        mode,
+        variables: Vec::new(),
+        depth: 0,
+        last_statement: None,
+    };
+    let expressions = parser.statements(false)?;
+    if let Token::RBrace = parser.peek() {
+        let span = parser.span();
+        return Err(parser.error("unexpected `}`", span));
+    }
+
+    if mode == Mode::Condition {
+        match (expressions.last(), parser.last_statement) {
+            (Some(expr), Some(span)) if !expr.kind().intersects(Kind::BOOLEAN) => {
+                let message = format!("expected boolean, got {}", expr.kind());
+                return Err(parser.error(message, span));
+            }
+            (None, _) => return Err(parser.error("the condition is empty", parser.span())),
+            _ => (),
+        }
+    }
+
+    Ok((expressions, parser.variables.len()))
+}
+
+/// The binary operators, from the lowest to the highest precedence.
+const LEVELS: usize = 7;
+
+enum Operator {
+    Coalesce,
+    Binary(BinaryOp),
+}
+
+fn binary_operator(token: &Token, level: usize) -> Option<Operator> {
+    let op = match (level, token) {
+        (0, Token::Coalesce) => return Some(Operator::Coalesce),
+        (1, Token::Or) => BinaryOp::Or,
+        (2, Token::And) => BinaryOp::And,
+        (3, Token::Eq) => BinaryOp::Eq,
+        (3, Token::Ne) => BinaryOp::Ne,
+        (4, Token::Lt) => BinaryOp::Lt,
+        (4, Token::Le) => BinaryOp::Le,
+        (4, Token::Gt) => BinaryOp::Gt,
+        (4, Token::Ge) => BinaryOp::Ge,
+        (5, Token::Plus) => BinaryOp::Add,
+        (5, Token::Minus) => BinaryOp::Sub,
+        (6, Token::Star) => BinaryOp::Mul,
+        (6, Token::Slash) => BinaryOp::Div,
+        (6, Token::Percent) => BinaryOp::Rem,
+        _ => return None,
+    };
+    Some(Operator::Binary(op))
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, Span)>,
+    position: usize,
+    mode: Mode,
+    /// The names and kinds of the variables, indexed by their slot.
+    variables: Vec<(String, Kind)>,
+    /// How deep inside `if` blocks we are.
+    depth: usize,
+    /// The span of the last parsed statement.
+    last_statement: Option<Span>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        // The last token is always `Eof`.
+        let index = (self.position + n).min(self.tokens.len() - 1);
+        &self.tokens[index].0
+    }
+
+    fn is_keyword(&self, n: usize, keyword: &str) -> bool {
+        matches!(self.peek_nth(n), Token::Identifier(name) if name == keyword)
+    }
+
+    fn span(&self) -> Span {
+        self.tokens[self.position].1
+    }
+
+    fn bump(&mut self) -> (Token, Span) {
+        let token = self.tokens[self.position].clone();
+        if self.position < self.tokens.len() - 1 {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn skip_newlines(&mut self) {
+        while let Token::Newline = self.peek() {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<Span, CompileError> {
+        if *self.peek() == expected {
+            Ok(self.bump().1)
+        } else {
+            let message = format!(
+                "expected `{}`, got {}",
+                expected.symbol(),
+                self.peek().describe()
+            );
+            Err(self.error(message, self.span()))
+        }
+    }
+
+    fn error(&self, message: impl Into<String>, span: Span) -> CompileError {
+        CompileError::new(message, span, self.source)
+    }
+
+    fn location(&self, span: Span) -> Location {
+        Location::from_offset(self.source, span.start)
+    }
+
+    /// Parse statements until the end of the program, or the end of the block
+    /// if `in_block` is set.
+    fn statements(&mut self, in_block: bool) -> Result<Vec<Expr>, CompileError> {
+        let mut statements = Vec::new();
+        loop {
+            while let Token::Newline | Token::Semicolon = self.peek() {
+                self.bump();
+            }
+            match self.peek() {
+                // A stray `}` at the top level is reported by `parse`.
+                Token::Eof | Token::RBrace => break,
+                _ => (),
+            }
+
+            let start = self.span();
+            statements.push(self.statement()?);
+            let end = self.tokens[self.position.saturating_sub(1)].1;
+            self.last_statement = Some(start.merge(end));
+
+            match self.peek() {
+                Token::Newline | Token::Semicolon | Token::Eof => (),
+                Token::RBrace if in_block => (),
+                other => {
+                    let message = format!("expected end of line, got {}", other.describe());
+                    return Err(self.error(message, self.span()));
+                }
+            }
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Expr, CompileError> {
+        let assignment = *self.peek_nth(1) == Token::Assign;
+        match self.peek().clone() {
+            Token::Path(path) if assignment => {
+                if self.mode == Mode::Condition {
+                    let span = self.span();
+                    return Err(self.error("fields can't be assigned in a condition", span));
+                }
+                self.bump();
+                self.bump();
+                self.skip_newlines();
+                let (value, _) = self.expression()?;
+                Ok(Expr::AssignPath(path, Box::new(value)))
+            }
+            Token::Variable(name) if assignment => {
+                self.bump();
+                self.bump();
+                self.skip_newlines();
+                let (value, _) = self.expression()?;
+                let index = self.define_variable(name, value.kind());
+                Ok(Expr::AssignVariable(index, Box::new(value)))
+            }
+            _ => self.expression().map(|(expr, _)| expr),
+        }
+    }
+
+    fn define_variable(&mut self, name: String, kind: Kind) -> usize {
+        match self.variables.iter().position(|(known, _)| *known == name) {
+            Some(index) => {
+                let known = &mut self.variables[index].1;
+                *known = *known | kind;
+                index
+            }
+            None => {
+                // A variable first assigned inside a block stays `null` when
+                // the block isn't executed.
+                let kind = if self.depth > 0 {
+                    kind | Kind::NULL
+                } else {
+                    kind
+                };
+                self.variables.push((name, kind));
+                self.variables.len() - 1
+            }
+        }
+    }
+
+    fn expression(&mut self) -> Result<(Expr, Span), CompileError> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<(Expr, Span), CompileError> {
+        if level == LEVELS {
+            return self.unary();
+        }
+
+        let (mut lhs, mut lhs_span) = self.binary(level + 1)?;
+        while let Some(operator) = binary_operator(self.peek(), level) {
+            self.bump();
+            self.skip_newlines();
+            let (rhs, rhs_span) = self.binary(level + 1)?;
+            let span = lhs_span.merge(rhs_span);
+            lhs = match operator {
+                Operator::Coalesce => Expr::Coalesce(Box::new(lhs), Box::new(rhs)),
+                Operator::Binary(op) => {
+                    check_binary(op, lhs.kind(), rhs.kind())
+                        .map_err(|message| self.error(message, span))?;
+                    Expr::Binary {
+                        op,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                        location: self.location(span),
+                    }
+                }
+            };
+            lhs_span = span;
+        }
+        Ok((lhs, lhs_span))
+    }
+
+    fn unary(&mut self) -> Result<(Expr, Span), CompileError> {
+        let accepted = match self.peek() {
+            Token::Bang => Kind::BOOLEAN,
+            Token::Minus => Kind::NUMBER,
+            _ => return self.primary(),
+        };
+
+        let (token, start) = self.bump();
+        let (expr, span) = self.unary()?;
+        if !expr.kind().intersects(accepted) {
+            let message = format!("expected {}, got {}", accepted, expr.kind());
+            return Err(self.error(message, span));
+        }
+
+        let span = start.merge(span);
+        let location = self.location(span);
+        let expr = match (token, expr) {
+            (Token::Minus, Expr::Literal(Value::Integer(value))) => {
+                Expr::Literal(Value::Integer(-value))
+            }
+            (Token::Minus, Expr::Literal(Value::Float(value))) => {
+                Expr::Literal(Value::Float(-value))
+            }
+            (Token::Minus, expr) => Expr::Negate(Box::new(expr), location),
+            (_, expr) => Expr::Not(Box::new(expr), location),
+        };
+        Ok((expr, span))
+    }
+
+    fn primary(&mut self) -> Result<(Expr, Span), CompileError> {
+        let (token, span) = self.bump();
+        let expr = match token {
+            Token::Path(path) => Expr::Path(path),
+            Token::Variable(name) => {
+                match self.variables.iter().position(|(known, _)| *known == name) {
+                    Some(index) => Expr::Variable(index, self.variables[index].1),
+                    None => {
+                        let message = format!("undefined variable `${}`", name);
+                        return Err(self.error(message, span));
+                    }
+                }
+            }
+            Token::String(value) => Expr::Literal(Value::from(value)),
+            Token::Integer(value) => Expr::Literal(Value::Integer(value)),
+            Token::Float(value) => Expr::Literal(Value::Float(value)),
+            Token::Identifier(name) => match name.as_str() {
+                "true" => Expr::Literal(Value::Boolean(true)),
+                "false" => Expr::Literal(Value::Boolean(false)),
+                "null" => Expr::Literal(Value::Null),
+                "if" => return self.if_expression(span),
+                _ if *self.peek() == Token::LParen => return self.call(name, span),
+                _ => {
+                    let message = format!("expected an expression, got `{}`", name);
+                    return Err(self.error(message, span));
+                }
+            },
+            Token::LParen => {
+                self.skip_newlines();
+                let (expr, _) = self.expression()?;
+                self.skip_newlines();
+                let end = self.expect(Token::RParen)?;
+                return Ok((expr, span.merge(end)));
+            }
+            Token::LBracket => return self.array(span),
+            Token::LBrace => return self.map(span),
+            other => {
+                let message = format!("expected an expression, got {}", other.describe());
+                return Err(self.error(message, span));
+            }
+        };
+        Ok((expr, span))
+    }
+
+    /// Parse the items of an array literal, after the opening `[`.
+    fn array(&mut self, start: Span) -> Result<(Expr, Span), CompileError> {
+        let mut items = Vec::new();
+        self.skip_newlines();
+        while *self.peek() != Token::RBracket {
+            items.push(self.expression()?.0);
+            self.skip_newlines();
+            if *self.peek() != Token::Comma {
+                break;
+            }
+            self.bump();
+            self.skip_newlines();
+        }
+        let end = self.expect(Token::RBracket)?;
+        Ok((Expr::Array(items), start.merge(end)))
+    }
+
+    /// Parse the entries of a map literal, after the opening `{`.
+    fn map(&mut self, start: Span) -> Result<(Expr, Span), CompileError> {
+        let mut entries = Vec::new();
+        self.skip_newlines();
+        while *self.peek() != Token::RBrace {
+            let key = match self.bump() {
+                (Token::String(key), _) | (Token::Identifier(key), _) => key,
+                (other, span) => {
+                    let message = format!("expected a map key, got {}", other.describe());
+                    return Err(self.error(message, span));
+                }
+            };
+            self.expect(Token::Colon)?;
+            self.skip_newlines();
+            entries.push((key, self.expression()?.0));
+            self.skip_newlines();
+            if *self.peek() != Token::Comma {
+                break;
+            }
+            self.bump();
+            self.skip_newlines();
+        }
+        let end = self.expect(Token::RBrace)?;
+        Ok((Expr::Map(entries), start.merge(end)))
+    }
+
+    /// Parse the rest of an `if` expression, after the `if` keyword.
+    fn if_expression(&mut self, start: Span) -> Result<(Expr, Span), CompileError> {
+        let (condition, condition_span) = self.expression()?;
+        if !condition.kind().intersects(Kind::BOOLEAN) {
+            let message = format!("expected boolean, got {}", condition.kind());
+            return Err(self.error(message, condition_span));
+        }
+
+        let (consequent, mut end) = self.block()?;
+
+        // `else` may follow on the next line.
+        let mut lookahead = 0;
+        while let Token::Newline = self.peek_nth(lookahead) {
+            lookahead += 1;
+        }
+        let alternative = if self.is_keyword(lookahead, "else") {
+            self.skip_newlines();
+            self.bump();
+            if self.is_keyword(0, "if") {
+                let (_, span) = self.bump();
+                let (expr, span) = self.if_expression(span)?;
+                end = span;
+                Some(vec![expr])
+            } else {
+                let (block, span) = self.block()?;
+                end = span;
+                Some(block)
+            }
+        } else {
+            None
+        };
+
+        let span = start.merge(end);
+        let expr = Expr::If {
+            condition: Box::new(condition),
+            consequent,
+            alternative,
+            location: self.location(condition_span),
+        };
+        Ok((expr, span))
+    }
+
+    /// Parse a `{ ... }` block of statements.
+    fn block(&mut self) -> Result<(Vec<Expr>, Span), CompileError> {
+        let start = self.expect(Token::LBrace)?;
+        self.depth += 1;
+        let statements = self.statements(true)?;
+        self.depth -= 1;
+        let end = self.expect(Token::RBrace)?;
+        Ok((statements, start.merge(end)))
+    }
+
+    /// Parse the arguments of a function call, after the function name.
+    fn call(&mut self, name: String, name_span: Span) -> Result<(Expr, Span), CompileError> {
+        let function = functions::find(&name)
+            .ok_or_else(|| self.error(format!("unknown function `{}`", name), name_span))?;
+        if function.modifies_event() && self.mode == Mode::Condition {
+            let message = format!("`{}` can't be used in a condition", name);
+            return Err(self.error(message, name_span));
+        }
+        let parameters = function.parameters();
+
+        self.expect(Token::LParen)?;
+        self.skip_newlines();
+
+        let mut values: Vec<Option<(Expr, Span)>> = parameters.iter().map(|_| None).collect();
+        let mut positional = 0;
+        while *self.peek() != Token::RParen {
+            let named = *self.peek_nth(1) == Token::Colon;
+            let keyword = match self.peek().clone() {
+                Token::Identifier(keyword) if named => {
+                    let (_, span) = self.bump();
+                    self.bump();
+                    self.skip_newlines();
+                    Some((keyword, span))
+                }
+                _ => None,
+            };
+            let (expr, span) = self.expression()?;
+
+            let index = match keyword {
+                Some((keyword, keyword_span)) => parameters
+                    .iter()
+                    .position(|parameter| parameter.keyword == keyword)
+                    .ok_or_else(|| {
+                        let message = format!("unknown argument `{}` for `{}`", keyword, name);
+                        self.error(message, keyword_span)
+                    })?,
+                None => {
+                    positional += 1;
+                    if positional > parameters.len() {
+                        let message = format!(
+                            "too many arguments for `{}`, expected at most {}",
+                            name,
+                            parameters.len()
+                        );
+                        return Err(self.error(message, span));
+                    }
+                    positional - 1
+                }
+            };
+
+            let parameter = parameters[index];
+            if values[index].is_some() {
+                let message = format!("argument `{}` is passed twice", parameter.keyword);
+                return Err(self.error(message, span));
+            }
+            if !parameter.kind.intersects(expr.kind()) {
+                let message = format!("expected {}, got {}", parameter.kind, expr.kind());
+                return Err(self.error(message, span));
+            }
+            values[index] = Some((expr, span));
+
+            self.skip_newlines();
+            if *self.peek() != Token::Comma {
+                break;
+            }
+            self.bump();
+            self.skip_newlines();
+        }
+        let end = self.expect(Token::RParen)?;
+        let span = name_span.merge(end);
+
+        if let Some(parameter) = parameters
+            .iter()
+            .zip(&values)
+            .find(|(parameter, value)| parameter.required && value.is_none())
+            .map(|(parameter, _)| parameter)
+        {
+            let message = format!("missing required argument `{}`", parameter.keyword);
+            return Err(self.error(message, span));
+        }
+
+        let call = function
+            .compile(Arguments::new(parameters, values))
+            .map_err(|error| self.error(error.message, error.span.unwrap_or(span)))?;
+        let expr = Expr::Call {
+            call,
+            kind: function.return_kind(),
+            location: self.location(span),
+        };
+        Ok((expr, span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(source: &str) -> String {
+        parse_error_in(source, Mode::Program)
+    }
+
+    fn parse_error_in(source: &str, mode: Mode) -> String {
+        parse(source, mode)
+            .err()
+            .expect("expected an error")
+            .message()
+            .to_owned()
+    }
+
+    #[test]
+    fn statement_separators() {
+        let (expressions, variables) =
+            parse(".a = 1; .b = 2\n\n$c = 3 # comment\n", Mode::Program).unwrap();
+        assert_eq!(expressions.len(), 3);
+        assert_eq!(variables, 1);
+    }
+
+    #[test]
+    fn variable_kinds() {
+        let (expressions, _) =
+            parse("$a = 1\nif true { $b = \"x\" }\n$a\n$b", Mode::Program).unwrap();
+        assert_eq!(expressions[2].kind(), Kind::INTEGER);
+        assert_eq!(expressions[3].kind(), Kind::BYTES | Kind::NULL);
+    }
+
+    #[test]
+    fn multiline_expressions() {
+        parse(
+            ".a = [\n  1,\n  2,\n]\n.b = upcase(\n  .c\n)\n.d = 1 +\n  2",
+            Mode::Program,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            parse_error(".a = 1 .b = 2"),
+            "expected end of line, got path"
+        );
+        assert_eq!(parse_error("}"), "unexpected `}`");
+        assert_eq!(
+            parse_error("if true { .a = 1"),
+            "expected `}`, got end of program"
+        );
+        assert_eq!(
+            parse_error("upcase(.a, .b)"),
+            "too many arguments for `upcase`, expected at most 1"
+        );
+        assert_eq!(
+            parse_error("upcase(foo: .a)"),
+            "unknown argument `foo` for `upcase`"
+        );
+        assert_eq!(parse_error("!1"), "expected boolean, got integer");
+        assert_eq!(
+            parse_error(".a = \"unterminated"),
+            "unterminated string literal"
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        parse(".status >= 500 && exists(.error)", Mode::Condition).unwrap();
+        parse("$limit = 500\n.status >= $limit", Mode::Condition).unwrap();
+
+        assert_eq!(
+            parse_error_in(".a = true", Mode::Condition),
+            "fields can't be assigned in a condition"
+        );
+        assert_eq!(
+            parse_error_in("del(.a) == null", Mode::Condition),
+            "`del` can't be used in a condition"
+        );
+        assert_eq!(
+            parse_error_in(".a == 1\nupcase(.b)", Mode::Condition),
+            "expected boolean, got string"
+        );
+        assert_eq!(
+            parse_error_in("# nothing", Mode::Condition),
+            "the condition is empty"
+        );
+    }
+}
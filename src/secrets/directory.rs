@@ -0,0 +1,59 @@
+use super::{LoadError, ReadFile};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+/// Reads each secret from the file of the same name in `path`, as written by
+/// Docker and Kubernetes secret mounts.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DirectoryBackendConfig {
+    pub path: PathBuf,
+}
+
+impl DirectoryBackendConfig {
+    pub(super) fn retrieve(
+        &self,
+        keys: &BTreeSet<String>,
+    ) -> Result<HashMap<String, String>, LoadError> {
+        keys.iter()
+            .map(|key| {
+                if key.starts_with('.') || key.contains(|c| c == '/' || c == '\\') {
+                    return Err(LoadError::InvalidKey {
+                        key: key.clone(),
+                        reason: "must be a file name within the directory",
+                    });
+                }
+
+                let path = self.path.join(key);
+                let contents = fs::read_to_string(&path).context(ReadFile { path })?;
+                let value = contents.trim_end_matches(|c| c == '\n' || c == '\r');
+                Ok((key.clone(), value.to_owned()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_paths_outside_directory() {
+        let config = DirectoryBackendConfig {
+            path: "/etc/vector/secrets".into(),
+        };
+
+        for key in &["../passwd", "nested/key", ".hidden"] {
+            let keys = vec![key.to_string()].into_iter().collect();
+            assert!(matches!(
+                config.retrieve(&keys),
+                Err(LoadError::InvalidKey { .. })
+            ));
+        }
+    }
+}
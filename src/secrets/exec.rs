@@ -0,0 +1,178 @@
+use super::{InvalidResponse, LoadError, Spawn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use snafu::ResultExt;
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Runs a helper command that is sent `{"version": "1.0", "secrets": [<keys>]}` on stdin and
+/// replies on stdout with `{"<key>": {"value": "<secret>", "error": null}, ...}`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExecBackendConfig {
+    pub command: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    5
+}
+
+#[derive(Deserialize, Debug)]
+struct ExecResponse {
+    value: Option<String>,
+    error: Option<String>,
+}
+
+impl ExecBackendConfig {
+    pub(super) fn retrieve(
+        &self,
+        keys: &BTreeSet<String>,
+    ) -> Result<HashMap<String, String>, LoadError> {
+        let command = self.command.join(" ");
+        let (program, args) = self.command.split_first().ok_or_else(|| LoadError::Spawn {
+            command: command.clone(),
+            source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "empty command"),
+        })?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(Spawn {
+                command: command.clone(),
+            })?;
+
+        let request = json!({ "version": "1.0", "secrets": keys });
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(request.to_string().as_bytes())
+            .context(Spawn {
+                command: command.clone(),
+            })?;
+
+        // Read the output on other threads so a chatty command can't block on a full pipe
+        // while we wait for it to exit.
+        let stdout = read_to_end(child.stdout.take().expect("stdout is piped"));
+        let stderr = read_to_end(child.stderr.take().expect("stderr is piped"));
+
+        let deadline = Instant::now() + Duration::from_secs(self.timeout_secs);
+        let status = loop {
+            match child.try_wait().context(Spawn {
+                command: command.clone(),
+            })? {
+                Some(status) => break status,
+                None if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(LoadError::Timeout {
+                        command,
+                        timeout_secs: self.timeout_secs,
+                    });
+                }
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+
+        let stdout = stdout.join().expect("reader thread panicked");
+        let stderr = stderr.join().expect("reader thread panicked");
+        if !status.success() {
+            return Err(LoadError::Failed {
+                command,
+                status,
+                stderr: String::from_utf8_lossy(&stderr).trim().to_owned(),
+            });
+        }
+
+        let response: HashMap<String, ExecResponse> =
+            serde_json::from_slice(&stdout).context(InvalidResponse {
+                command: command.clone(),
+            })?;
+
+        response
+            .into_iter()
+            .map(|(key, response)| match response {
+                ExecResponse {
+                    error: Some(reason),
+                    ..
+                } => Err(LoadError::NotFound { key, reason }),
+                ExecResponse {
+                    value: Some(value), ..
+                } => Ok((key, value)),
+                ExecResponse {
+                    value: None,
+                    error: None,
+                } => Err(LoadError::NotFound {
+                    key,
+                    reason: "no value returned".into(),
+                }),
+            })
+            .collect()
+    }
+}
+
+fn read_to_end(mut reader: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = reader.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn backend(script: &str) -> ExecBackendConfig {
+        ExecBackendConfig {
+            command: vec!["sh".into(), "-c".into(), script.into()],
+            timeout_secs: 1,
+        }
+    }
+
+    fn keys() -> BTreeSet<String> {
+        vec!["api_key".to_owned()].into_iter().collect()
+    }
+
+    #[test]
+    fn retrieves_secrets() {
+        let config = backend(
+            r#"grep -q '"secrets":\["api_key"\]' && echo '{"api_key": {"value": "hunter2", "error": null}}'"#,
+        );
+
+        let secrets = config.retrieve(&keys()).unwrap();
+        assert_eq!(secrets["api_key"], "hunter2");
+    }
+
+    #[test]
+    fn reports_errors() {
+        let config =
+            backend(r#"cat >/dev/null; echo '{"api_key": {"value": null, "error": "denied"}}'"#);
+        assert!(matches!(
+            config.retrieve(&keys()),
+            Err(LoadError::NotFound { reason, .. }) if reason == "denied"
+        ));
+
+        let config = backend("cat >/dev/null; echo oops >&2; exit 3");
+        match config.retrieve(&keys()) {
+            Err(LoadError::Failed { stderr, .. }) => assert_eq!(stderr, "oops"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let config = backend("cat >/dev/null; sleep 5");
+        assert!(matches!(
+            config.retrieve(&keys()),
+            Err(LoadError::Timeout { .. })
+        ));
+    }
+}
@@ -0,0 +1,360 @@
+//! Backends resolving `SECRET[backend.key]` references in config files, configured in the
+//! global `secret` section. References are resolved each time the config is loaded, so a
+//! reload picks up rotated secrets. Only string values are searched for references, so those
+//! in comments and keys are left alone.
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use snafu::Snafu;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt, io,
+    ops::Deref,
+    path::PathBuf,
+};
+
+mod directory;
+mod exec;
+mod vault;
+
+pub use directory::DirectoryBackendConfig;
+pub use exec::ExecBackendConfig;
+pub use vault::VaultBackendConfig;
+
+lazy_static! {
+    static ref RE: Regex = Regex::new(r"SECRET\[([[:word:]-]+)\.([^\]]+)\]").unwrap();
+}
+
+const REDACTED: &str = "**REDACTED**";
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretBackendConfig {
+    Exec(ExecBackendConfig),
+    Directory(DirectoryBackendConfig),
+    Vault(VaultBackendConfig),
+}
+
+impl SecretBackendConfig {
+    fn retrieve(&self, keys: &BTreeSet<String>) -> Result<HashMap<String, String>, LoadError> {
+        match self {
+            Self::Exec(config) => config.retrieve(keys),
+            Self::Directory(config) => config.retrieve(keys),
+            Self::Vault(config) => config.retrieve(keys),
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum LoadError {
+    #[snafu(display("Unknown secret backend {:?}", backend))]
+    UnknownBackend { backend: String },
+    #[snafu(display("Could not run {:?}: {}", command, source))]
+    Spawn { command: String, source: io::Error },
+    #[snafu(display("{:?} did not finish within {} seconds", command, timeout_secs))]
+    Timeout { command: String, timeout_secs: u64 },
+    #[snafu(display("{:?} exited with {}: {}", command, status, stderr))]
+    Failed {
+        command: String,
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    #[snafu(display("Invalid response from {:?}: {}", command, source))]
+    InvalidResponse {
+        command: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Could not read secret file {:?}: {}", path, source))]
+    ReadFile { path: PathBuf, source: io::Error },
+    #[snafu(display("Invalid secret key {:?}: {}", key, reason))]
+    InvalidKey { key: String, reason: &'static str },
+    #[snafu(display("Request to {} failed: {}", url, source))]
+    Request { url: String, source: crate::Error },
+    #[snafu(display("Secret {:?} not found: {}", key, reason))]
+    NotFound { key: String, reason: String },
+}
+
+/// The secrets resolved for a config, keyed by backend and secret key.
+#[derive(Default)]
+pub struct Secrets {
+    values: HashMap<(String, String), String>,
+}
+
+impl Secrets {
+    /// Resolves every reference in the parsed `configs`, asking each backend for all of its
+    /// keys at once.
+    pub fn resolve<'a>(
+        backends: &IndexMap<String, SecretBackendConfig>,
+        configs: impl IntoIterator<Item = &'a toml::Value>,
+    ) -> Result<Self, Vec<String>> {
+        let mut references = IndexMap::<String, BTreeSet<String>>::new();
+        for config in configs {
+            for_each_string(config, &mut |string| {
+                for caps in RE.captures_iter(string) {
+                    references
+                        .entry(caps[1].to_owned())
+                        .or_default()
+                        .insert(caps[2].to_owned());
+                }
+            });
+        }
+
+        let mut values = HashMap::new();
+        let mut errors = Vec::new();
+        for (backend, keys) in references {
+            let retrieved = match backends.get(&backend) {
+                Some(config) => config.retrieve(&keys),
+                None => Err(LoadError::UnknownBackend {
+                    backend: backend.clone(),
+                }),
+            };
+            match retrieved {
+                Ok(mut retrieved) => {
+                    for key in keys {
+                        match retrieved.remove(&key) {
+                            Some(value) => {
+                                values.insert((backend.clone(), key), value);
+                            }
+                            None => errors.push(format!(
+                                "Secret backend {:?} did not return {:?}",
+                                backend, key
+                            )),
+                        }
+                    }
+                }
+                Err(error) => errors.push(format!("Secret backend {:?}: {}", backend, error)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self { values })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Substitutes resolved secrets for their references in the string values of `config`,
+    /// returning whether it held any.
+    pub fn interpolate(&self, config: &mut toml::Value) -> bool {
+        let mut found = false;
+        for_each_string_mut(config, &mut |string| {
+            if !RE.is_match(string) {
+                return;
+            }
+            found = true;
+            *string = RE
+                .replace_all(string, |caps: &Captures<'_>| {
+                    self.values
+                        .get(&(caps[1].to_owned(), caps[2].to_owned()))
+                        .cloned()
+                        .unwrap_or_else(|| caps[0].to_owned())
+                })
+                .into_owned();
+        });
+        found
+    }
+
+    pub fn redactor(&self) -> Redactor {
+        Redactor {
+            values: self
+                .values
+                .values()
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn for_each_string<'a>(value: &'a toml::Value, f: &mut impl FnMut(&'a str)) {
+    match value {
+        toml::Value::String(string) => f(string),
+        toml::Value::Array(values) => {
+            for value in values {
+                for_each_string(value, f);
+            }
+        }
+        toml::Value::Table(table) => {
+            for value in table.values() {
+                for_each_string(value, f);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn for_each_string_mut(value: &mut toml::Value, f: &mut impl FnMut(&mut String)) {
+    match value {
+        toml::Value::String(string) => f(string),
+        toml::Value::Array(values) => {
+            for value in values {
+                for_each_string_mut(value, f);
+            }
+        }
+        toml::Value::Table(table) => {
+            for value in table.values_mut() {
+                for_each_string_mut(value, f);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Scrubs resolved secret values from messages shown to users.
+#[derive(Clone, Default)]
+pub struct Redactor {
+    values: Vec<String>,
+}
+
+impl Redactor {
+    pub fn redact(&self, message: &str) -> String {
+        self.values
+            .iter()
+            .fold(message.to_owned(), |message, value| {
+                message.replace(value.as_str(), REDACTED)
+            })
+    }
+
+    pub fn extend(&mut self, other: Redactor) {
+        self.values.extend(other.values);
+    }
+}
+
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Redactor({} secrets)", self.values.len())
+    }
+}
+
+/// A string, such as an API key, that is left out of `Debug` output.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SensitiveString(String);
+
+impl SensitiveString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for SensitiveString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SensitiveString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SensitiveString {
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+impl fmt::Debug for SensitiveString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for SensitiveString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+impl Serialize for SensitiveString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn backends(dir: &std::path::Path) -> IndexMap<String, SecretBackendConfig> {
+        vec![(
+            "files".to_owned(),
+            SecretBackendConfig::Directory(DirectoryBackendConfig {
+                path: dir.to_owned(),
+            }),
+        )]
+        .into_iter()
+        .collect()
+    }
+
+    fn parse(source: &str) -> toml::Value {
+        toml::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn resolves_references_in_string_values() {
+        let dir = crate::test_util::temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("api_key"), "abc\"123\n").unwrap();
+
+        // The commented out reference would fail to resolve if it were looked up.
+        let mut config = parse(
+            r#"
+# api_key = "SECRET[files.commented]"
+api_key = "SECRET[files.api_key]"
+literal = 'key-SECRET[files.api_key]'
+multiline = """
+SECRET[files.api_key]"""
+other = "SECRET[unused]"
+"#,
+        );
+        let secrets = Secrets::resolve(&backends(&dir), vec![&config]).unwrap();
+
+        assert!(secrets.interpolate(&mut config));
+        assert_eq!(config["api_key"].as_str(), Some("abc\"123"));
+        assert_eq!(config["literal"].as_str(), Some("key-abc\"123"));
+        assert_eq!(config["multiline"].as_str(), Some("abc\"123"));
+        assert_eq!(config["other"].as_str(), Some("SECRET[unused]"));
+        assert!(!secrets.interpolate(&mut parse(r#"plain = "value""#)));
+        assert_eq!(
+            secrets.redactor().redact("invalid key abc\"123"),
+            "invalid key **REDACTED**"
+        );
+    }
+
+    #[test]
+    fn reports_missing_secrets() {
+        let dir = crate::test_util::temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = parse("a = \"SECRET[files.missing]\"\nb = \"SECRET[nope.key]\"");
+        let errors = Secrets::resolve(&backends(&dir), vec![&config])
+            .err()
+            .unwrap();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("Secret backend \"files\": Could not read secret file"));
+        assert_eq!(
+            errors[1],
+            "Secret backend \"nope\": Unknown secret backend \"nope\""
+        );
+    }
+
+    #[test]
+    fn sensitive_strings_are_redacted() {
+        let key = SensitiveString::from("hunter2");
+        assert_eq!(format!("{:?}", key), "\"**REDACTED**\"");
+        assert_eq!(key.as_str(), "hunter2");
+        assert_eq!(serde_json::to_string(&key).unwrap(), "\"hunter2\"");
+    }
+}
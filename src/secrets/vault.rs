@@ -0,0 +1,206 @@
+use super::{LoadError, Request, SensitiveString};
+use crate::{
+    dns::Resolver,
+    sinks::util::http::HttpClient,
+    tls::{TlsOptions, TlsSettings},
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::{
+    collections::{BTreeSet, HashMap},
+    thread,
+    time::Duration,
+};
+
+/// Reads secrets from a Vault KV version 2 secrets engine, or any server speaking its API.
+/// Keys have the form `<path>#<field>`, with the field defaulting to `value`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct VaultBackendConfig {
+    pub address: String,
+    pub token: SensitiveString,
+    #[serde(default = "default_mount")]
+    pub mount: String,
+    pub namespace: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    pub tls: Option<TlsOptions>,
+}
+
+fn default_mount() -> String {
+    "secret".into()
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Deserialize, Debug)]
+struct KvResponse {
+    data: KvData,
+}
+
+#[derive(Deserialize, Debug)]
+struct KvData {
+    data: HashMap<String, serde_json::Value>,
+}
+
+impl VaultBackendConfig {
+    pub(super) fn retrieve(
+        &self,
+        keys: &BTreeSet<String>,
+    ) -> Result<HashMap<String, String>, LoadError> {
+        let mut paths = HashMap::<&str, Vec<(&str, &String)>>::new();
+        for key in keys {
+            let mut parts = key.splitn(2, '#');
+            let path = parts.next().unwrap_or_default();
+            let field = parts.next().unwrap_or("value");
+            if path.is_empty() {
+                return Err(LoadError::InvalidKey {
+                    key: key.clone(),
+                    reason: "expected `<path>#<field>`",
+                });
+            }
+            paths.entry(path).or_default().push((field, key));
+        }
+
+        let mut secrets = HashMap::new();
+        for (path, fields) in paths {
+            let mut data = self.read(path)?;
+            for (field, key) in fields {
+                match data.remove(field) {
+                    Some(serde_json::Value::String(value)) => {
+                        secrets.insert(key.clone(), value);
+                    }
+                    Some(_) => {
+                        return Err(LoadError::NotFound {
+                            key: key.clone(),
+                            reason: "the field is not a string".into(),
+                        })
+                    }
+                    None => {
+                        return Err(LoadError::NotFound {
+                            key: key.clone(),
+                            reason: format!("no field {:?} at {:?}", field, path),
+                        })
+                    }
+                }
+            }
+        }
+        Ok(secrets)
+    }
+
+    fn read(&self, path: &str) -> Result<HashMap<String, serde_json::Value>, LoadError> {
+        let url = format!(
+            "{}/v1/{}/data/{}",
+            self.address.trim_end_matches('/'),
+            self.mount.trim_matches('/'),
+            path.trim_start_matches('/')
+        );
+
+        // Configs are loaded from both inside and outside the runtime, so the request is
+        // made on a thread with a runtime of its own.
+        let config = self.clone();
+        let request_url = url.clone();
+        let response = thread::spawn(move || -> crate::Result<_> {
+            let mut runtime = tokio::runtime::Builder::new()
+                .basic_scheduler()
+                .enable_all()
+                .build()?;
+            runtime.block_on(config.fetch(request_url))
+        })
+        .join()
+        .expect("vault request thread panicked");
+
+        match response.context(Request { url: url.clone() })? {
+            (StatusCode::OK, body) => serde_json::from_slice::<KvResponse>(&body)
+                .map(|response| response.data.data)
+                .map_err(|error| LoadError::Request {
+                    url,
+                    source: error.into(),
+                }),
+            (StatusCode::NOT_FOUND, _) => Err(LoadError::NotFound {
+                key: path.into(),
+                reason: format!("{} returned 404", url),
+            }),
+            (status, body) => Err(LoadError::Request {
+                url,
+                source: format!("{}: {}", status, String::from_utf8_lossy(&body).trim()).into(),
+            }),
+        }
+    }
+
+    async fn fetch(self, url: String) -> crate::Result<(StatusCode, bytes::Bytes)> {
+        let tls = TlsSettings::from_options(&self.tls)?;
+        let mut client = HttpClient::new(Resolver, tls)?;
+
+        let mut request = http::Request::get(url).header("X-Vault-Token", self.token.as_str());
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace.as_str());
+        }
+        let request = request.body(hyper::Body::empty())?;
+
+        let response =
+            tokio::time::timeout(Duration::from_secs(self.timeout_secs), client.send(request))
+                .await??;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok((status, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sinks::util::test::build_test_server, test_util::next_addr};
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn reads_kv_v2_secrets() {
+        let address = next_addr();
+        let (mut rx, _trigger, server) = build_test_server(address);
+        tokio::spawn(server);
+
+        let config = VaultBackendConfig {
+            address: format!("http://{}", address),
+            token: "root".into(),
+            mount: default_mount(),
+            namespace: None,
+            timeout_secs: 5,
+            tls: None,
+        };
+        let keys = vec!["datadog#api_key".to_owned(), "datadog#app_key".to_owned()]
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+
+        // The test server replies with an empty body, which is not a valid KV response, but
+        // it lets us check the request that was made.
+        let result = tokio::task::spawn_blocking(move || config.retrieve(&keys))
+            .await
+            .unwrap();
+        assert!(matches!(result, Err(LoadError::Request { .. })));
+
+        let (parts, _) = rx.next().await.unwrap();
+        assert_eq!(parts.uri.path(), "/v1/secret/data/datadog");
+        assert_eq!(parts.headers["X-Vault-Token"], "root");
+    }
+
+    #[test]
+    fn rejects_empty_paths() {
+        let config = VaultBackendConfig {
+            address: "http://localhost:8200".into(),
+            token: "root".into(),
+            mount: default_mount(),
+            namespace: None,
+            timeout_secs: 5,
+            tls: None,
+        };
+        let keys = vec!["#api_key".to_owned()].into_iter().collect();
+
+        assert!(matches!(
+            config.retrieve(&keys),
+            Err(LoadError::InvalidKey { .. })
+        ));
+    }
+}
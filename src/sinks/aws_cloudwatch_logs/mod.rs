@@ -8,8 +8,9 @@ use crate::{
     sinks::util::{
         encoding::{EncodingConfig, EncodingConfiguration},
         retries::{FixedRetryPolicy, RetryLogic},
-        rusoto, BatchConfig, BatchSettings, Compression, EncodedLength, PartitionBatchSink,
-        PartitionBuffer, PartitionInnerBuffer, TowerRequestConfig, TowerRequestSettings, VecBuffer,
+        rusoto, BatchConfig, BatchSettings, Compression, EncodedEvent, EncodedLength,
+        PartitionBatchSink, PartitionBuffer, PartitionInnerBuffer, TowerRequestConfig,
+        TowerRequestSettings, VecBuffer,
     },
     template::Template,
 };
@@ -189,7 +190,9 @@ impl SinkConfig for CloudwatchLogsSinkConfig {
             let svc_sink = PartitionBatchSink::new(svc, buffer, batch.timeout, cx.acker())
                 .sink_map_err(|e| error!("Fatal cloudwatchlogs sink error: {}", e))
                 .with_flat_map(move |event| {
-                    iter_ok(partition_encode(event, &encoding, &log_group, &log_stream))
+                    iter_ok(EncodedEvent::encode(event, |event| {
+                        partition_encode(event, &encoding, &log_group, &log_stream)
+                    }))
                 });
             Box::new(svc_sink)
         };
@@ -4,13 +4,13 @@ use crate::{
     event::metric::{Metric, MetricKind, MetricValue},
     region::RegionOrEndpoint,
     sinks::util::{
-        retries::RetryLogic, rusoto, BatchConfig, BatchSettings, Compression, MetricBuffer,
-        TowerRequestConfig,
+        retries::RetryLogic, rusoto, BatchConfig, BatchSettings, Compression, EncodedEvent,
+        MetricBuffer, TowerRequestConfig,
     },
 };
 use chrono::{DateTime, SecondsFormat, Utc};
 use futures::{future::BoxFuture, FutureExt, TryFutureExt};
-use futures01::Sink;
+use futures01::{stream::iter_ok, Sink};
 use lazy_static::lazy_static;
 use rusoto_cloudwatch::{
     CloudWatch, CloudWatchClient, Dimension, MetricDatum, PutMetricDataError, PutMetricDataInput,
@@ -133,7 +133,8 @@ impl CloudWatchMetricsSvc {
                 batch.timeout,
                 cx.acker(),
             )
-            .sink_map_err(|e| error!("CloudwatchMetrics sink error: {}", e));
+            .sink_map_err(|e| error!("CloudwatchMetrics sink error: {}", e))
+            .with_flat_map(|event| iter_ok(EncodedEvent::encode(event, Some)));
 
         Ok(Box::new(sink))
     }
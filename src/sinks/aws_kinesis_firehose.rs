@@ -8,7 +8,8 @@ use crate::{
         retries::RetryLogic,
         rusoto,
         sink::Response,
-        BatchConfig, BatchSettings, Compression, EncodedLength, TowerRequestConfig, VecBuffer,
+        BatchConfig, BatchSettings, Compression, EncodedEvent, EncodedLength, TowerRequestConfig,
+        VecBuffer,
     },
 };
 use bytes::Bytes;
@@ -147,7 +148,9 @@ impl KinesisFirehoseService {
                 cx.acker(),
             )
             .sink_map_err(|e| error!("Fatal kinesis firehose sink error: {}", e))
-            .with_flat_map(move |e| iter_ok(encode_event(e, &encoding)));
+            .with_flat_map(move |e| {
+                iter_ok(EncodedEvent::encode(e, |e| encode_event(e, &encoding)))
+            });
 
         Ok(sink)
     }
@@ -9,7 +9,8 @@ use crate::{
         retries::RetryLogic,
         rusoto,
         sink::Response,
-        BatchConfig, BatchSettings, Compression, EncodedLength, TowerRequestConfig, VecBuffer,
+        BatchConfig, BatchSettings, Compression, EncodedEvent, EncodedLength, TowerRequestConfig,
+        VecBuffer,
     },
 };
 use bytes::Bytes;
@@ -155,7 +156,11 @@ impl KinesisService {
                 cx.acker(),
             )
             .sink_map_err(|e| error!("Fatal kinesis streams sink error: {}", e))
-            .with_flat_map(move |e| iter_ok(encode_event(e, &partition_key_field, &encoding)));
+            .with_flat_map(move |e| {
+                iter_ok(EncodedEvent::encode(e, |e| {
+                    encode_event(e, &partition_key_field, &encoding)
+                }))
+            });
 
         Ok(sink)
     }
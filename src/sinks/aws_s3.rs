@@ -9,8 +9,9 @@ use crate::{
         retries::RetryLogic,
         rusoto,
         sink::Response,
-        BatchConfig, BatchSettings, Buffer, Compression, InFlightLimit, PartitionBatchSink,
-        PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt, TowerRequestConfig,
+        BatchConfig, BatchSettings, Buffer, Compression, EncodedEvent, InFlightLimit,
+        PartitionBatchSink, PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt,
+        TowerRequestConfig,
     },
     template::Template,
 };
@@ -210,7 +211,11 @@ impl S3SinkConfig {
         let buffer = PartitionBuffer::new(Buffer::new(batch.size, self.compression));
 
         let sink = PartitionBatchSink::new(svc, buffer, batch.timeout, cx.acker())
-            .with_flat_map(move |e| iter_ok(encode_event(e, &key_prefix, &encoding)))
+            .with_flat_map(move |e| {
+                iter_ok(EncodedEvent::encode(e, |e| {
+                    encode_event(e, &key_prefix, &encoding)
+                }))
+            })
             .sink_map_err(|error| error!("Sink failed to flush: {}", error));
 
         Ok(Box::new(sink))
@@ -1,15 +1,15 @@
 use crate::{
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
-    event::{self, Event},
+    event::{self, Event, EventStatus},
     sinks::util::{
         encoding::{EncodingConfig, EncodingConfiguration},
-        StreamSink,
+        EncodedEvent, EncodedPayload, StreamSink, TestEncoder,
     },
 };
 use async_trait::async_trait;
 use futures::pin_mut;
 use futures::stream::{Stream, StreamExt};
-use futures01::future;
+use futures01::{future, Sink};
 use serde::{Deserialize, Serialize};
 use tokio::io::{self, AsyncWriteExt};
 
@@ -55,7 +55,9 @@ impl SinkConfig for ConsoleSinkConfig {
 
         let sink = WriterSink { output, encoding };
         let sink = streaming_sink::compat::adapt_to_topology(sink);
-        let sink = StreamSink::new(sink, cx.acker());
+        // The events keep their finalizers, which are resolved once they are written.
+        let sink = StreamSink::new(sink, cx.acker())
+            .with(|event: Event| Ok::<_, ()>(EncodedEvent::new(event)));
 
         Ok((Box::new(sink), Box::new(future::ok(()))))
     }
@@ -67,6 +69,26 @@ impl SinkConfig for ConsoleSinkConfig {
     fn sink_type(&self) -> &'static str {
         "console"
     }
+
+    fn build_test_encoder(&self) -> crate::Result<Option<Box<dyn TestEncoder>>> {
+        Ok(Some(Box::new(ConsoleTestEncoder {
+            encoding: self.encoding.clone(),
+        })))
+    }
+}
+
+struct ConsoleTestEncoder {
+    encoding: EncodingConfig<Encoding>,
+}
+
+impl TestEncoder for ConsoleTestEncoder {
+    fn encode(&mut self, event: Event) -> crate::Result<Option<EncodedPayload>> {
+        let line = encode_event(event, &self.encoding)?;
+        Ok(Some(EncodedPayload {
+            body: line.into(),
+            ..Default::default()
+        }))
+    }
 }
 
 fn encode_event(
@@ -118,8 +140,14 @@ impl StreamingSink for WriterSink {
         let output = &mut self.output;
         pin_mut!(output);
         pin_mut!(input);
-        while let Some(event) = input.next().await {
-            write_event_to_output(&mut output, event, &self.encoding).await?
+        while let Some(mut event) = input.next().await {
+            let finalizers = event.take_finalizers();
+            let result = write_event_to_output(&mut output, event, &self.encoding).await;
+            finalizers.update_status(match result {
+                Ok(()) => EventStatus::Delivered,
+                Err(_) => EventStatus::Rejected,
+            });
+            result?
         }
         Ok(())
     }
@@ -1,11 +1,12 @@
 use crate::{
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
     event::{log_schema, Event},
+    secrets::SensitiveString,
     sinks::util::{
         self,
         encoding::{EncodingConfig, EncodingConfiguration},
         tcp::TcpSink,
-        Encoding, StreamSink, UriSerde,
+        EncodedEvent, Encoding, StreamSink, UriSerde,
     },
     tls::{MaybeTlsSettings, TlsConfig},
 };
@@ -18,7 +19,7 @@ use serde::{Deserialize, Serialize};
 #[serde(deny_unknown_fields)]
 pub struct DatadogLogsConfig {
     endpoint: Option<UriSerde>,
-    api_key: String,
+    api_key: SensitiveString,
     encoding: EncodingConfig<Encoding>,
     tls: Option<TlsConfig>,
 }
@@ -57,8 +58,11 @@ impl SinkConfig for DatadogLogsConfig {
         let encoding = self.encoding.clone();
         let api_key = self.api_key.clone();
 
-        let sink = StreamSink::new(sink, cx.acker())
-            .with_flat_map(move |e| iter_ok(encode_event(e, &api_key, &encoding)));
+        let sink = StreamSink::new(sink, cx.acker()).with_flat_map(move |e| {
+            iter_ok(EncodedEvent::encode(e, |e| {
+                encode_event(e, &api_key, &encoding)
+            }))
+        });
 
         Ok((Box::new(sink), Box::new(healthcheck.compat())))
     }
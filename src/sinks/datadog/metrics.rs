@@ -4,6 +4,7 @@ use crate::{
         metric::{Metric, MetricKind, MetricValue},
         Event,
     },
+    secrets::SensitiveString,
     sinks::util::{
         http::{BatchedHttpSink, HttpClient, HttpSink},
         BatchConfig, BatchSettings, MetricBuffer, TowerRequestConfig,
@@ -38,7 +39,7 @@ pub struct DatadogConfig {
     // Deprecated name
     #[serde(alias = "host", default = "default_endpoint")]
     pub endpoint: String,
-    pub api_key: String,
+    pub api_key: SensitiveString,
     #[serde(default)]
     pub batch: BatchConfig,
     #[serde(default)]
@@ -165,7 +166,7 @@ impl HttpSink for DatadogSink {
 
         Request::post(self.uri.clone())
             .header("Content-Type", "application/json")
-            .header("DD-API-KEY", self.config.api_key.clone())
+            .header("DD-API-KEY", self.config.api_key.as_str())
             .body(body)
             .map_err(Into::into)
     }
@@ -185,7 +186,7 @@ async fn healthcheck(config: DatadogConfig, mut client: HttpClient) -> crate::Re
         .context(super::UriParseError)?;
 
     let request = Request::get(uri)
-        .header("DD-API-KEY", config.api_key)
+        .header("DD-API-KEY", config.api_key.as_str())
         .body(hyper::Body::empty())
         .unwrap();
 
@@ -10,7 +10,7 @@ use crate::{
         retries::{RetryAction, RetryLogic},
         rusoto, BatchConfig, BatchSettings, Buffer, Compression, TowerRequestConfig,
     },
-    template::{Template, TemplateError},
+    template::{OnMissing, Template, TemplateError},
     tls::{TlsOptions, TlsSettings},
 };
 use bytes::Bytes;
@@ -31,6 +31,7 @@ use serde_json::json;
 use snafu::{ResultExt, Snafu};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(deny_unknown_fields)]
@@ -61,6 +62,9 @@ pub struct ElasticSearchConfig {
 
     pub aws: Option<RegionOrEndpoint>,
     pub tls: Option<TlsOptions>,
+
+    #[serde(default)]
+    pub on_missing: OnMissing,
 }
 
 lazy_static! {
@@ -176,7 +180,7 @@ impl HttpSink for ElasticSearchCommon {
 
         let index = self
             .index
-            .render_string(&event)
+            .render_string_with(&event, &self.config.on_missing)
             .map_err(|missing_keys| {
                 emit!(ElasticSearchMissingKeys { keys: missing_keys });
             })
@@ -348,6 +352,10 @@ impl RetryLogic for ElasticSearchRetryLogic {
             _ => RetryAction::DontRetry(format!("response status: {}", status)),
         }
     }
+
+    fn retry_after(&self, response: &Self::Response) -> Option<Duration> {
+        crate::sinks::util::http::retry_after(response)
+    }
 }
 
 impl ElasticSearchCommon {
@@ -1,18 +1,19 @@
 use crate::expiring_hash_map::ExpiringHashMap;
 use crate::{
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
-    event::{self, Event},
+    event::{self, Event, EventStatus},
     sinks::util::{
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
-        StreamSink,
+        EncodedEvent, EncodedPayload, StreamSink, TestEncoder,
     },
-    template::Template,
+    template::{OnMissing, Template},
 };
 use async_compression::tokio_02::write::GzipEncoder;
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::pin_mut;
 use futures::stream::{Stream, StreamExt};
+use futures01::Sink;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 use tokio::{
@@ -40,6 +41,11 @@ pub struct FileSinkConfig {
         skip_serializing_if = "crate::serde::skip_serializing_if_default"
     )]
     pub compression: Compression,
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serde::skip_serializing_if_default"
+    )]
+    pub on_missing: OnMissing,
 }
 
 inventory::submit! {
@@ -119,7 +125,9 @@ impl SinkConfig for FileSinkConfig {
     fn build(&self, cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
         let sink = FileSink::new(&self);
         let sink = streaming_sink::compat::adapt_to_topology(sink);
-        let sink = StreamSink::new(sink, cx.acker());
+        // The events keep their finalizers, which are resolved once they are written.
+        let sink = StreamSink::new(sink, cx.acker())
+            .with(|event: Event| Ok::<_, ()>(EncodedEvent::new(event)));
         Ok((Box::new(sink), Box::new(futures01::future::ok(()))))
     }
 
@@ -130,6 +138,37 @@ impl SinkConfig for FileSinkConfig {
     fn sink_type(&self) -> &'static str {
         "file"
     }
+
+    fn build_test_encoder(&self) -> crate::Result<Option<Box<dyn TestEncoder>>> {
+        Ok(Some(Box::new(FileTestEncoder {
+            path: self.path.clone(),
+            encoding: self.encoding.clone(),
+            on_missing: self.on_missing.clone(),
+        })))
+    }
+}
+
+/// Renders the path each event would be written to along with the line
+/// written for it. Compression is not applied.
+struct FileTestEncoder {
+    path: Template,
+    encoding: EncodingConfigWithDefault<Encoding>,
+    on_missing: OnMissing,
+}
+
+impl TestEncoder for FileTestEncoder {
+    fn encode(&mut self, event: Event) -> crate::Result<Option<EncodedPayload>> {
+        let path = self
+            .path
+            .render_string_with(&event, &self.on_missing)
+            .map_err(|missing_keys| format!("keys {:?} do not exist on the event", missing_keys))?;
+
+        Ok(Some(EncodedPayload {
+            body: encode_event(&self.encoding, event).into(),
+            partition: Some(path),
+            ..Default::default()
+        }))
+    }
 }
 
 #[derive(Debug)]
@@ -139,6 +178,7 @@ pub struct FileSink {
     idle_timeout: Duration,
     files: ExpiringHashMap<Bytes, OutFile>,
     compression: Compression,
+    on_missing: OnMissing,
 }
 
 impl FileSink {
@@ -149,14 +189,15 @@ impl FileSink {
             idle_timeout: Duration::from_secs(config.idle_timeout_secs.unwrap_or(30)),
             files: ExpiringHashMap::default(),
             compression: config.compression,
+            on_missing: config.on_missing.clone(),
         }
     }
 
     /// Uses pass the `event` to `self.path` template to obtain the file path
     /// to store the event as.
     fn partition_event(&mut self, event: &Event) -> Option<bytes::Bytes> {
-        let bytes = match self.path.render(event) {
-            Ok(b) => b,
+        let bytes = match self.path.render_string_with(event, &self.on_missing) {
+            Ok(path) => Bytes::from(path),
             Err(missing_keys) => {
                 warn!(
                     message = "Keys do not exist on the event; dropping event.",
@@ -224,7 +265,8 @@ impl FileSink {
         Ok(())
     }
 
-    async fn process_event(&mut self, event: Event) {
+    async fn process_event(&mut self, mut event: Event) {
+        let finalizers = event.take_finalizers();
         let path = match self.partition_event(&event) {
             Some(path) => path,
             None => {
@@ -251,6 +293,7 @@ impl FileSink {
                     // Maybe other events will work though! Just log
                     // the error and skip this event.
                     error!(message = "Unable to open the file.", ?path, %error);
+                    finalizers.update_status(EventStatus::Rejected);
                     return;
                 }
             };
@@ -262,8 +305,12 @@ impl FileSink {
         };
 
         trace!(message = "Writing an event to file.", ?path);
-        if let Err(error) = write_event_to_file(file, event, &self.encoding).await {
-            error!(message = "Failed to write file.", ?path, %error);
+        match write_event_to_file(file, event, &self.encoding).await {
+            Ok(()) => finalizers.update_status(EventStatus::Delivered),
+            Err(error) => {
+                error!(message = "Failed to write file.", ?path, %error);
+                finalizers.update_status(EventStatus::Rejected);
+            }
         }
     }
 }
@@ -340,6 +387,7 @@ mod tests {
             idle_timeout_secs: None,
             encoding: Encoding::Text.into(),
             compression: Compression::None,
+            on_missing: OnMissing::Drop,
         };
 
         let mut sink = FileSink::new(&config);
@@ -365,6 +413,7 @@ mod tests {
             idle_timeout_secs: None,
             encoding: Encoding::Text.into(),
             compression: Compression::Gzip,
+            on_missing: OnMissing::Drop,
         };
 
         let mut sink = FileSink::new(&config);
@@ -396,6 +445,7 @@ mod tests {
             idle_timeout_secs: None,
             encoding: Encoding::Text.into(),
             compression: Compression::None,
+            on_missing: OnMissing::Drop,
         };
 
         let mut sink = FileSink::new(&config);
@@ -464,6 +514,60 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn partition_fallback() {
+        trace_init();
+
+        let directory = temp_dir();
+
+        let mut template = directory.to_string_lossy().to_string();
+        template.push_str("/{{ level | downcase }}.log");
+
+        let mut fallback = directory.to_string_lossy().to_string();
+        fallback.push_str("/unrouted.log");
+
+        let config = FileSinkConfig {
+            path: template.try_into().unwrap(),
+            idle_timeout_secs: None,
+            encoding: Encoding::Text.into(),
+            compression: Compression::None,
+            on_missing: OnMissing::Fallback(fallback),
+        };
+
+        let mut sink = FileSink::new(&config);
+
+        let (mut input, _events) = random_events_with_stream(32, 2);
+        input[0].as_mut_log().insert("level", "ERROR");
+
+        let events = stream::iter(input.clone().into_iter());
+        sink.run(events).await.unwrap();
+
+        assert_eq!(
+            lines_from_file(&directory.join("error.log")),
+            vec![input[0].as_log()[&event::log_schema().message_key()].to_string_lossy()]
+        );
+        assert_eq!(
+            lines_from_file(&directory.join("unrouted.log")),
+            vec![input[1].as_log()[&event::log_schema().message_key()].to_string_lossy()]
+        );
+    }
+
+    #[test]
+    fn test_encoder_fallback() {
+        let config = FileSinkConfig {
+            path: "{{ level }}.log".try_into().unwrap(),
+            idle_timeout_secs: None,
+            encoding: Encoding::Text.into(),
+            compression: Compression::None,
+            on_missing: OnMissing::Fallback("unrouted.log".into()),
+        };
+        let mut encoder = config.build_test_encoder().unwrap().unwrap();
+
+        let payload = encoder.encode(Event::from("hello")).unwrap().unwrap();
+        assert_eq!(payload.partition, Some("unrouted.log".into()));
+        assert_eq!(payload.body, Bytes::from("hello"));
+    }
+
     #[tokio::test]
     async fn reopening() {
         use pretty_assertions::assert_eq;
@@ -477,6 +581,7 @@ mod tests {
             idle_timeout_secs: Some(1),
             encoding: Encoding::Text.into(),
             compression: Compression::None,
+            on_missing: OnMissing::Drop,
         };
 
         let mut sink = FileSink::new(&config);
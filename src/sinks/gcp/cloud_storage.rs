@@ -8,8 +8,9 @@ use crate::{
             encoding::{EncodingConfig, EncodingConfiguration},
             http::{HttpClient, HttpClientFuture},
             retries::{RetryAction, RetryLogic},
-            BatchConfig, BatchSettings, Buffer, Compression, InFlightLimit, PartitionBatchSink,
-            PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt, TowerRequestConfig,
+            BatchConfig, BatchSettings, Buffer, Compression, EncodedEvent, InFlightLimit,
+            PartitionBatchSink, PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt,
+            TowerRequestConfig,
         },
         Healthcheck, RouterSink,
     },
@@ -31,6 +32,7 @@ use snafu::{ResultExt, Snafu};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::task::Poll;
+use std::time::Duration;
 use tower::{Service, ServiceBuilder};
 use tracing::field;
 use uuid::Uuid;
@@ -225,7 +227,11 @@ impl GcsSink {
 
         let sink = PartitionBatchSink::new(svc, buffer, batch.timeout, cx.acker())
             .sink_map_err(|e| error!("Fatal gcs sink error: {}", e))
-            .with_flat_map(move |e| iter_ok(encode_event(e, &key_prefix, &encoding)));
+            .with_flat_map(move |e| {
+                iter_ok(EncodedEvent::encode(e, |e| {
+                    encode_event(e, &key_prefix, &encoding)
+                }))
+            });
 
         Ok(Box::new(sink))
     }
@@ -459,6 +465,10 @@ impl RetryLogic for GcsRetryLogic {
             _ => RetryAction::DontRetry(format!("response status: {}", status)),
         }
     }
+
+    fn retry_after(&self, response: &Self::Response) -> Option<Duration> {
+        crate::sinks::util::http::retry_after(response)
+    }
 }
 
 #[cfg(test)]
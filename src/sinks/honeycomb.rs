@@ -1,6 +1,7 @@
 use crate::{
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
     event::{log_schema, Event, Value},
+    secrets::SensitiveString,
     sinks::util::{
         http::{BatchedHttpSink, HttpClient, HttpSink},
         BatchConfig, BatchSettings, BoxedRawValue, JsonArrayBuffer, TowerRequestConfig, UriSerde,
@@ -18,7 +19,7 @@ lazy_static::lazy_static! {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HoneycombConfig {
-    api_key: String,
+    api_key: SensitiveString,
 
     // TODO: we probably want to make this a template
     // but this limits us in how we can do our healthcheck.
@@ -93,7 +94,7 @@ impl HttpSink for HoneycombConfig {
 
     async fn build_request(&self, events: Self::Output) -> crate::Result<http::Request<Vec<u8>>> {
         let uri = self.build_uri();
-        let request = Request::post(uri).header("X-Honeycomb-Team", self.api_key.clone());
+        let request = Request::post(uri).header("X-Honeycomb-Team", self.api_key.as_str());
 
         let buf = serde_json::to_vec(&events).unwrap();
 
@@ -4,8 +4,8 @@ use crate::{
     sinks::util::{
         encoding::{EncodingConfig, EncodingConfiguration},
         http::{Auth, BatchedHttpSink, HttpClient, HttpSink},
-        BatchConfig, BatchSettings, Buffer, Compression, InFlightLimit, TowerRequestConfig,
-        UriSerde,
+        BatchConfig, BatchSettings, Buffer, Compression, HttpTestEncoder, InFlightLimit,
+        TestEncoder, TowerRequestConfig, UriSerde,
     },
     tls::{TlsOptions, TlsSettings},
 };
@@ -146,6 +146,21 @@ impl SinkConfig for HttpSinkConfig {
     fn sink_type(&self) -> &'static str {
         "http"
     }
+
+    fn build_test_encoder(&self) -> crate::Result<Option<Box<dyn TestEncoder>>> {
+        validate_headers(&self.headers, &self.auth)?;
+
+        let mut config = self.clone();
+        config.uri = build_uri(config.uri.clone()).into();
+
+        let batch = BatchSettings::default()
+            .bytes(bytesize::mib(10u64))
+            .timeout(1)
+            .parse_config(config.batch)?;
+        let buffer = Buffer::new(batch.size, Compression::None);
+
+        Ok(Some(Box::new(HttpTestEncoder::new(config, buffer))))
+    }
 }
 
 #[async_trait::async_trait]
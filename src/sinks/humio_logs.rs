@@ -77,7 +77,7 @@ impl HumioLogsConfig {
         let endpoint = self.endpoint.clone().unwrap_or_else(|| HOST.to_string());
 
         HecSinkConfig {
-            token: self.token.clone(),
+            token: self.token.clone().into(),
             endpoint,
             source: self.source.clone(),
             sourcetype: self.event_type.clone(),
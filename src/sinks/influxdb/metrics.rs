@@ -7,12 +7,12 @@ use crate::{
     },
     sinks::util::{
         http::{HttpBatchService, HttpClient, HttpRetryLogic},
-        BatchConfig, BatchSettings, MetricBuffer, TowerRequestConfig,
+        BatchConfig, BatchSettings, EncodedEvent, MetricBuffer, TowerRequestConfig,
     },
 };
 use bytes::Bytes;
 use futures::future::{ready, BoxFuture};
-use futures01::Sink;
+use futures01::{stream::iter_ok, Sink};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -121,7 +121,8 @@ impl InfluxDBSvc {
                 batch.timeout,
                 cx.acker(),
             )
-            .sink_map_err(|e| error!("Fatal influxdb sink error: {}", e));
+            .sink_map_err(|e| error!("Fatal influxdb sink error: {}", e))
+            .with_flat_map(|event| iter_ok(EncodedEvent::encode(event, Some)));
 
         Ok(Box::new(sink))
     }
@@ -1,11 +1,11 @@
 use crate::{
     buffers::Acker,
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
-    event::{self, Event, Value},
+    event::{self, Event, EventFinalizers, EventStatus, Value},
     kafka::{KafkaAuthConfig, KafkaCompression},
     serde::to_string,
     sinks::util::encoding::{EncodingConfig, EncodingConfigWithDefault, EncodingConfiguration},
-    template::{Template, TemplateError},
+    template::{OnMissing, Template, TemplateError},
 };
 use futures::{compat::Compat, FutureExt, TryFutureExt};
 use futures01::{
@@ -14,6 +14,7 @@ use futures01::{
 };
 use rdkafka::{
     consumer::{BaseConsumer, Consumer},
+    message::OwnedHeaders,
     producer::{DeliveryFuture, FutureProducer, FutureRecord},
 };
 use serde::{Deserialize, Serialize};
@@ -31,13 +32,20 @@ enum BuildError {
     KafkaCreateFailed { source: rdkafka::error::KafkaError },
     #[snafu(display("invalid topic template: {}", source))]
     TopicTemplate { source: TemplateError },
+    #[snafu(display("invalid key template: {}", source))]
+    KeyTemplate { source: TemplateError },
+    #[snafu(display("`key` and the deprecated `key_field` options cannot both be set"))]
+    BothKeyAndKeyField,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct KafkaSinkConfig {
     bootstrap_servers: String,
     topic: String,
+    key: Option<Template>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     key_field: Option<Atom>,
+    headers_key: Option<Atom>,
     encoding: EncodingConfigWithDefault<Encoding>,
     #[serde(default)]
     compression: KafkaCompression,
@@ -47,7 +55,11 @@ pub struct KafkaSinkConfig {
     socket_timeout_ms: u64,
     #[serde(default = "default_message_timeout_ms")]
     message_timeout_ms: u64,
+    #[serde(default)]
+    idempotent: bool,
     librdkafka_options: Option<HashMap<String, String>>,
+    #[serde(default)]
+    on_missing: OnMissing,
 }
 
 fn default_socket_timeout_ms() -> u64 {
@@ -70,9 +82,11 @@ pub enum Encoding {
 pub struct KafkaSink {
     producer: FutureProducer,
     topic: Template,
-    key_field: Option<Atom>,
+    on_missing: OnMissing,
+    key: Option<Template>,
+    headers_key: Option<Atom>,
     encoding: EncodingConfig<Encoding>,
-    in_flight: FuturesUnordered<MetadataFuture<Compat<DeliveryFuture>, usize>>,
+    in_flight: FuturesUnordered<MetadataFuture<Compat<DeliveryFuture>, (usize, EventFinalizers)>>,
 
     acker: Acker,
     seq_head: usize,
@@ -110,6 +124,14 @@ impl KafkaSinkConfig {
             .set("socket.timeout.ms", &self.socket_timeout_ms.to_string())
             .set("message.timeout.ms", &self.message_timeout_ms.to_string());
 
+        if self.idempotent {
+            // librdkafka adjusts `acks`, `retries` and `max.in.flight` to values
+            // compatible with idempotence, so retries can't duplicate or reorder records.
+            // Transactions are not supported, as the rdkafka release in use doesn't
+            // expose the transactional producer API.
+            client_config.set("enable.idempotence", "true");
+        }
+
         self.auth.apply(&mut client_config)?;
 
         if let Some(ref librdkafka_options) = self.librdkafka_options {
@@ -120,15 +142,32 @@ impl KafkaSinkConfig {
 
         Ok(client_config)
     }
+
+    fn key_template(&self) -> crate::Result<Option<Template>> {
+        match (&self.key, &self.key_field) {
+            (Some(_), Some(_)) => Err(BuildError::BothKeyAndKeyField.into()),
+            (Some(key), None) => Ok(Some(key.clone())),
+            (None, Some(key_field)) => {
+                warn!("The `key_field` setting is deprecated, use `key` instead");
+                let key =
+                    Template::try_from(format!("{{{{ {} }}}}", key_field)).context(KeyTemplate)?;
+                Ok(Some(key))
+            }
+            (None, None) => Ok(None),
+        }
+    }
 }
 
 impl KafkaSink {
     fn new(config: KafkaSinkConfig, acker: Acker) -> crate::Result<Self> {
         let producer = config.to_rdkafka()?.create().context(KafkaCreateFailed)?;
+        let key = config.key_template()?;
         Ok(KafkaSink {
             producer,
             topic: Template::try_from(config.topic).context(TopicTemplate)?,
-            key_field: config.key_field,
+            on_missing: config.on_missing,
+            key,
+            headers_key: config.headers_key,
             encoding: config.encoding.into(),
             in_flight: FuturesUnordered::new(),
             acker,
@@ -137,20 +176,56 @@ impl KafkaSink {
             pending_acks: HashSet::new(),
         })
     }
+
+    /// Marks the event `seqno` as done, acking every event up to the first one still in flight.
+    fn finish(&mut self, seqno: usize) {
+        self.pending_acks.insert(seqno);
+
+        let mut num_to_ack = 0;
+        while self.pending_acks.remove(&self.seq_tail) {
+            num_to_ack += 1;
+            self.seq_tail += 1
+        }
+        self.acker.ack(num_to_ack);
+    }
 }
 
 impl Sink for KafkaSink {
     type SinkItem = Event;
     type SinkError = ();
 
-    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
-        let topic = self.topic.render_string(&item).map_err(|missing_keys| {
-            error!(message = "Missing keys for topic", ?missing_keys);
-        })?;
+    fn start_send(
+        &mut self,
+        mut item: Self::SinkItem,
+    ) -> StartSend<Self::SinkItem, Self::SinkError> {
+        let topic = match self.topic.render_string_with(&item, &self.on_missing) {
+            Ok(topic) => topic,
+            Err(missing_keys) => {
+                warn!(
+                    message = "Keys do not exist on the event; dropping event.",
+                    ?missing_keys
+                );
+                // The dropped event still takes its turn, so that it is acked in order with
+                // the events in flight.
+                let seqno = self.seq_head;
+                self.seq_head += 1;
+                self.finish(seqno);
+                return Ok(AsyncSink::Ready);
+            }
+        };
+
+        let (key, headers, body) =
+            encode_event(item.clone(), &self.key, &self.headers_key, &self.encoding);
 
-        let (key, body) = encode_event(item.clone(), &self.key_field, &self.encoding);
+        let mut record = FutureRecord::to(&topic).payload(&body[..]);
 
-        let mut record = FutureRecord::to(&topic).key(&key).payload(&body[..]);
+        if let Some(key) = &key {
+            record = record.key(key);
+        }
+
+        if let Some(headers) = headers {
+            record = record.headers(headers);
+        }
 
         if let Some(Value::Timestamp(timestamp)) =
             item.as_log().get(&event::log_schema().timestamp_key())
@@ -179,9 +254,10 @@ impl Sink for KafkaSink {
 
         let seqno = self.seq_head;
         self.seq_head += 1;
+        let finalizers = item.take_finalizers();
 
         self.in_flight
-            .push(Compat::new(future).join(future01::ok(seqno)));
+            .push(Compat::new(future).join(future01::ok((seqno, finalizers))));
         Ok(AsyncSink::Ready)
     }
 
@@ -195,24 +271,24 @@ impl Sink for KafkaSink {
                 Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
 
                 // request finished, check for success
-                Ok(Async::Ready(Some((result, seqno)))) => {
-                    match result {
-                        Ok((partition, offset)) => trace!(
-                            "Produced message to partition {} at offset {}",
-                            partition,
-                            offset
-                        ),
-                        Err((e, _msg)) => error!("Kafka error: {}", e),
+                Ok(Async::Ready(Some((result, (seqno, finalizers))))) => {
+                    let status = match result {
+                        Ok((partition, offset)) => {
+                            trace!(
+                                "Produced message to partition {} at offset {}",
+                                partition,
+                                offset
+                            );
+                            EventStatus::Delivered
+                        }
+                        Err((e, _msg)) => {
+                            error!("Kafka error: {}", e);
+                            EventStatus::Rejected
+                        }
                     };
+                    finalizers.update_status(status);
 
-                    self.pending_acks.insert(seqno);
-
-                    let mut num_to_ack = 0;
-                    while self.pending_acks.remove(&self.seq_tail) {
-                        num_to_ack += 1;
-                        self.seq_tail += 1
-                    }
-                    self.acker.ack(num_to_ack);
+                    self.finish(seqno);
                 }
 
                 // request got canceled (according to docs)
@@ -253,15 +329,42 @@ async fn healthcheck(config: KafkaSinkConfig) -> crate::Result<()> {
 
 fn encode_event(
     mut event: Event,
-    key_field: &Option<Atom>,
+    key: &Option<Template>,
+    headers_key: &Option<Atom>,
     encoding: &EncodingConfig<Encoding>,
-) -> (Vec<u8>, Vec<u8>) {
-    encoding.apply_rules(&mut event);
-    let key = key_field
+) -> (Option<Vec<u8>>, Option<OwnedHeaders>, Vec<u8>) {
+    // The key and headers are taken before the encoding rules are applied, so they
+    // can refer to fields that are excluded from the payload.
+    let key = key.as_ref().and_then(|key| match key.render(&event) {
+        Ok(key) => Some(key.to_vec()),
+        Err(missing_keys) => {
+            warn!(
+                message = "Keys for message key do not exist on the event; sending without a key.",
+                ?missing_keys,
+                rate_limit_secs = 30,
+            );
+            None
+        }
+    });
+
+    let headers = headers_key
         .as_ref()
-        .and_then(|f| event.as_log().get(f))
-        .map(|v| v.as_bytes().to_vec())
-        .unwrap_or_default();
+        .and_then(|headers_key| event.as_log().get(headers_key))
+        .and_then(|headers| match headers {
+            Value::Map(headers) => Some(headers.iter().fold(
+                OwnedHeaders::new_with_capacity(headers.len()),
+                |acc, (name, value)| acc.add(name.as_str(), &value.as_bytes()[..]),
+            )),
+            _ => {
+                warn!(
+                    message = "Headers field is not a map; sending without headers.",
+                    rate_limit_secs = 30,
+                );
+                None
+            }
+        });
+
+    encoding.apply_rules(&mut event);
 
     let body = match encoding.codec() {
         Encoding::Json => serde_json::to_vec(&event.as_log()).unwrap(),
@@ -272,26 +375,27 @@ fn encode_event(
             .unwrap_or_default(),
     };
 
-    (key, body)
+    (key, headers, body)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::event::{self, Event};
-    use std::collections::BTreeMap;
+    use std::{collections::BTreeMap, sync::atomic::Ordering};
 
     #[test]
     fn kafka_encode_event_text() {
-        let key = "";
         let message = "hello world".to_string();
-        let (key_bytes, bytes) = encode_event(
+        let (key_bytes, headers, bytes) = encode_event(
             message.clone().into(),
             &None,
+            &None,
             &EncodingConfig::from(Encoding::Text),
         );
 
-        assert_eq!(&key_bytes[..], key.as_bytes());
+        assert_eq!(key_bytes, None);
+        assert!(headers.is_none());
         assert_eq!(&bytes[..], message.as_bytes());
     }
 
@@ -302,19 +406,136 @@ mod tests {
         event.as_mut_log().insert("key", "value");
         event.as_mut_log().insert("foo", "bar");
 
-        let (key, bytes) = encode_event(
+        let (key, _headers, bytes) = encode_event(
             event,
-            &Some("key".into()),
+            &Some(Template::try_from("{{ key }}").unwrap()),
+            &None,
             &EncodingConfig::from(Encoding::Json),
         );
 
         let map: BTreeMap<String, String> = serde_json::from_slice(&bytes[..]).unwrap();
 
-        assert_eq!(&key[..], b"value");
+        assert_eq!(key.as_deref(), Some(&b"value"[..]));
         assert_eq!(map[&event::log_schema().message_key().to_string()], message);
         assert_eq!(map["key"], "value".to_string());
         assert_eq!(map["foo"], "bar".to_string());
     }
+
+    #[test]
+    fn kafka_encode_event_key_template() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("app", "api");
+        event.as_mut_log().insert("region", "us-east-1");
+
+        let (key, _headers, _bytes) = encode_event(
+            event.clone(),
+            &Some(Template::try_from("{{ app }}-{{ region }}").unwrap()),
+            &None,
+            &EncodingConfig::from(Encoding::Text),
+        );
+        assert_eq!(key.as_deref(), Some(&b"api-us-east-1"[..]));
+
+        let (key, _headers, _bytes) = encode_event(
+            event,
+            &Some(Template::try_from("{{ missing }}").unwrap()),
+            &None,
+            &EncodingConfig::from(Encoding::Text),
+        );
+        assert_eq!(key, None);
+    }
+
+    #[test]
+    fn kafka_encode_event_headers() {
+        use rdkafka::message::Headers;
+
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("headers.trace_id", "abc123");
+        event.as_mut_log().insert("headers.retries", 3);
+
+        let (_key, headers, _bytes) = encode_event(
+            event,
+            &None,
+            &Some("headers".into()),
+            &EncodingConfig::from(Encoding::Text),
+        );
+
+        let headers = headers.unwrap();
+        assert_eq!(headers.count(), 2);
+        assert_eq!(headers.get(0), Some(("retries", &b"3"[..])));
+        assert_eq!(headers.get(1), Some(("trace_id", &b"abc123"[..])));
+    }
+
+    #[test]
+    fn kafka_encode_event_headers_not_a_map() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("headers", "not a map");
+
+        let (_key, headers, _bytes) = encode_event(
+            event,
+            &None,
+            &Some("headers".into()),
+            &EncodingConfig::from(Encoding::Text),
+        );
+
+        assert!(headers.is_none());
+    }
+
+    #[test]
+    fn kafka_deprecated_key_field() {
+        let config: KafkaSinkConfig = toml::from_str(
+            r#"
+            bootstrap_servers = "localhost:9091"
+            topic = "topic"
+            key_field = "user_id"
+            "#,
+        )
+        .unwrap();
+
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("user_id", "alice");
+        let (key, _headers, _bytes) = encode_event(
+            event,
+            &config.key_template().unwrap(),
+            &None,
+            &EncodingConfig::from(Encoding::Text),
+        );
+        assert_eq!(key.as_deref(), Some(&b"alice"[..]));
+
+        let config = KafkaSinkConfig {
+            key: Some(Template::try_from("{{ user_id }}").unwrap()),
+            ..config
+        };
+        assert!(config.key_template().is_err());
+    }
+
+    #[test]
+    fn kafka_idempotent_config() {
+        let config = KafkaSinkConfig {
+            bootstrap_servers: "localhost:9091".into(),
+            topic: "topic".into(),
+            idempotent: true,
+            ..Default::default()
+        };
+
+        let client_config = config.to_rdkafka().unwrap();
+        assert_eq!(client_config.get("enable.idempotence"), Some("true"));
+    }
+
+    #[test]
+    fn kafka_drops_events_missing_topic_keys() {
+        let config = KafkaSinkConfig {
+            bootstrap_servers: "localhost:9091".into(),
+            topic: "topic-{{ missing }}".into(),
+            ..Default::default()
+        };
+        let (acker, acks) = Acker::new_for_testing();
+        let mut sink = KafkaSink::new(config, acker).unwrap();
+
+        let result = sink.start_send(Event::from("hello world"));
+        assert!(matches!(result, Ok(AsyncSink::Ready)));
+        assert_eq!(acks.load(Ordering::SeqCst), 1);
+        assert_eq!(sink.seq_tail, 1);
+    }
 }
 
 #[cfg(feature = "kafka-integration-tests")]
@@ -347,7 +568,7 @@ mod integration_test {
             topic: topic.clone(),
             compression: KafkaCompression::None,
             encoding: EncodingConfigWithDefault::from(Encoding::Text),
-            key_field: None,
+            key: None,
             socket_timeout_ms: 60000,
             message_timeout_ms: 300000,
             ..Default::default()
@@ -448,7 +669,7 @@ mod integration_test {
             topic: format!("{}-%Y%m%d", topic),
             compression,
             encoding: EncodingConfigWithDefault::from(Encoding::Text),
-            key_field: None,
+            key: None,
             auth: kafka_auth.clone(),
             socket_timeout_ms: 60000,
             message_timeout_ms: 300000,
@@ -19,7 +19,7 @@ use crate::{
         buffer::loki::{LokiBuffer, LokiEvent, LokiRecord},
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
         http::{Auth, BatchedHttpSink, HttpClient, HttpSink},
-        BatchConfig, BatchSettings, TowerRequestConfig, UriSerde,
+        BatchConfig, BatchSettings, HttpTestEncoder, TestEncoder, TowerRequestConfig, UriSerde,
     },
     template::Template,
     tls::{TlsOptions, TlsSettings},
@@ -107,6 +107,23 @@ impl SinkConfig for LokiConfig {
     fn sink_type(&self) -> &'static str {
         "loki"
     }
+
+    fn build_test_encoder(&self) -> crate::Result<Option<Box<dyn TestEncoder>>> {
+        if self.labels.is_empty() {
+            return Err("`labels` must include at least one label.".into());
+        }
+
+        let batch_settings = BatchSettings::default()
+            .bytes(102_400)
+            .events(100_000)
+            .timeout(1)
+            .parse_config(self.batch)?;
+
+        Ok(Some(Box::new(HttpTestEncoder::new(
+            self.clone(),
+            LokiBuffer::new(batch_settings.size),
+        ))))
+    }
 }
 
 #[async_trait::async_trait]
@@ -59,6 +59,8 @@ pub mod splunk_hec;
 pub mod statsd;
 #[cfg(feature = "sinks-vector")]
 pub mod vector;
+#[cfg(feature = "sinks-wasm")]
+pub mod wasm;
 
 pub mod util;
 
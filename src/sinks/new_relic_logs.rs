@@ -1,5 +1,6 @@
 use crate::{
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
+    secrets::SensitiveString,
     sinks::{
         http::{HttpMethod, HttpSinkConfig},
         util::{
@@ -33,8 +34,8 @@ pub enum NewRelicLogsRegion {
 #[derive(Deserialize, Serialize, Debug, Derivative, Clone)]
 #[derivative(Default)]
 pub struct NewRelicLogsConfig {
-    pub license_key: Option<String>,
-    pub insert_key: Option<String>,
+    pub license_key: Option<SensitiveString>,
+    pub insert_key: Option<SensitiveString>,
     pub region: Option<NewRelicLogsRegion>,
     #[serde(skip_serializing_if = "skip_serializing_if_default", default)]
     pub encoding: EncodingConfigWithDefault<Encoding>,
@@ -95,9 +96,9 @@ impl NewRelicLogsConfig {
         let mut headers: IndexMap<String, String> = IndexMap::new();
 
         if let Some(license_key) = &self.license_key {
-            headers.insert("X-License-Key".to_owned(), license_key.clone());
+            headers.insert("X-License-Key".to_owned(), license_key.as_str().to_owned());
         } else if let Some(insert_key) = &self.insert_key {
-            headers.insert("X-Insert-Key".to_owned(), insert_key.clone());
+            headers.insert("X-Insert-Key".to_owned(), insert_key.as_str().to_owned());
         } else {
             return Err(Box::new(BuildError::MissingAuthParam));
         }
@@ -172,7 +173,7 @@ mod tests {
     #[test]
     fn new_relic_logs_check_config_defaults() {
         let mut nr_config = NewRelicLogsConfig::default();
-        nr_config.license_key = Some("foo".to_owned());
+        nr_config.license_key = Some("foo".into());
         let http_config = nr_config.create_config().unwrap();
 
         assert_eq!(
@@ -201,7 +202,7 @@ mod tests {
     #[test]
     fn new_relic_logs_check_config_custom() {
         let mut nr_config = NewRelicLogsConfig::default();
-        nr_config.insert_key = Some("foo".to_owned());
+        nr_config.insert_key = Some("foo".into());
         nr_config.region = Some(NewRelicLogsRegion::Eu);
         nr_config.batch.max_size = Some(bytesize::mib(8u64) as usize);
         nr_config.request.in_flight_limit = InFlightLimit::Fixed(12);
@@ -277,7 +278,7 @@ mod tests {
         let in_addr = next_addr();
 
         let mut nr_config = NewRelicLogsConfig::default();
-        nr_config.license_key = Some("foo".to_owned());
+        nr_config.license_key = Some("foo".into());
         let mut http_config = nr_config.create_config().unwrap();
         http_config.uri = format!("http://{}/fake_nr", in_addr)
             .parse::<http::Uri>()
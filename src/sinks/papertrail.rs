@@ -4,7 +4,7 @@ use crate::{
     sinks::util::{
         encoding::{EncodingConfig, EncodingConfiguration},
         tcp::TcpSink,
-        Encoding, StreamSink, UriSerde,
+        EncodedEvent, Encoding, StreamSink, UriSerde,
     },
     tls::{MaybeTlsSettings, TlsSettings},
 };
@@ -50,8 +50,9 @@ impl SinkConfig for PapertrailConfig {
 
         let encoding = self.encoding.clone();
 
-        let sink = StreamSink::new(sink, cx.acker())
-            .with_flat_map(move |e| iter_ok(encode_event(e, pid, &encoding)));
+        let sink = StreamSink::new(sink, cx.acker()).with_flat_map(move |e| {
+            iter_ok(EncodedEvent::encode(e, |e| encode_event(e, pid, &encoding)))
+        });
 
         Ok((Box::new(sink), Box::new(healthcheck.compat())))
     }
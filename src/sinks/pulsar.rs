@@ -1,7 +1,7 @@
 use crate::{
     buffers::Acker,
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
-    event::{self, Event},
+    event::{self, Event, EventStatus},
     sinks::util::encoding::{EncodingConfig, EncodingConfigWithDefault, EncodingConfiguration},
 };
 use futures::{lock::Mutex, FutureExt, TryFutureExt};
@@ -138,16 +138,25 @@ impl Sink for PulsarSink {
     type SinkItem = Event;
     type SinkError = ();
 
-    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+    fn start_send(
+        &mut self,
+        mut item: Self::SinkItem,
+    ) -> StartSend<Self::SinkItem, Self::SinkError> {
+        let finalizers = item.take_finalizers();
         let message = encode_event(item, &self.encoding).map_err(|_| ())?;
 
         let producer = Arc::clone(&self.producer);
         let fut = async move {
             let mut locked = producer.lock().await;
-            match locked.send(message.clone()).await {
+            let result = match locked.send(message.clone()).await {
                 Ok(fut) => fut.await,
                 Err(e) => Err(e),
-            }
+            };
+            finalizers.update_status(match result {
+                Ok(_) => EventStatus::Delivered,
+                Err(_) => EventStatus::Rejected,
+            });
+            result
         };
 
         let seqno = self.seq_head;
@@ -5,6 +5,7 @@ use crate::{
         SplunkEventEncodeError, SplunkEventSent, SplunkSourceMissingKeys,
         SplunkSourceTypeMissingKeys,
     },
+    secrets::SensitiveString,
     sinks::util::{
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
         http::{BatchedHttpSink, HttpClient, HttpSink},
@@ -33,7 +34,7 @@ pub enum BuildError {
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(deny_unknown_fields)]
 pub struct HecSinkConfig {
-    pub token: String,
+    pub token: SensitiveString,
     // Deprecated name
     #[serde(alias = "host")]
     pub endpoint: String,
@@ -213,7 +214,7 @@ impl HttpSink for HecSinkConfig {
 
         let mut builder = Request::post(uri)
             .header("Content-Type", "application/json")
-            .header("Authorization", format!("Splunk {}", self.token));
+            .header("Authorization", format!("Splunk {}", self.token.as_str()));
 
         if let Some(ce) = self.compression.content_encoding() {
             builder = builder.header("Content-Encoding", ce);
@@ -236,7 +237,7 @@ pub async fn healthcheck(config: HecSinkConfig, mut client: HttpClient) -> crate
         .context(super::UriParseError)?;
 
     let request = Request::get(uri)
-        .header("Authorization", format!("Splunk {}", config.token))
+        .header("Authorization", format!("Splunk {}", config.token.as_str()))
         .body(Body::empty())
         .unwrap();
 
@@ -702,7 +703,7 @@ mod integration_tests {
     ) -> super::HecSinkConfig {
         super::HecSinkConfig {
             endpoint: "http://localhost:8088/".into(),
-            token: get_token().await,
+            token: get_token().await.into(),
             host_key: "host".into(),
             compression: Compression::None,
             encoding: encoding.into(),
@@ -1,9 +1,9 @@
-use super::instant_now;
 use super::semaphore::ShrinkableSemaphore;
+use super::{instant_now, AutoConcurrencySettings};
 use crate::emit;
 use crate::internal_events::{
-    AutoConcurrencyAveragedRtt, AutoConcurrencyInFlight, AutoConcurrencyLimit,
-    AutoConcurrencyObservedRtt,
+    AutoConcurrencyAveragedRtt, AutoConcurrencyBackPressure, AutoConcurrencyInFlight,
+    AutoConcurrencyLimit, AutoConcurrencyObservedRtt,
 };
 use crate::sinks::util::retries::RetryLogic;
 #[cfg(test)]
@@ -14,23 +14,13 @@ use std::time::{Duration, Instant};
 use tokio::sync::OwnedSemaphorePermit;
 use tower::timeout::error::Elapsed;
 
-// This value was picked as a reasonable default while we ensure the
-// viability of the system. This value may need adjustment if later
-// analysis discovers we need higher or lower weighting on past RTT
-// weighting.
-const EWMA_ALPHA: f64 = 0.5;
-
-// This was picked as a reasonable default threshold ratio to avoid
-// dropping concurrency too aggressively when there is fluctuation in
-// the RTT measurements.
-const THRESHOLD_RATIO: f64 = 0.05;
-
 /// Shared class for `tokio::sync::Semaphore` that manages adjusting the
 /// semaphore size and other associated data.
 #[derive(Clone, Debug)]
 pub(super) struct Controller<L> {
     semaphore: Arc<ShrinkableSemaphore>,
     in_flight_limit: Option<usize>,
+    settings: AutoConcurrencySettings,
     logic: L,
     pub(super) inner: Arc<Mutex<Inner>>,
     #[cfg(test)]
@@ -44,7 +34,7 @@ pub(super) struct Inner {
     past_rtt: EWMA,
     next_update: Instant,
     current_rtt: Mean,
-    had_back_pressure: bool,
+    back_pressure: usize,
     reached_limit: bool,
 }
 
@@ -58,23 +48,28 @@ pub(super) struct ControllerStatistics {
 }
 
 impl<L> Controller<L> {
-    pub(super) fn new(in_flight_limit: Option<usize>, logic: L) -> Self {
+    pub(super) fn new(
+        in_flight_limit: Option<usize>,
+        settings: AutoConcurrencySettings,
+        logic: L,
+    ) -> Self {
         // If an in_flight_limit is specified, it becomse both the
         // current limit and the maximum, effectively bypassing all the
         // mechanisms. Otherwise, the current limit is set to 1 and the
-        // maximum to MAX_CONCURRENCY.
+        // maximum to `settings.max_limit`.
         let current_limit = in_flight_limit.unwrap_or(1);
         Self {
             semaphore: Arc::new(ShrinkableSemaphore::new(current_limit)),
             in_flight_limit,
+            settings,
             logic,
             inner: Arc::new(Mutex::new(Inner {
                 current_limit,
                 in_flight: 0,
-                past_rtt: Default::default(),
+                past_rtt: EWMA::new(settings.ewma_alpha),
                 next_update: instant_now(),
                 current_rtt: Default::default(),
-                had_back_pressure: false,
+                back_pressure: 0,
                 reached_limit: false,
             })),
             #[cfg(test)]
@@ -119,7 +114,7 @@ impl<L> Controller<L> {
         let rtt = rtt.as_secs_f64();
 
         if is_back_pressure {
-            inner.had_back_pressure = true;
+            inner.back_pressure += 1;
         }
 
         #[cfg(test)]
@@ -178,8 +173,12 @@ impl<L> Controller<L> {
                         past_rtt = inner.past_rtt.update(current_rtt);
                     }
                     inner.next_update = now + Duration::from_secs_f64(past_rtt);
+                    emit!(AutoConcurrencyBackPressure {
+                        count: inner.back_pressure as u64
+                    });
+
                     inner.current_rtt.reset();
-                    inner.had_back_pressure = false;
+                    inner.back_pressure = 0;
                     inner.reached_limit = false;
                 }
             }
@@ -187,15 +186,16 @@ impl<L> Controller<L> {
     }
 
     fn manage_limit(&self, inner: &mut MutexGuard<Inner>, past_rtt: f64, current_rtt: Option<f64>) {
-        let threshold = past_rtt * THRESHOLD_RATIO;
+        let threshold = past_rtt * self.settings.rtt_deviation_threshold;
+        let had_back_pressure = inner.back_pressure > 0;
 
         // Normal quick responses trigger an increase in the
         // concurrency limit. Note that we only check this if we had
         // requests to go beyond the current limit to prevent
         // increasing the limit beyond what we have evidence for.
-        if inner.current_limit < super::MAX_CONCURRENCY
+        if inner.current_limit < self.settings.max_limit
             && inner.reached_limit
-            && !inner.had_back_pressure
+            && !had_back_pressure
             && current_rtt.is_some()
             && current_rtt.unwrap() <= past_rtt
         {
@@ -207,17 +207,20 @@ impl<L> Controller<L> {
         // to increasing response times, trigger a decrease in the
         // concurrency limit.
         else if inner.current_limit > 1
-            && (inner.had_back_pressure || current_rtt.unwrap_or(0.0) >= past_rtt + threshold)
+            && (had_back_pressure || current_rtt.unwrap_or(0.0) >= past_rtt + threshold)
         {
-            // Decrease (multiplicative) the current concurrency limit
-            let to_forget = inner.current_limit / 2;
+            // Decrease (multiplicative) the current concurrency limit,
+            // by at least one but never below a single request.
+            let to_forget =
+                (inner.current_limit as f64 * (1.0 - self.settings.decrease_ratio)) as usize;
+            let to_forget = to_forget.max(1).min(inner.current_limit - 1);
             self.semaphore.forget_permits(to_forget);
             inner.current_limit -= to_forget;
         }
         emit!(AutoConcurrencyLimit {
             concurrency: inner.current_limit as u64,
             reached_limit: inner.reached_limit,
-            had_back_pressure: inner.had_back_pressure,
+            had_back_pressure,
             current_rtt: current_rtt.map(Duration::from_secs_f64),
             past_rtt: Duration::from_secs_f64(past_rtt),
         });
@@ -252,12 +255,20 @@ where
 }
 
 /// Exponentially Weighted Moving Average
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug)]
 struct EWMA {
+    alpha: f64,
     average: Option<f64>,
 }
 
 impl EWMA {
+    fn new(alpha: f64) -> Self {
+        Self {
+            alpha,
+            average: None,
+        }
+    }
+
     fn average(&self) -> Option<f64> {
         self.average
     }
@@ -266,7 +277,7 @@ impl EWMA {
     fn update(&mut self, point: f64) -> f64 {
         let average = match self.average {
             None => point,
-            Some(avg) => point * EWMA_ALPHA + avg * (1.0 - EWMA_ALPHA),
+            Some(avg) => point * self.alpha + avg * (1.0 - self.alpha),
         };
         self.average = Some(average);
         average
@@ -320,7 +331,7 @@ mod tests {
 
     #[test]
     fn ewma_update_works() {
-        let mut mean = EWMA::default();
+        let mut mean = EWMA::new(0.5);
         assert_eq!(mean.average(), None);
         mean.update(2.0);
         assert_eq!(mean.average(), Some(2.0));
@@ -1,4 +1,4 @@
-use super::AutoConcurrencyLimit;
+use super::{AutoConcurrencyLimit, AutoConcurrencySettings};
 use crate::sinks::util::retries::RetryLogic;
 use tower::Layer;
 
@@ -7,14 +7,20 @@ use tower::Layer;
 #[derive(Debug, Clone)]
 pub(crate) struct AutoConcurrencyLimitLayer<L> {
     in_flight_limit: Option<usize>,
+    settings: AutoConcurrencySettings,
     logic: L,
 }
 
 impl<L> AutoConcurrencyLimitLayer<L> {
     /// Create a new concurrency limit layer.
-    pub fn new(in_flight_limit: Option<usize>, logic: L) -> Self {
+    pub fn new(
+        in_flight_limit: Option<usize>,
+        settings: AutoConcurrencySettings,
+        logic: L,
+    ) -> Self {
         AutoConcurrencyLimitLayer {
             in_flight_limit,
+            settings,
             logic,
         }
     }
@@ -24,6 +30,11 @@ impl<S, L: RetryLogic> Layer<S> for AutoConcurrencyLimitLayer<L> {
     type Service = AutoConcurrencyLimit<S, L>;
 
     fn layer(&self, service: S) -> Self::Service {
-        AutoConcurrencyLimit::new(service, self.logic.clone(), self.in_flight_limit)
+        AutoConcurrencyLimit::new(
+            service,
+            self.logic.clone(),
+            self.in_flight_limit,
+            self.settings,
+        )
     }
 }
@@ -7,11 +7,83 @@ mod semaphore;
 mod service;
 mod tests;
 
+use serde::{de, Deserialize, Deserializer, Serialize};
+
 pub(super) const MAX_CONCURRENCY: usize = 200;
 
 pub(crate) use layer::AutoConcurrencyLimitLayer;
 pub(crate) use service::AutoConcurrencyLimit;
 
+/// Tunables for the adaptive concurrency controller, configured in a
+/// sink's `request.adaptive_concurrency` table. These only take effect
+/// when `in_flight_limit` is set to `"auto"`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoConcurrencySettings {
+    /// The fraction of the current limit kept when the controller
+    /// detects back pressure.
+    #[serde(deserialize_with = "deserialize_ratio")]
+    pub decrease_ratio: f64,
+    /// The weight given to the newest RTT measurement in the moving
+    /// average the controller compares against.
+    #[serde(deserialize_with = "deserialize_ratio")]
+    pub ewma_alpha: f64,
+    /// How far, as a fraction of the averaged RTT, a measured RTT may
+    /// rise before it is treated as back pressure.
+    #[serde(deserialize_with = "deserialize_non_negative")]
+    pub rtt_deviation_threshold: f64,
+    /// The upper bound on the concurrency limit.
+    #[serde(deserialize_with = "deserialize_positive")]
+    pub max_limit: usize,
+}
+
+impl Default for AutoConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            decrease_ratio: 0.5,
+            ewma_alpha: 0.5,
+            rtt_deviation_threshold: 0.05,
+            max_limit: MAX_CONCURRENCY,
+        }
+    }
+}
+
+fn deserialize_ratio<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if value > 0.0 && value < 1.0 {
+        Ok(value)
+    } else {
+        Err(de::Error::invalid_value(
+            de::Unexpected::Float(value),
+            &"a number between 0 and 1",
+        ))
+    }
+}
+
+fn deserialize_non_negative<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(de::Error::invalid_value(
+            de::Unexpected::Float(value),
+            &"a non-negative number",
+        ))
+    }
+}
+
+fn deserialize_positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let value = usize::deserialize(deserializer)?;
+    if value > 0 {
+        Ok(value)
+    } else {
+        Err(de::Error::invalid_value(
+            de::Unexpected::Unsigned(0),
+            &"positive integer",
+        ))
+    }
+}
+
 pub(self) fn instant_now() -> std::time::Instant {
     tokio::time::Instant::now().into()
 }
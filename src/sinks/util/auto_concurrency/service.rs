@@ -1,5 +1,6 @@
 use super::controller::Controller;
 use super::future::ResponseFuture;
+use super::AutoConcurrencySettings;
 use crate::sinks::util::retries::RetryLogic;
 
 use tower::Service;
@@ -32,10 +33,15 @@ enum State {
 
 impl<S, L> AutoConcurrencyLimit<S, L> {
     /// Create a new automated concurrency limiter.
-    pub(crate) fn new(inner: S, logic: L, in_flight_limit: Option<usize>) -> Self {
+    pub(crate) fn new(
+        inner: S,
+        logic: L,
+        in_flight_limit: Option<usize>,
+        settings: AutoConcurrencySettings,
+    ) -> Self {
         AutoConcurrencyLimit {
             inner,
-            controller: Arc::new(Controller::new(in_flight_limit, logic)),
+            controller: Arc::new(Controller::new(in_flight_limit, settings, logic)),
             state: State::Empty,
         }
     }
@@ -3,7 +3,7 @@
 #![cfg(all(test, not(target_os = "macos"), feature = "sources-generator"))]
 
 use super::controller::ControllerStatistics;
-use super::MAX_CONCURRENCY;
+use super::{AutoConcurrencySettings, MAX_CONCURRENCY};
 use crate::{
     assert_within,
     config::{self, DataType, SinkConfig, SinkContext},
@@ -11,8 +11,8 @@ use crate::{
     metrics::{self, capture_metrics, get_controller},
     sinks::{
         util::{
-            retries::RetryLogic, BatchSettings, EncodedLength, InFlightLimit, TowerRequestConfig,
-            VecBuffer,
+            retries::RetryLogic, BatchSettings, EncodedEvent, EncodedLength, InFlightLimit,
+            TowerRequestConfig, VecBuffer,
         },
         Healthcheck, RouterSink,
     },
@@ -24,23 +24,34 @@ use futures::{
     compat::Future01CompatExt,
     future::{pending, BoxFuture},
 };
-use futures01::{future, Sink};
+use futures01::{future, stream::iter_ok, Sink};
 use rand::{distributions::Exp1, prelude::*};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
 use std::task::Poll;
 use std::time::{Duration, Instant};
 use tokio::time::{delay_for, delay_until};
 use tower::Service;
 
-#[derive(Copy, Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize)]
 struct TestParams {
     // The delay is the base time every request takes return.
     #[serde(default)]
     delay: Duration,
 
+    // A recorded sequence of response times, replayed in order (and
+    // repeated as needed) as the base time of each request in place of
+    // `delay`.
+    #[serde(skip)]
+    latency_profile: Option<Arc<Vec<Duration>>>,
+
     // The jitter is the amount of per-request response time randomness,
     // as a fraction of `delay`. The average response time will be
     // `delay * (1 + jitter)` and will have an exponential distribution
@@ -101,11 +112,13 @@ impl SinkConfig for TestConfig {
                 .get_ref()
                 .get_ref()
                 .get_ref()
+                .get_ref()
                 .controller
                 .stats,
         );
         *self.controller_stats.lock().unwrap() = stats;
 
+        let sink = sink.with_flat_map(|event| iter_ok(EncodedEvent::encode(event, Some)));
         Ok((Box::new(sink), Box::new(healthcheck)))
     }
 
@@ -126,13 +139,25 @@ impl SinkConfig for TestConfig {
 struct TestSink {
     stats: Arc<Mutex<Statistics>>,
     params: TestParams,
+    next_sample: Arc<AtomicUsize>,
 }
 
 impl TestSink {
     fn new(config: &TestConfig) -> Self {
         Self {
             stats: Arc::clone(&config.stats),
-            params: config.params,
+            params: config.params.clone(),
+            next_sample: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn base_delay(&self) -> Duration {
+        match &self.params.latency_profile {
+            Some(profile) if !profile.is_empty() => {
+                let sample = self.next_sample.fetch_add(1, Ordering::Relaxed);
+                profile[sample % profile.len()]
+            }
+            _ => self.params.delay,
         }
     }
 }
@@ -162,8 +187,8 @@ impl Service<Vec<Event>> for TestSink {
         stats.in_flight.adjust(1, now);
         let in_flight = stats.in_flight.level();
 
-        let params = self.params;
-        let delay = params.delay.mul_f64(
+        let params = &self.params;
+        let delay = self.base_delay().mul_f64(
             1.0 + (in_flight - 1) as f64 * params.concurrency_scale
                 + thread_rng().sample(Exp1) * params.jitter,
         );
@@ -173,6 +198,7 @@ impl Service<Vec<Event>> for TestSink {
             stats.in_flight.adjust(-1, now);
             Box::pin(pending())
         } else {
+            let concurrency_defer = params.concurrency_defer;
             let stats2 = Arc::clone(&self.stats);
             Box::pin(async move {
                 delay.await;
@@ -180,7 +206,7 @@ impl Service<Vec<Event>> for TestSink {
                 let in_flight = stats.in_flight.level();
                 stats.in_flight.adjust(-1, Instant::now());
 
-                if params.concurrency_defer > 0 && in_flight >= params.concurrency_defer {
+                if concurrency_defer > 0 && in_flight >= concurrency_defer {
                     Err(Error::Deferred)
                 } else {
                     Ok(Response::Ok)
@@ -233,16 +259,27 @@ async fn run_test4(
     interval: Option<f64>,
     params: TestParams,
     in_flight_limit: InFlightLimit,
+) -> TestData {
+    let request = TowerRequestConfig {
+        in_flight_limit,
+        rate_limit_num: Some(9999),
+        timeout_secs: Some(1),
+        ..Default::default()
+    };
+    run_test_with(lines, interval, params, request).await
+}
+
+async fn run_test_with(
+    lines: usize,
+    interval: Option<f64>,
+    params: TestParams,
+    request: TowerRequestConfig,
 ) -> TestData {
     let _ = metrics::init();
 
+    let in_flight_limit = request.in_flight_limit;
     let test_config = TestConfig {
-        request: TowerRequestConfig {
-            in_flight_limit,
-            rate_limit_num: Some(9999),
-            timeout_secs: Some(1),
-            ..Default::default()
-        },
+        request,
         params,
         ..Default::default()
     };
@@ -297,7 +334,19 @@ async fn run_test4(
             matches!(metrics.get("auto_concurrency_limit").unwrap().value,
                      MetricValue::Distribution { .. })
         );
+        assert!(
+            matches!(metrics.get("auto_concurrency_current_limit").unwrap().value,
+                     MetricValue::Gauge { .. })
+        );
     }
+    assert!(
+        matches!(metrics.get("auto_concurrency_current_rtt_nanoseconds").unwrap().value,
+                 MetricValue::Gauge { .. })
+    );
+    assert!(
+        matches!(metrics.get("auto_concurrency_back_pressure").unwrap().value,
+                 MetricValue::Gauge { .. })
+    );
     assert!(
         matches!(metrics.get("auto_concurrency_in_flight").unwrap().value,
                  MetricValue::Distribution { .. })
@@ -640,3 +689,140 @@ async fn jittery_link_small() {
     assert_within!(c_in_flight.max, 6, MAX_CONCURRENCY, "{:#?}", results);
     assert_within!(c_in_flight.mean, 4.0, 20.0, "{:#?}", results);
 }
+
+// Recorded latency profiles live alongside their test cases, so the
+// controller's tunables can be adjusted in a case file and checked
+// against real response time behavior. Setting
+// `AUTO_CONCURRENCY_PROFILE` to the path of a case file runs only that
+// case.
+const PROFILES_DIR: &str = "tests/data/auto_concurrency";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileCase {
+    // The latency profile, one response time in milliseconds per line,
+    // relative to the case file.
+    profile: PathBuf,
+    lines: usize,
+    interval: Option<f64>,
+    #[serde(default)]
+    concurrency_scale: f64,
+    #[serde(default)]
+    concurrency_defer: usize,
+    #[serde(default)]
+    concurrency_drop: usize,
+    #[serde(default)]
+    adaptive_concurrency: AutoConcurrencySettings,
+    #[serde(default)]
+    expect: ProfileExpectations,
+}
+
+// Each expectation is an inclusive `[min, max]` range.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileExpectations {
+    in_flight_mean: Option<(f64, f64)>,
+    in_flight_max: Option<(usize, usize)>,
+    concurrency_limit_mean: Option<(f64, f64)>,
+    concurrency_limit_max: Option<(usize, usize)>,
+    observed_rtt_mean: Option<(f64, f64)>,
+}
+
+fn read_latency_profile(path: &Path) -> Vec<Duration> {
+    fs::read_to_string(path)
+        .unwrap_or_else(|error| panic!("Could not read {:?}: {}", path, error))
+        .lines()
+        .map(|line| line.splitn(2, '#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let millis = line
+                .parse::<f64>()
+                .unwrap_or_else(|_| panic!("Invalid latency {:?} in {:?}", line, path));
+            Duration::from_secs_f64(millis / 1000.0)
+        })
+        .collect()
+}
+
+async fn run_profile_case(path: &Path) {
+    let case: ProfileCase = toml::from_str(&fs::read_to_string(path).unwrap())
+        .unwrap_or_else(|error| panic!("Invalid test case {:?}: {}", path, error));
+    let profile = read_latency_profile(&path.parent().unwrap().join(&case.profile));
+    assert!(!profile.is_empty(), "Empty latency profile for {:?}", path);
+
+    let params = TestParams {
+        latency_profile: Some(Arc::new(profile)),
+        concurrency_scale: case.concurrency_scale,
+        concurrency_defer: case.concurrency_defer,
+        concurrency_drop: case.concurrency_drop,
+        ..Default::default()
+    };
+    let request = TowerRequestConfig {
+        in_flight_limit: InFlightLimit::Auto,
+        rate_limit_num: Some(9999),
+        timeout_secs: Some(1),
+        adaptive_concurrency: case.adaptive_concurrency,
+        ..Default::default()
+    };
+    let results = run_test_with(case.lines, case.interval, params, request).await;
+
+    let expect = &case.expect;
+    let in_flight = results.stats.in_flight.stats().unwrap();
+    if let Some((min, max)) = expect.in_flight_mean {
+        assert_within!(in_flight.mean, min, max, "{:?}: {:#?}", path, results);
+    }
+    if let Some((min, max)) = expect.in_flight_max {
+        assert_within!(in_flight.max, min, max, "{:?}: {:#?}", path, results);
+    }
+    let concurrency_limit = results.cstats.concurrency_limit.stats().unwrap();
+    if let Some((min, max)) = expect.concurrency_limit_mean {
+        assert_within!(
+            concurrency_limit.mean,
+            min,
+            max,
+            "{:?}: {:#?}",
+            path,
+            results
+        );
+    }
+    if let Some((min, max)) = expect.concurrency_limit_max {
+        assert_within!(
+            concurrency_limit.max,
+            min,
+            max,
+            "{:?}: {:#?}",
+            path,
+            results
+        );
+    }
+    assert!(
+        concurrency_limit.max <= case.adaptive_concurrency.max_limit,
+        "{:?}: {:#?}",
+        path,
+        results
+    );
+    if let Some((min, max)) = expect.observed_rtt_mean {
+        let observed_rtt = results.cstats.observed_rtt.stats().unwrap();
+        assert_within!(observed_rtt.mean, min, max, "{:?}: {:#?}", path, results);
+    }
+}
+
+#[tokio::test]
+async fn recorded_profiles() {
+    let cases = match std::env::var_os("AUTO_CONCURRENCY_PROFILE") {
+        Some(path) => vec![PathBuf::from(path)],
+        None => {
+            let mut cases = fs::read_dir(PROFILES_DIR)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+                .collect::<Vec<_>>();
+            cases.sort();
+            cases
+        }
+    };
+    assert!(!cases.is_empty(), "No test cases in {}", PROFILES_DIR);
+
+    for case in cases {
+        run_profile_case(&case).await;
+    }
+}
@@ -1,3 +1,5 @@
+use super::EncodedEvent;
+use crate::event::EventFinalizers;
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
@@ -203,6 +205,7 @@ pub trait Batch: Sized {
 pub struct StatefulBatch<B> {
     inner: B,
     was_full: bool,
+    finalizers: EventFinalizers,
 }
 
 impl<B> From<B> for StatefulBatch<B> {
@@ -210,6 +213,7 @@ impl<B> From<B> for StatefulBatch<B> {
         Self {
             inner,
             was_full: false,
+            finalizers: Default::default(),
         }
     }
 }
@@ -222,6 +226,34 @@ impl<B> StatefulBatch<B> {
     pub fn into_inner(self) -> B {
         self.inner
     }
+
+    /// Pushes an encoded event, keeping its finalizers with the batch so
+    /// they are resolved along with the request it ends up in.
+    pub fn push_encoded(
+        &mut self,
+        item: EncodedEvent<B::Input>,
+    ) -> PushResult<EncodedEvent<B::Input>>
+    where
+        B: Batch,
+    {
+        let EncodedEvent { item, finalizers } = item;
+        let num_items = self.inner.num_items();
+        match self.push(item) {
+            PushResult::Ok(full) => {
+                // Events the batch discards, such as those too large for it,
+                // aren't part of the request, so their finalizers are dropped.
+                if self.inner.num_items() > num_items {
+                    self.finalizers.merge(finalizers);
+                }
+                PushResult::Ok(full)
+            }
+            PushResult::Overflow(item) => PushResult::Overflow(EncodedEvent { item, finalizers }),
+        }
+    }
+
+    pub fn take_finalizers(&mut self) -> EventFinalizers {
+        std::mem::take(&mut self.finalizers)
+    }
 }
 
 impl<B> Batch for StatefulBatch<B>
@@ -257,6 +289,7 @@ where
         Self {
             inner: self.inner.fresh(),
             was_full: false,
+            finalizers: Default::default(),
         }
     }
 
@@ -284,7 +284,7 @@ fn compress_distribution(values: Vec<f64>, sample_rates: Vec<u32>) -> (Vec<f64>,
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::sinks::util::BatchSink;
+    use crate::sinks::util::{BatchSink, EncodedEvent};
     use crate::{
         buffers::Acker,
         event::metric::{Metric, MetricValue, StatisticKind},
@@ -330,7 +330,8 @@ mod test {
             MetricBuffer::new(batch_size),
             Duration::from_secs(0),
             acker,
-        );
+        )
+        .with(|event: Event| Ok::<_, crate::Error>(EncodedEvent::new(event)));
 
         (buffered, sent_requests)
     }
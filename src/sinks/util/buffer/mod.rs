@@ -171,8 +171,7 @@ impl Batch for Buffer {
 #[cfg(test)]
 mod test {
     use super::{Buffer, Compression};
-    use crate::buffers::Acker;
-    use crate::sinks::util::{BatchSettings, BatchSink};
+    use crate::sinks::util::{BatchSettings, BatchSink, EncodedEvent};
     use futures::{compat::Future01CompatExt, future};
     use futures01::Sink;
     use std::{
@@ -210,7 +209,7 @@ mod test {
 
         let _ = buffered
             .sink_map_err(drop)
-            .send_all(futures01::stream::iter_ok(input))
+            .send_all(futures01::stream::iter_ok(input.map(EncodedEvent::new)))
             .compat()
             .await
             .unwrap();
@@ -0,0 +1,446 @@
+//! Stop sending requests to a service that keeps failing.
+//!
+//! After `failure_threshold` consecutive failed attempts the breaker
+//! opens and the service stops reporting readiness, so events back up
+//! into the sink's buffer instead of being sent. Once `reset_timeout_secs`
+//! has passed a single probe request is let through: if it succeeds the
+//! breaker closes again, otherwise it stays open for another timeout.
+
+use super::retries::RetryLogic;
+use crate::emit;
+use crate::internal_events::{
+    CircuitBreakerClosed, CircuitBreakerHalfOpened, CircuitBreakerOpened,
+};
+use futures::{ready, FutureExt};
+use pin_project::pin_project;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use tokio::time::{delay_until, Delay, Instant};
+use tower::{timeout::error::Elapsed, Layer, Service};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: usize,
+    pub reset_timeout_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerLayer<L> {
+    config: Option<CircuitBreakerConfig>,
+    logic: L,
+}
+
+impl<L> CircuitBreakerLayer<L> {
+    /// Create a new circuit breaker layer. The breaker is disabled when
+    /// `config` is `None`.
+    pub fn new(config: Option<CircuitBreakerConfig>, logic: L) -> Self {
+        Self { config, logic }
+    }
+}
+
+impl<S, L: RetryLogic> Layer<S> for CircuitBreakerLayer<L> {
+    type Service = CircuitBreaker<S, L>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CircuitBreaker {
+            inner,
+            logic: self.logic.clone(),
+            breaker: self.config.map(|config| Arc::new(Breaker::new(config))),
+            sleep: None,
+            probe: false,
+        }
+    }
+}
+
+/// The state shared by all clones of a `CircuitBreaker` service.
+#[derive(Debug)]
+struct Breaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    status: Status,
+    consecutive_failures: usize,
+    // Tasks waiting for a half-open probe to finish.
+    waiters: Vec<Waker>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Status {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+impl Breaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BreakerState {
+                status: Status::Closed,
+                consecutive_failures: 0,
+                waiters: Vec::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state
+            .lock()
+            .expect("Circuit breaker mutex is poisoned")
+    }
+
+    fn open(&self, state: &mut BreakerState) {
+        let reset_timeout = Duration::from_secs(self.config.reset_timeout_secs);
+        state.status = Status::Open {
+            until: Instant::now() + reset_timeout,
+        };
+        emit!(CircuitBreakerOpened {
+            consecutive_failures: state.consecutive_failures,
+            reset_timeout,
+        });
+    }
+
+    fn record(&self, success: bool, probe: bool) {
+        let mut state = self.lock();
+        if success {
+            state.consecutive_failures = 0;
+            if let Status::HalfOpen { .. } = state.status {
+                state.status = Status::Closed;
+                emit!(CircuitBreakerClosed);
+                wake_all(&mut state);
+            }
+        } else {
+            state.consecutive_failures += 1;
+            match state.status {
+                Status::HalfOpen { .. } if probe => {
+                    self.open(&mut state);
+                    wake_all(&mut state);
+                }
+                Status::Closed if state.consecutive_failures >= self.config.failure_threshold => {
+                    self.open(&mut state)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Lets another request probe the service if a probe was abandoned
+    /// before its result was known.
+    fn release_probe(&self) {
+        let mut state = self.lock();
+        if state.status == (Status::HalfOpen { probing: true }) {
+            state.status = Status::HalfOpen { probing: false };
+            wake_all(&mut state);
+        }
+    }
+}
+
+fn wake_all(state: &mut BreakerState) {
+    for waker in state.waiters.drain(..) {
+        waker.wake();
+    }
+}
+
+/// Stops sending requests to the inner service while it is failing.
+pub struct CircuitBreaker<S, L> {
+    inner: S,
+    logic: L,
+    breaker: Option<Arc<Breaker>>,
+    sleep: Option<Delay>,
+    // Whether this instance holds the half-open probe.
+    probe: bool,
+}
+
+impl<S, L> CircuitBreaker<S, L> {
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn poll_closed(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let breaker = match &self.breaker {
+            Some(breaker) => breaker,
+            None => return Poll::Ready(()),
+        };
+
+        loop {
+            let mut state = breaker.lock();
+            match state.status {
+                Status::Closed => return Poll::Ready(()),
+                Status::Open { until } => {
+                    if Instant::now() >= until {
+                        state.status = Status::HalfOpen { probing: false };
+                        emit!(CircuitBreakerHalfOpened);
+                        continue;
+                    }
+                    drop(state);
+
+                    let sleep = match &mut self.sleep {
+                        Some(sleep) if sleep.deadline() == until => sleep,
+                        sleep => {
+                            *sleep = Some(delay_until(until));
+                            sleep.as_mut().unwrap()
+                        }
+                    };
+                    ready!(sleep.poll_unpin(cx));
+                    self.sleep = None;
+                }
+                Status::HalfOpen { probing } => {
+                    if self.probe {
+                        return Poll::Ready(());
+                    } else if !probing {
+                        state.status = Status::HalfOpen { probing: true };
+                        self.probe = true;
+                        return Poll::Ready(());
+                    } else {
+                        state.waiters.push(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<S: Clone, L: Clone> Clone for CircuitBreaker<S, L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            logic: self.logic.clone(),
+            breaker: self.breaker.as_ref().map(Arc::clone),
+            sleep: None,
+            probe: false,
+        }
+    }
+}
+
+/// A probe reserved by `poll_ready` but never called with is released, so
+/// that the other clones don't wait on it forever.
+impl<S, L> Drop for CircuitBreaker<S, L> {
+    fn drop(&mut self) {
+        if let (true, Some(breaker)) = (self.probe, &self.breaker) {
+            breaker.release_probe();
+        }
+    }
+}
+
+impl<S: fmt::Debug, L> fmt::Debug for CircuitBreaker<S, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("inner", &self.inner)
+            .field("breaker", &self.breaker)
+            .field("probe", &self.probe)
+            .finish()
+    }
+}
+
+impl<S, L, Request> Service<Request> for CircuitBreaker<S, L>
+where
+    S: Service<Request>,
+    S::Error: Into<crate::Error>,
+    L: RetryLogic<Response = S::Response>,
+{
+    type Response = S::Response;
+    type Error = crate::Error;
+    type Future = ResponseFuture<S::Future, L>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.poll_closed(cx));
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let attempt = self.breaker.as_ref().map(|breaker| Attempt {
+            breaker: Arc::clone(breaker),
+            probe: mem::replace(&mut self.probe, false),
+            done: false,
+        });
+        ResponseFuture {
+            inner: self.inner.call(request),
+            logic: self.logic.clone(),
+            attempt,
+        }
+    }
+}
+
+/// Reports the outcome of a request to the breaker, releasing a probe
+/// that is dropped before it completes.
+#[derive(Debug)]
+struct Attempt {
+    breaker: Arc<Breaker>,
+    probe: bool,
+    done: bool,
+}
+
+impl Attempt {
+    fn finish(&mut self, success: bool) {
+        self.done = true;
+        self.breaker.record(success, self.probe);
+    }
+}
+
+impl Drop for Attempt {
+    fn drop(&mut self) {
+        if self.probe && !self.done {
+            self.breaker.release_probe();
+        }
+    }
+}
+
+#[pin_project]
+#[derive(Debug)]
+pub struct ResponseFuture<F, L> {
+    #[pin]
+    inner: F,
+    logic: L,
+    attempt: Option<Attempt>,
+}
+
+impl<F, L, E> Future for ResponseFuture<F, L>
+where
+    F: Future<Output = Result<L::Response, E>>,
+    L: RetryLogic,
+    E: Into<crate::Error>,
+{
+    type Output = Result<L::Response, crate::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.inner.poll(cx)).map_err(Into::into);
+        if let Some(attempt) = this.attempt {
+            // Only failures that would be retried say anything about the
+            // health of the service.
+            let failed = match &output {
+                Ok(response) => this.logic.should_retry_response(response).is_retryable(),
+                Err(error) => match error.downcast_ref::<L::Error>() {
+                    Some(error) => this.logic.is_retriable_error(error),
+                    None => error.downcast_ref::<Elapsed>().is_some(),
+                },
+            };
+            attempt.finish(!failed);
+        }
+        Poll::Ready(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::trace_init;
+    use tokio::time;
+    use tokio_test::{assert_pending, assert_ready_ok, task};
+    use tower_test::{assert_request_eq, mock};
+
+    #[derive(Debug, Clone)]
+    struct TestRetryLogic;
+
+    impl RetryLogic for TestRetryLogic {
+        type Error = TestError;
+        type Response = &'static str;
+
+        fn is_retriable_error(&self, _error: &Self::Error) -> bool {
+            true
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestError;
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "error")
+        }
+    }
+
+    impl std::error::Error for TestError {}
+
+    fn layer() -> CircuitBreakerLayer<TestRetryLogic> {
+        CircuitBreakerLayer::new(
+            Some(CircuitBreakerConfig {
+                failure_threshold: 2,
+                reset_timeout_secs: 10,
+            }),
+            TestRetryLogic,
+        )
+    }
+
+    #[tokio::test]
+    async fn opens_after_consecutive_failures_and_probes() {
+        trace_init();
+
+        time::pause();
+
+        let (mut svc, mut handle) = mock::spawn_layer(layer());
+
+        for _ in 0..2 {
+            assert_ready_ok!(svc.poll_ready());
+            let mut response = task::spawn(svc.call("hello"));
+            assert_request_eq!(handle, "hello").send_error(TestError);
+            assert!(response.await.is_err());
+        }
+
+        // The breaker is open, so no more requests are sent.
+        assert_pending!(svc.poll_ready());
+
+        time::advance(Duration::from_secs(11)).await;
+        assert_ready_ok!(svc.poll_ready());
+
+        // Other clones wait for the probe to finish.
+        let mut waiting = mock::Spawn::new(svc.get_ref().clone());
+        assert_pending!(waiting.poll_ready());
+
+        let mut response = task::spawn(svc.call("probe"));
+        assert_request_eq!(handle, "probe").send_response("world");
+        assert_eq!(response.await.unwrap(), "world");
+
+        // A successful probe closes the breaker again.
+        assert_ready_ok!(waiting.poll_ready());
+        assert_ready_ok!(svc.poll_ready());
+    }
+
+    #[tokio::test]
+    async fn releases_probe_when_dropped_before_call() {
+        trace_init();
+
+        time::pause();
+
+        let (mut svc, mut handle) = mock::spawn_layer(layer());
+
+        for _ in 0..2 {
+            assert_ready_ok!(svc.poll_ready());
+            let mut response = task::spawn(svc.call("hello"));
+            assert_request_eq!(handle, "hello").send_error(TestError);
+            assert!(response.await.is_err());
+        }
+
+        time::advance(Duration::from_secs(11)).await;
+        let mut waiting = mock::Spawn::new(svc.get_ref().clone());
+
+        // The probe is reserved, but the service holding it goes away without using it.
+        assert_ready_ok!(svc.poll_ready());
+        assert_pending!(waiting.poll_ready());
+        drop(svc);
+
+        assert_ready_ok!(waiting.poll_ready());
+    }
+}
@@ -1,6 +1,6 @@
 use super::{
     retries::{RetryAction, RetryLogic},
-    sink, Batch, TowerBatchedSink, TowerRequestSettings,
+    sink, Batch, EncodedEvent, TowerBatchedSink, TowerRequestSettings,
 };
 use crate::{
     buffers::Acker,
@@ -69,7 +69,7 @@ where
     // An empty slot is needed to buffer an item where we encoded it but
     // the inner sink is applying back pressure. This trick is used in the `WithFlatMap`
     // sink combinator. https://docs.rs/futures/0.1.29/src/futures/sink/with_flat_map.rs.html#20
-    slot: Option<B::Input>,
+    slot: Option<EncodedEvent<B::Input>>,
 }
 
 impl<T, B> BatchedHttpSink<T, B, HttpRetryLogic>
@@ -150,7 +150,8 @@ where
         }
         assert!(self.slot.is_none(), "poll_complete did not clear slot");
 
-        if let Some(item) = self.sink.encode_event(item) {
+        let sink = &self.sink;
+        if let Some(item) = EncodedEvent::encode(item, |event| sink.encode_event(event)) {
             self.slot = Some(item);
             self.poll_complete()?;
         }
@@ -370,6 +371,36 @@ impl RetryLogic for HttpRetryLogic {
             _ => RetryAction::DontRetry(format!("response status: {}", status)),
         }
     }
+
+    fn retry_after(&self, response: &Self::Response) -> Option<Duration> {
+        retry_after(response)
+    }
+}
+
+/// Parses the `Retry-After` header of a `429 Too Many Requests` or `503
+/// Service Unavailable` response, given either as a number of seconds
+/// or as an HTTP date.
+pub fn retry_after<B>(response: &http::Response<B>) -> Option<Duration> {
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {}
+        _ => return None,
+    }
+
+    let value = response
+        .headers()
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            // A date in the past means we may retry right away.
+            let delay = date.signed_duration_since(chrono::Utc::now());
+            Some(delay.to_std().unwrap_or_else(|_| Duration::from_secs(0)))
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -399,9 +430,15 @@ impl Auth {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test_util::next_addr;
-    use futures::{compat::Future01CompatExt, future::ready};
-    use futures01::Stream;
+    use crate::{
+        event::{BatchNotifier, EventStatus},
+        sinks::util::{
+            test::build_test_server, BatchSettings, Buffer, Compression, TowerRequestConfig,
+        },
+        test_util::next_addr,
+    };
+    use futures::{compat::Future01CompatExt, future::ready, FutureExt};
+    use futures01::{stream, Stream};
     use hyper::{
         service::{make_service_fn, service_fn},
         {Body, Response, Server, Uri},
@@ -427,6 +464,33 @@ mod test {
             .is_not_retryable());
     }
 
+    #[test]
+    fn util_http_retry_after() {
+        let logic = HttpRetryLogic;
+        let response = |status, retry_after: &str| {
+            Response::builder()
+                .status(status)
+                .header("Retry-After", retry_after)
+                .body(Bytes::new())
+                .unwrap()
+        };
+
+        assert_eq!(
+            logic.retry_after(&response(429, "120")),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            logic.retry_after(&response(503, "Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::from_secs(0))
+        );
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        let delay = logic.retry_after(&response(503, &date)).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+
+        assert_eq!(logic.retry_after(&response(429, "soon")), None);
+        assert_eq!(logic.retry_after(&response(500, "120")), None);
+    }
+
     #[tokio::test]
     async fn util_http_it_makes_http_requests() {
         let addr = next_addr();
@@ -477,4 +541,83 @@ mod test {
         let (body, _rest) = rx.into_future().compat().await.unwrap();
         assert_eq!(body.unwrap(), "hello");
     }
+
+    struct NonEmptySink(Uri);
+
+    #[async_trait::async_trait]
+    impl HttpSink for NonEmptySink {
+        type Input = Vec<u8>;
+        type Output = Vec<u8>;
+
+        fn encode_event(&self, event: Event) -> Option<Self::Input> {
+            let message = event
+                .as_log()
+                .get(&crate::event::log_schema().message_key())
+                .map(|message| message.as_bytes().to_vec())
+                .unwrap_or_default();
+            if message.is_empty() {
+                None
+            } else {
+                Some(message)
+            }
+        }
+
+        async fn build_request(&self, body: Self::Output) -> crate::Result<Request<Vec<u8>>> {
+            Request::post(&self.0).body(body).map_err(Into::into)
+        }
+    }
+
+    #[tokio::test]
+    async fn batched_http_sink_finalizes_unencoded_events_as_dropped() {
+        let addr = next_addr();
+        let uri = format!("http://{}/", addr).parse::<Uri>().unwrap();
+        let (mut rx, _trigger, server) = build_test_server(addr);
+        tokio::spawn(server);
+
+        let (acker, _) = Acker::new_for_testing();
+        let client = HttpClient::new(Resolver, None).unwrap();
+        let batch = BatchSettings::default().bytes(9999).events(10);
+        let request: TowerRequestConfig = Default::default();
+        let sink = BatchedHttpSink::new(
+            NonEmptySink(uri),
+            Buffer::new(batch.size, Compression::None),
+            request.unwrap_with(&Default::default()),
+            batch.timeout,
+            client,
+            acker,
+        );
+
+        let mut receivers = Vec::new();
+        let events = vec!["first", "", "last"]
+            .into_iter()
+            .map(|message| {
+                let (batch, receiver) = BatchNotifier::new_with_receiver();
+                receivers.push(receiver);
+                let mut event = Event::from(message);
+                event.add_batch_notifier(batch);
+                event
+            })
+            .collect::<Vec<_>>();
+
+        sink.send_all(stream::iter_ok(events))
+            .compat()
+            .await
+            .unwrap();
+
+        let (_, body) = rx.recv().await.unwrap();
+        assert_eq!(&body[..], b"firstlast");
+
+        let statuses = receivers
+            .into_iter()
+            .map(|receiver| receiver.now_or_never())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                Some(EventStatus::Delivered),
+                Some(EventStatus::Dropped),
+                Some(EventStatus::Delivered)
+            ]
+        );
+    }
 }
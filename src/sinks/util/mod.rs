@@ -1,6 +1,7 @@
 pub mod auto_concurrency;
 pub mod batch;
 pub mod buffer;
+pub mod circuit_breaker;
 pub mod encoding;
 pub mod http;
 pub mod retries;
@@ -11,12 +12,13 @@ pub mod sink;
 pub mod tcp;
 #[cfg(test)]
 pub mod test;
+pub mod test_encoder;
 pub mod udp;
 #[cfg(all(feature = "sinks-socket", unix))]
 pub mod unix;
 pub mod uri;
 
-use crate::event::{self, Event};
+use crate::event::{self, Event, EventFinalizers};
 use bytes::Bytes;
 use encoding::{EncodingConfig, EncodingConfiguration};
 use serde::{Deserialize, Serialize};
@@ -33,6 +35,7 @@ pub use service::{
     TowerRequestSettings,
 };
 pub use sink::{BatchSink, PartitionBatchSink, StreamSink};
+pub use test_encoder::{EncodedPayload, HttpTestEncoder, TestEncoder};
 pub use uri::UriSerde;
 
 #[derive(Debug, Snafu)]
@@ -80,3 +83,44 @@ pub fn encode_event(mut event: Event, encoding: &EncodingConfig<Encoding>) -> Op
     .map_err(|error| error!(message = "Unable to encode.", %error))
     .ok()
 }
+
+/// An item a sink encoded from an event, along with the finalizers of that
+/// event. They are resolved once the item is delivered or given up on, so
+/// that it's never mistaken for another event sent alongside it.
+#[derive(Debug)]
+pub struct EncodedEvent<I> {
+    pub item: I,
+    pub finalizers: EventFinalizers,
+}
+
+impl<I> EncodedEvent<I> {
+    /// Wraps an item that isn't tracked by any finalizers, such as one
+    /// encoded from a metric.
+    pub fn new(item: I) -> Self {
+        Self {
+            item,
+            finalizers: Default::default(),
+        }
+    }
+
+    /// Encodes `event` with `encode`, carrying its finalizers over to the
+    /// resulting item. The finalizers of an event that isn't encoded are
+    /// dropped along with it.
+    pub fn encode(mut event: Event, encode: impl FnOnce(Event) -> Option<I>) -> Option<Self> {
+        let finalizers = event.take_finalizers();
+        encode(event).map(|item| Self { item, finalizers })
+    }
+
+    pub fn map<T>(self, f: impl FnOnce(I) -> T) -> EncodedEvent<T> {
+        EncodedEvent {
+            item: f(self.item),
+            finalizers: self.finalizers,
+        }
+    }
+}
+
+impl<I> From<I> for EncodedEvent<I> {
+    fn from(item: I) -> Self {
+        Self::new(item)
+    }
+}
@@ -1,5 +1,7 @@
 use crate::Error;
 use futures::FutureExt;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 use std::{
     cmp,
     future::Future,
@@ -29,6 +31,27 @@ pub trait RetryLogic: Clone + Send + Sync + 'static {
         // Treat the default as the request is successful
         RetryAction::Successful
     }
+
+    /// The minimum time the service asked us to wait before retrying
+    /// this response, such as from an HTTP `Retry-After` header.
+    fn retry_after(&self, _response: &Self::Response) -> Option<Duration> {
+        None
+    }
+}
+
+/// How the delay between retries is randomized.
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Eq, PartialEq, Serialize)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterMode {
+    /// Wait exactly the Fibonacci backoff.
+    #[derivative(Default)]
+    None,
+    /// Wait a random time between zero and the Fibonacci backoff.
+    Full,
+    /// Wait a random time between the initial backoff and three times
+    /// the previous delay.
+    Decorrelated,
 }
 
 #[derive(Debug, Clone)]
@@ -36,7 +59,10 @@ pub struct FixedRetryPolicy<L> {
     remaining_attempts: usize,
     previous_duration: Duration,
     current_duration: Duration,
+    initial_backoff: Duration,
     max_duration: Duration,
+    jitter_mode: JitterMode,
+    previous_delay: Duration,
     logic: L,
 }
 
@@ -56,11 +82,19 @@ impl<L: RetryLogic> FixedRetryPolicy<L> {
             remaining_attempts,
             previous_duration: Duration::from_secs(0),
             current_duration: initial_backoff,
+            initial_backoff,
             max_duration,
+            jitter_mode: JitterMode::None,
+            previous_delay: initial_backoff,
             logic,
         }
     }
 
+    pub fn with_jitter(mut self, jitter_mode: JitterMode) -> Self {
+        self.jitter_mode = jitter_mode;
+        self
+    }
+
     fn advance(&self) -> FixedRetryPolicy<L> {
         let next_duration: Duration = self.previous_duration + self.current_duration;
 
@@ -68,7 +102,10 @@ impl<L: RetryLogic> FixedRetryPolicy<L> {
             remaining_attempts: self.remaining_attempts - 1,
             previous_duration: self.current_duration,
             current_duration: cmp::min(next_duration, self.max_duration),
+            initial_backoff: self.initial_backoff,
             max_duration: self.max_duration,
+            jitter_mode: self.jitter_mode,
+            previous_delay: self.previous_delay,
             logic: self.logic.clone(),
         }
     }
@@ -77,11 +114,36 @@ impl<L: RetryLogic> FixedRetryPolicy<L> {
         self.current_duration
     }
 
-    fn build_retry(&self) -> RetryPolicyFuture<L> {
-        let policy = self.advance();
-        let delay = delay_for(self.backoff());
+    /// The backoff with the configured jitter applied.
+    fn jittered_backoff(&self) -> Duration {
+        let (low, high) = match self.jitter_mode {
+            JitterMode::None => return self.backoff(),
+            JitterMode::Full => (Duration::from_secs(0), self.backoff()),
+            JitterMode::Decorrelated => (
+                self.initial_backoff,
+                cmp::min(self.previous_delay * 3, self.max_duration),
+            ),
+        };
+        let (low, high) = (low.as_millis() as u64, high.as_millis() as u64);
+        if high <= low {
+            return Duration::from_millis(low);
+        }
+        Duration::from_millis(thread_rng().gen_range(low, high + 1))
+    }
 
-        debug!(message = "retrying request.", delay_ms = %self.backoff().as_millis());
+    fn build_retry(&self, retry_after: Option<Duration>) -> RetryPolicyFuture<L> {
+        let mut backoff = self.jittered_backoff();
+        if let Some(retry_after) = retry_after {
+            // Honor the service's request, but never wait longer than
+            // the configured maximum.
+            backoff = cmp::max(backoff, cmp::min(retry_after, self.max_duration));
+        }
+
+        let mut policy = self.advance();
+        policy.previous_delay = backoff;
+        let delay = delay_for(backoff);
+
+        debug!(message = "retrying request.", delay_ms = %backoff.as_millis());
         RetryPolicyFuture { delay, policy }
     }
 }
@@ -104,7 +166,7 @@ where
                 match self.logic.should_retry_response(response) {
                     RetryAction::Retry(reason) => {
                         warn!(message = "Retrying after response.", %reason);
-                        Some(self.build_retry())
+                        Some(self.build_retry(self.logic.retry_after(response)))
                     }
 
                     RetryAction::DontRetry(reason) => {
@@ -124,14 +186,14 @@ where
                 if let Some(expected) = error.downcast_ref::<L::Error>() {
                     if self.logic.is_retriable_error(expected) {
                         warn!("retrying after error: {}", expected);
-                        Some(self.build_retry())
+                        Some(self.build_retry(None))
                     } else {
                         error!(message = "encountered non-retriable error.", %error);
                         None
                     }
                 } else if error.downcast_ref::<Elapsed>().is_some() {
                     warn!("request timed out.");
-                    Some(self.build_retry())
+                    Some(self.build_retry(None))
                 } else {
                     error!(message = "unexpected error type.", %error);
                     None
@@ -293,6 +355,80 @@ mod tests {
         assert_eq!(Duration::from_secs(10), policy.backoff());
     }
 
+    #[test]
+    fn full_jitter_stays_within_backoff() {
+        let mut policy = FixedRetryPolicy::new(
+            10,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+        )
+        .with_jitter(JitterMode::Full);
+
+        for _ in 0..9 {
+            for _ in 0..10 {
+                assert!(policy.jittered_backoff() <= policy.backoff());
+            }
+            policy = policy.advance();
+        }
+    }
+
+    #[test]
+    fn decorrelated_jitter_grows_from_previous_delay() {
+        let policy = FixedRetryPolicy::new(
+            10,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+        )
+        .with_jitter(JitterMode::Decorrelated);
+
+        let mut previous = Duration::from_secs(1);
+        let mut policy = policy;
+        for _ in 0..9 {
+            let delay = policy.jittered_backoff();
+            assert!(delay >= Duration::from_secs(1));
+            assert!(delay <= cmp::min(previous * 3, Duration::from_secs(10)));
+
+            policy = policy.advance();
+            policy.previous_delay = delay;
+            previous = delay;
+        }
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after() {
+        trace_init();
+
+        time::pause();
+
+        let policy = FixedRetryPolicy::new(
+            5,
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+            SvcRetryLogic,
+        );
+
+        let (mut svc, mut handle) = mock::spawn_layer(RetryLayer::new(policy));
+
+        assert_ready_ok!(svc.poll_ready());
+
+        let mut fut = task::spawn(svc.call("hello"));
+        assert_request_eq!(handle, "hello").send_response("busy");
+        assert_pending!(fut.poll());
+
+        // The backoff alone would have retried by now.
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(fut.poll());
+        assert_pending!(handle.poll_request());
+
+        time::advance(Duration::from_secs(4)).await;
+        assert_pending!(fut.poll());
+
+        assert_request_eq!(handle, "hello").send_response("world");
+        assert_eq!(fut.await.unwrap(), "world");
+    }
+
     #[derive(Debug, Clone)]
     struct SvcRetryLogic;
 
@@ -303,6 +439,20 @@ mod tests {
         fn is_retriable_error(&self, error: &Self::Error) -> bool {
             error.0
         }
+
+        fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+            match *response {
+                "busy" => RetryAction::Retry("busy".into()),
+                _ => RetryAction::Successful,
+            }
+        }
+
+        fn retry_after(&self, response: &Self::Response) -> Option<Duration> {
+            match *response {
+                "busy" => Some(Duration::from_secs(5)),
+                _ => None,
+            }
+        }
     }
 
     #[derive(Debug)]
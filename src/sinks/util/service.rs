@@ -1,5 +1,8 @@
-use super::auto_concurrency::{AutoConcurrencyLimit, AutoConcurrencyLimitLayer};
-use super::retries::{FixedRetryPolicy, RetryLogic};
+use super::auto_concurrency::{
+    AutoConcurrencyLimit, AutoConcurrencyLimitLayer, AutoConcurrencySettings,
+};
+use super::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerLayer};
+use super::retries::{FixedRetryPolicy, JitterMode, RetryLogic};
 use super::sink::Response;
 use super::{Batch, BatchSink};
 use crate::buffers::Acker;
@@ -21,7 +24,8 @@ use tower::{
     Service, ServiceBuilder,
 };
 
-pub type Svc<S, L> = RateLimit<Retry<FixedRetryPolicy<L>, AutoConcurrencyLimit<Timeout<S>, L>>>;
+pub type Svc<S, L> =
+    RateLimit<Retry<FixedRetryPolicy<L>, CircuitBreaker<AutoConcurrencyLimit<Timeout<S>, L>, L>>>;
 pub type TowerBatchedSink<S, B, L, Request> = BatchSink<Svc<S, L>, B, Request>;
 
 pub trait ServiceBuilderExt<L> {
@@ -160,6 +164,11 @@ pub struct TowerRequestConfig<T = InFlightLimit> {
     pub retry_attempts: Option<usize>,         // max_value()
     pub retry_max_duration_secs: Option<u64>,
     pub retry_initial_backoff_secs: Option<u64>, // 1
+    #[serde(default)]
+    pub retry_jitter_mode: JitterMode,
+    #[serde(default)]
+    pub adaptive_concurrency: AutoConcurrencySettings,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
 }
 
 impl<T: InFlightLimitOption> TowerRequestConfig<T> {
@@ -189,6 +198,9 @@ impl<T: InFlightLimitOption> TowerRequestConfig<T> {
                     .or(defaults.retry_initial_backoff_secs)
                     .unwrap_or(1),
             ),
+            retry_jitter_mode: self.retry_jitter_mode,
+            adaptive_concurrency: self.adaptive_concurrency,
+            circuit_breaker: self.circuit_breaker,
         }
     }
 }
@@ -202,6 +214,9 @@ pub struct TowerRequestSettings {
     pub retry_attempts: usize,
     pub retry_max_duration_secs: Duration,
     pub retry_initial_backoff_secs: Duration,
+    pub retry_jitter_mode: JitterMode,
+    pub adaptive_concurrency: AutoConcurrencySettings,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
 }
 
 impl TowerRequestSettings {
@@ -212,6 +227,7 @@ impl TowerRequestSettings {
             self.retry_max_duration_secs,
             logic,
         )
+        .with_jitter(self.retry_jitter_mode)
     }
 
     pub fn batch_sink<B, L, S, Request>(
@@ -239,8 +255,13 @@ impl TowerRequestSettings {
         let service = ServiceBuilder::new()
             .rate_limit(self.rate_limit_num, self.rate_limit_duration)
             .retry(policy)
+            .layer(CircuitBreakerLayer::new(
+                self.circuit_breaker,
+                retry_logic.clone(),
+            ))
             .layer(AutoConcurrencyLimitLayer::new(
                 self.in_flight_limit,
+                self.adaptive_concurrency,
                 retry_logic,
             ))
             .timeout(self.timeout)
@@ -278,6 +299,10 @@ where
                 self.settings.rate_limit_duration,
             )
             .retry(policy)
+            .layer(CircuitBreakerLayer::new(
+                self.settings.circuit_breaker,
+                self.retry_logic.clone(),
+            ))
             .timeout(self.settings.timeout)
             .service(inner);
 
@@ -368,4 +393,70 @@ mod tests {
         toml::from_str::<TowerRequestConfigTest>(r#"in_flight_limit = -9"#)
             .expect_err("Invalid in_flight_limit didn't fail on negative number");
     }
+
+    #[test]
+    fn adaptive_concurrency_settings_work() {
+        type TowerRequestConfigTest = TowerRequestConfig<InFlightLimit>;
+
+        let cfg = toml::from_str::<TowerRequestConfigTest>("").expect("Empty config failed");
+        assert_eq!(cfg.adaptive_concurrency, AutoConcurrencySettings::default());
+
+        let cfg = toml::from_str::<TowerRequestConfigTest>(
+            r#"
+            in_flight_limit = "auto"
+            [adaptive_concurrency]
+            decrease_ratio = 0.9
+            max_limit = 50
+            "#,
+        )
+        .expect("Adaptive concurrency settings failed");
+        let settings = cfg.unwrap_with(&Default::default()).adaptive_concurrency;
+        assert_eq!(settings.decrease_ratio, 0.9);
+        assert_eq!(settings.max_limit, 50);
+        assert_eq!(
+            settings.ewma_alpha,
+            AutoConcurrencySettings::default().ewma_alpha
+        );
+
+        toml::from_str::<TowerRequestConfigTest>("[adaptive_concurrency]\ndecrease_ratio = 1.5")
+            .expect_err("Invalid decrease_ratio didn't fail");
+
+        toml::from_str::<TowerRequestConfigTest>("[adaptive_concurrency]\nmax_limit = 0")
+            .expect_err("Invalid max_limit didn't fail on zero");
+
+        toml::from_str::<TowerRequestConfigTest>("[adaptive_concurrency]\nalpha = 0.5")
+            .expect_err("Unknown adaptive_concurrency option didn't fail");
+    }
+
+    #[test]
+    fn retry_jitter_and_circuit_breaker_settings_work() {
+        type TowerRequestConfigTest = TowerRequestConfig<InFlightLimit>;
+
+        let settings = toml::from_str::<TowerRequestConfigTest>("")
+            .expect("Empty config failed")
+            .unwrap_with(&Default::default());
+        assert_eq!(settings.retry_jitter_mode, JitterMode::None);
+        assert_eq!(settings.circuit_breaker, None);
+
+        let settings = toml::from_str::<TowerRequestConfigTest>(
+            r#"
+            retry_jitter_mode = "decorrelated"
+            [circuit_breaker]
+            failure_threshold = 3
+            "#,
+        )
+        .expect("Retry settings failed")
+        .unwrap_with(&Default::default());
+        assert_eq!(settings.retry_jitter_mode, JitterMode::Decorrelated);
+        assert_eq!(
+            settings.circuit_breaker,
+            Some(CircuitBreakerConfig {
+                failure_threshold: 3,
+                reset_timeout_secs: 30,
+            })
+        );
+
+        toml::from_str::<TowerRequestConfigTest>(r#"retry_jitter_mode = "random""#)
+            .expect_err("Invalid retry_jitter_mode didn't fail");
+    }
 }
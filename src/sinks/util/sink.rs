@@ -34,7 +34,11 @@
 
 use super::batch::{Batch, PushResult, StatefulBatch};
 use super::buffer::partition::Partition;
-use crate::buffers::Acker;
+use super::EncodedEvent;
+use crate::{
+    buffers::Acker,
+    event::{EventFinalizers, EventStatus},
+};
 use futures::{
     compat::{Compat, Future01CompatExt},
     FutureExt, TryFutureExt,
@@ -61,14 +65,16 @@ const STREAM_SINK_MAX: usize = 10_000;
 /// Simple stream based sink adapter.
 ///
 /// This will wrap any inner sink acking all events
-/// as soon as poll_complete returns ready. `start_send`
-/// will also attempt to fully flush if the amount of
-/// in flight acks is larger than `STREAM_SINK_MAX`.
+/// as soon as poll_complete returns ready, and marking
+/// them as delivered. `start_send` will also attempt to
+/// fully flush if the amount of in flight acks is larger
+/// than `STREAM_SINK_MAX`.
 #[derive(Debug)]
 pub struct StreamSink<T> {
     inner: T,
     acker: Acker,
     pending: usize,
+    finalizers: EventFinalizers,
     closing_inner: bool,
 }
 
@@ -78,20 +84,23 @@ impl<T> StreamSink<T> {
             inner,
             acker,
             pending: 0,
+            finalizers: Default::default(),
             closing_inner: false,
         }
     }
 }
 
 impl<T: Sink> Sink for StreamSink<T> {
-    type SinkItem = T::SinkItem;
+    type SinkItem = EncodedEvent<T::SinkItem>;
     type SinkError = T::SinkError;
 
     fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
         trace!("Sending item.");
+        let EncodedEvent { item, finalizers } = item;
         match self.inner.start_send(item)? {
             AsyncSink::Ready => {
                 self.pending += 1;
+                self.finalizers.merge(finalizers);
                 trace!(message = "Submit successful.", pending_acks = self.pending);
 
                 if self.pending >= STREAM_SINK_MAX {
@@ -103,7 +112,7 @@ impl<T: Sink> Sink for StreamSink<T> {
 
             AsyncSink::NotReady(item) => {
                 trace!("Inner sink applying back pressure.");
-                Ok(AsyncSink::NotReady(item))
+                Ok(AsyncSink::NotReady(EncodedEvent { item, finalizers }))
             }
         }
     }
@@ -114,6 +123,7 @@ impl<T: Sink> Sink for StreamSink<T> {
         trace!(message = "Acking events.", acking_num = self.pending);
         self.acker.ack(self.pending);
         self.pending = 0;
+        std::mem::take(&mut self.finalizers).update_status(EventStatus::Delivered);
 
         Ok(().into())
     }
@@ -144,7 +154,9 @@ impl<T: Sink> Sink for StreamSink<T> {
 /// Service based acking will only ack events when all prior request
 /// batches have been acked. This means if sequential requests r1, r2,
 /// and r3 are dispatched and r2 and r3 complete, all events contained
-/// in all requests will not be acked until r1 has completed.
+/// in all requests will not be acked until r1 has completed. The
+/// finalizers of the events in a request are resolved with the outcome
+/// of that request as soon as it completes.
 pub struct BatchSink<S, B, Request> {
     service: ServiceSink<S, Request>,
     batch: StatefulBatch<B>,
@@ -205,7 +217,7 @@ where
     S::Response: Response,
     B: Batch<Output = Request>,
 {
-    type SinkItem = B::Input;
+    type SinkItem = EncodedEvent<B::Input>;
     type SinkError = crate::Error;
 
     fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
@@ -230,7 +242,7 @@ where
             self.linger = Some(delay);
         }
 
-        match self.batch.push(item) {
+        match self.batch.push_encoded(item) {
             PushResult::Ok(false) => Ok(AsyncSink::Ready),
             PushResult::Ok(true) => {
                 self.poll_complete()?;
@@ -253,12 +265,13 @@ where
                     try_ready!(self.service.poll_ready());
 
                     trace!("Service ready; Sending batch.");
-                    let batch = self.batch.fresh_replace();
+                    let mut batch = self.batch.fresh_replace();
 
+                    let finalizers = batch.take_finalizers();
                     let batch_size = batch.num_items();
                     let request = batch.finish();
 
-                    let fut = self.service.call(request, batch_size).compat();
+                    let fut = self.service.call(request, batch_size, finalizers).compat();
                     tokio::spawn(fut);
 
                     // Disable linger timeout
@@ -319,14 +332,16 @@ type LingerDelay<K> = Box<dyn Future<Item = LingerState<K>, Error = ()> + Send +
 /// Service based acking will only ack events when all prior request
 /// batches have been acked. This means if sequential requests r1, r2,
 /// and r3 are dispatched and r2 and r3 complete, all events contained
-/// in all requests will not be acked until r1 has completed.
+/// in all requests will not be acked until r1 has completed. The
+/// finalizers of the events in a request are resolved with the outcome
+/// of that request as soon as it completes.
 pub struct PartitionBatchSink<B, S, K, Request> {
     batch: StatefulBatch<B>,
     service: ServiceSink<S, Request>,
     partitions: HashMap<K, StatefulBatch<B>>,
     timeout: Duration,
     closing: bool,
-    sending: VecDeque<B>,
+    sending: VecDeque<StatefulBatch<B>>,
     lingers: FuturesUnordered<LingerDelay<K>>,
     linger_handles: HashMap<K, oneshot::Sender<K>>,
 }
@@ -386,21 +401,26 @@ where
         self.lingers.push(Box::new(fut));
     }
 
-    fn poll_send(&mut self, batch: B) -> Poll<(), crate::Error> {
+    fn poll_send(&mut self, mut batch: StatefulBatch<B>) -> Poll<(), crate::Error> {
         if let Async::NotReady = self.service.poll_ready()? {
             self.sending.push_front(batch);
         } else {
+            let finalizers = batch.take_finalizers();
             let batch_size = batch.num_items();
             let batch = batch.finish();
 
-            let fut = self.service.call(batch, batch_size).compat();
+            let fut = self.service.call(batch, batch_size, finalizers).compat();
             tokio::spawn(fut);
         }
 
         self.service.poll_complete()
     }
 
-    fn handle_full_batch(&mut self, item: B::Input, partition: &K) -> FullBatchResult<B::Input> {
+    fn handle_full_batch(
+        &mut self,
+        item: EncodedEvent<B::Input>,
+        partition: &K,
+    ) -> FullBatchResult<EncodedEvent<B::Input>> {
         trace!("Batch full; driving service to completion.");
         if let Err(error) = self.poll_complete() {
             return FullBatchResult::Result(Err(error));
@@ -415,7 +435,7 @@ where
                     );
                     FullBatchResult::Result(Ok(AsyncSink::NotReady(item)))
                 } else {
-                    match batch.push(item) {
+                    match batch.push_encoded(item) {
                         PushResult::Ok(full) => {
                             if full {
                                 if let Err(error) = self.poll_complete() {
@@ -448,7 +468,7 @@ where
     S::Error: Into<crate::Error> + Send + 'static,
     S::Response: Response,
 {
-    type SinkItem = B::Input;
+    type SinkItem = EncodedEvent<B::Input>;
     type SinkError = crate::Error;
 
     fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
@@ -472,7 +492,7 @@ where
             }
         }
 
-        let partition = item.partition();
+        let partition = item.item.partition();
 
         let item = match self.partitions.get_mut(&partition) {
             Some(batch) => {
@@ -483,7 +503,7 @@ where
                     }
                 } else {
                     trace!("Adding event to batch.");
-                    match batch.push(item) {
+                    match batch.push_encoded(item) {
                         PushResult::Ok(full) => {
                             if full {
                                 self.poll_complete()?;
@@ -507,7 +527,7 @@ where
         // or the batch got submitted by polling_complete above.
         let mut batch = self.batch.fresh();
 
-        match batch.push(item) {
+        match batch.push_encoded(item) {
             PushResult::Overflow(_) => unreachable!("Empty buffer overflowed"),
             PushResult::Ok(full) => {
                 self.set_linger(partition.clone());
@@ -575,7 +595,7 @@ where
         }
 
         for batch in ready_batches.into_iter().chain(partitions) {
-            self.poll_send(batch.into_inner())?;
+            self.poll_send(batch)?;
         }
 
         // If we still have an inflight partition then
@@ -652,6 +672,7 @@ where
         &mut self,
         req: Request,
         batch_size: usize,
+        finalizers: EventFinalizers,
     ) -> Box<dyn Future<Item = (), Error = ()> + Send + 'static> {
         let seqno = self.seq_head;
         self.seq_head += 1;
@@ -670,20 +691,28 @@ where
         let response = Compat::new(Box::pin(self.service.call(req)))
             .map_err(Into::into)
             .then(move |result| {
-                match result {
+                let status = match result {
                     Ok(response) if response.is_successful() => {
                         trace!(message = "Response successful.", ?response);
+                        EventStatus::Delivered
                     }
                     Ok(response) => {
                         error!(message = "Response wasn't successful.", ?response);
+                        EventStatus::Rejected
                     }
                     Err(error) => {
                         error!(
                             message = "Request failed.",
                             %error,
                         );
+                        EventStatus::Rejected
                     }
-                }
+                };
+
+                // The events of this request are finalized as soon as it
+                // completes, whatever the state of the requests before it.
+                finalizers.update_status(status);
+                drop(finalizers);
 
                 // If the rx end is dropped we still completed
                 // the request so this is a weird case that we can
@@ -750,6 +779,7 @@ mod tests {
     use super::*;
     use crate::{
         buffers::Acker,
+        event::{BatchNotifier, BatchStatusReceiver, EventFinalizer},
         sinks::util::{buffer::partition::Partition, BatchSettings, EncodedLength, VecBuffer},
     };
     use bytes::Bytes;
@@ -792,7 +822,7 @@ mod tests {
 
         let _ = buffered
             .sink_map_err(drop)
-            .send_all(futures01::stream::iter_ok(0..22))
+            .send_all(futures01::stream::iter_ok((0..22).map(EncodedEvent::new)))
             .compat()
             .await
             .unwrap();
@@ -829,9 +859,9 @@ mod tests {
 
             let mut sink = BatchSink::new(svc, VecBuffer::new(batch.size), TIMEOUT, acker);
 
-            assert!(sink.start_send(0).unwrap().is_ready());
-            assert!(sink.start_send(1).unwrap().is_ready());
-            assert!(sink.start_send(2).unwrap().is_ready());
+            assert!(sink.start_send(EncodedEvent::new(0)).unwrap().is_ready());
+            assert!(sink.start_send(EncodedEvent::new(1)).unwrap().is_ready());
+            assert!(sink.start_send(EncodedEvent::new(2)).unwrap().is_ready());
             yield_now().await;
 
             assert_eq!(ack_counter.load(Relaxed), 0);
@@ -849,9 +879,9 @@ mod tests {
 
             assert_eq!(ack_counter.load(Relaxed), 3);
 
-            assert!(sink.start_send(3).unwrap().is_ready());
-            assert!(sink.start_send(4).unwrap().is_ready());
-            assert!(sink.start_send(5).unwrap().is_ready());
+            assert!(sink.start_send(EncodedEvent::new(3)).unwrap().is_ready());
+            assert!(sink.start_send(EncodedEvent::new(4)).unwrap().is_ready());
+            assert!(sink.start_send(EncodedEvent::new(5)).unwrap().is_ready());
 
             advance_time(Duration::from_secs(2)).await;
 
@@ -891,7 +921,7 @@ mod tests {
 
         let _ = buffered
             .sink_map_err(drop)
-            .send_all(futures01::stream::iter_ok(0..22))
+            .send_all(futures01::stream::iter_ok((0..22).map(EncodedEvent::new)))
             .compat()
             .await
             .unwrap();
@@ -921,8 +951,14 @@ mod tests {
         let batch = BatchSettings::default().bytes(9999).events(10);
         let mut buffered = BatchSink::new(svc, VecBuffer::new(batch.size), TIMEOUT, acker);
 
-        assert!(buffered.start_send(0).unwrap().is_ready());
-        assert!(buffered.start_send(1).unwrap().is_ready());
+        assert!(buffered
+            .start_send(EncodedEvent::new(0))
+            .unwrap()
+            .is_ready());
+        assert!(buffered
+            .start_send(EncodedEvent::new(1))
+            .unwrap()
+            .is_ready());
 
         futures01::future::poll_fn(|| buffered.close())
             .compat()
@@ -948,8 +984,14 @@ mod tests {
             let batch = BatchSettings::default().bytes(9999).events(10);
             let mut buffered = BatchSink::new(svc, VecBuffer::new(batch.size), TIMEOUT, acker);
 
-            assert!(buffered.start_send(0).unwrap().is_ready());
-            assert!(buffered.start_send(1).unwrap().is_ready());
+            assert!(buffered
+                .start_send(EncodedEvent::new(0))
+                .unwrap()
+                .is_ready());
+            assert!(buffered
+                .start_send(EncodedEvent::new(1))
+                .unwrap()
+                .is_ready());
 
             // Move clock forward by linger timeout + 1 sec
             advance_time(TIMEOUT + Duration::from_secs(1)).await;
@@ -980,7 +1022,7 @@ mod tests {
 
         let (_buffered, _) = buffered
             .sink_map_err(drop)
-            .send_all(futures01::stream::iter_ok(0..22))
+            .send_all(futures01::stream::iter_ok((0..22).map(EncodedEvent::new)))
             .compat()
             .await
             .unwrap();
@@ -1013,7 +1055,9 @@ mod tests {
         let input = vec![Partitions::A, Partitions::B];
         let (_buffered, _) = buffered
             .sink_map_err(drop)
-            .send_all(futures01::stream::iter_ok(input))
+            .send_all(futures01::stream::iter_ok(
+                input.into_iter().map(EncodedEvent::new),
+            ))
             .compat()
             .await
             .unwrap();
@@ -1040,7 +1084,9 @@ mod tests {
         let input = vec![Partitions::A, Partitions::B, Partitions::A, Partitions::B];
         let (_buffered, _) = buffered
             .sink_map_err(drop)
-            .send_all(futures01::stream::iter_ok(input))
+            .send_all(futures01::stream::iter_ok(
+                input.into_iter().map(EncodedEvent::new),
+            ))
             .compat()
             .await
             .unwrap();
@@ -1072,7 +1118,7 @@ mod tests {
             let mut buffered =
                 PartitionBatchSink::new(svc, VecBuffer::new(batch.size), TIMEOUT, acker);
 
-            buffered.start_send(1 as usize).unwrap();
+            buffered.start_send(EncodedEvent::new(1 as usize)).unwrap();
             buffered.poll_complete().unwrap();
 
             advance_time(TIMEOUT + Duration::from_secs(1)).await;
@@ -1087,6 +1133,56 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn partition_batch_sink_finalizes_each_request() {
+        run_as_future01(async {
+            let (acker, ack_counter) = Acker::new_for_testing();
+
+            let svc = tower::service_fn(|req: Vec<Partitions>| {
+                if req[0] == Partitions::B {
+                    future::err("bad")
+                } else {
+                    future::ok("good")
+                }
+            });
+
+            let batch = BatchSettings::default().bytes(9999).events(2);
+            let mut sink = PartitionBatchSink::new(svc, VecBuffer::new(batch.size), TIMEOUT, acker);
+
+            let (first, mut first_status) = finalized(Partitions::A);
+            let (second, mut second_status) = finalized(Partitions::B);
+            let (third, mut third_status) = finalized(Partitions::A);
+
+            // The batch of the first partition fills up and is sent, while
+            // the event of the second partition lingers in between.
+            assert!(sink.start_send(first).unwrap().is_ready());
+            assert!(sink.start_send(second).unwrap().is_ready());
+            assert!(sink.start_send(third).unwrap().is_ready());
+            yield_now().await;
+            sink.poll_complete().unwrap();
+
+            assert_eq!(ack_counter.load(Relaxed), 2);
+            assert_eq!(
+                (&mut first_status).now_or_never(),
+                Some(EventStatus::Delivered)
+            );
+            assert_eq!(
+                (&mut third_status).now_or_never(),
+                Some(EventStatus::Delivered)
+            );
+            assert_eq!((&mut second_status).now_or_never(), None);
+
+            futures01::future::poll_fn(|| sink.close())
+                .compat()
+                .await
+                .unwrap();
+
+            assert_eq!(ack_counter.load(Relaxed), 3);
+            assert_eq!(second_status.now_or_never(), Some(EventStatus::Rejected));
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn service_sink_doesnt_propagate_error() {
         run_as_future01(async {
@@ -1106,8 +1202,8 @@ mod tests {
             let mut sink = ServiceSink::new(svc, acker);
 
             // send some initial requests
-            let mut fut1 = sink.call(1, 1);
-            let mut fut2 = sink.call(2, 2);
+            let mut fut1 = sink.call(1, 1, Default::default());
+            let mut fut2 = sink.call(2, 2, Default::default());
 
             assert_eq!(ack_counter.load(Relaxed), 0);
 
@@ -1118,8 +1214,8 @@ mod tests {
             assert_eq!(ack_counter.load(Relaxed), 3);
 
             // send one request that will error and one normal
-            let mut fut3 = sink.call(3, 3); // i will error
-            let mut fut4 = sink.call(4, 4);
+            let mut fut3 = sink.call(3, 3, Default::default()); // i will error
+            let mut fut4 = sink.call(4, 4, Default::default());
 
             // make sure they all "worked"
             assert!(fut3.poll().unwrap().is_ready());
@@ -1130,6 +1226,12 @@ mod tests {
         .await;
     }
 
+    fn finalized<T>(item: T) -> (EncodedEvent<T>, BatchStatusReceiver) {
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        let finalizers = EventFinalizers::new(EventFinalizer::new(batch));
+        (EncodedEvent { item, finalizers }, receiver)
+    }
+
     #[derive(Debug, PartialEq, Eq, Ord, PartialOrd)]
     enum Partitions {
         A,
@@ -6,7 +6,9 @@ use crate::{
         TcpConnectionDisconnected, TcpConnectionEstablished, TcpConnectionFailed,
         TcpConnectionShutdown, TcpEventSent, TcpFlushError,
     },
-    sinks::util::{encode_event, encoding::EncodingConfig, Encoding, SinkBuildError, StreamSink},
+    sinks::util::{
+        encode_event, encoding::EncodingConfig, EncodedEvent, Encoding, SinkBuildError, StreamSink,
+    },
     sinks::{Healthcheck, RouterSink},
     tls::{MaybeTlsSettings, MaybeTlsStream, TlsConfig, TlsError},
 };
@@ -63,8 +65,11 @@ impl TcpSinkConfig {
 
         let encoding = self.encoding.clone();
         let sink = Box::new(
-            StreamSink::new(tcp, cx.acker())
-                .with_flat_map(move |event| iter_ok(encode_event(event, &encoding))),
+            StreamSink::new(tcp, cx.acker()).with_flat_map(move |event| {
+                iter_ok(EncodedEvent::encode(event, |event| {
+                    encode_event(event, &encoding)
+                }))
+            }),
         );
 
         Ok((sink, Box::new(healthcheck.compat())))
@@ -0,0 +1,81 @@
+//! Support for running a sink's encoding layer from `vector test`.
+//!
+//! A `TestEncoder` takes the place of a sink's transport: each event is
+//! encoded exactly as the sink would encode it and the resulting payload is
+//! handed back to the test runner instead of being sent anywhere.
+
+use super::{http::HttpSink, Batch, PushResult};
+use crate::event::Event;
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+/// A payload a sink would have sent for a single event.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncodedPayload {
+    pub body: Bytes,
+    pub headers: IndexMap<String, String>,
+    /// The partition key the event was routed to, such as a rendered file
+    /// path, if the sink partitions its output.
+    pub partition: Option<String>,
+}
+
+pub trait TestEncoder: Send {
+    /// Encodes a single event, returning `None` if the sink drops it.
+    fn encode(&mut self, event: Event) -> crate::Result<Option<EncodedPayload>>;
+}
+
+/// Runs an `HttpSink`'s event encoder and request builder against a batch
+/// holding a single event.
+///
+/// Bodies are captured from the batch before any request compression, so
+/// tests can assert on them directly.
+pub struct HttpTestEncoder<T, B> {
+    sink: T,
+    batch: B,
+}
+
+impl<T, B> HttpTestEncoder<T, B>
+where
+    T: HttpSink,
+    B: Batch<Input = T::Input, Output = T::Output> + Send,
+{
+    pub fn new(sink: T, batch: B) -> Self {
+        Self { sink, batch }
+    }
+}
+
+impl<T, B> TestEncoder for HttpTestEncoder<T, B>
+where
+    T: HttpSink,
+    B: Batch<Input = T::Input, Output = T::Output> + Send,
+{
+    fn encode(&mut self, event: Event) -> crate::Result<Option<EncodedPayload>> {
+        let item = match self.sink.encode_event(event) {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let mut batch = self.batch.fresh();
+        if let PushResult::Overflow(_) = batch.push(item) {
+            return Err("event does not fit in a single batch".into());
+        }
+
+        let request = futures::executor::block_on(self.sink.build_request(batch.finish()))?;
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_owned(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+
+        Ok(Some(EncodedPayload {
+            body: request.into_body().into(),
+            headers,
+            partition: None,
+        }))
+    }
+}
@@ -1,4 +1,6 @@
-use super::{encode_event, encoding::EncodingConfig, Encoding, SinkBuildError, StreamSink};
+use super::{
+    encode_event, encoding::EncodingConfig, EncodedEvent, Encoding, SinkBuildError, StreamSink,
+};
 use crate::{
     config::SinkContext,
     dns::{Resolver, ResolverFuture},
@@ -56,7 +58,9 @@ pub fn raw_udp(
     let sink = UdpSink::new(host, port, cx.resolver())?;
     let sink = StreamSink::new(sink, cx.acker());
     Ok(Box::new(sink.with_flat_map(move |event| {
-        iter_ok(encode_event(event, &encoding))
+        iter_ok(EncodedEvent::encode(event, |event| {
+            encode_event(event, &encoding)
+        }))
     })))
 }
 
@@ -4,7 +4,7 @@ use crate::{
         UnixSocketConnectionEstablished, UnixSocketConnectionFailure, UnixSocketError,
         UnixSocketEventSent,
     },
-    sinks::util::{encode_event, encoding::EncodingConfig, Encoding, StreamSink},
+    sinks::util::{encode_event, encoding::EncodingConfig, EncodedEvent, Encoding, StreamSink},
     sinks::{Healthcheck, RouterSink},
 };
 use bytes::Bytes;
@@ -38,8 +38,11 @@ impl UnixSinkConfig {
         let unix = UnixSink::new(self.path.clone());
         let sink = StreamSink::new(unix, cx.acker());
 
-        let sink =
-            Box::new(sink.with_flat_map(move |event| iter_ok(encode_event(event, &encoding))));
+        let sink = Box::new(sink.with_flat_map(move |event| {
+            iter_ok(EncodedEvent::encode(event, |event| {
+                encode_event(event, &encoding)
+            }))
+        }));
         let healthcheck = healthcheck(self.path.clone()).boxed().compat();
 
         Ok((sink, Box::new(healthcheck)))
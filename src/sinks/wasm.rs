@@ -0,0 +1,348 @@
+use super::streaming_sink::{self, StreamingSink};
+use crate::{
+    buffers::Acker,
+    config::{DataType, SinkConfig, SinkContext, SinkDescription},
+    emit,
+    event::{Event, EventFinalizers, EventStatus},
+    internal_events::{WasmSinkDeliveryRetrying, WasmSinkEventsDropped},
+    wasm::{defaults, WasmModule},
+};
+use async_trait::async_trait;
+use futures::{pin_mut, stream::Stream, StreamExt};
+use futures01::future;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+use tokio::time::{delay_for, timeout_at, Instant};
+use vector_wasm::{Delivery, Role, WasmModuleConfig};
+
+/// Sink specific information needed to construct a [`WasmModuleConfig`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WasmSinkConfig {
+    /// The location of the source WASM or WAT module.
+    pub module: PathBuf,
+    /// The location of the WASM artifact cache.
+    pub artifact_cache: PathBuf,
+    #[serde(default = "defaults::heap_memory_size")]
+    pub heap_memory_size: usize,
+    /// The amount of fuel a single call into the module may consume. Unlimited if unset.
+    #[serde(default)]
+    pub max_fuel: Option<u64>,
+    /// The maximum number of events passed to `send` at once.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// The maximum time an incomplete batch is held before it's passed to `send`.
+    #[serde(default = "default_batch_timeout_secs")]
+    pub batch_timeout_secs: u64,
+    /// How often a batch is passed again when the module asks to retry, before it's dropped.
+    #[serde(default = "default_retry_attempts")]
+    pub retry_attempts: usize,
+    /// The time to wait before passing a batch again.
+    #[serde(default = "default_retry_backoff_secs")]
+    pub retry_backoff_secs: u64,
+    /// The hosts the module may reach.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Options to be passed to the WASM module.
+    #[serde(default)]
+    pub options: HashMap<String, serde_json::Value>,
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_batch_timeout_secs() -> u64 {
+    1
+}
+
+fn default_retry_attempts() -> usize {
+    5
+}
+
+fn default_retry_backoff_secs() -> u64 {
+    1
+}
+
+impl Into<WasmModuleConfig> for WasmSinkConfig {
+    fn into(self) -> WasmModuleConfig {
+        let mut config = WasmModuleConfig::new(
+            Role::Sink,
+            self.module,
+            self.artifact_cache,
+            self.options,
+            self.heap_memory_size,
+        );
+        config
+            .set_max_fuel(self.max_fuel)
+            .set_allowed_hosts(self.allowed_hosts);
+        config
+    }
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<WasmSinkConfig>("wasm")
+}
+
+#[typetag::serde(name = "wasm")]
+impl SinkConfig for WasmSinkConfig {
+    fn build(&self, cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        if self.batch_size == 0 {
+            return Err("`batch_size` must be positive.".into());
+        }
+        let module = WasmModule::build(self.clone())?;
+
+        let sink = WasmSink {
+            module: Some(module),
+            acker: cx.acker(),
+            batch_size: self.batch_size,
+            batch_timeout: Duration::from_secs(self.batch_timeout_secs),
+            retry_attempts: self.retry_attempts,
+            retry_backoff: Duration::from_secs(self.retry_backoff_secs),
+        };
+        let sink = streaming_sink::compat::adapt_to_topology(sink);
+        let healthcheck: super::Healthcheck = Box::new(future::ok(()));
+
+        Ok((sink, healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "wasm"
+    }
+}
+
+struct WasmSink {
+    /// Taken while the module runs on the blocking thread pool.
+    module: Option<WasmModule>,
+    acker: Acker,
+    batch_size: usize,
+    batch_timeout: Duration,
+    retry_attempts: usize,
+    retry_backoff: Duration,
+}
+
+impl WasmSink {
+    /// Pass `events` to the module until it acks or rejects them, or the retries run out.
+    /// The events are acked in all cases, as they are either delivered or dropped, but
+    /// they are only finalized as delivered if the module acked them.
+    async fn deliver(&mut self, mut events: Vec<Event>) -> crate::Result<()> {
+        let count = events.len();
+        let mut finalizers = EventFinalizers::default();
+        for event in &mut events {
+            finalizers.merge(event.take_finalizers());
+        }
+
+        let mut attempt = 0;
+        let status = loop {
+            let module = self
+                .module
+                .take()
+                .expect("module is returned after each call");
+            let batch = events.clone();
+            let (module, delivery) = module.blocking(move |module| module.send(batch)).await?;
+            self.module = Some(module);
+
+            match delivery {
+                Ok(Delivery::Ack) => break EventStatus::Delivered,
+                Ok(Delivery::Retry) if attempt < self.retry_attempts => {
+                    attempt += 1;
+                    emit!(WasmSinkDeliveryRetrying { count, attempt });
+                    delay_for(self.retry_backoff).await;
+                }
+                Ok(Delivery::Retry) => {
+                    emit!(WasmSinkEventsDropped {
+                        count,
+                        reason: "Retries exhausted.".into(),
+                    });
+                    break EventStatus::Rejected;
+                }
+                Ok(Delivery::Reject) => {
+                    emit!(WasmSinkEventsDropped {
+                        count,
+                        reason: "Rejected by the module.".into(),
+                    });
+                    break EventStatus::Rejected;
+                }
+                Err(error) => {
+                    emit!(WasmSinkEventsDropped {
+                        count,
+                        reason: error.to_string(),
+                    });
+                    break EventStatus::Rejected;
+                }
+            }
+        };
+        finalizers.update_status(status);
+        self.acker.ack(count);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StreamingSink for WasmSink {
+    async fn run(
+        &mut self,
+        input: impl Stream<Item = Event> + Send + Sync + 'static,
+    ) -> crate::Result<()> {
+        pin_mut!(input);
+        while let Some(event) = input.next().await {
+            let mut batch = vec![event];
+            let deadline = Instant::now() + self.batch_timeout;
+            while batch.len() < self.batch_size {
+                match timeout_at(deadline, input.next()).await {
+                    Ok(Some(event)) => batch.push(event),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+            self.deliver(batch).await?;
+        }
+
+        if let Some(module) = self.module.take() {
+            let (_, _events) = module.blocking(WasmModule::shutdown).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{metric::MetricValue, BatchNotifier},
+        metrics::{capture_metrics, get_controller},
+    };
+    use futures::FutureExt;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn sink(options: &str) -> crate::Result<(WasmSink, Arc<AtomicUsize>)> {
+        let config: WasmSinkConfig = toml::from_str(&format!(
+            r#"
+            module = "target/wasm32-wasi/release/delivery.wasm"
+            artifact_cache = "target/artifacts"
+            retry_attempts = 2
+            retry_backoff_secs = 0
+            {}
+            "#,
+            options
+        ))?;
+        let (acker, acks) = Acker::new_for_testing();
+        let sink = WasmSink {
+            module: Some(WasmModule::build(config.clone())?),
+            acker,
+            batch_size: config.batch_size,
+            batch_timeout: Duration::from_secs(config.batch_timeout_secs),
+            retry_attempts: config.retry_attempts,
+            retry_backoff: Duration::from_secs(config.retry_backoff_secs),
+        };
+        Ok((sink, acks))
+    }
+
+    /// The value of the counter `name` the `wasm` sink emitted.
+    fn counter(name: &str) -> f64 {
+        capture_metrics(&get_controller().unwrap())
+            .map(Event::into_metric)
+            .filter(|metric| metric.name == name)
+            .filter(|metric| {
+                metric
+                    .tags
+                    .as_ref()
+                    .and_then(|tags| tags.get("component_type"))
+                    .map_or(false, |kind| kind == "wasm")
+            })
+            .map(|metric| match metric.value {
+                MetricValue::Counter { value } => value,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    async fn deliver(options: &str) -> crate::Result<(usize, Option<EventStatus>, f64, f64)> {
+        let (mut sink, acks) = sink(options)?;
+        let retries = counter("delivery_retries");
+        let discarded = counter("events_discarded");
+
+        let (batch, status) = BatchNotifier::new_with_receiver();
+        let events = vec![Event::from("one"), Event::from("two")]
+            .into_iter()
+            .map(|mut event| {
+                event.add_batch_notifier(Arc::clone(&batch));
+                event
+            })
+            .collect();
+        drop(batch);
+        sink.deliver(events).await?;
+
+        Ok((
+            acks.load(Ordering::SeqCst),
+            status.now_or_never(),
+            counter("delivery_retries") - retries,
+            counter("events_discarded") - discarded,
+        ))
+    }
+
+    // The cases run in a single test, as the metrics are shared between all of them.
+    #[tokio::test]
+    async fn deliver_maps_onto_acks_and_drops() -> crate::Result<()> {
+        crate::test_util::trace_init();
+        let _ = crate::metrics::init();
+
+        // Acked batches are acked without dropping anything.
+        assert_eq!(
+            deliver(r#"options.delivery = "ack""#).await?,
+            (2, Some(EventStatus::Delivered), 0.0, 0.0)
+        );
+
+        // Retried batches are passed again until the module acks them.
+        assert_eq!(
+            deliver(
+                r#"
+                options.delivery = "ack"
+                options.retries = 2
+                "#
+            )
+            .await?,
+            (2, Some(EventStatus::Delivered), 2.0, 0.0)
+        );
+
+        // Once the retries run out, the batch is dropped and rejected, but still acked.
+        assert_eq!(
+            deliver(r#"options.delivery = "retry""#).await?,
+            (2, Some(EventStatus::Rejected), 2.0, 2.0)
+        );
+
+        // Rejected batches are dropped right away.
+        assert_eq!(
+            deliver(r#"options.delivery = "reject""#).await?,
+            (2, Some(EventStatus::Rejected), 0.0, 2.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_config() {
+        let config: WasmSinkConfig = toml::from_str(
+            r#"
+            module = "module.wasm"
+            artifact_cache = "target/artifacts"
+            allowed_hosts = ["example.com:443"]
+            options.endpoint = "https://example.com"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.batch_size, 100);
+        assert_eq!(config.retry_attempts, 5);
+
+        let config: WasmModuleConfig = config.into();
+        assert_eq!(config.role, Role::Sink);
+        assert_eq!(config.allowed_hosts, vec!["example.com:443".to_string()]);
+        assert_eq!(config.max_fuel, None);
+    }
+}
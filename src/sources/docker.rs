@@ -17,7 +17,7 @@ use bollard::{
     errors::Error as DockerError,
     service::{ContainerInspectResponse, SystemEventsResponse},
     system::EventsOptions,
-    Docker,
+    Docker, API_DEFAULT_VERSION,
 };
 use bytes::{Buf, Bytes};
 use chrono::{DateTime, FixedOffset, Local, ParseError, Utc};
@@ -27,11 +27,17 @@ use futures::{
     sink::SinkExt,
     FutureExt, Stream, StreamExt, TryFutureExt,
 };
+use glob::Pattern;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::sync::Arc;
-use std::{collections::HashMap, convert::TryFrom, env};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    convert::TryFrom,
+    env,
+    path::PathBuf,
+};
 use string_cache::DefaultAtom as Atom;
 use tokio::sync::mpsc;
 use tracing::field;
@@ -39,6 +45,9 @@ use tracing::field;
 /// The beginning of image names of vector docker images packaged by vector.
 const VECTOR_IMAGE_NAME: &str = "timberio/vector";
 
+/// Timeout in seconds for requests to the Docker API, as used by bollard's defaults.
+const DOCKER_TIMEOUT: u64 = 120;
+
 lazy_static! {
     static ref STDERR: Bytes = "stderr".into();
     static ref STDOUT: Bytes = "stdout".into();
@@ -52,20 +61,63 @@ lazy_static! {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields, default)]
 pub struct DockerConfig {
+    docker_host: Option<String>,
+    tls: Option<DockerTlsConfig>,
     include_containers: Option<Vec<String>>, // Starts with actually, not include
     include_labels: Option<Vec<String>>,
     include_images: Option<Vec<String>>,
+    exclude_containers: Option<Vec<String>>,
+    exclude_labels: Option<Vec<String>>,
+    exclude_images: Option<Vec<String>>,
+    copy_labels: bool,
+    label_fields: HashMap<String, String>,
     partial_event_marker_field: Option<Atom>,
     auto_partial_merge: bool,
     multiline: Option<MultilineConfig>,
 }
 
+/// Certificates used to connect to an `https://` Docker host.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DockerTlsConfig {
+    ca_file: PathBuf,
+    crt_file: PathBuf,
+    key_file: PathBuf,
+}
+
+impl DockerTlsConfig {
+    /// The certificates Docker itself uses, found in `DOCKER_CERT_PATH` or
+    /// `~/.docker`.
+    fn from_env() -> crate::Result<Self> {
+        let dir = match env::var_os("DOCKER_CERT_PATH") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(
+                env::var_os("HOME")
+                    .ok_or("Unable to find the home directory for Docker certificates")?,
+            )
+            .join(".docker"),
+        };
+        Ok(Self {
+            ca_file: dir.join("ca.pem"),
+            crt_file: dir.join("cert.pem"),
+            key_file: dir.join("key.pem"),
+        })
+    }
+}
+
 impl Default for DockerConfig {
     fn default() -> Self {
         Self {
+            docker_host: None,
+            tls: None,
             include_containers: None,
             include_labels: None,
             include_images: None,
+            exclude_containers: None,
+            exclude_labels: None,
+            exclude_images: None,
+            copy_labels: true,
+            label_fields: HashMap::new(),
             partial_event_marker_field: Some(event::PARTIAL.clone()),
             auto_partial_merge: true,
             multiline: None,
@@ -96,6 +148,27 @@ impl DockerConfig {
         }
     }
 
+    /// The fields holding the container labels: `label.<key>` for every label if
+    /// `copy_labels` is set, and the configured `label_fields`.
+    fn label_fields<'a>(
+        &self,
+        labels: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> Vec<(Atom, Value)> {
+        let mut fields = Vec::new();
+        for (key, value) in labels {
+            if self.copy_labels {
+                fields.push((
+                    ("label.".to_owned() + key).into(),
+                    value.as_bytes().to_owned().into(),
+                ));
+            }
+            if let Some(field) = self.label_fields.get(key) {
+                fields.push((field.as_str().into(), value.as_bytes().to_owned().into()));
+            }
+        }
+        fields
+    }
+
     fn with_empty_partial_event_marker_field_as_none(mut self) -> Self {
         if let Some(val) = &self.partial_event_marker_field {
             if val.is_empty() {
@@ -106,6 +179,56 @@ impl DockerConfig {
     }
 }
 
+/// Glob patterns of containers that must not be logged.
+struct ContainerExcludes {
+    /// Matched against container IDs and names.
+    containers: Vec<Pattern>,
+    /// Matched against both `key` and `key=value` of each label.
+    labels: Vec<Pattern>,
+    images: Vec<Pattern>,
+}
+
+impl ContainerExcludes {
+    fn new(config: &DockerConfig) -> Result<Self, glob::PatternError> {
+        let compile = |patterns: &Option<Vec<String>>| {
+            patterns
+                .iter()
+                .flatten()
+                .map(|pattern| Pattern::new(pattern))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(ContainerExcludes {
+            containers: compile(&config.exclude_containers)?,
+            labels: compile(&config.exclude_labels)?,
+            images: compile(&config.exclude_images)?,
+        })
+    }
+
+    fn excluded<'a>(
+        &self,
+        id: &str,
+        names: impl IntoIterator<Item = &'a str>,
+        image: Option<&str>,
+        labels: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> bool {
+        let matches = |patterns: &[Pattern], value: &str| {
+            patterns.iter().any(|pattern| pattern.matches(value))
+        };
+
+        matches(&self.containers, id)
+            || names
+                .into_iter()
+                .any(|name| matches(&self.containers, name))
+            || image
+                .map(|image| matches(&self.images, image))
+                .unwrap_or(false)
+            || labels.into_iter().any(|(key, value)| {
+                matches(&self.labels, key) || matches(&self.labels, &format!("{}={}", key, value))
+            })
+    }
+}
+
 inventory::submit! {
     SourceDescription::new::<DockerConfig>("docker")
 }
@@ -159,6 +282,7 @@ impl SourceConfig for DockerConfig {
 
 struct DockerSourceCore {
     config: DockerConfig,
+    excludes: ContainerExcludes,
     line_agg_config: Option<line_agg::Config>,
     docker: Docker,
     /// Only logs created at, or after this moment are logged.
@@ -169,7 +293,7 @@ impl DockerSourceCore {
     fn new(config: DockerConfig) -> crate::Result<Self> {
         // ?NOTE: Constructs a new Docker instance for a docker host listening at url specified by an env var DOCKER_HOST.
         // ?      Otherwise connects to unix socket which requires sudo privileges, or docker group membership.
-        let docker = docker()?;
+        let docker = docker(config.docker_host.as_deref(), config.tls.as_ref())?;
 
         // Only log events created at-or-after this moment are logged.
         let now = Local::now();
@@ -184,8 +308,11 @@ impl DockerSourceCore {
             None
         };
 
+        let excludes = ContainerExcludes::new(&config)?;
+
         Ok(DockerSourceCore {
             config,
+            excludes,
             line_agg_config,
             docker,
             now_timestamp: now.into(),
@@ -339,6 +466,7 @@ impl DockerSource {
                 let id = container.id.unwrap();
                 let names = container.names.unwrap();
                 let image = container.image.unwrap();
+                let labels = container.labels.unwrap_or_default();
 
                 trace!(
                     message = "found already running container.",
@@ -350,18 +478,28 @@ impl DockerSource {
                     return;
                 }
 
-                if !self.esb.core.config.container_name_included(
-                    id.as_str(),
-                    names.iter().map(|s| {
-                        // In this case bollard / shiplift gives names with starting '/' so it needs to be removed.
-                        let s = s.as_str();
-                        if s.starts_with('/') {
-                            s.split_at('/'.len_utf8()).1
-                        } else {
-                            s
-                        }
-                    }),
-                ) {
+                // In this case bollard / shiplift gives names with starting '/' so it needs to be removed.
+                let names = names.iter().map(|s| {
+                    let s = s.as_str();
+                    if s.starts_with('/') {
+                        s.split_at('/'.len_utf8()).1
+                    } else {
+                        s
+                    }
+                });
+
+                if !self
+                    .esb
+                    .core
+                    .config
+                    .container_name_included(id.as_str(), names.clone())
+                    || self.esb.core.excludes.excluded(
+                        id.as_str(),
+                        names,
+                        Some(image.as_str()),
+                        labels.iter(),
+                    )
+                {
                     trace!(message = "container excluded.", id = field::display(&id));
                     return;
                 }
@@ -442,7 +580,18 @@ impl DockerSource {
                                             attributes.get("image").map(|s| s.as_str()),
                                         );
 
-                                        if include_name && self_check {
+                                        // Container events carry the labels of the container
+                                        // as attributes, next to its name and image.
+                                        let excluded = self.esb.core.excludes.excluded(
+                                            id.as_str(),
+                                            attributes.get("name").map(|s| s.as_str()),
+                                            attributes.get("image").map(|s| s.as_str()),
+                                            attributes
+                                                .iter()
+                                                .filter(|(key, _)| *key != "name" && *key != "image"),
+                                        );
+
+                                        if include_name && self_check && !excluded {
                                             self.containers.insert(id.clone(), self.esb.start(id));
                                         }
                                     }
@@ -509,7 +658,7 @@ impl EventStreamBuilder {
                 .inspect_container(id.as_str(), None::<InspectContainerOptions>)
                 .await
             {
-                Ok(details) => match ContainerMetadata::from_details(details) {
+                Ok(details) => match ContainerMetadata::from_details(details, &this.core.config) {
                     Ok(metadata) => {
                         let info = ContainerLogInfo::new(id, metadata, this.core.now_timestamp);
                         this.start_event_stream(info).await;
@@ -559,7 +708,7 @@ impl EventStreamBuilder {
         });
 
         // Create event streamer
-        let mut partial_event_merge_state = None;
+        let mut partial_event_merge_states = HashMap::new();
 
         let events_stream = stream
             .map(|value| {
@@ -568,7 +717,7 @@ impl EventStreamBuilder {
                         message,
                         self.core.config.partial_event_marker_field.clone(),
                         self.core.config.auto_partial_merge,
-                        &mut partial_event_merge_state,
+                        &mut partial_event_merge_states,
                     )),
                     Err(error) => {
                         // On any error, restart connection
@@ -732,7 +881,7 @@ impl ContainerLogInfo {
         log_output: LogOutput,
         partial_event_marker_field: Option<Atom>,
         auto_partial_merge: bool,
-        partial_event_merge_state: &mut Option<LogEventMergeState>,
+        partial_event_merge_states: &mut HashMap<Bytes, LogEventMergeState>,
     ) -> Option<Event> {
         let (stream, mut bytes_message) = match log_output {
             LogOutput::StdErr { message } => (STDERR.clone(), message),
@@ -812,7 +961,7 @@ impl ContainerLogInfo {
             log_event.insert(event::log_schema().message_key().clone(), bytes_message);
 
             // Stream we got the message from.
-            log_event.insert(STREAM.clone(), stream);
+            log_event.insert(STREAM.clone(), stream.clone());
 
             // Timestamp of the event.
             if let Some(timestamp) = timestamp {
@@ -847,19 +996,23 @@ impl ContainerLogInfo {
         let log_event = if auto_partial_merge {
             // Partial event events merging logic.
 
+            // Partial events are merged separately for each stream, since
+            // stdout and stderr lines can be interleaved.
+
             // If event is partial, stash it and return `None`.
             if is_partial {
                 // If we already have a partial event merge state, the current
                 // message has to be merged into that existing state.
                 // Otherwise, create a new partial event merge state with the
                 // current message being the initial one.
-                if let Some(partial_event_merge_state) = partial_event_merge_state {
-                    partial_event_merge_state.merge_in_next_event(
+                match partial_event_merge_states.entry(stream) {
+                    Entry::Occupied(mut entry) => entry.get_mut().merge_in_next_event(
                         log_event,
                         &[event::log_schema().message_key().clone()],
-                    );
-                } else {
-                    *partial_event_merge_state = Some(LogEventMergeState::new(log_event));
+                    ),
+                    Entry::Vacant(entry) => {
+                        entry.insert(LogEventMergeState::new(log_event));
+                    }
                 };
                 return None;
             };
@@ -868,7 +1021,7 @@ impl ContainerLogInfo {
             // state from before, the current event must be a final event, that
             // would give us a merged event we can return.
             // Otherwise it's just a regular event that we return as-is.
-            match partial_event_merge_state.take() {
+            match partial_event_merge_states.remove(&stream) {
                 Some(partial_event_merge_state) => partial_event_merge_state
                     .merge_in_final_event(log_event, &[event::log_schema().message_key().clone()]),
                 None => log_event,
@@ -899,7 +1052,7 @@ impl ContainerLogInfo {
 }
 
 struct ContainerMetadata {
-    /// label.key -> String
+    /// label.key and promoted label fields -> String
     labels: Vec<(Atom, Value)>,
     /// name -> String
     name: Value,
@@ -910,7 +1063,10 @@ struct ContainerMetadata {
 }
 
 impl ContainerMetadata {
-    fn from_details(details: ContainerInspectResponse) -> Result<Self, ParseError> {
+    fn from_details(
+        details: ContainerInspectResponse,
+        source_config: &DockerConfig,
+    ) -> Result<Self, ParseError> {
         let config = details.config.unwrap();
         let name = details.name.unwrap();
         let created = details.created.unwrap();
@@ -918,16 +1074,7 @@ impl ContainerMetadata {
         let labels = config
             .labels
             .as_ref()
-            .map(|map| {
-                map.iter()
-                    .map(|(key, value)| {
-                        (
-                            ("label.".to_owned() + key).into(),
-                            value.as_bytes().to_owned().into(),
-                        )
-                    })
-                    .collect()
-            })
+            .map(|map| source_config.label_fields(map))
             .unwrap_or_default();
 
         Ok(ContainerMetadata {
@@ -939,17 +1086,43 @@ impl ContainerMetadata {
     }
 }
 
-fn docker() -> Result<Docker, DockerError> {
-    let scheme = env::var("DOCKER_HOST").ok().and_then(|host| {
-        let uri = host.parse::<hyper::Uri>().expect("invalid url");
-        uri.into_parts().scheme
-    });
+fn docker(host: Option<&str>, tls: Option<&DockerTlsConfig>) -> crate::Result<Docker> {
+    let host = match host {
+        Some(host) => host.to_owned(),
+        None => match env::var("DOCKER_HOST") {
+            Ok(host) => host,
+            Err(_) => return Ok(Docker::connect_with_local_defaults()?),
+        },
+    };
 
-    match scheme.as_ref().map(|s| s.as_str()) {
-        Some("http") => Docker::connect_with_http_defaults(),
-        Some("https") => Docker::connect_with_ssl_defaults(),
-        _ => Docker::connect_with_local_defaults(),
-    }
+    let scheme = host
+        .parse::<hyper::Uri>()
+        .map_err(|error| format!("Invalid Docker host {:?}: {}", host, error))?
+        .into_parts()
+        .scheme;
+
+    let docker = match scheme.as_ref().map(|s| s.as_str()) {
+        Some("http") | Some("tcp") => {
+            Docker::connect_with_http(&host, DOCKER_TIMEOUT, API_DEFAULT_VERSION)?
+        }
+        Some("https") => {
+            let tls = match tls {
+                Some(tls) => tls.clone(),
+                None => DockerTlsConfig::from_env()?,
+            };
+            Docker::connect_with_ssl(
+                &host,
+                &tls.key_file,
+                &tls.crt_file,
+                &tls.ca_file,
+                DOCKER_TIMEOUT,
+                API_DEFAULT_VERSION,
+            )?
+        }
+        Some("unix") => Docker::connect_with_unix(&host, DOCKER_TIMEOUT, API_DEFAULT_VERSION)?,
+        _ => Docker::connect_with_local_defaults()?,
+    };
+    Ok(docker)
 }
 
 fn line_agg_adapter(
@@ -977,6 +1150,286 @@ fn line_agg_adapter(
     })
 }
 
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    fn field(name: &str, value: &str) -> (Atom, Value) {
+        (name.into(), value.into())
+    }
+
+    #[test]
+    fn excludes_containers_by_glob() {
+        let excludes = ContainerExcludes::new(&DockerConfig {
+            exclude_containers: Some(vec!["sidecar-*".into(), "0123*".into()]),
+            exclude_images: Some(vec!["*/pause:*".into()]),
+            exclude_labels: Some(vec!["com.example.ignore".into(), "env=dev*".into()]),
+            ..DockerConfig::default()
+        })
+        .unwrap();
+        let none = HashMap::new();
+
+        assert!(excludes.excluded("abcd", vec!["sidecar-1"], None, &none));
+        assert!(excludes.excluded("01234567", vec!["app"], None, &none));
+        assert!(!excludes.excluded("abcd", vec!["app"], Some("busybox"), &none));
+        assert!(excludes.excluded("abcd", vec!["app"], Some("k8s.gcr.io/pause:3.2"), &none));
+        assert!(excludes.excluded(
+            "abcd",
+            vec!["app"],
+            None,
+            &labels(&[("com.example.ignore", "")])
+        ));
+        assert!(excludes.excluded(
+            "abcd",
+            vec!["app"],
+            None,
+            &labels(&[("env", "development")])
+        ));
+        assert!(!excludes.excluded("abcd", vec!["app"], None, &labels(&[("env", "production")])));
+    }
+
+    #[test]
+    fn rejects_invalid_exclude_patterns() {
+        assert!(ContainerExcludes::new(&DockerConfig {
+            exclude_containers: Some(vec!["[".into()]),
+            ..DockerConfig::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn maps_labels_to_fields() {
+        let labels = labels(&[("com.example.service", "api"), ("other", "value")]);
+
+        let config = DockerConfig {
+            label_fields: vec![("com.example.service".into(), "service".into())]
+                .into_iter()
+                .collect(),
+            ..DockerConfig::default()
+        };
+        let mut fields = config.label_fields(&labels);
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            fields,
+            vec![
+                field("label.com.example.service", "api"),
+                field("label.other", "value"),
+                field("service", "api"),
+            ]
+        );
+
+        let config = DockerConfig {
+            copy_labels: false,
+            ..config
+        };
+        assert_eq!(config.label_fields(&labels), vec![field("service", "api")]);
+    }
+}
+
+/// Runs the source against a minimal Docker API served on a Unix socket.
+#[cfg(all(test, unix))]
+mod mock_api_tests {
+    use super::*;
+    use crate::{
+        test_util::{collect_n, temp_file},
+        Pipeline,
+    };
+    use futures::compat::Future01CompatExt;
+    use hyper::{
+        body::Sender,
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use std::sync::Mutex;
+    use tokio::{
+        net::UnixListener,
+        time::{timeout, Duration},
+    };
+
+    struct Container {
+        id: &'static str,
+        name: &'static str,
+        labels: &'static [(&'static str, &'static str)],
+        /// (stream, line) pairs, lines without a trailing newline are partial.
+        logs: &'static [(u8, &'static str)],
+    }
+
+    const CONTAINERS: &[Container] = &[
+        Container {
+            id: "0a0a0a",
+            name: "app-1",
+            labels: &[("com.example.service", "api")],
+            logs: &[(1, "first "), (2, "error line\n"), (1, "second\n")],
+        },
+        Container {
+            id: "0b0b0b",
+            name: "sidecar-1",
+            labels: &[],
+            logs: &[(1, "sidecar line\n")],
+        },
+    ];
+
+    fn container(path: &str) -> Option<&'static Container> {
+        CONTAINERS
+            .iter()
+            .find(|container| path.contains(&format!("/containers/{}/", container.id)))
+    }
+
+    fn labels_json(container: &Container) -> serde_json::Value {
+        container
+            .labels
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.into()))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// Encodes the lines in the multiplexed format used when the container has no TTY.
+    fn log_frames(container: &Container) -> Vec<u8> {
+        let mut frames = Vec::new();
+        for (i, &(stream, line)) in container.logs.iter().enumerate() {
+            let timestamp = Utc::now() + chrono::Duration::milliseconds(i as i64);
+            let payload = format!(
+                "{} {}",
+                timestamp.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+                line
+            );
+            frames.extend_from_slice(&[stream, 0, 0, 0]);
+            frames.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            frames.extend_from_slice(payload.as_bytes());
+        }
+        frames
+    }
+
+    async fn handle(
+        req: Request<Body>,
+        open_streams: Arc<Mutex<Vec<Sender>>>,
+    ) -> Result<Response<Body>, hyper::Error> {
+        let path = req.uri().path().to_owned();
+
+        let body = if path.ends_with("/events") {
+            // Keep the stream open, but never send any events.
+            let (sender, body) = Body::channel();
+            open_streams.lock().unwrap().push(sender);
+            body
+        } else if path.ends_with("/containers/json") {
+            let list = CONTAINERS
+                .iter()
+                .map(|container| {
+                    serde_json::json!({
+                        "Id": container.id,
+                        "Names": [format!("/{}", container.name)],
+                        "Image": "busybox",
+                        "Labels": labels_json(container),
+                    })
+                })
+                .collect::<Vec<_>>();
+            Body::from(serde_json::to_vec(&list).unwrap())
+        } else if let (true, Some(container)) = (path.ends_with("/logs"), container(&path)) {
+            let (mut sender, body) = Body::channel();
+            sender
+                .try_send_data(log_frames(container).into())
+                .expect("Could not send logs");
+            open_streams.lock().unwrap().push(sender);
+            body
+        } else if let (true, Some(container)) = (path.ends_with("/json"), container(&path)) {
+            Body::from(
+                serde_json::to_vec(&serde_json::json!({
+                    "Id": container.id,
+                    "Name": format!("/{}", container.name),
+                    "Created": "2020-01-01T00:00:00Z",
+                    "Config": {
+                        "Image": "busybox",
+                        "Labels": labels_json(container),
+                    },
+                }))
+                .unwrap(),
+            )
+        } else {
+            return Ok(Response::builder().status(404).body(Body::empty()).unwrap());
+        };
+
+        Ok(Response::new(body))
+    }
+
+    fn mock_docker_api() -> String {
+        let path = temp_file();
+        let mut listener = UnixListener::bind(&path).unwrap();
+        let open_streams = Arc::new(Mutex::new(Vec::new()));
+
+        let service = make_service_fn(move |_| {
+            let open_streams = Arc::clone(&open_streams);
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req| {
+                    handle(req, Arc::clone(&open_streams))
+                }))
+            }
+        });
+        tokio::spawn(async move {
+            let incoming = hyper::server::accept::from_stream(listener.incoming());
+            if let Err(error) = Server::builder(incoming).serve(service).await {
+                error!(message = "Mock Docker API failed.", %error);
+            }
+        });
+
+        format!("unix://{}", path.display())
+    }
+
+    #[tokio::test]
+    async fn excludes_maps_labels_and_merges_partial_lines_per_stream() {
+        let config = DockerConfig {
+            docker_host: Some(mock_docker_api()),
+            exclude_containers: Some(vec!["sidecar-*".into()]),
+            label_fields: vec![("com.example.service".into(), "service".into())]
+                .into_iter()
+                .collect(),
+            ..DockerConfig::default()
+        };
+
+        let (sender, recv) = Pipeline::new_test();
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                sender,
+            )
+            .unwrap();
+        tokio::spawn(source.compat());
+
+        let mut events = timeout(Duration::from_secs(5), collect_n(recv, 2))
+            .await
+            .expect("Timed out waiting for events")
+            .unwrap();
+        events.sort_by_key(|event| event.as_log()[&STREAM].as_bytes());
+
+        let log = events[0].as_log();
+        assert_eq!(log[&STREAM], "stderr".into());
+        assert_eq!(log[&event::log_schema().message_key()], "error line".into());
+
+        let log = events[1].as_log();
+        assert_eq!(log[&STREAM], "stdout".into());
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "first second".into()
+        );
+
+        for event in &events {
+            let log = event.as_log();
+            assert_eq!(log[&NAME], "app-1".into());
+            assert_eq!(log[&Atom::from("service")], "api".into());
+            assert_eq!(log[&Atom::from("label.com.example.service")], "api".into());
+        }
+    }
+}
+
 #[cfg(all(test, feature = "docker-integration-tests"))]
 mod tests {
     use super::*;
@@ -1232,7 +1685,7 @@ mod tests {
 
         let out = source_with(&[name], None);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id = container_log_n(1, name, Some(label), message, &docker).await;
         let events = collect_n(out, 1).await.unwrap();
@@ -1260,7 +1713,7 @@ mod tests {
 
         let out = source_with(&[name], None);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id = container_log_n(2, name, None, message, &docker).await;
         let events = collect_n(out, 2).await.unwrap();
@@ -1286,7 +1739,7 @@ mod tests {
 
         let out = source_with(&[name1], None);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id0 = container_log_n(1, name0, None, "13", &docker).await;
         let id1 = container_log_n(1, name1, None, message, &docker).await;
@@ -1311,7 +1764,7 @@ mod tests {
 
         let out = source_with(&[name0, name1], label);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id0 = container_log_n(1, name0, None, "13", &docker).await;
         let id1 = container_log_n(1, name1, Some(label), message, &docker).await;
@@ -1333,7 +1786,7 @@ mod tests {
         let name = "vector_test_currently_running";
         let label = "vector_test_label_currently_running";
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
         let id = running_container(name, Some(label), message, &docker).await;
         let out = source_with(&[name], None);
 
@@ -1368,7 +1821,7 @@ mod tests {
 
         let out = source_with_config(config);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id = container_log_n(1, name, None, message, &docker).await;
         let events = collect_n(out, 1).await.unwrap();
@@ -1393,7 +1846,7 @@ mod tests {
 
         let exclude_out = source_with_config(config_ex);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id = container_log_n(1, name, None, message, &docker).await;
         container_remove(&id, &docker).await;
@@ -1417,7 +1870,7 @@ mod tests {
             ..DockerConfig::default()
         };
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id = running_container(name, None, message, &docker).await;
         let exclude_out = source_with_config(config_ex);
@@ -1442,7 +1895,7 @@ mod tests {
 
         let out = source_with(&[name], None);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let id = container_log_n(1, name, None, message.as_str(), &docker).await;
         let events = collect_n(out, 1).await.unwrap();
@@ -1481,7 +1934,7 @@ mod tests {
 
         let out = source_with_config(config);
 
-        let docker = docker().unwrap();
+        let docker = docker(None, None).unwrap();
 
         let command = emitted_messages
             .into_iter()
@@ -1,7 +1,7 @@
 use super::util::MultilineConfig;
 use crate::{
     config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
-    event::{self, Event},
+    event::{self, BatchNotifier, BatchStatusReceiver, Event, EventStatus},
     internal_events::{FileEventReceived, FileSourceInternalEventsEmitter},
     line_agg::{self, LineAgg},
     shutdown::ShutdownSignal,
@@ -11,7 +11,7 @@ use crate::{
 use bytes::Bytes;
 use file_source::{
     paths_provider::glob::{Glob, MatchOptions},
-    FileServer, Fingerprinter,
+    Checkpointer, FileFingerprint, FilePosition, FileServer, Fingerprinter, Line,
 };
 use futures::{
     compat::{Compat, Compat01As03, Compat01As03Sink, Future01CompatExt},
@@ -76,6 +76,7 @@ pub struct FileConfig {
     pub max_read_bytes: usize,
     pub oldest_first: bool,
     pub remove_after: Option<u64>,
+    pub acknowledgements: bool,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
@@ -104,6 +105,10 @@ impl From<FingerprintingConfig> for Fingerprinter {
     }
 }
 
+/// How many lines can be waiting for their events to be delivered before
+/// the source stops looking at whether the next ones are.
+const PENDING_ACKNOWLEDGEMENTS: usize = 1024;
+
 fn default_max_line_bytes() -> usize {
     bytesize::kib(100u64) as usize
 }
@@ -130,6 +135,7 @@ impl Default for FileConfig {
             max_read_bytes: 2048,
             oldest_first: false,
             remove_after: None,
+            acknowledgements: false,
         }
     }
 }
@@ -193,7 +199,6 @@ pub fn file_source(
         start_at_beginning: config.start_at_beginning,
         ignore_before,
         max_line_bytes: config.max_line_bytes,
-        data_dir,
         glob_minimum_cooldown,
         fingerprinter: config.fingerprinting.clone().into(),
         oldest_first: config.oldest_first,
@@ -201,6 +206,9 @@ pub fn file_source(
         emitter: FileSourceInternalEventsEmitter,
     };
 
+    let checkpointer = Checkpointer::new(&data_dir);
+    let checkpoints = checkpointer.view();
+
     let file_key = config.file_key.clone();
     let host_key = config
         .host_key
@@ -213,6 +221,7 @@ pub fn file_source(
     let multiline_config = config.multiline.clone();
     let message_start_indicator = config.message_start_indicator.clone();
     let multi_line_timeout = config.multi_line_timeout;
+    let acknowledgements = config.acknowledgements;
     Box::new(future::lazy(move || {
         info!(message = "Starting file server.", ?include, ?exclude);
 
@@ -224,14 +233,25 @@ pub fn file_source(
             let rx = StreamExt::filter_map(Compat01As03::new(rx), |val| {
                 futures::future::ready(val.ok())
             });
-            let logic = line_agg::Logic::new(config);
+            // An aggregated line is only processed up to the last line in it.
+            let logic = line_agg::Logic::new(config).with_merge_context(
+                |context: &mut (FileFingerprint, FilePosition), next| *context = next,
+            );
             Box::new(Compat::new(
-                LineAgg::new(rx.map(|(line, src)| (src, line, ())), logic)
-                    .map(|(src, line, _context)| (line, src))
-                    .map(Ok),
+                LineAgg::new(
+                    rx.map(|line: Line| (line.filename, line.text, (line.file_id, line.offset))),
+                    logic,
+                )
+                .map(|(filename, text, (file_id, offset))| Line {
+                    text,
+                    filename,
+                    file_id,
+                    offset,
+                })
+                .map(Ok),
             ))
         };
-        let messages: Box<dyn Stream<Item = (Bytes, String), Error = ()> + Send> =
+        let messages: Box<dyn Stream<Item = Line, Error = ()> + Send> =
             if let Some(ref multiline_config) = multiline_config {
                 wrap_with_line_agg(
                     rx,
@@ -249,15 +269,59 @@ pub fn file_source(
                 Box::new(rx)
             };
 
+        // With acknowledgements, the lines whose events haven't been delivered
+        // yet, in the order they were read. Their files are only checkpointed
+        // up to them once they are.
+        let (pending_tx, pending_rx) = futures::channel::mpsc::unbounded::<(
+            BatchStatusReceiver,
+            FileFingerprint,
+            FilePosition,
+        )>();
+        if acknowledgements {
+            let checkpoints = checkpoints.clone();
+            tokio::spawn(
+                pending_rx
+                    .map(|(receiver, file_id, offset)| {
+                        receiver.map(move |status| (status, file_id, offset))
+                    })
+                    .buffered(PENDING_ACKNOWLEDGEMENTS)
+                    .for_each(move |(status, file_id, offset)| {
+                        if status == EventStatus::Rejected {
+                            // Holding back the checkpoint would only have the same
+                            // lines read, and most likely rejected, again.
+                            warn!(
+                                message = "Event was rejected by a sink, checkpointing anyway.",
+                                file_id, offset,
+                            );
+                        }
+                        checkpoints.update(file_id, offset);
+                        futures::future::ready(())
+                    })
+                    .instrument(current_span()),
+            );
+        }
+
         // Once file server ends this will run until it has finished processing remaining
         // logs in the queue.
         let span = current_span();
         let span2 = span.clone();
         tokio::spawn(
             messages
-                .map(move |(msg, file): (Bytes, String)| {
+                .map(move |line: Line| {
                     let _enter = span2.enter();
-                    create_event(msg, file, &host_key, &hostname, &file_key)
+                    let (file_id, offset) = (line.file_id, line.offset);
+                    let mut event =
+                        create_event(line.text, line.filename, &host_key, &hostname, &file_key);
+                    if acknowledgements {
+                        let (batch, receiver) = BatchNotifier::new_with_receiver();
+                        event.add_batch_notifier(batch);
+                        // The receiving task only goes away with the runtime.
+                        pending_tx.unbounded_send((receiver, file_id, offset)).ok();
+                    } else {
+                        // Lines are checkpointed as soon as they are read.
+                        checkpoints.update(file_id, offset);
+                    }
+                    event
                 })
                 .forward(out.sink_map_err(|e| error!(%e)))
                 .map(|_| ())
@@ -268,7 +332,8 @@ pub fn file_source(
         let span = info_span!("file_server");
         spawn_blocking(move || {
             let _enter = span.enter();
-            let result = file_server.run(Compat01As03Sink::new(tx), shutdown.compat());
+            let result =
+                file_server.run(Compat01As03Sink::new(tx), shutdown.compat(), checkpointer);
             // Panic if we encounter any error originating from the file server.
             // We're at the `spawn_blocking` call, the panic will be caught and
             // passed to the `JoinHandle` error, similar to the usual threads.
@@ -891,6 +956,66 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn file_checkpoints_after_acknowledgement() {
+        let dir = tempdir().unwrap();
+        let config = file::FileConfig {
+            include: vec![dir.path().join("*")],
+            acknowledgements: true,
+            ..test_default_file_config(&dir)
+        };
+
+        let path = dir.path().join("file");
+        let mut file = File::create(&path).unwrap();
+        sleep_500_millis().await;
+
+        // Only the first line is delivered before the server shuts down.
+        {
+            let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
+
+            let (tx, rx) = Pipeline::new_test();
+            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            tokio::spawn(source.compat());
+
+            sleep_500_millis().await;
+            writeln!(&mut file, "first line").unwrap();
+            writeln!(&mut file, "second line").unwrap();
+
+            let (first, rx) = wait_with_timeout(rx.into_future().compat()).await;
+            first
+                .unwrap()
+                .take_finalizers()
+                .update_status(EventStatus::Delivered);
+            let (second, rx) = wait_with_timeout(rx.into_future().compat()).await;
+            let second = second.unwrap();
+            sleep_500_millis().await;
+
+            drop(trigger_shutdown);
+
+            let received = wait_with_timeout(rx.collect().compat()).await;
+            assert!(received.is_empty());
+            drop(second);
+        }
+        // Restart server, the undelivered line is read again.
+        {
+            let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
+
+            let (tx, rx) = Pipeline::new_test();
+            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            tokio::spawn(source.compat());
+
+            sleep_500_millis().await;
+            drop(trigger_shutdown);
+
+            let received = wait_with_timeout(rx.collect().compat()).await;
+            let lines = received
+                .into_iter()
+                .map(|event| event.as_log()[&event::log_schema().message_key()].to_string_lossy())
+                .collect::<Vec<_>>();
+            assert_eq!(lines, vec!["second line"]);
+        }
+    }
+
     #[tokio::test]
     async fn file_start_position_server_restart_with_file_rotation() {
         let dir = tempdir().unwrap();
@@ -1457,4 +1582,52 @@ mod tests {
             Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::NotFound),
         }
     }
+
+    #[tokio::test]
+    async fn remove_file_after_acknowledgement() {
+        trace_init();
+
+        let remove_after = 1;
+
+        let (tx, rx) = Pipeline::new_test();
+        let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
+
+        let dir = tempdir().unwrap();
+        let config = file::FileConfig {
+            include: vec![dir.path().join("*")],
+            remove_after: Some(remove_after),
+            acknowledgements: true,
+            ..test_default_file_config(&dir)
+        };
+
+        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        tokio::spawn(source.compat());
+
+        let path = dir.path().join("file");
+        let mut file = File::create(&path).unwrap();
+
+        sleep_500_millis().await; // The files must be observed at their original lengths before writing to them
+
+        writeln!(&mut file, "line").unwrap();
+        std::mem::drop(file);
+
+        // The file is kept while its line is undelivered.
+        let (event, rx) = wait_with_timeout(rx.into_future().compat()).await;
+        let mut event = event.unwrap();
+        delay_for(Duration::from_secs(remove_after + 1)).await;
+        assert!(
+            path.exists(),
+            "File was removed before its line was delivered"
+        );
+
+        event
+            .take_finalizers()
+            .update_status(EventStatus::Delivered);
+        delay_for(Duration::from_secs(remove_after + 1)).await;
+        assert!(!path.exists(), "File wasn't removed");
+
+        drop(trigger_shutdown);
+        let received = wait_with_timeout(rx.collect().compat()).await;
+        assert!(received.is_empty());
+    }
 }
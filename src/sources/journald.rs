@@ -1,7 +1,7 @@
 use crate::{
     config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
     event,
-    event::{Event, LogEvent, Value},
+    event::{BatchNotifier, Event, EventStatus, LogEvent, Value},
     internal_events::{JournaldEventReceived, JournaldInvalidRecord},
     shutdown::ShutdownSignal,
     Pipeline,
@@ -20,15 +20,16 @@ use nix::{
     unistd::Pid,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::{Error as JsonError, Value as JsonValue};
+use serde_json::{Error as JsonError, Map as JsonMap, Value as JsonValue};
 use snafu::{ResultExt, Snafu};
-use std::collections::{HashMap, HashSet};
-use std::fs::{File, OpenOptions};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
-use std::iter::FromIterator;
 use std::path::PathBuf;
 use std::process::{Child, ChildStdout, Command, Stdio};
 use std::str::FromStr;
+use std::sync::Arc;
 use string_cache::DefaultAtom as Atom;
 use tokio::{
     task::spawn_blocking,
@@ -37,11 +38,15 @@ use tokio::{
 use tracing::{dispatcher, field};
 
 const DEFAULT_BATCH_SIZE: usize = 16;
+/// journald doesn't store fields larger than this, so a larger size in an export file means
+/// it's corrupt.
+const MAX_EXPORT_FIELD_SIZE: u64 = 768 * 1024 * 1024;
 
 lazy_static! {
     static ref CURSOR: Atom = Atom::from("__CURSOR");
     static ref HOSTNAME: Atom = Atom::from("_HOSTNAME");
     static ref MESSAGE: Atom = Atom::from("MESSAGE");
+    static ref PRIORITY: Atom = Atom::from("PRIORITY");
     static ref SYSTEMD_UNIT: Atom = Atom::from("_SYSTEMD_UNIT");
     static ref SOURCE_TIMESTAMP: Atom = Atom::from("_SOURCE_REALTIME_TIMESTAMP");
     static ref RECEIVED_TIMESTAMP: Atom = Atom::from("__REALTIME_TIMESTAMP");
@@ -59,6 +64,12 @@ enum BuildError {
         unit
     ))]
     DuplicatedUnit { unit: String },
+    #[snafu(display("Cannot use both `journal_directory` and `export_directory`"))]
+    BothJournalAndExportDirectory,
+    #[snafu(display("Invalid journal match expression {:?}", expression))]
+    InvalidMatch { expression: String },
+    #[snafu(display("Could not read exported journal directory {:?}: {}", path.display(), source))]
+    ExportDirectoryRead { path: PathBuf, source: io::Error },
 }
 
 #[derive(Deserialize, Serialize, Debug, Default)]
@@ -68,18 +79,27 @@ pub struct JournaldConfig {
     pub units: Vec<String>,
     pub include_units: Vec<String>,
     pub exclude_units: Vec<String>,
+    pub include_matches: Vec<String>,
+    pub exclude_matches: Vec<String>,
     pub data_dir: Option<PathBuf>,
     pub batch_size: Option<usize>,
     pub journalctl_path: Option<PathBuf>,
+    pub journal_directory: Option<PathBuf>,
+    pub export_directory: Option<PathBuf>,
     #[serde(default)]
     pub remap_priority: bool,
+    pub rename_fields: HashMap<String, String>,
+    #[serde(default)]
+    pub drop_address_fields: bool,
+    #[serde(default)]
+    pub acknowledgements: bool,
 }
 
 inventory::submit! {
     SourceDescription::new::<JournaldConfig>("journald")
 }
 
-type Record = HashMap<Atom, String>;
+type Record = HashMap<Atom, Value>;
 
 #[typetag::serde(name = "journald")]
 impl SourceConfig for JournaldConfig {
@@ -113,18 +133,35 @@ impl SourceConfig for JournaldConfig {
             return Err(BuildError::DuplicatedUnit { unit }.into());
         }
 
+        let filter = RecordFilter::new(
+            include_units,
+            exclude_units,
+            parse_matches(&self.include_matches)?,
+            parse_matches(&self.exclude_matches)?,
+        );
+
         let checkpointer = Checkpointer::new(data_dir)
             .map_err(|err| format!("Unable to open checkpoint file: {}", err))?;
 
-        self.source::<Journalctl>(
-            out,
-            shutdown,
-            checkpointer,
-            include_units,
-            exclude_units,
-            batch_size,
-            self.remap_priority,
-        )
+        match (&self.journal_directory, &self.export_directory) {
+            (Some(_), Some(_)) => Err(BuildError::BothJournalAndExportDirectory.into()),
+            (_, Some(_)) => self.source::<ExportFiles>(
+                out,
+                shutdown,
+                checkpointer,
+                filter,
+                batch_size,
+                self.remap_priority,
+            ),
+            (_, None) => self.source::<Journalctl>(
+                out,
+                shutdown,
+                checkpointer,
+                filter,
+                batch_size,
+                self.remap_priority,
+            ),
+        }
     }
 
     fn output_type(&self) -> DataType {
@@ -142,17 +179,24 @@ impl JournaldConfig {
         out: Pipeline,
         shutdown: ShutdownSignal,
         mut checkpointer: Checkpointer,
-        include_units: HashSet<String>,
-        exclude_units: HashSet<String>,
+        filter: RecordFilter,
         batch_size: usize,
         remap_priority: bool,
     ) -> crate::Result<super::Source>
     where
         J: JournalSource + Send + 'static,
     {
-        let out = out
-            .sink_map_err(|_| ())
-            .with(|record: Record| future::ok(create_event(record)));
+        let rename_fields = self.rename_fields.clone();
+        let drop_address_fields = self.drop_address_fields;
+        let out = out.sink_map_err(|_| ()).with(
+            move |(record, batch): (Record, Option<Arc<BatchNotifier>>)| {
+                let mut event = create_event(record, &rename_fields, drop_address_fields);
+                if let Some(batch) = batch {
+                    event.add_batch_notifier(batch);
+                }
+                future::ok(event)
+            },
+        );
 
         // Retrieve the saved checkpoint, and use it to seek forward in the journald log
         let cursor = match checkpointer.get() {
@@ -167,19 +211,20 @@ impl JournaldConfig {
         };
 
         let (journal, close) = J::new(self, cursor)?;
+        let acknowledgements = self.acknowledgements;
 
         Ok(Box::new(future::lazy(move || {
             info!(message = "Starting journald server.",);
 
             let journald_server = JournaldServer {
                 journal,
-                include_units,
-                exclude_units,
+                filter,
                 channel: out,
                 shutdown: shutdown.clone(),
                 checkpointer,
                 batch_size,
                 remap_priority,
+                acknowledgements,
             };
             let span = info_span!("journald-server");
             let dispatcher = dispatcher::get_default(|d| d.clone());
@@ -196,8 +241,12 @@ impl JournaldConfig {
     }
 }
 
-fn create_event(record: Record) -> Event {
-    let mut log = LogEvent::from_iter(record);
+fn create_event(
+    record: Record,
+    rename_fields: &HashMap<String, String>,
+    drop_address_fields: bool,
+) -> Event {
+    let mut log = record.into_iter().collect::<LogEvent>();
     // Convert some journald-specific field names into Vector standard ones.
     if let Some(message) = log.remove(&MESSAGE) {
         log.insert(event::log_schema().message_key().clone(), message);
@@ -223,6 +272,22 @@ fn create_event(record: Record) -> Event {
             }
         }
     }
+    // Address fields (such as `__REALTIME_TIMESTAMP`) describe the position of the
+    // entry in the journal rather than the entry itself.
+    if drop_address_fields {
+        let address_fields = log
+            .keys()
+            .filter(|key| key.starts_with("__"))
+            .collect::<Vec<_>>();
+        for key in address_fields {
+            log.remove(&Atom::from(key));
+        }
+    }
+    for (from, to) in rename_fields {
+        if let Some(value) = log.remove(&Atom::from(from.as_str())) {
+            log.insert(to, value);
+        }
+    }
     // Add source type
     log.try_insert(
         event::log_schema().source_type_key(),
@@ -273,6 +338,10 @@ impl JournalSource for Journalctl {
         command.arg("--show-cursor");
         command.arg("--output=json");
 
+        if let Some(journal_directory) = &config.journal_directory {
+            command.arg(format!("--directory={}", journal_directory.display()));
+        }
+
         let current_boot = config.current_boot_only.unwrap_or(true);
         if current_boot {
             command.arg("--boot");
@@ -314,21 +383,183 @@ impl Iterator for Journalctl {
     }
 }
 
+/// Reads files written by `journalctl --output=export` from a directory, in
+/// file name order, and converts each entry into the same JSON records that
+/// `journalctl --output=json` produces. This needs neither `journalctl` nor
+/// a running systemd.
+struct ExportFiles {
+    /// All the files, to start over from if `skip_until` is never found.
+    all_files: Vec<PathBuf>,
+    files: VecDeque<PathBuf>,
+    current: Option<BufReader<File>>,
+    /// Entries are skipped up to and including the one with this cursor.
+    skip_until: Option<String>,
+}
+
+impl JournalSource for ExportFiles {
+    fn new(
+        config: &JournaldConfig,
+        cursor: Option<String>,
+    ) -> crate::Result<(Self, Box<dyn FnOnce() + Send>)> {
+        let path = config
+            .export_directory
+            .clone()
+            .expect("export_directory must be set");
+        let mut files = fs::read_dir(&path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .context(ExportDirectoryRead { path })?
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        files.sort();
+
+        Ok((
+            ExportFiles {
+                files: files.clone().into(),
+                all_files: files,
+                current: None,
+                skip_until: cursor,
+            },
+            Box::new(|| ()),
+        ))
+    }
+}
+
+impl Iterator for ExportFiles {
+    type Item = Result<String, io::Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current.is_none() {
+                if self.files.is_empty() {
+                    if let Some(cursor) = self.skip_until.take() {
+                        warn!(
+                            message = "Checkpointed cursor not found in the export files, reading them from the first entry.",
+                            %cursor,
+                        );
+                        self.files = self.all_files.clone().into();
+                    }
+                }
+                let path = self.files.pop_front()?;
+                match File::open(&path) {
+                    Ok(file) => self.current = Some(BufReader::new(file)),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+
+            let reader = self.current.as_mut().expect("reader was just opened");
+            let entry = match read_export_entry(reader) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    self.current = None;
+                    continue;
+                }
+                Err(err) => {
+                    self.current = None;
+                    return Some(Err(err));
+                }
+            };
+
+            if let Some(cursor) = &self.skip_until {
+                if entry.get(CURSOR.as_ref()).and_then(|c| c.as_str()) == Some(cursor.as_str()) {
+                    self.skip_until = None;
+                }
+                continue;
+            }
+
+            return Some(Ok(JsonValue::Object(entry).to_string()));
+        }
+    }
+}
+
+/// Reads a single entry of the journal export format. Text fields are
+/// written as `NAME=value\n`; binary fields as `NAME\n`, followed by the
+/// value's length as a little-endian 64 bit integer, the value, and `\n`.
+/// Entries are separated by an empty line.
+///
+/// As with `journalctl --output=json`, values that are not valid UTF-8 are
+/// converted into arrays of bytes, and repeated fields into arrays of values.
+fn read_export_entry<R: BufRead>(reader: &mut R) -> io::Result<Option<JsonMap<String, JsonValue>>> {
+    let mut entry = JsonMap::new();
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(if entry.is_empty() { None } else { Some(entry) });
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        if line.is_empty() {
+            if entry.is_empty() {
+                continue;
+            }
+            return Ok(Some(entry));
+        }
+
+        let (name, value) = match line.iter().position(|&b| b == b'=') {
+            Some(pos) => (
+                String::from_utf8_lossy(&line[..pos]).into_owned(),
+                line[pos + 1..].to_vec(),
+            ),
+            None => {
+                let mut size = [0u8; 8];
+                reader.read_exact(&mut size)?;
+                let size = u64::from_le_bytes(size);
+                if size > MAX_EXPORT_FIELD_SIZE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Field {:?} is {} bytes long, more than journald allows.",
+                            String::from_utf8_lossy(&line),
+                            size
+                        ),
+                    ));
+                }
+                let mut value = Vec::new();
+                reader.by_ref().take(size).read_to_end(&mut value)?;
+                if value.len() as u64 != size {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                let mut newline = [0u8; 1];
+                reader.read_exact(&mut newline)?;
+                (String::from_utf8_lossy(&line).into_owned(), value)
+            }
+        };
+
+        let value = match String::from_utf8(value) {
+            Ok(value) => JsonValue::String(value),
+            Err(err) => JsonValue::Array(err.into_bytes().into_iter().map(Into::into).collect()),
+        };
+        match entry.get_mut(&name) {
+            None => {
+                entry.insert(name, value);
+            }
+            Some(JsonValue::Array(values)) if values.iter().all(|v| !v.is_number()) => {
+                values.push(value)
+            }
+            Some(existing) => *existing = JsonValue::Array(vec![existing.take(), value]),
+        }
+    }
+}
+
 struct JournaldServer<J, T> {
     journal: J,
-    include_units: HashSet<String>,
-    exclude_units: HashSet<String>,
+    filter: RecordFilter,
     channel: T,
     shutdown: ShutdownSignal,
     checkpointer: Checkpointer,
     batch_size: usize,
     remap_priority: bool,
+    acknowledgements: bool,
 }
 
 impl<J, T> JournaldServer<J, T>
 where
     J: JournalSource,
-    T: Sink<SinkItem = Record, SinkError = ()>,
+    T: Sink<SinkItem = (Record, Option<Arc<BatchNotifier>>), SinkError = ()>,
 {
     pub fn run(mut self) {
         let timeout = Duration::from_millis(500); // arbitrary timeout
@@ -336,9 +567,19 @@ where
         let mut shutdown = self.shutdown.compat();
 
         loop {
-            let mut saw_record = false;
             let mut at_end = false;
+            let mut closed = false;
+            // The cursor of the last record that was either filtered out or
+            // accepted downstream, and so can be safely checkpointed.
             let mut cursor: Option<String> = None;
+            // With acknowledgements, the checkpoint waits for the events of the
+            // batch to be delivered instead.
+            let (batch, receiver) = if self.acknowledgements {
+                let (batch, receiver) = BatchNotifier::new_with_receiver();
+                (Some(batch), Some(receiver))
+            } else {
+                (None, None)
+            };
 
             for _ in 0..self.batch_size {
                 let text = match self.journal.next() {
@@ -356,45 +597,72 @@ where
                     }
                 };
 
-                let mut record = match decode_record(&text, self.remap_priority) {
+                let mut record = match decode_record(&text) {
                     Ok(record) => record,
                     Err(error) => {
                         emit!(JournaldInvalidRecord { error, text });
                         continue;
                     }
                 };
-                if let Some(tmp) = record.remove(&CURSOR) {
-                    cursor = Some(tmp);
-                }
-
-                saw_record = true;
+                let record_cursor = record
+                    .remove(&CURSOR)
+                    .map(|cursor| String::from_utf8_lossy(&cursor.as_bytes()).into_owned());
 
-                let unit = record.get(&SYSTEMD_UNIT);
-                if filter_unit(unit, &self.include_units, &self.exclude_units) {
+                if self.filter.filter(&record) {
+                    cursor = record_cursor.or(cursor);
                     continue;
                 }
 
+                if self.remap_priority {
+                    if let Some(priority) = record.get_mut(&PRIORITY) {
+                        remap_priority(priority);
+                    }
+                }
+
                 emit!(JournaldEventReceived {
                     byte_size: text.len()
                 });
 
-                match channel.send(record).wait() {
-                    Ok(_) => {}
-                    Err(()) => error!(message = "Could not send journald log"),
+                match channel.send((record, batch.clone())).wait() {
+                    Ok(_) => cursor = record_cursor.or(cursor),
+                    Err(()) => {
+                        error!(message = "Could not send journald log");
+                        closed = true;
+                        break;
+                    }
                 }
             }
 
-            if saw_record {
-                if let Some(cursor) = cursor {
-                    if let Err(err) = self.checkpointer.set(&cursor) {
-                        error!(
-                            message = "Could not set journald checkpoint.",
-                            error = field::display(&err)
-                        );
+            drop(batch);
+            if let Some(receiver) = receiver {
+                match block_on(select(shutdown, receiver)) {
+                    // The batch isn't checkpointed, so it's read again on the next start.
+                    Either::Left((_, _)) => return,
+                    Either::Right((status, future)) => {
+                        if status == EventStatus::Rejected {
+                            warn!(
+                                message =
+                                    "Events were rejected by a sink, checkpointing them anyway."
+                            );
+                        }
+                        shutdown = future;
                     }
                 }
             }
 
+            if let Some(cursor) = cursor {
+                if let Err(err) = self.checkpointer.set(&cursor) {
+                    error!(
+                        message = "Could not set journald checkpoint.",
+                        error = field::display(&err)
+                    );
+                }
+            }
+
+            if closed {
+                return;
+            }
+
             if at_end {
                 // This works only if run inside tokio context since we are using
                 // tokio's Timer. Outside of such context, this will panic on the first
@@ -410,26 +678,37 @@ where
     }
 }
 
-fn decode_record(text: &str, remap: bool) -> Result<Record, JsonError> {
-    let mut record = serde_json::from_str::<JsonValue>(&text)?;
-    // journalctl will output non-ASCII messages using an array
-    // of integers. Look for those messages and re-parse them.
-    record.get_mut("MESSAGE").and_then(|message| {
-        message
-            .as_array()
-            .and_then(|v| decode_array(&v))
-            .map(|decoded| *message = decoded)
-    });
-    if remap {
-        record.get_mut("PRIORITY").map(remap_priority);
+fn decode_record(text: &str) -> Result<Record, JsonError> {
+    let record = serde_json::from_str::<JsonMap<String, JsonValue>>(&text)?;
+    Ok(record
+        .into_iter()
+        .filter_map(|(key, value)| decode_value(value).map(|value| (Atom::from(key), value)))
+        .collect())
+}
+
+fn decode_value(value: JsonValue) -> Option<Value> {
+    match value {
+        // journalctl outputs `null` for fields it does not show, such
+        // as those too large to print.
+        JsonValue::Null => None,
+        JsonValue::String(text) => Some(Value::Bytes(text.into())),
+        // journalctl will output binary and non-UTF-8 values using an
+        // array of integers, and fields with multiple values as an
+        // array of values.
+        JsonValue::Array(array) => Some(match decode_array(&array) {
+            Some(bytes) => Value::Bytes(bytes.into()),
+            None => Value::Array(array.into_iter().filter_map(decode_value).collect()),
+        }),
+        value => Some(value.into()),
     }
-    serde_json::from_value(record)
 }
 
-fn decode_array(array: &[JsonValue]) -> Option<JsonValue> {
-    // From the array of values, turn all the numbers into bytes, and
-    // then the bytes into a string, but return None if any value in the
-    // array was not a valid byte.
+fn decode_array(array: &[JsonValue]) -> Option<Vec<u8>> {
+    // From the array of values, turn all the numbers into bytes, but
+    // return None if any value in the array was not a valid byte.
+    if array.is_empty() {
+        return None;
+    }
     array
         .iter()
         .map(|item| {
@@ -438,12 +717,11 @@ fn decode_array(array: &[JsonValue]) -> Option<JsonValue> {
                 _ => None,
             })
         })
-        .collect::<Option<Vec<u8>>>()
-        .map(|array| String::from_utf8_lossy(&array).into())
+        .collect()
 }
 
-fn remap_priority(priority: &mut JsonValue) {
-    if let Some(num) = priority.as_str().and_then(|s| usize::from_str(s).ok()) {
+fn remap_priority(priority: &mut Value) {
+    if let Some(num) = priority_number(priority) {
         let text = match num {
             0 => "EMERG",
             1 => "ALERT",
@@ -455,7 +733,163 @@ fn remap_priority(priority: &mut JsonValue) {
             7 => "DEBUG",
             _ => "UNKNOWN",
         };
-        *priority = JsonValue::String(text.into());
+        *priority = Value::Bytes(text.into());
+    }
+}
+
+fn priority_number(priority: &Value) -> Option<usize> {
+    match priority {
+        Value::Bytes(bytes) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| usize::from_str(s).ok()),
+        _ => None,
+    }
+}
+
+/// A single journal match expression, such as `_TRANSPORT=kernel` or
+/// `PRIORITY<=4`.
+#[derive(Clone, Debug, PartialEq)]
+struct JournalMatch {
+    field: Atom,
+    op: MatchOp,
+    value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MatchOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl FromStr for JournalMatch {
+    type Err = BuildError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = || BuildError::InvalidMatch {
+            expression: expression.into(),
+        };
+
+        // Journal field names consist only of upper case letters, digits
+        // and underscores, so the operator starts at the first other character.
+        let split = expression
+            .find(|c: char| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+            .ok_or_else(invalid)?;
+        let (field, rest) = expression.split_at(split);
+        if field.is_empty() {
+            return Err(invalid());
+        }
+
+        let (op, value) = [
+            ("!=", MatchOp::Ne),
+            ("<=", MatchOp::Le),
+            (">=", MatchOp::Ge),
+            ("=", MatchOp::Eq),
+            ("<", MatchOp::Lt),
+            (">", MatchOp::Gt),
+        ]
+        .iter()
+        .find(|(token, _)| rest.starts_with(token))
+        .map(|(token, op)| (*op, &rest[token.len()..]))
+        .ok_or_else(invalid)?;
+
+        Ok(JournalMatch {
+            field: field.into(),
+            op,
+            value: value.into(),
+        })
+    }
+}
+
+impl JournalMatch {
+    fn matches(&self, record: &Record) -> bool {
+        match record.get(&self.field) {
+            None => self.op == MatchOp::Ne,
+            Some(Value::Array(values)) => match self.op {
+                // A negated match on a multi-valued field holds only if
+                // none of the values are equal.
+                MatchOp::Ne => values.iter().all(|value| self.matches_value(value)),
+                _ => values.iter().any(|value| self.matches_value(value)),
+            },
+            Some(value) => self.matches_value(value),
+        }
+    }
+
+    fn matches_value(&self, value: &Value) -> bool {
+        let value = value.as_bytes();
+        let value = String::from_utf8_lossy(&value);
+        let ordering = match (value.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(left), Ok(right)) => left.partial_cmp(&right),
+            _ => Some(value.as_ref().cmp(self.value.as_str())),
+        };
+        match (self.op, ordering) {
+            (MatchOp::Eq, _) => value == self.value,
+            (MatchOp::Ne, _) => value != self.value,
+            (_, None) => false,
+            (MatchOp::Lt, Some(ordering)) => ordering == Ordering::Less,
+            (MatchOp::Le, Some(ordering)) => ordering != Ordering::Greater,
+            (MatchOp::Gt, Some(ordering)) => ordering == Ordering::Greater,
+            (MatchOp::Ge, Some(ordering)) => ordering != Ordering::Less,
+        }
+    }
+}
+
+fn parse_matches(expressions: &[String]) -> Result<Vec<JournalMatch>, BuildError> {
+    expressions.iter().map(|s| s.parse()).collect()
+}
+
+/// Decides which records are dropped, based on their unit and on the
+/// configured match expressions.
+#[derive(Debug, Default)]
+struct RecordFilter {
+    include_units: HashSet<String>,
+    exclude_units: HashSet<String>,
+    /// As with `journalctl`, included matches on the same field are
+    /// combined with OR, and matches on different fields with AND.
+    include_matches: Vec<Vec<JournalMatch>>,
+    exclude_matches: Vec<JournalMatch>,
+}
+
+impl RecordFilter {
+    fn new(
+        include_units: HashSet<String>,
+        exclude_units: HashSet<String>,
+        include_matches: Vec<JournalMatch>,
+        exclude_matches: Vec<JournalMatch>,
+    ) -> Self {
+        let mut grouped: Vec<Vec<JournalMatch>> = Vec::new();
+        for include in include_matches {
+            match grouped
+                .iter_mut()
+                .find(|group| group[0].field == include.field)
+            {
+                Some(group) => group.push(include),
+                None => grouped.push(vec![include]),
+            }
+        }
+
+        RecordFilter {
+            include_units,
+            exclude_units,
+            include_matches: grouped,
+            exclude_matches,
+        }
+    }
+
+    /// Should the given record be filtered (excluded)?
+    fn filter(&self, record: &Record) -> bool {
+        let unit = record
+            .get(&SYSTEMD_UNIT)
+            .map(|unit| String::from_utf8_lossy(&unit.as_bytes()).into_owned());
+        filter_unit(unit.as_ref(), &self.include_units, &self.exclude_units)
+            || !self
+                .include_matches
+                .iter()
+                .all(|group| group.iter().any(|m| m.matches(record)))
+            || self.exclude_matches.iter().any(|m| m.matches(record))
     }
 }
 
@@ -560,7 +994,7 @@ mod checkpointer_tests {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Pipeline;
+    use crate::{test_util::collect_n, Pipeline};
     use futures01::stream::Stream;
     use std::{
         io::{self, BufReader, Cursor},
@@ -617,39 +1051,61 @@ mod tests {
     }
 
     async fn run_journal(iunits: &[&str], xunits: &[&str], cursor: Option<&str>) -> Vec<Event> {
+        let include_units = HashSet::<String>::from_iter(iunits.iter().map(|&s| s.into()));
+        let exclude_units = HashSet::<String>::from_iter(xunits.iter().map(|&s| s.into()));
+        let filter = RecordFilter::new(include_units, exclude_units, vec![], vec![]);
+        run_source::<FakeJournal>(&JournaldConfig::default(), filter, cursor)
+            .await
+            .0
+    }
+
+    async fn run_journal_matches(imatches: &[&str], xmatches: &[&str]) -> Vec<Event> {
+        let include_matches = imatches.iter().map(|s| s.parse().unwrap()).collect();
+        let exclude_matches = xmatches.iter().map(|s| s.parse().unwrap()).collect();
+        let filter = RecordFilter::new(
+            HashSet::new(),
+            HashSet::new(),
+            include_matches,
+            exclude_matches,
+        );
+        run_source::<FakeJournal>(&JournaldConfig::default(), filter, None)
+            .await
+            .0
+    }
+
+    /// Runs the source until it is idle, returning the received events and
+    /// the saved checkpoint.
+    async fn run_source<J: JournalSource + Send + 'static>(
+        config: &JournaldConfig,
+        filter: RecordFilter,
+        cursor: Option<&str>,
+    ) -> (Vec<Event>, Option<String>) {
         let (tx, rx) = Pipeline::new_test();
         let (trigger, shutdown, _) = ShutdownSignal::new_wired();
         let tempdir = tempdir().unwrap();
         let mut checkpointer =
             Checkpointer::new(tempdir.path().to_path_buf()).expect("Creating checkpointer failed!");
-        let include_units = HashSet::<String>::from_iter(iunits.iter().map(|&s| s.into()));
-        let exclude_units = HashSet::<String>::from_iter(xunits.iter().map(|&s| s.into()));
 
         if let Some(cursor) = cursor {
             checkpointer.set(cursor).expect("Could not set checkpoint");
         }
 
-        let config = JournaldConfig::default();
         let source = config
-            .source::<FakeJournal>(
-                tx,
-                shutdown,
-                checkpointer,
-                include_units,
-                exclude_units,
-                DEFAULT_BATCH_SIZE,
-                true,
-            )
+            .source::<J>(tx, shutdown, checkpointer, filter, DEFAULT_BATCH_SIZE, true)
             .expect("Creating journald source failed");
         tokio::spawn(source.compat());
 
         delay_for(Duration::from_millis(100)).await;
         drop(trigger);
 
-        timeout(Duration::from_secs(1), rx.collect().compat())
+        let events = timeout(Duration::from_secs(1), rx.collect().compat())
             .await
             .expect("Unclosed channel")
-            .unwrap()
+            .unwrap();
+
+        let mut checkpointer =
+            Checkpointer::new(tempdir.path().to_path_buf()).expect("Creating checkpointer failed!");
+        (events, checkpointer.get().unwrap())
     }
 
     #[tokio::test]
@@ -720,6 +1176,319 @@ mod tests {
         assert_eq!(timestamp(&received[1]), value_ts(1578529839, 140005000));
     }
 
+    #[tokio::test]
+    async fn includes_matches() {
+        let received = run_journal_matches(&["PRIORITY<=5"], &[]).await;
+        assert_eq!(received.len(), 3);
+        assert_eq!(message(&received[0]), Value::Bytes("¿Hello?".into()));
+        assert_eq!(priority(&received[0]), Value::Bytes("NOTICE".into()));
+        assert_eq!(
+            message(&received[1]),
+            Value::Bytes("Missing timestamp".into())
+        );
+        assert_eq!(
+            message(&received[2]),
+            Value::Bytes("Different timestamps".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn combines_included_matches() {
+        // Matches on the same field are ORed, and on different fields ANDed.
+        let received = run_journal_matches(
+            &[
+                "_SYSTEMD_UNIT=unit.service",
+                "_SYSTEMD_UNIT=stdout",
+                "PRIORITY>2",
+            ],
+            &[],
+        )
+        .await;
+        assert_eq!(received.len(), 2);
+        assert_eq!(message(&received[0]), Value::Bytes("unit message".into()));
+        assert_eq!(
+            message(&received[1]),
+            Value::Bytes("Different timestamps".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn excludes_matches() {
+        let received = run_journal_matches(&[], &["_SYSTEMD_UNIT=stdout", "PRIORITY=7"]).await;
+        assert_eq!(received.len(), 2);
+        assert_eq!(
+            message(&received[0]),
+            Value::Bytes("System Initialization".into())
+        );
+        assert_eq!(message(&received[1]), Value::Bytes("¿Hello?".into()));
+    }
+
+    #[test]
+    fn parses_matches() {
+        assert_eq!(
+            "PRIORITY<=4".parse::<JournalMatch>().unwrap(),
+            JournalMatch {
+                field: "PRIORITY".into(),
+                op: MatchOp::Le,
+                value: "4".into(),
+            }
+        );
+        assert_eq!(
+            "_TRANSPORT!=kernel".parse::<JournalMatch>().unwrap(),
+            JournalMatch {
+                field: "_TRANSPORT".into(),
+                op: MatchOp::Ne,
+                value: "kernel".into(),
+            }
+        );
+        assert_eq!(
+            "_SYSTEMD_UNIT=a=b.service".parse::<JournalMatch>().unwrap(),
+            JournalMatch {
+                field: "_SYSTEMD_UNIT".into(),
+                op: MatchOp::Eq,
+                value: "a=b.service".into(),
+            }
+        );
+        assert!("PRIORITY".parse::<JournalMatch>().is_err());
+        assert!("=4".parse::<JournalMatch>().is_err());
+        assert!("priority=4".parse::<JournalMatch>().is_err());
+        assert!("PRIORITY~4".parse::<JournalMatch>().is_err());
+    }
+
+    #[test]
+    fn matches_multiple_values() {
+        let mut record = Record::new();
+        record.insert("TAG".into(), Value::Array(vec!["one".into(), "two".into()]));
+
+        let matches = |expr: &str| expr.parse::<JournalMatch>().unwrap().matches(&record);
+        assert!(matches("TAG=one"));
+        assert!(matches("TAG=two"));
+        assert!(!matches("TAG=three"));
+        assert!(!matches("TAG!=one"));
+        assert!(matches("TAG!=three"));
+        assert!(matches("MISSING!=one"));
+        assert!(!matches("MISSING=one"));
+    }
+
+    #[tokio::test]
+    async fn renames_and_drops_fields() {
+        let config = JournaldConfig {
+            rename_fields: vec![("_SYSTEMD_UNIT".to_string(), "unit".to_string())]
+                .into_iter()
+                .collect(),
+            drop_address_fields: true,
+            ..Default::default()
+        };
+        let (received, _) = run_source::<FakeJournal>(&config, RecordFilter::default(), None).await;
+        assert_eq!(received.len(), 5);
+        let log = received[3].as_log();
+        assert_eq!(log[&"unit".into()], Value::Bytes("stdout".into()));
+        assert!(!log.contains(&"_SYSTEMD_UNIT".into()));
+        assert!(!log.contains(&"__REALTIME_TIMESTAMP".into()));
+        assert_eq!(timestamp(&received[3]), value_ts(1578529839, 140004000));
+    }
+
+    #[tokio::test]
+    async fn checkpoints_accepted_records() {
+        let (received, checkpoint) =
+            run_source::<FakeJournal>(&JournaldConfig::default(), RecordFilter::default(), None)
+                .await;
+        assert_eq!(received.len(), 5);
+        assert_eq!(checkpoint, Some("4".into()));
+    }
+
+    #[tokio::test]
+    async fn does_not_checkpoint_rejected_records() {
+        let (tx, rx) = Pipeline::new_test();
+        drop(rx);
+        let (_trigger, shutdown, _) = ShutdownSignal::new_wired();
+        let tempdir = tempdir().unwrap();
+        let checkpointer =
+            Checkpointer::new(tempdir.path().to_path_buf()).expect("Creating checkpointer failed!");
+
+        let source = JournaldConfig::default()
+            .source::<FakeJournal>(
+                tx,
+                shutdown,
+                checkpointer,
+                RecordFilter::default(),
+                DEFAULT_BATCH_SIZE,
+                true,
+            )
+            .expect("Creating journald source failed");
+        timeout(Duration::from_secs(1), source.compat())
+            .await
+            .expect("Source did not stop")
+            .unwrap();
+
+        let mut checkpointer =
+            Checkpointer::new(tempdir.path().to_path_buf()).expect("Creating checkpointer failed!");
+        assert_eq!(checkpointer.get().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn checkpoints_after_acknowledgement() {
+        let (tx, rx) = Pipeline::new_test();
+        let (_trigger, shutdown, _) = ShutdownSignal::new_wired();
+        let tempdir = tempdir().unwrap();
+        let checkpointer =
+            Checkpointer::new(tempdir.path().to_path_buf()).expect("Creating checkpointer failed!");
+
+        let config = JournaldConfig {
+            acknowledgements: true,
+            ..Default::default()
+        };
+        let source = config
+            .source::<FakeJournal>(
+                tx,
+                shutdown,
+                checkpointer,
+                RecordFilter::default(),
+                DEFAULT_BATCH_SIZE,
+                true,
+            )
+            .expect("Creating journald source failed");
+        tokio::spawn(source.compat());
+
+        let checkpoint = || {
+            Checkpointer::new(tempdir.path().to_path_buf())
+                .expect("Creating checkpointer failed!")
+                .get()
+                .unwrap()
+        };
+
+        let received = collect_n(rx, 5).await.unwrap();
+        delay_for(Duration::from_millis(100)).await;
+        assert_eq!(checkpoint(), None);
+
+        for mut event in received {
+            event
+                .take_finalizers()
+                .update_status(EventStatus::Delivered);
+        }
+        delay_for(Duration::from_millis(100)).await;
+        assert_eq!(checkpoint(), Some("4".into()));
+    }
+
+    #[tokio::test]
+    async fn does_not_checkpoint_unacknowledged_events_on_shutdown() {
+        let (tx, rx) = Pipeline::new_test();
+        let (trigger, shutdown, _) = ShutdownSignal::new_wired();
+        let tempdir = tempdir().unwrap();
+        let checkpointer =
+            Checkpointer::new(tempdir.path().to_path_buf()).expect("Creating checkpointer failed!");
+
+        let config = JournaldConfig {
+            acknowledgements: true,
+            ..Default::default()
+        };
+        let source = config
+            .source::<FakeJournal>(
+                tx,
+                shutdown,
+                checkpointer,
+                RecordFilter::default(),
+                DEFAULT_BATCH_SIZE,
+                true,
+            )
+            .expect("Creating journald source failed");
+        let source = tokio::spawn(source.compat());
+
+        let received = collect_n(rx, 5).await.unwrap();
+        drop(trigger);
+        timeout(Duration::from_secs(1), source)
+            .await
+            .expect("Source did not shut down while waiting for acknowledgements")
+            .unwrap()
+            .unwrap();
+
+        for mut event in received {
+            event
+                .take_finalizers()
+                .update_status(EventStatus::Delivered);
+        }
+        let checkpoint = Checkpointer::new(tempdir.path().to_path_buf())
+            .expect("Creating checkpointer failed!")
+            .get()
+            .unwrap();
+        assert_eq!(checkpoint, None);
+    }
+
+    const EXPORT_ENTRY_1: &[u8] = b"__CURSOR=s=1\n__REALTIME_TIMESTAMP=1578529839140001\n_SYSTEMD_UNIT=sysinit.target\nMESSAGE=System Initialization\nPRIORITY=6\n\n";
+    const EXPORT_ENTRY_2: &[u8] = b"__CURSOR=s=2\n__REALTIME_TIMESTAMP=1578529839140002\n_SYSTEMD_UNIT=unit.service\nMESSAGE\n\x08\x00\x00\x00\x00\x00\x00\x00two\nline\nPRIORITY=7\n\n";
+    const EXPORT_ENTRY_3: &[u8] = b"__CURSOR=s=3\n__REALTIME_TIMESTAMP=1578529839140003\n_SYSTEMD_UNIT=unit.service\nMESSAGE\n\x03\x00\x00\x00\x00\x00\x00\x00\xff\x00\x01\nTAG=a\nTAG=b\n\n";
+
+    fn export_config() -> (tempfile::TempDir, JournaldConfig) {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("1.export"),
+            [EXPORT_ENTRY_1, EXPORT_ENTRY_2].concat(),
+        )
+        .unwrap();
+        fs::write(dir.path().join("2.export"), EXPORT_ENTRY_3).unwrap();
+        let config = JournaldConfig {
+            export_directory: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        (dir, config)
+    }
+
+    #[tokio::test]
+    async fn reads_export_files() {
+        let (_dir, config) = export_config();
+        let (received, checkpoint) =
+            run_source::<ExportFiles>(&config, RecordFilter::default(), None).await;
+        assert_eq!(received.len(), 3);
+        assert_eq!(
+            message(&received[0]),
+            Value::Bytes("System Initialization".into())
+        );
+        assert_eq!(timestamp(&received[0]), value_ts(1578529839, 140001000));
+        assert_eq!(message(&received[1]), Value::Bytes("two\nline".into()));
+        assert_eq!(priority(&received[1]), Value::Bytes("DEBUG".into()));
+        assert_eq!(
+            message(&received[2]),
+            Value::Bytes(Bytes::from(&b"\xff\x00\x01"[..]))
+        );
+        assert_eq!(
+            received[2].as_log()[&"TAG".into()],
+            Value::Array(vec!["a".into(), "b".into()])
+        );
+        assert_eq!(checkpoint, Some("s=3".into()));
+    }
+
+    #[tokio::test]
+    async fn resumes_export_files_from_checkpoint() {
+        let (_dir, config) = export_config();
+        let (received, _) =
+            run_source::<ExportFiles>(&config, RecordFilter::default(), Some("s=1")).await;
+        assert_eq!(received.len(), 2);
+        assert_eq!(message(&received[0]), Value::Bytes("two\nline".into()));
+    }
+
+    #[tokio::test]
+    async fn restarts_export_files_from_unknown_checkpoint() {
+        let (_dir, config) = export_config();
+        let (received, checkpoint) =
+            run_source::<ExportFiles>(&config, RecordFilter::default(), Some("s=missing")).await;
+        assert_eq!(received.len(), 3);
+        assert_eq!(
+            message(&received[0]),
+            Value::Bytes("System Initialization".into())
+        );
+        assert_eq!(checkpoint, Some("s=3".into()));
+    }
+
+    #[test]
+    fn rejects_oversized_export_fields() {
+        let mut entry = b"__CURSOR=s=1\nMESSAGE\n".to_vec();
+        entry.extend_from_slice(&u64::MAX.to_le_bytes());
+        entry.extend_from_slice(b"short\n\n");
+
+        let err = read_export_entry(&mut io::Cursor::new(entry)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn filter_unit_works_correctly() {
         let empty: HashSet<String> = vec![].into_iter().collect();
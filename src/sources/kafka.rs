@@ -1,7 +1,10 @@
 use crate::{
     config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
-    event::{self, Event},
-    internal_events::{KafkaEventFailed, KafkaEventReceived, KafkaOffsetUpdateFailed},
+    event::{self, BatchNotifier, Event, EventStatus, Value},
+    internal_events::{
+        KafkaEventFailed, KafkaEventReceived, KafkaOffsetCommitFailed, KafkaOffsetUpdateFailed,
+        KafkaRebalance, KafkaRebalanceKind,
+    },
     kafka::KafkaAuthConfig,
     shutdown::ShutdownSignal,
     Pipeline,
@@ -10,17 +13,21 @@ use bytes::Bytes;
 use chrono::{TimeZone, Utc};
 use futures::{
     compat::{Compat, Future01CompatExt},
+    stream::FuturesOrdered,
     FutureExt, StreamExt,
 };
 use futures01::Sink;
 use rdkafka::{
+    client::ClientContext,
     config::ClientConfig,
-    consumer::{Consumer, StreamConsumer},
-    message::Message,
+    consumer::{CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer},
+    error::KafkaResult,
+    message::{BorrowedMessage, Headers, Message},
+    topic_partition_list::TopicPartitionList,
 };
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use std::{collections::HashMap, sync::Arc};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Snafu)]
 enum BuildError {
@@ -47,9 +54,16 @@ pub struct KafkaSourceConfig {
     #[serde(default = "default_commit_interval_ms")]
     commit_interval_ms: u64,
     key_field: Option<String>,
+    topic_key: Option<String>,
+    partition_key: Option<String>,
+    offset_key: Option<String>,
+    timestamp_key: Option<String>,
+    headers_key: Option<String>,
     librdkafka_options: Option<HashMap<String, String>>,
     #[serde(flatten)]
     auth: KafkaAuthConfig,
+    #[serde(default)]
+    acknowledgements: bool,
 }
 
 fn default_session_timeout_ms() -> u64 {
@@ -100,92 +114,204 @@ impl SourceConfig for KafkaSourceConfig {
 fn kafka_source(
     config: &KafkaSourceConfig,
     shutdown: ShutdownSignal,
-    out: Pipeline,
+    mut out: Pipeline,
 ) -> crate::Result<super::Source> {
-    let key_field = config.key_field.clone();
-    let consumer = Arc::new(create_consumer(config)?);
+    let config = config.clone();
+    let consumer = create_consumer(&config)?;
 
     let fut = async move {
-        Arc::clone(&consumer)
-            .start()
-            .take_until(shutdown.clone().compat())
-            .then(move |message| {
-                let key_field = key_field.clone();
-                let consumer = Arc::clone(&consumer);
-
-                async move {
-                    match message {
-                        Err(error) => {
-                            emit!(KafkaEventFailed { error });
-                            Err(())
-                        }
-                        Ok(msg) => {
-                            emit!(KafkaEventReceived {
-                                byte_size: msg.payload_len()
-                            });
-
-                            let payload = match msg.payload() {
-                                None => return Err(()), // skip messages with empty payload
-                                Some(payload) => payload,
-                            };
-                            let mut event = Event::new_empty_log();
-                            let log = event.as_mut_log();
-
-                            log.insert(event::log_schema().message_key().clone(), payload.to_vec());
-
-                            // Extract timestamp from kafka message
-                            let timestamp = msg
-                                .timestamp()
-                                .to_millis()
-                                .and_then(|millis| Utc.timestamp_millis_opt(millis).latest())
-                                .unwrap_or_else(Utc::now);
-                            log.insert(event::log_schema().timestamp_key().clone(), timestamp);
-
-                            // Add source type
-                            log.insert(event::log_schema().source_type_key(), Bytes::from("kafka"));
-
-                            if let Some(key_field) = &key_field {
-                                match msg.key() {
-                                    None => (),
-                                    Some(key) => {
-                                        log.insert(key_field.clone(), key.to_vec());
-                                    }
-                                }
+        let mut stream = consumer.start().take_until(shutdown.compat());
+        // With acknowledgements, the messages whose events haven't been delivered
+        // yet, in the order they were read.
+        let mut pending = FuturesOrdered::new();
+
+        loop {
+            tokio::select! {
+                message = stream.next() => match message {
+                    None => break,
+                    Some(Err(error)) => {
+                        emit!(KafkaEventFailed { error });
+                    }
+                    Some(Ok(msg)) => {
+                        emit!(KafkaEventReceived {
+                            byte_size: msg.payload_len()
+                        });
+
+                        let (batch, receiver) = if config.acknowledgements {
+                            let (batch, receiver) = BatchNotifier::new_with_receiver();
+                            (Some(batch), Some(receiver))
+                        } else {
+                            (None, None)
+                        };
+
+                        // Messages with an empty payload are skipped, but their offset
+                        // is still stored so they are not redelivered.
+                        if let Some(mut event) = message_to_event(&config, &msg) {
+                            if let Some(batch) = batch {
+                                event.add_batch_notifier(batch);
                             }
-
-                            consumer.store_offset(&msg).map_err(|error| {
-                                emit!(KafkaOffsetUpdateFailed { error });
-                            })?;
-
-                            Ok(event)
+                            out = match out.send(event).compat().await {
+                                Ok(out) => out,
+                                Err(error) => {
+                                    error!(message = "Error sending to sink", error = ?error);
+                                    return Err(());
+                                }
+                            };
                         }
-                    }
-                }
-            })
-            // Try `forward` after removing old futures.
-            // Error: implementation of `futures_core::stream::Stream` is not general enough
-            // .forward(
-            //     out.sink_compat()
-            //         .sink_map_err(|e| error!(message = "Error sending to sink", error = ?e)),
-            // )
-            .for_each(|item| {
-                let out = out.clone();
-                async move {
-                    if let Ok(item) = item {
-                        if let Err(e) = out.send(item).compat().await {
-                            error!(message = "Error sending to sink", error = ?e);
+
+                        // The offset is only stored once the event has been accepted
+                        // downstream, or delivered when acknowledgements are enabled, so
+                        // that it is committed on the next interval.
+                        match receiver {
+                            Some(receiver) => pending.push(receiver.map(move |status| (status, msg))),
+                            None => store_offset(&consumer, &msg, EventStatus::Delivered),
                         }
                     }
+                },
+                Some((status, msg)) = pending.next(), if !pending.is_empty() => {
+                    store_offset(&consumer, &msg, status);
                 }
-            })
-            .await;
+            }
+        }
+
+        // Wait for the events that are still on their way.
+        while let Some((status, msg)) = pending.next().await {
+            store_offset(&consumer, &msg, status);
+        }
+
+        // Flush the offsets stored since the last periodic commit.
+        if let Err(error) = consumer.commit_consumer_state(CommitMode::Sync) {
+            emit!(KafkaOffsetCommitFailed { error });
+        }
+
         Ok(())
     };
 
     Ok(Box::new(Compat::new(fut.boxed())))
 }
 
-fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer> {
+fn store_offset(
+    consumer: &StreamConsumer<KafkaSourceContext>,
+    msg: &BorrowedMessage<'_>,
+    status: EventStatus,
+) {
+    if status == EventStatus::Rejected {
+        // Holding back the offset would only stall the partition, as the same
+        // message would most likely be rejected again.
+        warn!(
+            message = "Event was rejected by a sink, storing its offset anyway.",
+            topic = msg.topic(),
+            partition = msg.partition(),
+            offset = msg.offset(),
+        );
+    }
+    if let Err(error) = consumer.store_offset(msg) {
+        emit!(KafkaOffsetUpdateFailed { error });
+    }
+}
+
+fn message_to_event<M: Message>(config: &KafkaSourceConfig, msg: &M) -> Option<Event> {
+    let payload = msg.payload()?;
+
+    let mut event = Event::new_empty_log();
+    let log = event.as_mut_log();
+
+    log.insert(event::log_schema().message_key().clone(), payload.to_vec());
+
+    // Extract timestamp from kafka message
+    let kafka_timestamp = msg
+        .timestamp()
+        .to_millis()
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).latest());
+    log.insert(
+        event::log_schema().timestamp_key().clone(),
+        kafka_timestamp.unwrap_or_else(Utc::now),
+    );
+
+    // Add source type
+    log.insert(event::log_schema().source_type_key(), Bytes::from("kafka"));
+
+    if let Some(key_field) = &config.key_field {
+        if let Some(key) = msg.key() {
+            log.insert(key_field, key.to_vec());
+        }
+    }
+
+    if let Some(topic_key) = &config.topic_key {
+        log.insert(topic_key, msg.topic());
+    }
+
+    if let Some(partition_key) = &config.partition_key {
+        log.insert(partition_key, msg.partition());
+    }
+
+    if let Some(offset_key) = &config.offset_key {
+        log.insert(offset_key, msg.offset());
+    }
+
+    if let (Some(timestamp_key), Some(timestamp)) = (&config.timestamp_key, kafka_timestamp) {
+        log.insert(timestamp_key, timestamp);
+    }
+
+    if let Some(headers_key) = &config.headers_key {
+        let mut headers = BTreeMap::new();
+        if let Some(borrowed) = msg.headers() {
+            for i in 0..borrowed.count() {
+                if let Some((name, value)) = borrowed.get(i) {
+                    headers.insert(name.to_string(), Value::from(value.to_vec()));
+                }
+            }
+        }
+        log.insert(headers_key, headers);
+    }
+
+    Some(event)
+}
+
+/// Reports consumer group rebalances and commit failures as internal events.
+struct KafkaSourceContext;
+
+impl ClientContext for KafkaSourceContext {}
+
+impl ConsumerContext for KafkaSourceContext {
+    fn pre_rebalance(&self, rebalance: &Rebalance) {
+        emit!(KafkaRebalance {
+            kind: KafkaRebalanceKind::from(rebalance),
+            stage: "pre",
+        });
+    }
+
+    fn post_rebalance(&self, rebalance: &Rebalance) {
+        emit!(KafkaRebalance {
+            kind: KafkaRebalanceKind::from(rebalance),
+            stage: "post",
+        });
+    }
+
+    fn commit_callback(&self, result: KafkaResult<()>, _offsets: &TopicPartitionList) {
+        if let Err(error) = result {
+            emit!(KafkaOffsetCommitFailed { error });
+        }
+    }
+}
+
+impl From<&Rebalance<'_>> for KafkaRebalanceKind {
+    fn from(rebalance: &Rebalance<'_>) -> Self {
+        match rebalance {
+            Rebalance::Assign(partitions) => KafkaRebalanceKind::Assign {
+                partitions: partitions.count(),
+            },
+            Rebalance::Revoke { .. } => KafkaRebalanceKind::Revoke,
+            Rebalance::Error(error) => KafkaRebalanceKind::Error {
+                error: error.clone(),
+            },
+        }
+    }
+}
+
+fn create_consumer(
+    config: &KafkaSourceConfig,
+) -> crate::Result<StreamConsumer<KafkaSourceContext>> {
     let mut client_config = ClientConfig::new();
     client_config
         .set("group.id", &config.group_id)
@@ -211,7 +337,9 @@ fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer>
         }
     }
 
-    let consumer: StreamConsumer = client_config.create().context(KafkaCreateError)?;
+    let consumer: StreamConsumer<KafkaSourceContext> = client_config
+        .create_with_context(KafkaSourceContext)
+        .context(KafkaCreateError)?;
     let topics: Vec<&str> = config.topics.iter().map(|s| s.as_str()).collect();
     consumer.subscribe(&topics).context(KafkaSubscribeError)?;
 
@@ -220,8 +348,10 @@ fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer>
 
 #[cfg(test)]
 mod test {
-    use super::{kafka_source, KafkaSourceConfig};
-    use crate::{shutdown::ShutdownSignal, Pipeline};
+    use super::{kafka_source, message_to_event, KafkaSourceConfig};
+    use crate::{event, shutdown::ShutdownSignal, Pipeline};
+    use chrono::{TimeZone, Utc};
+    use rdkafka::message::{OwnedHeaders, OwnedMessage, Timestamp};
 
     fn make_config() -> KafkaSourceConfig {
         KafkaSourceConfig {
@@ -252,6 +382,54 @@ mod test {
         };
         assert!(kafka_source(&config, ShutdownSignal::noop(), Pipeline::new_test().0).is_err());
     }
+
+    #[test]
+    fn kafka_message_to_event() {
+        let config = KafkaSourceConfig {
+            topic_key: Some("topic".to_string()),
+            partition_key: Some("partition".to_string()),
+            offset_key: Some("offset".to_string()),
+            timestamp_key: Some("kafka_timestamp".to_string()),
+            headers_key: Some("headers".to_string()),
+            ..make_config()
+        };
+        let msg = OwnedMessage::new(
+            Some(b"my message".to_vec()),
+            Some(b"my key".to_vec()),
+            "my-topic".to_string(),
+            Timestamp::CreateTime(1_500_000_000_000),
+            3,
+            42,
+            Some(OwnedHeaders::new().add("header", "value")),
+        );
+
+        let event = message_to_event(&config, &msg).unwrap();
+        let log = event.as_log();
+        let timestamp = Utc.timestamp_millis(1_500_000_000_000);
+        assert_eq!(log[&event::log_schema().message_key()], "my message".into());
+        assert_eq!(log[&event::log_schema().timestamp_key()], timestamp.into());
+        assert_eq!(log[&event::log_schema().source_type_key()], "kafka".into());
+        assert_eq!(log[&"message_key".into()], "my key".into());
+        assert_eq!(log[&"topic".into()], "my-topic".into());
+        assert_eq!(log[&"partition".into()], 3.into());
+        assert_eq!(log[&"offset".into()], 42.into());
+        assert_eq!(log[&"kafka_timestamp".into()], timestamp.into());
+        assert_eq!(log[&"headers.header".into()], "value".into());
+    }
+
+    #[test]
+    fn kafka_message_without_payload_is_skipped() {
+        let msg = OwnedMessage::new(
+            None,
+            None,
+            "my-topic".to_string(),
+            Timestamp::NotAvailable,
+            0,
+            0,
+            None,
+        );
+        assert!(message_to_event(&make_config(), &msg).is_none());
+    }
 }
 
 #[cfg(feature = "kafka-integration-tests")]
@@ -268,6 +446,7 @@ mod integration_test {
     use futures::compat::Future01CompatExt;
     use rdkafka::{
         config::ClientConfig,
+        message::OwnedHeaders,
         producer::{FutureProducer, FutureRecord},
         util::Timeout,
     };
@@ -275,7 +454,14 @@ mod integration_test {
 
     const BOOTSTRAP_SERVER: &str = "localhost:9092";
 
-    async fn send_event(topic: String, key: &str, text: &str, timestamp: i64) {
+    async fn send_event(
+        topic: String,
+        key: &str,
+        text: &str,
+        timestamp: i64,
+        header_key: &str,
+        header_value: &str,
+    ) {
         let producer: FutureProducer = ClientConfig::new()
             .set("bootstrap.servers", BOOTSTRAP_SERVER)
             .set("produce.offset.report", "true")
@@ -286,7 +472,8 @@ mod integration_test {
         let record = FutureRecord::to(&topic)
             .payload(text)
             .key(key)
-            .timestamp(timestamp);
+            .timestamp(timestamp)
+            .headers(OwnedHeaders::new().add(header_key, header_value));
 
         if let Err(err) = producer.send(record, Timeout::Never).await {
             panic!("Cannot send event to Kafka: {:?}", err);
@@ -309,6 +496,11 @@ mod integration_test {
             session_timeout_ms: 6000,
             commit_interval_ms: 5000,
             key_field: Some("message_key".to_string()),
+            topic_key: Some("topic".to_string()),
+            partition_key: Some("partition".to_string()),
+            offset_key: Some("offset".to_string()),
+            timestamp_key: Some("kafka_timestamp".to_string()),
+            headers_key: Some("headers".to_string()),
             socket_timeout_ms: 60000,
             fetch_wait_max_ms: 100,
             ..Default::default()
@@ -320,6 +512,8 @@ mod integration_test {
             "my key",
             "my message",
             now.timestamp_millis(),
+            "my header",
+            "my header value",
         )
         .await;
 
@@ -348,5 +542,19 @@ mod integration_test {
             events[0].as_log()[event::log_schema().timestamp_key()],
             now.into()
         );
+        assert_eq!(
+            events[0].as_log()[&Atom::from("topic")],
+            topic.clone().into()
+        );
+        assert_eq!(events[0].as_log()[&Atom::from("partition")], 0.into());
+        assert_eq!(events[0].as_log()[&Atom::from("offset")], 0.into());
+        assert_eq!(
+            events[0].as_log()[&Atom::from("kafka_timestamp")],
+            now.into()
+        );
+        assert_eq!(
+            events[0].as_log()[&Atom::from("headers.my header")],
+            "my header value".into()
+        );
     }
 }
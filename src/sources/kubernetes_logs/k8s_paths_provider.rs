@@ -7,18 +7,25 @@ use crate::kubernetes as k8s;
 use evmap::ReadHandle;
 use file_source::paths_provider::PathsProvider;
 use k8s_openapi::api::core::v1::Pod;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// A paths provider implementation that uses the state obtained from the
 /// the k8s API.
 pub struct K8sPathsProvider {
     pods_state_reader: ReadHandle<String, k8s::state::evmap::Value<Pod>>,
+    exclude_paths: Vec<glob::Pattern>,
 }
 
 impl K8sPathsProvider {
     /// Create a new [`K8sPathsProvider`].
-    pub fn new(pods_state_reader: ReadHandle<String, k8s::state::evmap::Value<Pod>>) -> Self {
-        Self { pods_state_reader }
+    pub fn new(
+        pods_state_reader: ReadHandle<String, k8s::state::evmap::Value<Pod>>,
+        exclude_paths: Vec<glob::Pattern>,
+    ) -> Self {
+        Self {
+            pods_state_reader,
+            exclude_paths,
+        }
     }
 }
 
@@ -48,6 +55,7 @@ impl PathsProvider for K8sPathsProvider {
                 trace!(message = "Providing log paths for pod", ?uid);
                 list_pod_log_paths(real_glob, pod)
             })
+            .filter(|path| !is_excluded(&self.exclude_paths, path))
             .collect()
     }
 }
@@ -85,6 +93,15 @@ where
         })
 }
 
+/// Check whether the `path` matches any of the exclusion patterns.
+/// The patterns are matched against the full log file path, so, for instance,
+/// `**/istio-proxy/**` excludes the logs of all the `istio-proxy` containers.
+fn is_excluded(exclude_paths: &[glob::Pattern], path: &Path) -> bool {
+    exclude_paths
+        .iter()
+        .any(|pattern| pattern.matches_path(path))
+}
+
 fn real_glob(pattern: &str) -> impl Iterator<Item = PathBuf> {
     glob::glob(pattern)
         .expect("the pattern is supposed to always be correct")
@@ -93,7 +110,7 @@ fn real_glob(pattern: &str) -> impl Iterator<Item = PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use super::{extract_pod_logs_directory, list_pod_log_paths};
+    use super::{extract_pod_logs_directory, is_excluded, list_pod_log_paths};
     use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::ObjectMeta};
     use std::path::PathBuf;
 
@@ -226,4 +243,41 @@ mod tests {
             assert_eq!(actual_paths, expected_paths)
         }
     }
+
+    #[test]
+    fn test_is_excluded() {
+        let exclude_paths = vec![
+            glob::Pattern::new("**/istio-proxy/**").unwrap(),
+            glob::Pattern::new("/var/log/pods/kube-system_*/**").unwrap(),
+        ];
+
+        let cases = vec![
+            (
+                "/var/log/pods/sandbox0-ns_sandbox0-name_sandbox0-uid/container1/0.log",
+                false,
+            ),
+            (
+                "/var/log/pods/sandbox0-ns_sandbox0-name_sandbox0-uid/istio-proxy/0.log",
+                true,
+            ),
+            (
+                "/var/log/pods/kube-system_coredns_sandbox0-uid/coredns/0.log",
+                true,
+            ),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(
+                is_excluded(&exclude_paths, &PathBuf::from(path)),
+                expected,
+                "{}",
+                path
+            );
+        }
+
+        assert!(!is_excluded(
+            &[],
+            &PathBuf::from("/var/log/pods/a_b_c/d/0.log")
+        ));
+    }
 }
@@ -8,6 +8,7 @@
 use crate::event::{self, Event};
 use crate::internal_events::{
     FileSourceInternalEventsEmitter, KubernetesLogsEventAnnotationFailed,
+    KubernetesLogsEventNamespaceAnnotationFailed, KubernetesLogsEventNodeAnnotationFailed,
     KubernetesLogsEventReceived,
 };
 use crate::kubernetes as k8s;
@@ -20,15 +21,17 @@ use crate::{
     Pipeline,
 };
 use bytes::Bytes;
-use file_source::{FileServer, FileServerShutdown, Fingerprinter};
+use file_source::{Checkpointer, FileServer, FileServerShutdown, Fingerprinter, Line};
 use futures::{future::FutureExt, sink::Sink, stream::StreamExt};
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::core::v1::{Namespace, Node, Pod};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
 
 mod k8s_paths_provider;
 mod lifecycle;
+mod namespace_metadata_annotator;
+mod node_metadata_annotator;
 mod parser;
 mod partial_events_merger;
 mod path_helpers;
@@ -38,6 +41,8 @@ mod util;
 
 use k8s_paths_provider::K8sPathsProvider;
 use lifecycle::Lifecycle;
+use namespace_metadata_annotator::NamespaceMetadataAnnotator;
+use node_metadata_annotator::NodeMetadataAnnotator;
 use pod_metadata_annotator::PodMetadataAnnotator;
 
 /// The key we use for `file` field.
@@ -62,6 +67,25 @@ pub struct Config {
 
     /// Specifies the field names for metadata annotation.
     annotation_fields: pod_metadata_annotator::FieldsSpec,
+
+    /// Annotate the events with the labels and annotations of the
+    /// `Namespace` the `Pod` belongs to.
+    annotate_namespace_metadata: bool,
+
+    /// Annotate the events with the labels and annotations of the `Node`
+    /// Vector runs at.
+    annotate_node_metadata: bool,
+
+    /// A field selector to apply in addition to the built-in one when
+    /// watching `Pod`s.
+    extra_field_selector: String,
+
+    /// A label selector to apply in addition to the built-in one when
+    /// watching `Pod`s.
+    extra_label_selector: String,
+
+    /// A list of glob patterns to exclude from reading the log files.
+    exclude_paths_glob_patterns: Vec<String>,
 }
 
 inventory::submit! {
@@ -113,6 +137,11 @@ struct Source {
     data_dir: PathBuf,
     auto_partial_merge: bool,
     fields_spec: pod_metadata_annotator::FieldsSpec,
+    annotate_namespace_metadata: bool,
+    annotate_node_metadata: bool,
+    field_selector: String,
+    label_selector: String,
+    exclude_paths: Vec<glob::Pattern>,
 }
 
 impl Source {
@@ -144,12 +173,26 @@ impl Source {
 
         let data_dir = globals.resolve_and_make_data_subdir(None, name)?;
 
+        let field_selector = prepare_field_selector(config, &self_node_name);
+        let label_selector = prepare_label_selector(config);
+
+        let exclude_paths = config
+            .exclude_paths_glob_patterns
+            .iter()
+            .map(|pattern| glob::Pattern::new(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+
         Ok(Self {
             client,
             self_node_name,
             data_dir,
             auto_partial_merge: config.auto_partial_merge,
             fields_spec: config.annotation_fields.clone(),
+            annotate_namespace_metadata: config.annotate_namespace_metadata,
+            annotate_node_metadata: config.annotate_node_metadata,
+            field_selector,
+            label_selector,
+            exclude_paths,
         })
     }
 
@@ -164,12 +207,15 @@ impl Source {
             data_dir,
             auto_partial_merge,
             fields_spec,
+            annotate_namespace_metadata,
+            annotate_node_metadata,
+            field_selector,
+            label_selector,
+            exclude_paths,
         } = self;
 
-        let field_selector = format!("spec.nodeName={}", self_node_name);
-        let label_selector = "vector.dev/exclude!=true".to_owned();
-
-        let watcher = k8s::api_watcher::ApiWatcher::new(client, Pod::watch_pod_for_all_namespaces);
+        let watcher =
+            k8s::api_watcher::ApiWatcher::new(client.clone(), Pod::watch_pod_for_all_namespaces);
         let watcher = k8s::instrumenting_watcher::InstrumentingWatcher::new(watcher);
         let (state_reader, state_writer) = evmap::new();
         let state_writer =
@@ -187,7 +233,54 @@ impl Source {
         );
         let reflector_process = reflector.run();
 
-        let paths_provider = K8sPathsProvider::new(state_reader.clone());
+        // Namespaces are cluster-global, so we watch all of them.
+        let (mut namespace_reflector, namespace_annotator) = if annotate_namespace_metadata {
+            let watcher =
+                k8s::api_watcher::ApiWatcher::new(client.clone(), Namespace::watch_namespace);
+            let watcher = k8s::instrumenting_watcher::InstrumentingWatcher::new(watcher);
+            let (state_reader, state_writer) = evmap::new();
+            let state_writer =
+                k8s::state::evmap::Writer::new(state_writer, Some(Duration::from_millis(10)))
+                    .keyed_by(k8s::state::evmap::Key::Name);
+            let state_writer = k8s::state::instrumenting::Writer::new(state_writer);
+            let state_writer =
+                k8s::state::delayed_delete::Writer::new(state_writer, Duration::from_secs(60));
+            let reflector = k8s::reflector::Reflector::new(
+                watcher,
+                state_writer,
+                None,
+                None,
+                Duration::from_secs(1),
+            );
+            let annotator = NamespaceMetadataAnnotator::new(state_reader, fields_spec.clone());
+            (Some(reflector), Some(annotator))
+        } else {
+            (None, None)
+        };
+
+        // We only care about the node we're running at.
+        let (mut node_reflector, node_annotator) = if annotate_node_metadata {
+            let watcher = k8s::api_watcher::ApiWatcher::new(client, Node::watch_node);
+            let watcher = k8s::instrumenting_watcher::InstrumentingWatcher::new(watcher);
+            let (state_reader, state_writer) = evmap::new();
+            let state_writer =
+                k8s::state::evmap::Writer::new(state_writer, Some(Duration::from_millis(10)));
+            let state_writer = k8s::state::instrumenting::Writer::new(state_writer);
+            let reflector = k8s::reflector::Reflector::new(
+                watcher,
+                state_writer,
+                Some(format!("metadata.name={}", self_node_name)),
+                None,
+                Duration::from_secs(1),
+            );
+            let annotator =
+                NodeMetadataAnnotator::new(state_reader, self_node_name, fields_spec.clone());
+            (Some(reflector), Some(annotator))
+        } else {
+            (None, None)
+        };
+
+        let paths_provider = K8sPathsProvider::new(state_reader.clone(), exclude_paths);
         let annotator = PodMetadataAnnotator::new(state_reader, fields_spec);
 
         // TODO: maybe some of the parameters have to be configurable.
@@ -198,7 +291,6 @@ impl Source {
             start_at_beginning: true,
             ignore_before: None,
             max_line_bytes,
-            data_dir,
             glob_minimum_cooldown: Duration::from_secs(10),
             fingerprinter: Fingerprinter::FirstLineChecksum {
                 max_line_length: max_line_bytes,
@@ -208,13 +300,18 @@ impl Source {
             emitter: FileSourceInternalEventsEmitter,
         };
 
-        let (file_source_tx, file_source_rx) =
-            futures::channel::mpsc::channel::<(Bytes, String)>(100);
+        let checkpointer = Checkpointer::new(&data_dir);
+        let checkpoints = checkpointer.view();
+
+        let (file_source_tx, file_source_rx) = futures::channel::mpsc::channel::<Line>(100);
 
         let mut parser = parser::build();
         let mut partial_events_merger = partial_events_merger::build(auto_partial_merge);
 
-        let events = file_source_rx.map(move |(bytes, file)| {
+        let events = file_source_rx.map(move |line| {
+            // Lines are checkpointed as soon as they are read.
+            checkpoints.update(line.file_id, line.offset);
+            let (bytes, file) = (line.text, line.filename);
             emit!(KubernetesLogsEventReceived {
                 file: &file,
                 byte_size: bytes.len(),
@@ -223,6 +320,16 @@ impl Source {
             if annotator.annotate(&mut event, &file).is_none() {
                 emit!(KubernetesLogsEventAnnotationFailed { event: &event });
             }
+            if let Some(ref namespace_annotator) = namespace_annotator {
+                if namespace_annotator.annotate(&mut event, &file).is_none() {
+                    emit!(KubernetesLogsEventNamespaceAnnotationFailed { event: &event });
+                }
+            }
+            if let Some(ref node_annotator) = node_annotator {
+                if node_annotator.annotate(&mut event).is_none() {
+                    emit!(KubernetesLogsEventNodeAnnotationFailed { event: &event });
+                }
+            }
             event
         });
         let events = events
@@ -245,16 +352,40 @@ impl Source {
                 });
             slot.bind(Box::pin(fut));
         }
-        {
+        if let Some(ref mut namespace_reflector) = namespace_reflector {
             let (slot, shutdown) = lifecycle.add();
-            let fut = util::run_file_server(file_server, file_source_tx, shutdown).map(|result| {
+            let fut = util::cancel_on_signal(namespace_reflector.run(), shutdown).map(|result| {
                 match result {
-                    Ok(FileServerShutdown) => info!(message = "file server completed gracefully"),
-                    Err(error) => error!(message = "file server exited with an error", ?error),
+                    Ok(()) => info!(message = "namespace reflector process completed gracefully"),
+                    Err(error) => error!(
+                        message = "namespace reflector process exited with an error",
+                        ?error
+                    ),
                 }
             });
             slot.bind(Box::pin(fut));
         }
+        if let Some(ref mut node_reflector) = node_reflector {
+            let (slot, shutdown) = lifecycle.add();
+            let fut =
+                util::cancel_on_signal(node_reflector.run(), shutdown).map(|result| match result {
+                    Ok(()) => info!(message = "node reflector process completed gracefully"),
+                    Err(error) => error!(
+                        message = "node reflector process exited with an error",
+                        ?error
+                    ),
+                });
+            slot.bind(Box::pin(fut));
+        }
+        {
+            let (slot, shutdown) = lifecycle.add();
+            let fut = util::run_file_server(file_server, file_source_tx, shutdown, checkpointer)
+                .map(|result| match result {
+                    Ok(FileServerShutdown) => info!(message = "file server completed gracefully"),
+                    Err(error) => error!(message = "file server exited with an error", ?error),
+                });
+            slot.bind(Box::pin(fut));
+        }
         {
             let (slot, shutdown) = lifecycle.add();
             let fut = util::complete_with_deadline_on_signal(
@@ -299,8 +430,82 @@ fn create_event(line: Bytes, file: &str) -> Event {
     event
 }
 
+/// Prepare the field selector for watching `Pod`s, combining the built-in
+/// node filter with the user-provided extra selector.
+fn prepare_field_selector(config: &Config, self_node_name: &str) -> String {
+    let field_selector = format!("spec.nodeName={}", self_node_name);
+    merge_selectors(field_selector, &config.extra_field_selector)
+}
+
+/// Prepare the label selector for watching `Pod`s, combining the built-in
+/// exclusion label filter with the user-provided extra selector.
+fn prepare_label_selector(config: &Config) -> String {
+    let label_selector = "vector.dev/exclude!=true".to_owned();
+    merge_selectors(label_selector, &config.extra_label_selector)
+}
+
+/// Kubernetes selectors are ANDed when separated with a comma.
+fn merge_selectors(builtin: String, extra: &str) -> String {
+    let extra = extra.trim();
+    if extra.is_empty() {
+        builtin
+    } else {
+        format!("{},{}", builtin, extra)
+    }
+}
+
 /// This function returns the default value for `self_node_name` variable
 /// as it should be at the generated config file.
 fn default_self_node_name_env_template() -> String {
     format!("${{{}}}", SELF_NODE_NAME_ENV_KEY.to_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_field_selector() {
+        let cases = vec![
+            (Config::default(), "node0", "spec.nodeName=node0"),
+            (
+                Config {
+                    extra_field_selector: "metadata.name!=pod0".to_owned(),
+                    ..Config::default()
+                },
+                "node0",
+                "spec.nodeName=node0,metadata.name!=pod0",
+            ),
+            (
+                Config {
+                    extra_field_selector: "  ".to_owned(),
+                    ..Config::default()
+                },
+                "node0",
+                "spec.nodeName=node0",
+            ),
+        ];
+
+        for (config, self_node_name, expected) in cases {
+            assert_eq!(prepare_field_selector(&config, self_node_name), expected);
+        }
+    }
+
+    #[test]
+    fn test_prepare_label_selector() {
+        let cases = vec![
+            (Config::default(), "vector.dev/exclude!=true"),
+            (
+                Config {
+                    extra_label_selector: "app!=noisy,tier in (web,api)".to_owned(),
+                    ..Config::default()
+                },
+                "vector.dev/exclude!=true,app!=noisy,tier in (web,api)",
+            ),
+        ];
+
+        for (config, expected) in cases {
+            assert_eq!(prepare_label_selector(&config), expected);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Annotates events with namespace metadata.
+
+#![deny(missing_docs)]
+
+use super::{
+    path_helpers::parse_log_file_path,
+    pod_metadata_annotator::{annotate_from_map, FieldsSpec},
+};
+use crate::{event::LogEvent, kubernetes as k8s, Event};
+use evmap::ReadHandle;
+use k8s_openapi::{api::core::v1::Namespace, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+
+/// Annotate the event with namespace metadata.
+pub struct NamespaceMetadataAnnotator {
+    namespaces_state_reader: ReadHandle<String, k8s::state::evmap::Value<Namespace>>,
+    fields_spec: FieldsSpec,
+}
+
+impl NamespaceMetadataAnnotator {
+    /// Create a new [`NamespaceMetadataAnnotator`].
+    pub fn new(
+        namespaces_state_reader: ReadHandle<String, k8s::state::evmap::Value<Namespace>>,
+        fields_spec: FieldsSpec,
+    ) -> Self {
+        Self {
+            namespaces_state_reader,
+            fields_spec,
+        }
+    }
+}
+
+impl NamespaceMetadataAnnotator {
+    /// Annotates an event with the information from the
+    /// [`Namespace::metadata`] of the namespace the pod that produced the
+    /// `file` belongs to.
+    ///
+    /// The log file path only carries the namespace name, so the state has to
+    /// be keyed by name, see [`k8s::state::evmap::Key::Name`].
+    pub fn annotate(&self, event: &mut Event, file: &str) -> Option<()> {
+        let log = event.as_mut_log();
+        let namespace_name = parse_log_file_path(file)?.pod_namespace;
+        let guard = self.namespaces_state_reader.get(namespace_name)?;
+        let entry = guard.get_one()?;
+        let namespace: &Namespace = entry.as_ref();
+        annotate_from_metadata(log, &self.fields_spec, &namespace.metadata);
+        Some(())
+    }
+}
+
+fn annotate_from_metadata(log: &mut LogEvent, fields_spec: &FieldsSpec, metadata: &ObjectMeta) {
+    if let Some(labels) = &metadata.labels {
+        annotate_from_map(log, &fields_spec.namespace_labels, labels);
+    }
+    if let Some(annotations) = &metadata.annotations {
+        annotate_from_map(log, &fields_spec.namespace_annotations, annotations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::{
+        mock_watcher::{self, MockWatcher},
+        reflector::Reflector,
+        state::Write,
+    };
+    use futures::{channel::mpsc, SinkExt, StreamExt};
+    use k8s_openapi::{apimachinery::pkg::apis::meta::v1::WatchEvent, WatchResponse};
+    use std::time::Duration;
+
+    fn make_namespace(uid: &str, name: &str, labels: &[(&str, &str)]) -> Namespace {
+        Namespace {
+            metadata: ObjectMeta {
+                uid: Some(uid.to_owned()),
+                name: Some(name.to_owned()),
+                labels: Some(
+                    labels
+                        .iter()
+                        .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                        .collect(),
+                ),
+                annotations: Some(
+                    vec![("owner".to_owned(), format!("{}-team", name))]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..ObjectMeta::default()
+            },
+            ..Namespace::default()
+        }
+    }
+
+    #[test]
+    fn test_annotate_from_metadata() {
+        let cases = vec![
+            (
+                FieldsSpec::default(),
+                ObjectMeta::default(),
+                LogEvent::default(),
+            ),
+            (
+                FieldsSpec::default(),
+                make_namespace("uid0", "sandbox0", &[("team.io/name", "infra")]).metadata,
+                {
+                    let mut log = LogEvent::default();
+                    log.insert("kubernetes.namespace_labels.team\\.io/name", "infra");
+                    log.insert("kubernetes.namespace_annotations.owner", "sandbox0-team");
+                    log
+                },
+            ),
+            (
+                FieldsSpec {
+                    namespace_labels: "ns_labels".to_owned(),
+                    namespace_annotations: "ns_annotations".to_owned(),
+                    ..FieldsSpec::default()
+                },
+                make_namespace("uid0", "sandbox0", &[("env", "prod")]).metadata,
+                {
+                    let mut log = LogEvent::default();
+                    log.insert("ns_labels.env", "prod");
+                    log.insert("ns_annotations.owner", "sandbox0-team");
+                    log
+                },
+            ),
+        ];
+
+        for (fields_spec, metadata, expected) in cases.into_iter() {
+            let mut log = LogEvent::default();
+            annotate_from_metadata(&mut log, &fields_spec, &metadata);
+            assert_eq!(log, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_annotate_looks_up_namespace_by_name() {
+        let (state_reader, state_writer) = evmap::new();
+        let mut state_writer = k8s::state::evmap::Writer::new(state_writer, None)
+            .keyed_by(k8s::state::evmap::Key::Name);
+        state_writer
+            .add(make_namespace("uid0", "sandbox0-ns", &[("env", "prod")]))
+            .await;
+        state_writer
+            .add(make_namespace("uid1", "sandbox1-ns", &[("env", "dev")]))
+            .await;
+
+        let annotator = NamespaceMetadataAnnotator::new(state_reader, FieldsSpec::default());
+
+        let mut event = Event::from("hello");
+        annotator
+            .annotate(
+                &mut event,
+                "/var/log/pods/sandbox1-ns_sandbox0-name_sandbox0-uid/container0/1.log",
+            )
+            .unwrap();
+        assert_eq!(
+            event.as_log()[&"kubernetes.namespace_labels.env".into()],
+            "dev".into()
+        );
+
+        // Unknown namespace.
+        let mut event = Event::from("hello");
+        assert!(annotator
+            .annotate(
+                &mut event,
+                "/var/log/pods/other-ns_sandbox0-name_sandbox0-uid/container0/1.log",
+            )
+            .is_none());
+
+        drop(state_writer);
+    }
+
+    #[tokio::test]
+    async fn test_annotate_from_reflected_namespaces() {
+        let (state_reader, state_writer) = evmap::new();
+        let state_writer = k8s::state::evmap::Writer::new(state_writer, None)
+            .keyed_by(k8s::state::evmap::Key::Name);
+        let annotator = NamespaceMetadataAnnotator::new(state_reader, FieldsSpec::default());
+
+        let (watcher_events_tx, mut watcher_events_rx) = mpsc::channel(0);
+        let (mut watcher_invocations_tx, watcher_invocations_rx) = mpsc::channel(0);
+        let watcher = MockWatcher::<Namespace>::new(watcher_events_tx, watcher_invocations_rx);
+        let mut reflector =
+            Reflector::new(watcher, state_writer, None, None, Duration::from_millis(1));
+
+        let logic = tokio::spawn(async move {
+            assert!(matches!(
+                watcher_events_rx.next().await.unwrap(),
+                mock_watcher::ScenarioEvent::Invocation(_)
+            ));
+
+            let (mut watch_stream_tx, watch_stream_rx) = mpsc::channel(0);
+            watcher_invocations_tx
+                .send(mock_watcher::ScenarioActionInvocation::Ok(watch_stream_rx))
+                .await
+                .unwrap();
+            let events = vec![
+                WatchEvent::Added(make_namespace("uid0", "sandbox0-ns", &[("env", "prod")])),
+                WatchEvent::Added(make_namespace("uid1", "sandbox1-ns", &[("env", "dev")])),
+                WatchEvent::Modified(make_namespace("uid1", "sandbox1-ns", &[("env", "qa")])),
+            ];
+            for event in events {
+                assert_eq!(
+                    watcher_events_rx.next().await.unwrap(),
+                    mock_watcher::ScenarioEvent::Stream
+                );
+                watch_stream_tx
+                    .send(mock_watcher::ScenarioActionStream::Ok(WatchResponse::Ok(
+                        event,
+                    )))
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(
+                watcher_events_rx.next().await.unwrap(),
+                mock_watcher::ScenarioEvent::Stream
+            );
+            watch_stream_tx
+                .send(mock_watcher::ScenarioActionStream::Done)
+                .await
+                .unwrap();
+
+            // Terminate the reflector.
+            assert!(matches!(
+                watcher_events_rx.next().await.unwrap(),
+                mock_watcher::ScenarioEvent::Invocation(_)
+            ));
+            watcher_invocations_tx
+                .send(mock_watcher::ScenarioActionInvocation::ErrOther)
+                .await
+                .unwrap();
+        });
+
+        let result = reflector.run().await;
+        logic.await.unwrap();
+        result.unwrap_err();
+
+        let mut event = Event::from("hello");
+        annotator
+            .annotate(
+                &mut event,
+                "/var/log/pods/sandbox1-ns_sandbox0-name_sandbox0-uid/container0/1.log",
+            )
+            .unwrap();
+        assert_eq!(
+            event.as_log()[&"kubernetes.namespace_labels.env".into()],
+            "qa".into()
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Annotates events with node metadata.
+
+#![deny(missing_docs)]
+
+use super::pod_metadata_annotator::{annotate_from_map, FieldsSpec};
+use crate::{event::LogEvent, kubernetes as k8s, Event};
+use evmap::ReadHandle;
+use k8s_openapi::{api::core::v1::Node, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+
+/// Annotate the event with the metadata of the node Vector runs at.
+pub struct NodeMetadataAnnotator {
+    nodes_state_reader: ReadHandle<String, k8s::state::evmap::Value<Node>>,
+    self_node_name: String,
+    fields_spec: FieldsSpec,
+}
+
+impl NodeMetadataAnnotator {
+    /// Create a new [`NodeMetadataAnnotator`].
+    pub fn new(
+        nodes_state_reader: ReadHandle<String, k8s::state::evmap::Value<Node>>,
+        self_node_name: String,
+        fields_spec: FieldsSpec,
+    ) -> Self {
+        Self {
+            nodes_state_reader,
+            self_node_name,
+            fields_spec,
+        }
+    }
+}
+
+impl NodeMetadataAnnotator {
+    /// Annotates an event with the information from the [`Node::metadata`]
+    /// of the node Vector runs at.
+    /// All the log files we read are local to this node, so there's no need
+    /// to inspect the file path.
+    pub fn annotate(&self, event: &mut Event) -> Option<()> {
+        let log = event.as_mut_log();
+        let guard = self.nodes_state_reader.read()?;
+        let node = guard.iter().find_map(|(_uid, values)| {
+            let node: &Node = values.get_one()?;
+            if node.metadata.name.as_deref() == Some(self.self_node_name.as_str()) {
+                Some(node)
+            } else {
+                None
+            }
+        })?;
+        annotate_from_metadata(log, &self.fields_spec, &node.metadata);
+        Some(())
+    }
+}
+
+fn annotate_from_metadata(log: &mut LogEvent, fields_spec: &FieldsSpec, metadata: &ObjectMeta) {
+    if let Some(labels) = &metadata.labels {
+        annotate_from_map(log, &fields_spec.node_labels, labels);
+    }
+    if let Some(annotations) = &metadata.annotations {
+        annotate_from_map(log, &fields_spec.node_annotations, annotations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::{
+        mock_watcher::{self, MockWatcher},
+        reflector::Reflector,
+        state::Write,
+    };
+    use futures::{channel::mpsc, SinkExt, StreamExt};
+    use k8s_openapi::{apimachinery::pkg::apis::meta::v1::WatchEvent, WatchResponse};
+    use std::time::Duration;
+
+    fn make_node(uid: &str, name: &str, zone: &str) -> Node {
+        Node {
+            metadata: ObjectMeta {
+                uid: Some(uid.to_owned()),
+                name: Some(name.to_owned()),
+                labels: Some(
+                    vec![("topology.kubernetes.io/zone".to_owned(), zone.to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                annotations: Some(
+                    vec![("node.alpha.kubernetes.io/ttl".to_owned(), "0".to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..ObjectMeta::default()
+            },
+            ..Node::default()
+        }
+    }
+
+    #[test]
+    fn test_annotate_from_metadata() {
+        let cases = vec![
+            (
+                FieldsSpec::default(),
+                ObjectMeta::default(),
+                LogEvent::default(),
+            ),
+            (
+                FieldsSpec::default(),
+                make_node("uid0", "node0", "zone-a").metadata,
+                {
+                    let mut log = LogEvent::default();
+                    log.insert(
+                        "kubernetes.node_labels.topology\\.kubernetes\\.io/zone",
+                        "zone-a",
+                    );
+                    log.insert(
+                        "kubernetes.node_annotations.node\\.alpha\\.kubernetes\\.io/ttl",
+                        "0",
+                    );
+                    log
+                },
+            ),
+            (
+                FieldsSpec {
+                    node_labels: "node.labels".to_owned(),
+                    node_annotations: "node.annotations".to_owned(),
+                    ..FieldsSpec::default()
+                },
+                make_node("uid0", "node0", "zone-a").metadata,
+                {
+                    let mut log = LogEvent::default();
+                    log.insert("node.labels.topology\\.kubernetes\\.io/zone", "zone-a");
+                    log.insert("node.annotations.node\\.alpha\\.kubernetes\\.io/ttl", "0");
+                    log
+                },
+            ),
+        ];
+
+        for (fields_spec, metadata, expected) in cases.into_iter() {
+            let mut log = LogEvent::default();
+            annotate_from_metadata(&mut log, &fields_spec, &metadata);
+            assert_eq!(log, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_annotate_uses_self_node() {
+        let (state_reader, state_writer) = evmap::new();
+        let mut state_writer = k8s::state::evmap::Writer::new(state_writer, None);
+
+        let annotator =
+            NodeMetadataAnnotator::new(state_reader, "node1".to_owned(), FieldsSpec::default());
+
+        // Nothing is known about the node yet.
+        let mut event = Event::from("hello");
+        assert!(annotator.annotate(&mut event).is_none());
+
+        state_writer.add(make_node("uid0", "node0", "zone-a")).await;
+        state_writer.add(make_node("uid1", "node1", "zone-b")).await;
+
+        let mut event = Event::from("hello");
+        annotator.annotate(&mut event).unwrap();
+        assert_eq!(
+            event.as_log()[&"kubernetes.node_labels.topology\\.kubernetes\\.io/zone".into()],
+            "zone-b".into()
+        );
+
+        drop(state_writer);
+    }
+
+    #[tokio::test]
+    async fn test_annotate_from_reflected_node() {
+        let (state_reader, state_writer) = evmap::new();
+        let state_writer = k8s::state::evmap::Writer::new(state_writer, None);
+        let annotator =
+            NodeMetadataAnnotator::new(state_reader, "node1".to_owned(), FieldsSpec::default());
+
+        let (watcher_events_tx, mut watcher_events_rx) = mpsc::channel(0);
+        let (mut watcher_invocations_tx, watcher_invocations_rx) = mpsc::channel(0);
+        let watcher = MockWatcher::<Node>::new(watcher_events_tx, watcher_invocations_rx);
+        let mut reflector = Reflector::new(
+            watcher,
+            state_writer,
+            Some("metadata.name=node1".to_owned()),
+            None,
+            Duration::from_millis(1),
+        );
+
+        let logic = tokio::spawn(async move {
+            // Only the node Vector runs at is watched.
+            let watch_optional = match watcher_events_rx.next().await.unwrap() {
+                mock_watcher::ScenarioEvent::Invocation(val) => val,
+                _ => panic!("Unexpected event from watcher mock"),
+            };
+            assert_eq!(
+                watch_optional.field_selector,
+                Some("metadata.name=node1".to_owned())
+            );
+
+            let (mut watch_stream_tx, watch_stream_rx) = mpsc::channel(0);
+            watcher_invocations_tx
+                .send(mock_watcher::ScenarioActionInvocation::Ok(watch_stream_rx))
+                .await
+                .unwrap();
+            assert_eq!(
+                watcher_events_rx.next().await.unwrap(),
+                mock_watcher::ScenarioEvent::Stream
+            );
+            watch_stream_tx
+                .send(mock_watcher::ScenarioActionStream::Ok(WatchResponse::Ok(
+                    WatchEvent::Added(make_node("uid1", "node1", "zone-b")),
+                )))
+                .await
+                .unwrap();
+            assert_eq!(
+                watcher_events_rx.next().await.unwrap(),
+                mock_watcher::ScenarioEvent::Stream
+            );
+            watch_stream_tx
+                .send(mock_watcher::ScenarioActionStream::Done)
+                .await
+                .unwrap();
+
+            // Terminate the reflector.
+            assert!(matches!(
+                watcher_events_rx.next().await.unwrap(),
+                mock_watcher::ScenarioEvent::Invocation(_)
+            ));
+            watcher_invocations_tx
+                .send(mock_watcher::ScenarioActionInvocation::ErrOther)
+                .await
+                .unwrap();
+        });
+
+        let result = reflector.run().await;
+        logic.await.unwrap();
+        result.unwrap_err();
+
+        let mut event = Event::from("hello");
+        annotator.annotate(&mut event).unwrap();
+        assert_eq!(
+            event.as_log()[&"kubernetes.node_labels.topology\\.kubernetes\\.io/zone".into()],
+            "zone-b".into()
+        );
+    }
+}
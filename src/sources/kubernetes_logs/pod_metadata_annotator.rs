@@ -10,6 +10,7 @@ use crate::{
 use evmap::ReadHandle;
 use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::ObjectMeta};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields, default)]
@@ -18,6 +19,10 @@ pub struct FieldsSpec {
     pub pod_namespace: String,
     pub pod_uid: String,
     pub pod_labels: String,
+    pub namespace_labels: String,
+    pub namespace_annotations: String,
+    pub node_labels: String,
+    pub node_annotations: String,
 }
 
 impl Default for FieldsSpec {
@@ -27,6 +32,10 @@ impl Default for FieldsSpec {
             pod_namespace: "kubernetes.pod_namespace".to_owned(),
             pod_uid: "kubernetes.pod_uid".to_owned(),
             pod_labels: "kubernetes.pod_labels".to_owned(),
+            namespace_labels: "kubernetes.namespace_labels".to_owned(),
+            namespace_annotations: "kubernetes.namespace_annotations".to_owned(),
+            node_labels: "kubernetes.node_labels".to_owned(),
+            node_annotations: "kubernetes.node_annotations".to_owned(),
         }
     }
 }
@@ -79,13 +88,20 @@ fn annotate_from_metadata(log: &mut LogEvent, fields_spec: &FieldsSpec, metadata
     }
 
     if let Some(labels) = &metadata.labels {
-        // Calculate and cache the prefix path.
-        let prefix_path = PathIter::new(fields_spec.pod_labels.as_ref()).collect::<Vec<_>>();
-        for (key, val) in labels.iter() {
-            let mut path = prefix_path.clone();
-            path.push(PathComponent::Key(key.clone()));
-            log.insert_path(path, val.to_owned());
-        }
+        annotate_from_map(log, &fields_spec.pod_labels, labels);
+    }
+}
+
+/// Insert every entry of the `map` as a flat field under the `prefix` path.
+/// Keys are not split at `.`, so `app.kubernetes.io/name` stays a single
+/// field.
+pub(super) fn annotate_from_map(log: &mut LogEvent, prefix: &str, map: &BTreeMap<String, String>) {
+    // Calculate and cache the prefix path.
+    let prefix_path = PathIter::new(prefix).collect::<Vec<_>>();
+    for (key, val) in map.iter() {
+        let mut path = prefix_path.clone();
+        path.push(PathComponent::Key(key.clone()));
+        log.insert_path(path, val.to_owned());
     }
 }
 
@@ -133,6 +149,7 @@ mod tests {
                     pod_namespace: "ns".to_owned(),
                     pod_uid: "uid".to_owned(),
                     pod_labels: "labels".to_owned(),
+                    ..FieldsSpec::default()
                 },
                 ObjectMeta {
                     name: Some("sandbox0-name".to_owned()),
@@ -1,6 +1,6 @@
-use bytes::Bytes;
 use file_source::{
-    paths_provider::PathsProvider, FileServer, FileServerShutdown, FileSourceInternalEvents,
+    paths_provider::PathsProvider, Checkpointer, FileServer, FileServerShutdown,
+    FileSourceInternalEvents, Line,
 };
 use futures::future::{select, Either};
 use futures::{pin_mut, Sink};
@@ -15,18 +15,19 @@ pub async fn run_file_server<PP, E, C, S>(
     file_server: FileServer<PP, E>,
     chans: C,
     shutdown: S,
+    checkpointer: Checkpointer,
 ) -> Result<FileServerShutdown, tokio::task::JoinError>
 where
     PP: PathsProvider + Send + 'static,
     E: FileSourceInternalEvents,
-    C: Sink<(Bytes, String)> + Unpin + Send + 'static,
-    <C as Sink<(Bytes, String)>>::Error: Error + Send,
+    C: Sink<Line> + Unpin + Send + 'static,
+    <C as Sink<Line>>::Error: Error + Send,
     S: Future + Unpin + Send + 'static,
 {
     let span = info_span!("file_server");
     let join_handle = spawn_blocking(move || {
         let _enter = span.enter();
-        let result = file_server.run(chans, shutdown);
+        let result = file_server.run(chans, shutdown, checkpointer);
         result.expect("file server exited with an error")
     });
     join_handle.await
@@ -33,6 +33,8 @@ pub mod stdin;
 pub mod syslog;
 #[cfg(feature = "sources-vector")]
 pub mod vector;
+#[cfg(feature = "sources-wasm")]
+pub mod wasm;
 
 mod util;
 
@@ -1,6 +1,6 @@
 use crate::{
     config::{DataType, GlobalOptions, SourceConfig},
-    event::{self, Event, LogEvent, Value},
+    event::{self, BatchNotifier, BatchStatusReceiver, Event, EventStatus, LogEvent, Value},
     internal_events::{
         SplunkHECEventReceived, SplunkHECRequestBodyInvalid, SplunkHECRequestError,
         SplunkHECRequestReceived,
@@ -23,6 +23,7 @@ use snafu::Snafu;
 use std::{
     io::Read,
     net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
 };
 use string_cache::DefaultAtom as Atom;
 use warp::{filters::BoxedFilter, path, reject::Rejection, reply::Response, Filter, Reply};
@@ -45,6 +46,8 @@ pub struct SplunkConfig {
     /// Splunk HEC token
     token: Option<String>,
     tls: Option<TlsConfig>,
+    /// Only reply once the events of a request have been delivered
+    acknowledgements: bool,
 }
 
 impl SplunkConfig {
@@ -63,6 +66,7 @@ impl Default for SplunkConfig {
             address: default_socket_address(),
             token: None,
             tls: None,
+            acknowledgements: false,
         }
     }
 }
@@ -148,6 +152,7 @@ impl SourceConfig for SplunkConfig {
 /// Shared data for responding to requests.
 struct SplunkSource {
     credentials: Option<Bytes>,
+    acknowledgements: bool,
 }
 
 impl SplunkSource {
@@ -157,10 +162,12 @@ impl SplunkSource {
                 .token
                 .as_ref()
                 .map(|token| format!("Splunk {}", token).into()),
+            acknowledgements: config.acknowledgements,
         }
     }
 
     fn event_service(&self, out: Pipeline) -> BoxedFilter<(Response,)> {
+        let acknowledgements = self.acknowledgements;
         warp::post()
             .and(path!("event").or(path!("event" / "1.0")))
             .and(self.authorization())
@@ -177,20 +184,24 @@ impl SplunkSource {
                       body: Bytes| {
                     let out = out.clone();
                     async move {
+                        let (batch, receiver) = new_batch(acknowledgements);
                         // Construct event parser
                         if gzip {
                             EventStream::new(GzDecoder::new(body.reader()), channel, host)
+                                .map(move |event| add_batch_notifier(event, &batch))
                                 .forward(out.clone().sink_map_err(|_| ApiError::ServerShutdown))
                                 .map(|_| ())
                                 .compat()
-                                .await
+                                .await?
                         } else {
                             EventStream::new(body.reader(), channel, host)
+                                .map(move |event| add_batch_notifier(event, &batch))
                                 .forward(out.clone().sink_map_err(|_| ApiError::ServerShutdown))
                                 .map(|_| ())
                                 .compat()
-                                .await
+                                .await?
                         }
+                        finish_batch(receiver).await
                     }
                 },
             )
@@ -199,6 +210,7 @@ impl SplunkSource {
     }
 
     fn raw_service(&self, out: Pipeline) -> BoxedFilter<(Response,)> {
+        let acknowledgements = self.acknowledgements;
         warp::post()
             .and(path!("raw" / "1.0").or(path!("raw")))
             .and(self.authorization())
@@ -220,12 +232,15 @@ impl SplunkSource {
                 move |_, _, channel: String, host: Option<String>, gzip: bool, body: Bytes| {
                     let out = out.clone();
                     async move {
+                        let (batch, receiver) = new_batch(acknowledgements);
                         // Construct event parser
                         futures01::stream::once(raw_event(body, gzip, channel, host))
+                            .map(move |event| add_batch_notifier(event, &batch))
                             .forward(out.clone().sink_map_err(|_| ApiError::ServerShutdown))
                             .map(|_| ())
                             .compat()
-                            .await
+                            .await?;
+                        finish_batch(receiver).await
                     }
                 },
             )
@@ -323,6 +338,36 @@ impl SplunkSource {
     }
 }
 
+/// Tracks the events of a request when acknowledgements are enabled.
+fn new_batch(acknowledgements: bool) -> (Option<Arc<BatchNotifier>>, Option<BatchStatusReceiver>) {
+    if acknowledgements {
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        (Some(batch), Some(receiver))
+    } else {
+        (None, None)
+    }
+}
+
+fn add_batch_notifier(mut event: Event, batch: &Option<Arc<BatchNotifier>>) -> Event {
+    if let Some(batch) = batch {
+        event.add_batch_notifier(Arc::clone(batch));
+    }
+    event
+}
+
+/// Waits for the events of a request to be delivered, if it's being tracked.
+/// The batch is only resolved once the stream that attached it has been
+/// dropped, along with every event it produced.
+async fn finish_batch(receiver: Option<BatchStatusReceiver>) -> Result<(), Rejection> {
+    match receiver {
+        Some(receiver) => match receiver.await {
+            EventStatus::Rejected => Err(ApiError::DeliveryFailed.into()),
+            EventStatus::Delivered | EventStatus::Dropped => Ok(()),
+        },
+        None => Ok(()),
+    }
+}
+
 /// Constructs one ore more events from json-s coming from reader.
 /// If errors, it's done with input.
 struct EventStream<R: Read> {
@@ -644,6 +689,7 @@ pub(crate) enum ApiError {
     EmptyEventField { event: usize },
     MissingEventField { event: usize },
     BadRequest,
+    DeliveryFailed,
 }
 
 impl From<ApiError> for Rejection {
@@ -679,6 +725,8 @@ mod splunk_response {
             json_to_bytes(json!({"text":"unsupported content encoding"}));
         pub static ref NO_CHANNEL: Bytes =
             json_to_bytes(json!({"text":"Data channel is missing","code":10}));
+        pub static ref DELIVERY_FAILED: Bytes =
+            json_to_bytes(json!({"text":"Events could not be delivered","code":9}));
     }
 }
 
@@ -721,6 +769,10 @@ async fn finish_err(rejection: Rejection) -> Result<(Response,), Rejection> {
                 event_error("Event field is required", 12, event)
             }
             ApiError::BadRequest => empty_response(StatusCode::BAD_REQUEST),
+            ApiError::DeliveryFailed => response_json(
+                StatusCode::SERVICE_UNAVAILABLE,
+                splunk_response::DELIVERY_FAILED.as_ref(),
+            ),
         },))
     } else {
         Err(rejection)
@@ -765,7 +817,7 @@ mod tests {
     use super::{parse_timestamp, SplunkConfig};
     use crate::{
         config::{GlobalOptions, SinkConfig, SinkContext, SourceConfig},
-        event::{self, Event},
+        event::{self, Event, EventStatus},
         shutdown::ShutdownSignal,
         sinks::{
             splunk_hec::{Encoding, HecSinkConfig},
@@ -777,7 +829,7 @@ mod tests {
     };
     use chrono::{TimeZone, Utc};
     use futures::compat::Future01CompatExt;
-    use futures01::{stream, sync::mpsc, Future, Sink};
+    use futures01::{stream, sync::mpsc, Future, Sink, Stream};
     use std::net::SocketAddr;
 
     /// Splunk token
@@ -788,6 +840,13 @@ mod tests {
     }
 
     async fn source_with(token: Option<String>) -> (mpsc::Receiver<Event>, SocketAddr) {
+        source_with_acknowledgements(token, false).await
+    }
+
+    async fn source_with_acknowledgements(
+        token: Option<String>,
+        acknowledgements: bool,
+    ) -> (mpsc::Receiver<Event>, SocketAddr) {
         let (sender, recv) = Pipeline::new_test();
         let address = next_addr();
         tokio::spawn(async move {
@@ -795,6 +854,7 @@ mod tests {
                 address,
                 token,
                 tls: None,
+                acknowledgements,
             }
             .build_async(
                 "default",
@@ -1063,6 +1123,33 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn acknowledgements() {
+        trace_init();
+
+        let (mut source, address) =
+            source_with_acknowledgements(Some(TOKEN.to_owned()), true).await;
+
+        for (status, code) in &[(EventStatus::Delivered, 200), (EventStatus::Rejected, 503)] {
+            let response = tokio::spawn(async move {
+                post(address, "services/collector/raw", "acknowledged").await
+            });
+
+            // The request is only answered once the event has been finalized.
+            let (event, rest) = source.into_future().compat().await.ok().unwrap();
+            source = rest;
+            let mut event = event.unwrap();
+            assert_eq!(
+                event.as_log()[&event::log_schema().message_key()],
+                "acknowledged".into()
+            );
+            event.take_finalizers().update_status(*status);
+            drop(event);
+
+            assert_eq!(*code, response.await.unwrap());
+        }
+    }
+
     #[tokio::test]
     async fn no_data() {
         trace_init();
@@ -0,0 +1,190 @@
+use crate::{
+    config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+    event::Event,
+    shutdown::ShutdownSignal,
+    wasm::{defaults, WasmModule},
+    Pipeline,
+};
+use futures::{
+    compat::Future01CompatExt,
+    future::{FutureExt, TryFutureExt},
+    stream::{self, StreamExt},
+};
+use futures01::Sink;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, iter, path::PathBuf, time::Duration};
+use tokio::{select, time::interval};
+use vector_wasm::{Role, WasmModuleConfig};
+
+/// Source specific information needed to construct a [`WasmModuleConfig`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WasmSourceConfig {
+    /// The location of the source WASM or WAT module.
+    pub module: PathBuf,
+    /// The location of the WASM artifact cache.
+    pub artifact_cache: PathBuf,
+    #[serde(default = "defaults::heap_memory_size")]
+    pub heap_memory_size: usize,
+    /// The amount of fuel a single call into the module may consume. Unlimited if unset.
+    #[serde(default)]
+    pub max_fuel: Option<u64>,
+    /// The interval between calls to the `poll` export of the module.
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// The hosts the module may reach.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Options to be passed to the WASM module.
+    #[serde(default)]
+    pub options: HashMap<String, serde_json::Value>,
+}
+
+fn default_poll_interval_secs() -> u64 {
+    1
+}
+
+impl Into<WasmModuleConfig> for WasmSourceConfig {
+    fn into(self) -> WasmModuleConfig {
+        let mut config = WasmModuleConfig::new(
+            Role::Source,
+            self.module,
+            self.artifact_cache,
+            self.options,
+            self.heap_memory_size,
+        );
+        config
+            .set_max_fuel(self.max_fuel)
+            .set_allowed_hosts(self.allowed_hosts);
+        config
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<WasmSourceConfig>("wasm")
+}
+
+#[typetag::serde(name = "wasm")]
+impl SourceConfig for WasmSourceConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        if self.poll_interval_secs == 0 {
+            return Err("`poll_interval_secs` must be positive.".into());
+        }
+        let module = WasmModule::build(self.clone())?;
+        let fut = run(module, self.poll_interval_secs, shutdown, out)
+            .boxed()
+            .compat();
+        Ok(Box::new(fut))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn source_type(&self) -> &'static str {
+        "wasm"
+    }
+}
+
+/// Calls `poll` every `poll_interval_secs`, and the `timer_handler` of each timer the module
+/// registered, forwarding the events the module emits.
+async fn run(
+    mut module: WasmModule,
+    poll_interval_secs: u64,
+    shutdown: ShutdownSignal,
+    mut out: Pipeline,
+) -> Result<(), ()> {
+    let poll = interval(Duration::from_secs(poll_interval_secs))
+        .map(|_| None)
+        .boxed();
+    let timers = module.timers().into_iter().map(|timer| {
+        interval(Duration::from_secs(timer.interval_seconds))
+            .map(move |_| Some(timer.id))
+            .boxed()
+    });
+    let mut ticks = stream::select_all(iter::once(poll).chain(timers));
+    let mut shutdown = shutdown.compat();
+
+    loop {
+        let tick = select! {
+            Some(tick) = ticks.next() => tick,
+            _ = &mut shutdown => break,
+            else => break,
+        };
+
+        let (returned, events) = module
+            .blocking(move |module| match tick {
+                None => module.poll(),
+                Some(id) => module.timer_handler(id),
+            })
+            .await
+            .map_err(|error| error!(message = "WASM module failed.", %error))?;
+        module = returned;
+        out = send(out, events).await?;
+    }
+
+    let (_, events) = module
+        .blocking(WasmModule::shutdown)
+        .await
+        .map_err(|error| error!(message = "WASM module failed.", %error))?;
+    send(out, events).await?;
+    Ok(())
+}
+
+async fn send(out: Pipeline, events: crate::Result<Vec<Event>>) -> Result<Pipeline, ()> {
+    let events = events.unwrap_or_else(|error| {
+        error!(message = "WASM module call failed.", %error);
+        Vec::new()
+    });
+    let (out, _) = out
+        .send_all(futures01::stream::iter_ok(events))
+        .compat()
+        .await
+        .map_err(|error| error!(message = "Error sending events.", %error))?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{event, test_util::collect_n};
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn polls_module_and_timers() -> crate::Result<()> {
+        crate::test_util::trace_init();
+
+        let config: WasmSourceConfig = toml::from_str(
+            r#"
+            module = "target/wasm32-wasi/release/poll.wasm"
+            artifact_cache = "target/artifacts"
+            "#,
+        )?;
+        let module = WasmModule::build(config.clone())?;
+        let (tx, rx) = Pipeline::new_test();
+        tokio::spawn(run(
+            module,
+            config.poll_interval_secs,
+            ShutdownSignal::noop(),
+            tx,
+        ));
+
+        // Both the poll and the timer tick right away.
+        let events = timeout(Duration::from_secs(5), collect_n(rx, 2))
+            .await?
+            .unwrap();
+        let mut messages = events
+            .iter()
+            .map(|event| event.as_log()[&event::log_schema().message_key()].to_string_lossy())
+            .collect::<Vec<_>>();
+        messages.sort();
+        assert_eq!(messages, vec!["polled", "timer"]);
+        Ok(())
+    }
+}
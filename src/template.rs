@@ -1,18 +1,21 @@
 use crate::{
     event::{self, Value},
+    internal_events::TemplateRenderingError,
     Event,
 };
 use bytes::Bytes;
 use chrono::{
     format::{strftime::StrftimeItems, Item},
-    Utc,
+    DateTime, TimeZone, Utc,
 };
+use derivative::Derivative;
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 use serde::{
     de::{self, Deserialize, Deserializer, Visitor},
     ser::{Serialize, Serializer},
 };
+use sha2::{Digest, Sha256, Sha512};
 use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
@@ -29,11 +32,16 @@ pub struct Template {
     src_bytes: Bytes,
     has_ts: bool,
     has_fields: bool,
+    placeholders: Vec<Placeholder>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum TemplateError {
     StrftimeError,
+    InvalidFilter {
+        filter: String,
+        reason: &'static str,
+    },
 }
 
 impl Error for TemplateError {}
@@ -42,10 +50,30 @@ impl fmt::Display for TemplateError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::StrftimeError => write!(f, "Invalid strftime item"),
+            Self::InvalidFilter { filter, reason } => {
+                write!(f, "Invalid template filter `{}`: {}", filter, reason)
+            }
         }
     }
 }
 
+/// What to do with an event when a template references fields it does not
+/// have.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Derivative)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OnMissing {
+    /// Drop the event with a warning.
+    #[derivative(Default)]
+    Drop,
+    /// Use the given literal in place of the rendered template, for example
+    /// to send the event to a catch-all file or index.
+    Fallback(String),
+    /// Drop the event and report it as a processing error. Sinks have no error
+    /// output, so the event isn't routed anywhere else.
+    DropWithError,
+}
+
 impl TryFrom<&str> for Template {
     type Error = TemplateError;
 
@@ -54,15 +82,21 @@ impl TryFrom<&str> for Template {
             (pair.0 || is_error(&item), pair.1 || is_dynamic(&item))
         });
         if has_error {
-            Err(TemplateError::StrftimeError)
-        } else {
-            Ok(Template {
-                src: src.into(),
-                src_bytes: Vec::from(src.as_bytes()).into(),
-                has_ts: is_dynamic,
-                has_fields: RE.is_match(src),
-            })
+            return Err(TemplateError::StrftimeError);
         }
+
+        let placeholders = RE
+            .captures_iter(src)
+            .map(|c| Placeholder::parse(c.get(1).expect("src should match regex").as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Template {
+            src: src.into(),
+            src_bytes: Vec::from(src.as_bytes()).into(),
+            has_ts: is_dynamic,
+            has_fields: !placeholders.is_empty(),
+            placeholders,
+        })
     }
 }
 
@@ -100,10 +134,10 @@ impl Template {
     pub fn render(&self, event: &Event) -> Result<Bytes, Vec<Atom>> {
         match (self.has_fields, self.has_ts) {
             (false, false) => Ok(self.src_bytes.clone()),
-            (true, false) => render_fields(&self.src, event).map(Bytes::from),
+            (true, false) => self.render_fields(event).map(Bytes::from),
             (false, true) => Ok(render_timestamp(&self.src, event).into()),
             (true, true) => {
-                let tmp = render_fields(&self.src, event)?;
+                let tmp = self.render_fields(event)?;
                 Ok(render_timestamp(&tmp, event).into())
             }
         }
@@ -114,14 +148,32 @@ impl Template {
             .map(|bytes| String::from_utf8(Vec::from(bytes.as_ref())).expect("this is a bug"))
     }
 
+    /// Renders the template, resolving missing fields according to
+    /// `on_missing`. An error means the event should be dropped.
+    pub fn render_string_with(
+        &self,
+        event: &Event,
+        on_missing: &OnMissing,
+    ) -> Result<String, Vec<Atom>> {
+        self.render_string(event)
+            .or_else(|missing_keys| match on_missing {
+                OnMissing::Drop => Err(missing_keys),
+                OnMissing::Fallback(value) => Ok(value.clone()),
+                OnMissing::DropWithError => {
+                    emit!(TemplateRenderingError {
+                        template: &self.src,
+                        missing_keys: &missing_keys,
+                    });
+                    Err(missing_keys)
+                }
+            })
+    }
+
     pub fn get_fields(&self) -> Option<Vec<Atom>> {
         if self.has_fields {
-            RE.captures_iter(&self.src)
-                .map(|c| {
-                    c.get(1)
-                        .map(|s| Atom::from(s.as_str().trim()))
-                        .expect("src should match regex")
-                })
+            self.placeholders
+                .iter()
+                .map(|p| p.key.clone())
                 .collect::<Vec<_>>()
                 .into()
         } else {
@@ -136,28 +188,200 @@ impl Template {
     pub fn get_ref(&self) -> &Bytes {
         &self.src_bytes
     }
+
+    fn render_fields(&self, event: &Event) -> Result<String, Vec<Atom>> {
+        let mut missing_fields = Vec::new();
+        // Placeholders were parsed from the same matches, in the same order.
+        let mut placeholders = self.placeholders.iter();
+        let out = RE
+            .replace_all(&self.src, |_: &Captures<'_>| {
+                let placeholder = placeholders.next().expect("src should match regex");
+                match placeholder.render(event) {
+                    Some(val) => val,
+                    None => {
+                        missing_fields.push(placeholder.key.clone());
+                        String::new()
+                    }
+                }
+            })
+            .into_owned();
+        if missing_fields.is_empty() {
+            Ok(out)
+        } else {
+            Err(missing_fields)
+        }
+    }
 }
 
-fn render_fields(src: &str, event: &Event) -> Result<String, Vec<Atom>> {
-    let mut missing_fields = Vec::new();
-    let out = RE
-        .replace_all(src, |caps: &Captures<'_>| {
-            let key = caps
-                .get(1)
-                .map(|s| Atom::from(s.as_str().trim()))
-                .expect("src should match regex");
-            if let Some(val) = event.as_log().get(&key) {
-                val.to_string_lossy()
-            } else {
-                missing_fields.push(key.clone());
-                String::new()
+/// A `{{ field | filter: "argument" | ... }}` placeholder.
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    key: Atom,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Default(String),
+    Upcase,
+    Downcase,
+    Truncate(usize),
+    Slug,
+    Sha256,
+    Sha512,
+    Strftime(String),
+}
+
+impl Placeholder {
+    fn parse(src: &str) -> Result<Self, TemplateError> {
+        let mut parts = split_unquoted(src, '|').into_iter();
+        let key = Atom::from(parts.next().unwrap_or_default().trim());
+        let filters = parts.map(Filter::parse).collect::<Result<_, _>>()?;
+        Ok(Self { key, filters })
+    }
+
+    /// Returns `None` if the field is missing, or if a filter cannot be
+    /// applied to its value, and no `default` filter follows.
+    fn render(&self, event: &Event) -> Option<String> {
+        self.filters
+            .iter()
+            .fold(field_value(&self.key, event), |value, filter| {
+                filter.apply(value)
+            })
+            .map(|value| value.to_string_lossy())
+    }
+}
+
+impl Filter {
+    fn parse(src: &str) -> Result<Self, TemplateError> {
+        let mut parts = split_unquoted(src, ':').into_iter();
+        let name = parts.next().unwrap_or_default().trim();
+        let argument = parts.next().map(|arg| unquote(arg.trim()));
+        let invalid = |reason| TemplateError::InvalidFilter {
+            filter: src.trim().into(),
+            reason,
+        };
+        if parts.next().is_some() {
+            return Err(invalid("expected a single argument"));
+        }
+
+        let filter = match (name, argument) {
+            ("default", Some(value)) => Filter::Default(value),
+            ("upcase", None) => Filter::Upcase,
+            ("downcase", None) => Filter::Downcase,
+            ("truncate", Some(length)) => Filter::Truncate(
+                length
+                    .parse()
+                    .map_err(|_| invalid("expected a non-negative integer length"))?,
+            ),
+            ("slug", None) => Filter::Slug,
+            ("sha256", None) => Filter::Sha256,
+            ("sha512", None) => Filter::Sha512,
+            ("strftime", Some(format)) => {
+                if StrftimeItems::new(&format).any(|item| is_error(&item)) {
+                    return Err(invalid("invalid strftime format"));
+                }
+                Filter::Strftime(format)
             }
-        })
-        .into_owned();
-    if missing_fields.is_empty() {
-        Ok(out)
+            ("default", None) | ("truncate", None) | ("strftime", None) => {
+                return Err(invalid("missing argument"))
+            }
+            ("upcase", Some(_))
+            | ("downcase", Some(_))
+            | ("slug", Some(_))
+            | ("sha256", Some(_))
+            | ("sha512", Some(_)) => return Err(invalid("takes no argument")),
+            _ => return Err(invalid("unknown filter")),
+        };
+        Ok(filter)
+    }
+
+    fn apply(&self, value: Option<Value>) -> Option<Value> {
+        let value = match (self, value) {
+            (Filter::Default(default), None) => return Some(default.as_str().into()),
+            (Filter::Strftime(format), Some(value)) => {
+                return value_timestamp(&value).map(|ts| ts.format(format).to_string().into())
+            }
+            (_, None) => return None,
+            (Filter::Default(_), Some(value)) => return Some(value),
+            (_, Some(value)) => value.to_string_lossy(),
+        };
+
+        let value = match self {
+            Filter::Upcase => value.to_uppercase(),
+            Filter::Downcase => value.to_lowercase(),
+            Filter::Truncate(length) => value.chars().take(*length).collect(),
+            Filter::Slug => slugify(&value),
+            Filter::Sha256 => format!("{:x}", Sha256::digest(value.as_bytes())),
+            Filter::Sha512 => format!("{:x}", Sha512::digest(value.as_bytes())),
+            Filter::Default(_) | Filter::Strftime(_) => unreachable!("handled above"),
+        };
+        Some(value.into())
+    }
+}
+
+/// Splits `src` on `separator`, ignoring separators inside double quotes.
+fn split_unquoted(src: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in src.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            parts.push(&src[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    parts.push(&src[start..]);
+    parts
+}
+
+fn unquote(src: &str) -> String {
+    if src.len() >= 2 && src.starts_with('"') && src.ends_with('"') {
+        src[1..src.len() - 1].into()
     } else {
-        Err(missing_fields)
+        src.into()
+    }
+}
+
+/// Lowercases `src` and joins its alphanumeric runs with single dashes.
+fn slugify(src: &str) -> String {
+    src.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Timestamps are used as is, strings are parsed as RFC 3339 and integers
+/// are taken as seconds since the Unix epoch.
+fn value_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::Timestamp(ts) => Some(*ts),
+        Value::Bytes(bytes) => DateTime::parse_from_rfc3339(&String::from_utf8_lossy(bytes))
+            .ok()
+            .map(|ts| ts.with_timezone(&Utc)),
+        Value::Integer(seconds) => Utc.timestamp_opt(*seconds, 0).single(),
+        _ => None,
+    }
+}
+
+/// Log fields may be nested paths such as `a.b[0]`. Metrics expose their
+/// `name`, `timestamp` and, through `tags.<tag>`, their tags.
+fn field_value(key: &Atom, event: &Event) -> Option<Value> {
+    match event {
+        Event::Log(log) => log.get(key).cloned(),
+        Event::Metric(metric) => match &key[..] {
+            "name" => Some(metric.name.as_str().into()),
+            "timestamp" => metric.timestamp.map(Value::Timestamp),
+            key if key.starts_with("tags.") => metric
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get(&key["tags.".len()..]))
+                .map(|tag| tag.as_str().into()),
+            _ => None,
+        },
     }
 }
 
@@ -166,7 +390,7 @@ fn render_timestamp(src: &str, event: &Event) -> String {
         Event::Log(log) => log
             .get(&event::log_schema().timestamp_key())
             .and_then(Value::as_timestamp),
-        _ => None,
+        Event::Metric(metric) => metric.timestamp.as_ref(),
     };
     if let Some(ts) = timestamp {
         ts.format(src).to_string()
@@ -215,6 +439,7 @@ impl Serialize for Template {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::event::metric::{Metric, MetricKind, MetricValue};
     use chrono::TimeZone;
 
     #[test]
@@ -295,6 +520,33 @@ mod tests {
         assert_eq!(Ok(Bytes::from("stream-abcd")), template.render(&event))
     }
 
+    #[test]
+    fn render_metric_fields() {
+        let event = Event::Metric(Metric {
+            name: "requests".into(),
+            timestamp: Some(Utc.ymd(2001, 2, 3).and_hms(4, 5, 6)),
+            tags: Some(
+                vec![("host".to_owned(), "localhost".to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+
+        let template = Template::try_from("{{name}}-{{ tags.host }}-%Y").unwrap();
+        assert_eq!(
+            Ok(Bytes::from("requests-localhost-2001")),
+            template.render(&event)
+        );
+
+        let template = Template::try_from("{{ tags.missing }}-{{ message }}").unwrap();
+        assert_eq!(
+            Err(vec![Atom::from("tags.missing"), Atom::from("message")]),
+            template.render(&event)
+        );
+    }
+
     #[test]
     fn render_dynamic_missing_key() {
         let event = Event::from("hello world");
@@ -424,4 +676,124 @@ mod tests {
             TemplateError::StrftimeError
         );
     }
+
+    #[test]
+    fn render_filters() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("app", "My Web App!");
+        event.as_mut_log().insert("user", "ABCdef");
+        event.as_mut_log().insert(
+            "nested.list",
+            Value::Array(vec!["first".into(), "second".into()]),
+        );
+
+        let render = |src: &str| Template::try_from(src).unwrap().render_string(&event);
+
+        assert_eq!(render("{{ app | slug }}"), Ok("my-web-app".into()));
+        assert_eq!(render("{{ user | upcase }}"), Ok("ABCDEF".into()));
+        assert_eq!(render("{{ user|downcase|truncate: 3 }}"), Ok("abc".into()));
+        assert_eq!(render("{{ nested.list[1] | upcase }}"), Ok("SECOND".into()));
+        assert_eq!(
+            render("{{ user | sha256 }}"),
+            Ok("057e5833fca53ae19901247bd5e68039100561b8535f346dff7d6a4dcc7bf996".into())
+        );
+    }
+
+    #[test]
+    fn render_default_filter() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("app", "web");
+
+        let template = Template::try_from(r#"{{ app | default: "unknown" }}"#).unwrap();
+        assert_eq!(Ok("web".into()), template.render_string(&event));
+
+        let template =
+            Template::try_from(r#"{{ missing | upcase | default: "a | b: c" }}-{{ app }}"#)
+                .unwrap();
+        assert_eq!(Ok("a | b: c-web".into()), template.render_string(&event));
+        assert_eq!(
+            template.get_fields(),
+            Some(vec!["missing".into(), "app".into()])
+        );
+
+        let template = Template::try_from("{{ missing | upcase }}").unwrap();
+        assert_eq!(
+            Err(vec![Atom::from("missing")]),
+            template.render_string(&event)
+        );
+    }
+
+    #[test]
+    fn render_strftime_filter() {
+        let ts = Utc.ymd(2001, 2, 3).and_hms(4, 5, 6);
+
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("created", ts);
+        event.as_mut_log().insert("updated", "2010-11-12T13:14:15Z");
+        event.as_mut_log().insert("deleted", 0_i64);
+        event.as_mut_log().insert("invalid", "yesterday");
+        event.as_mut_log().insert("overflow", i64::MAX);
+
+        let render = |src: &str| Template::try_from(src).unwrap().render_string(&event);
+
+        assert_eq!(
+            render(r#"{{ created | strftime: "%Y/%m/%d" }}"#),
+            Ok("2001/02/03".into())
+        );
+        assert_eq!(
+            render(r#"{{ updated | strftime: "%H:%M" }}"#),
+            Ok("13:14".into())
+        );
+        assert_eq!(
+            render(r#"{{ deleted | strftime: "%Y" }}"#),
+            Ok("1970".into())
+        );
+        assert_eq!(
+            render(r#"{{ invalid | strftime: "%Y" }}"#),
+            Err(vec![Atom::from("invalid")])
+        );
+        assert_eq!(
+            render(r#"{{ overflow | strftime: "%Y" }}"#),
+            Err(vec![Atom::from("overflow")])
+        );
+    }
+
+    #[test]
+    fn invalid_filters() {
+        let error = |src: &str| match Template::try_from(src).unwrap_err() {
+            TemplateError::InvalidFilter { reason, .. } => reason,
+            error => panic!("unexpected error: {}", error),
+        };
+
+        assert_eq!(error("{{ foo | reverse }}"), "unknown filter");
+        assert_eq!(error("{{ foo | default }}"), "missing argument");
+        assert_eq!(error("{{ foo | upcase: 1 }}"), "takes no argument");
+        assert_eq!(
+            error("{{ foo | truncate: -1 }}"),
+            "expected a non-negative integer length"
+        );
+        assert_eq!(
+            error(r#"{{ foo | default: "a": "b" }}"#),
+            "expected a single argument"
+        );
+    }
+
+    #[test]
+    fn render_with_on_missing() {
+        let event = Event::from("hello world");
+        let template = Template::try_from("{{ app }}.log").unwrap();
+
+        assert_eq!(
+            template.render_string_with(&event, &OnMissing::Drop),
+            Err(vec![Atom::from("app")])
+        );
+        assert_eq!(
+            template.render_string_with(&event, &OnMissing::Fallback("unrouted.log".into())),
+            Ok("unrouted.log".into())
+        );
+        assert_eq!(
+            template.render_string_with(&event, &OnMissing::DropWithError),
+            Err(vec![Atom::from("app")])
+        );
+    }
 }
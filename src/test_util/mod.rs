@@ -483,7 +483,9 @@ pub async fn start_topology(
     require_healthy: bool,
 ) -> (RunningTopology, mpsc::UnboundedReceiver<()>) {
     let diff = ConfigDiff::initial(&config);
-    let pieces = topology::validate(&config, &diff).await.unwrap();
+    let pieces = topology::validate(&config, &diff, HashMap::new())
+        .await
+        .unwrap();
     topology::start_validated(config, diff, pieces, require_healthy)
         .await
         .unwrap()
@@ -7,28 +7,122 @@ use crate::{
     buffers,
     config::{DataType, SinkContext, TransformContext},
     dns::Resolver,
+    enrichment_tables::EnrichmentTables,
     event::Event,
     shutdown::SourceShutdownCoordinator,
+    sinks::RouterSink,
     Pipeline,
 };
 use futures::{compat::Future01CompatExt, FutureExt};
-use futures01::{sync::mpsc, Future, Stream};
-use std::collections::HashMap;
+use futures01::{
+    sync::{mpsc, oneshot},
+    Async, AsyncSink, Future, Poll, Sink, StartSend, Stream,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 use tokio::time::{timeout, Duration};
 
+/// A sink's buffer: its input, its output once no sink is reading from it, and its acker.
+/// Kept across reloads so that a rebuilt sink carries on where the old one left off.
+pub type BuiltBuffer = (
+    Input,
+    Arc<Mutex<Option<buffers::EventStream>>>,
+    buffers::Acker,
+);
+
+/// The events sent to a component, and those it's done with: read by a transform, or acked
+/// by a sink.
+#[derive(Clone, Debug, Default)]
+pub struct EventCounts {
+    sent: Arc<AtomicUsize>,
+    done: Arc<AtomicUsize>,
+}
+
+impl EventCounts {
+    /// Events the component has been sent but hasn't finished with.
+    pub fn pending(&self) -> usize {
+        let sent = self.sent.load(Ordering::Relaxed);
+        sent.saturating_sub(self.done.load(Ordering::Relaxed))
+    }
+}
+
+/// The input of a transform or sink, counting the events sent to it.
+#[derive(Clone)]
+pub struct Input {
+    buffer: buffers::BufferInputCloner,
+    counts: EventCounts,
+}
+
+impl Input {
+    fn new(buffer: buffers::BufferInputCloner) -> Self {
+        Self {
+            buffer,
+            counts: EventCounts::default(),
+        }
+    }
+
+    pub fn get(&self) -> RouterSink {
+        Box::new(CountSent {
+            inner: self.buffer.get(),
+            sent: Arc::clone(&self.counts.sent),
+        })
+    }
+}
+
+struct CountSent {
+    inner: RouterSink,
+    sent: Arc<AtomicUsize>,
+}
+
+impl Sink for CountSent {
+    type SinkItem = Event;
+    type SinkError = ();
+
+    fn start_send(&mut self, item: Event) -> StartSend<Event, ()> {
+        let result = self.inner.start_send(item);
+        if let Ok(AsyncSink::Ready) = result {
+            self.sent.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), ()> {
+        self.inner.poll_complete()
+    }
+
+    fn close(&mut self) -> Poll<(), ()> {
+        self.inner.close()
+    }
+}
+
 pub struct Pieces {
-    pub inputs: HashMap<String, (buffers::BufferInputCloner, Vec<String>)>,
+    pub inputs: HashMap<String, (Input, Vec<String>)>,
     pub outputs: HashMap<String, fanout::ControlChannel>,
     pub tasks: HashMap<String, Task>,
     pub source_tasks: HashMap<String, Task>,
     pub healthchecks: HashMap<String, Task>,
     pub shutdown_coordinator: SourceShutdownCoordinator,
+    pub buffers: HashMap<String, BuiltBuffer>,
+    pub detach_triggers: HashMap<String, oneshot::Sender<()>>,
+    /// The new inputs of transforms that are rewired rather than rebuilt.
+    pub rewired_inputs: HashMap<String, Vec<String>>,
+    /// Event counts of the components whose undelivered events are lost on shutdown: transforms,
+    /// and sinks with memory buffers.
+    pub counts: HashMap<String, EventCounts>,
 }
 
 /// Builds only the new pieces, and doesn't check their topology.
+///
+/// Sinks found in `buffers` reuse the given buffer instead of building a new one.
 pub async fn build_pieces(
     config: &super::Config,
     diff: &ConfigDiff,
+    mut buffers: HashMap<String, BuiltBuffer>,
 ) -> Result<Pieces, Vec<String>> {
     let mut inputs = HashMap::new();
     let mut outputs = HashMap::new();
@@ -36,12 +130,17 @@ pub async fn build_pieces(
     let mut source_tasks = HashMap::new();
     let mut healthchecks = HashMap::new();
     let mut shutdown_coordinator = SourceShutdownCoordinator::default();
+    let mut built_buffers = HashMap::new();
+    let mut detach_triggers = HashMap::new();
+    let mut counts = HashMap::new();
 
     let mut errors = vec![];
 
     // TODO: remove the unimplemented
     let resolver = Resolver;
 
+    let enrichment_tables = EnrichmentTables::load(&config.enrichment_tables)?;
+
     // Build sources
     for (name, source) in config
         .sources
@@ -97,7 +196,10 @@ pub async fn build_pieces(
 
         let typetag = transform.inner.transform_type();
 
-        let cx = TransformContext { resolver };
+        let cx = TransformContext {
+            resolver,
+            enrichment_tables: enrichment_tables.clone(),
+        };
 
         let input_type = transform.inner.input_type();
         let transform = match transform.inner.build_async(cx).await {
@@ -109,7 +211,14 @@ pub async fn build_pieces(
         };
 
         let (input_tx, input_rx) = futures01::sync::mpsc::channel(100);
-        let input_tx = buffers::BufferInputCloner::Memory(input_tx, buffers::WhenFull::Block);
+        let input_tx = Input::new(buffers::BufferInputCloner::Memory(
+            input_tx,
+            buffers::WhenFull::Block,
+        ));
+        let done = Arc::clone(&input_tx.counts.done);
+        let input_rx = input_rx.inspect(move |_| {
+            done.fetch_add(1, Ordering::Relaxed);
+        });
 
         let (output, control) = Fanout::new();
 
@@ -120,11 +229,19 @@ pub async fn build_pieces(
             .compat();
         let task = Task::new(name, typetag, transform);
 
+        counts.insert(name.clone(), input_tx.counts.clone());
         inputs.insert(name.clone(), (input_tx, trans_inputs.clone()));
         outputs.insert(name.clone(), control);
         tasks.insert(name.clone(), task);
     }
 
+    let rewired_inputs = config
+        .transforms
+        .iter()
+        .filter(|(name, _)| diff.transforms.to_rewire.contains(*name))
+        .map(|(name, transform)| (name.clone(), transform.inputs.clone()))
+        .collect();
+
     // Build sinks
     for (name, sink) in config
         .sinks
@@ -137,16 +254,35 @@ pub async fn build_pieces(
         let typetag = sink.inner.sink_type();
         let input_type = sink.inner.input_type();
 
-        let buffer = sink.buffer.build(&config.global.data_dir, &name);
-        let (tx, rx, acker) = match buffer {
-            Err(error) => {
-                errors.push(format!("Sink \"{}\": {}", name, error));
-                continue;
-            }
-            Ok(buffer) => buffer,
+        let (tx, rx, acker) = match buffers.remove(name) {
+            Some(buffer) => buffer,
+            None => match sink.buffer.build(&config.global.data_dir, &name) {
+                Err(error) => {
+                    errors.push(format!("Sink \"{}\": {}", name, error));
+                    continue;
+                }
+                Ok((tx, rx, acker)) => {
+                    let tx = Input::new(tx);
+                    // Memory buffers don't need acks, but counting them tells shutdown how
+                    // many events the sink didn't get to deliver.
+                    let acker = match acker {
+                        buffers::Acker::Null => {
+                            buffers::Acker::Counting(Arc::clone(&tx.counts.done))
+                        }
+                        acker => acker,
+                    };
+                    (tx, Arc::new(Mutex::new(Some(rx))), acker)
+                }
+            },
         };
+        if let buffers::Acker::Counting(_) = acker {
+            counts.insert(name.clone(), tx.counts.clone());
+        }
 
-        let cx = SinkContext { resolver, acker };
+        let cx = SinkContext {
+            resolver,
+            acker: acker.clone(),
+        };
 
         let (sink, healthcheck) = match sink.inner.build_async(cx).await {
             Err(error) => {
@@ -156,6 +292,11 @@ pub async fn build_pieces(
             Ok((sink, healthcheck)) => (sink, healthcheck),
         };
 
+        let (detach_trigger, detach) = oneshot::channel();
+        let rx = Detachable {
+            detach: Some(detach),
+            buffer: Arc::clone(&rx),
+        };
         let sink = filter_event_type(rx, input_type)
             .forward(sink)
             .map(|_| debug!("Finished"))
@@ -188,6 +329,8 @@ pub async fn build_pieces(
         };
         let healthcheck_task = Task::new(name, typetag, healthcheck_task);
 
+        built_buffers.insert(name.clone(), (tx.clone(), rx, acker));
+        detach_triggers.insert(name.clone(), detach_trigger);
         inputs.insert(name.clone(), (tx, sink_inputs.clone()));
         healthchecks.insert(name.clone(), healthcheck_task);
         tasks.insert(name.clone(), task);
@@ -201,6 +344,10 @@ pub async fn build_pieces(
             source_tasks,
             healthchecks,
             shutdown_coordinator,
+            buffers: built_buffers,
+            detach_triggers,
+            rewired_inputs,
+            counts,
         };
 
         Ok(pieces)
@@ -209,6 +356,36 @@ pub async fn build_pieces(
     }
 }
 
+/// Reads a sink's buffer until the topology takes it away for the sink replacing this one, at
+/// which point the stream ends, letting the old sink flush and finish. The buffer never leaves
+/// the topology's hands, so it can be taken over even from a sink that's stuck and no longer
+/// polling its input; the detach trigger then only wakes the old sink up.
+struct Detachable {
+    detach: Option<oneshot::Receiver<()>>,
+    buffer: Arc<Mutex<Option<buffers::EventStream>>>,
+}
+
+impl Stream for Detachable {
+    type Item = Event;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Event>, ()> {
+        if let Some(detach) = &mut self.detach {
+            match detach.poll() {
+                Ok(Async::NotReady) => {}
+                // Either the buffer has been taken, or the sink is being removed, in which
+                // case it drains its buffer as before.
+                Ok(Async::Ready(())) | Err(_) => self.detach = None,
+            }
+        }
+
+        match self.buffer.lock().unwrap().as_mut() {
+            Some(inner) => inner.poll(),
+            None => Ok(Async::Ready(None)),
+        }
+    }
+}
+
 fn filter_event_type<S>(
     stream: S,
     data_type: DataType,
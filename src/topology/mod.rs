@@ -12,34 +12,62 @@ mod task;
 pub mod unit_test;
 
 use crate::{
-    buffers,
     config::{self, Config, ConfigDiff},
+    internal_events::TopologyShutdownSummary,
     shutdown::SourceShutdownCoordinator,
-    topology::{builder::Pieces, task::Task},
+    topology::{
+        builder::{BuiltBuffer, EventCounts, Input, Pieces},
+        task::Task,
+    },
+};
+use futures::{compat::Future01CompatExt, future, FutureExt, TryFutureExt};
+use futures01::{
+    sync::{mpsc, oneshot},
+    Future,
 };
-use futures::{compat::Future01CompatExt, future, FutureExt, StreamExt, TryFutureExt};
-use futures01::{sync::mpsc, Future};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
 };
-use tokio::time::{delay_until, interval, Duration, Instant};
+use tokio::time::{interval, timeout_at, Duration, Instant};
 use tracing_futures::Instrument;
 
 // TODO: Result is only for compat, remove when not needed
 type TaskHandle = tokio::task::JoinHandle<Result<(), ()>>;
 
+/// How long shutdown waits for components when there are no sinks to take deadlines from.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
 #[allow(dead_code)]
 pub struct RunningTopology {
-    inputs: HashMap<String, buffers::BufferInputCloner>,
+    inputs: HashMap<String, Input>,
     outputs: HashMap<String, fanout::ControlChannel>,
     source_tasks: HashMap<String, TaskHandle>,
     tasks: HashMap<String, TaskHandle>,
     shutdown_coordinator: SourceShutdownCoordinator,
+    buffers: HashMap<String, BuiltBuffer>,
+    detach_triggers: HashMap<String, oneshot::Sender<()>>,
+    counts: HashMap<String, EventCounts>,
     config: Config,
     abort_tx: mpsc::UnboundedSender<()>,
 }
 
+/// What stopping a topology left behind.
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    /// Components still running when their deadline passed.
+    pub unfinished: Vec<String>,
+    /// Events sent to transforms and memory-buffered sinks that they never delivered.
+    pub undelivered: BTreeMap<String, usize>,
+}
+
+impl ShutdownReport {
+    pub fn dropped_events(&self) -> bool {
+        !self.undelivered.is_empty()
+    }
+}
+
 pub async fn start_validated(
     config: Config,
     diff: ConfigDiff,
@@ -55,6 +83,9 @@ pub async fn start_validated(
         shutdown_coordinator: SourceShutdownCoordinator::default(),
         source_tasks: HashMap::new(),
         tasks: HashMap::new(),
+        buffers: HashMap::new(),
+        detach_triggers: HashMap::new(),
+        counts: HashMap::new(),
         abort_tx,
     };
 
@@ -71,10 +102,14 @@ pub async fn start_validated(
     Some((running_topology, abort_rx))
 }
 
-pub async fn validate(config: &Config, diff: &ConfigDiff) -> Option<Pieces> {
+pub async fn validate(
+    config: &Config,
+    diff: &ConfigDiff,
+    buffers: HashMap<String, BuiltBuffer>,
+) -> Option<Pieces> {
     let check_build_result = match (
         config::check(config),
-        builder::build_pieces(config, diff).await,
+        builder::build_pieces(config, diff, buffers).await,
     ) {
         (Ok(warnings), Ok(new_pieces)) => Ok((new_pieces, warnings)),
         (Err(t_errors), Err(p_errors)) => Err(t_errors.into_iter().chain(p_errors).collect()),
@@ -84,7 +119,7 @@ pub async fn validate(config: &Config, diff: &ConfigDiff) -> Option<Pieces> {
     match check_build_result {
         Err(errors) => {
             for error in errors {
-                error!("Configuration error: {}", error);
+                error!("Configuration error: {}", config.redact(&error));
             }
             None
         }
@@ -115,68 +150,117 @@ impl RunningTopology {
         self.shutdown_coordinator.shutdown_tripwire()
     }
 
-    /// Sends the shutdown signal to all sources and returns a future that resolves
-    /// once all components (sources, transforms, and sinks) have finished shutting down.
-    /// Transforms and sinks should shut down automatically once their input tasks finish.
-    /// Note that this takes ownership of `self`, so once this function returns everything in the
-    /// RunningTopology instance has been dropped except for the `tasks` map, which gets moved
-    /// into the returned future and is used to poll for when the tasks have completed. One the
-    /// returned future is dropped then everything from this RunningTopology instance is fully
-    /// dropped.
-    pub fn stop(self) -> impl Future<Item = (), Error = ()> {
-        // Create handy handles collections of all tasks for the subsequent operations.
-        let mut wait_handles = Vec::new();
-        // We need a Vec here since source components have two tasks. One for pump in self.tasks,
-        // and the other for source in self.source_tasks.
-        let mut check_handles = HashMap::<String, Vec<_>>::new();
-
-        // We need to give some time to the sources to gracefully shutdown, so we will merge
-        // them with other tasks.
-        for (name, task) in self.tasks.into_iter().chain(self.source_tasks.into_iter()) {
-            let task = futures::compat::Compat::new(task)
-                .map(|_result| ())
-                .or_else(|_| futures01::future::ok(())) // Consider an errored task to be shutdown
-                .shared();
-
-            wait_handles.push(task.clone());
-            check_handles.entry(name).or_default().push(task);
-        }
-
+    /// Sends the shutdown signal to all sources and returns a future that resolves once all
+    /// components (sources, transforms, and sinks) have finished shutting down, or have been
+    /// given up on, with a report of the events that were left undelivered.
+    ///
+    /// Components are drained in order: sources first, then transforms and sinks as their
+    /// inputs close. Each sink is given until its `shutdown_timeout_secs`, counted from now, to
+    /// flush, and sources are forced to stop by the last of those deadlines. Note that this takes
+    /// ownership of `self`, so everything but the running tasks is dropped once this returns.
+    pub fn stop(self) -> impl Future<Item = ShutdownReport, Error = ()> {
+        let start = Instant::now();
+        let sink_deadlines = self
+            .config
+            .sinks
+            .iter()
+            .map(|(name, sink)| {
+                let timeout = Duration::from_secs(sink.shutdown_timeout_secs);
+                (name.clone(), start + timeout)
+            })
+            .collect::<HashMap<_, _>>();
         // If we reach this, we will forcefully shutdown the sources.
-        let deadline = Instant::now() + Duration::from_secs(60);
-
-        // If we reach the deadline, this future will print out which components won't
-        // gracefully shutdown since we will start to forcefully shutdown the sources.
-        let mut check_handles2 = check_handles.clone();
-        let timeout = delay_until(deadline).map(move |_| {
-            // Remove all tasks that have shutdown.
-            check_handles2.retain(|_name, handles| {
-                retain(handles, |handle| {
-                    handle.poll().map(|p| p.is_not_ready()).unwrap_or(false)
-                });
-                !handles.is_empty()
-            });
-            let remaining_components = check_handles2.keys().cloned().collect::<Vec<_>>();
+        let deadline = sink_deadlines
+            .values()
+            .max()
+            .copied()
+            .unwrap_or_else(|| start + DEFAULT_SHUTDOWN_TIMEOUT);
 
-            error!(
-                "Failed to gracefully shut down in time. Killing: {}",
-                remaining_components.join(", ")
-            );
+        let source_names = self.config.sources.keys().cloned().collect::<Vec<_>>();
+        let transform_names = self.config.transforms.keys().cloned().collect::<Vec<_>>();
+
+        let mut tasks = self.tasks;
+        let mut source_tasks = self.source_tasks;
+        let counts = self.counts;
+        let remaining = Arc::new(Mutex::new(tasks.keys().cloned().collect::<HashSet<_>>()));
 
-            Ok(())
-        });
+        // Now kick off the shutdown process by shutting down the sources.
+        let source_shutdown_complete = self.shutdown_coordinator.shutdown_all(deadline).compat();
+        let sources = future::join_all(source_names.into_iter().map(|name| {
+            let server = source_tasks.remove(&name);
+            let pump = tasks.remove(&name);
+            let remaining = Arc::clone(&remaining);
+            async move {
+                if let Some(server) = server {
+                    let _ = server.await;
+                }
+                // A forced source may never let go of its output, leaving the pump running.
+                if let Some(pump) = pump {
+                    if timeout_at(deadline, pump).await.is_ok() {
+                        remaining.lock().unwrap().remove(&name);
+                    }
+                }
+            }
+        }));
+
+        let transforms = transform_names
+            .into_iter()
+            .filter_map(|name| {
+                let task = tasks.remove(&name)?;
+                Some(drain(name, task, deadline, Arc::clone(&remaining)))
+            })
+            .collect::<Vec<_>>();
+
+        let sinks = tasks
+            .into_iter()
+            .map(|(name, task)| {
+                let deadline = sink_deadlines.get(&name).copied().unwrap_or(deadline);
+                drain(name, task, deadline, Arc::clone(&remaining))
+            })
+            .collect::<Vec<_>>();
+
+        let shutdown = async move {
+            let _ = future::join(source_shutdown_complete, sources).await;
+            let mut unfinished = future::join_all(transforms).await;
+            unfinished.extend(future::join_all(sinks).await);
+
+            let mut unfinished = unfinished.into_iter().flatten().collect::<Vec<_>>();
+            unfinished.sort();
+            if !unfinished.is_empty() {
+                error!(
+                    "Failed to gracefully shut down in time. Killing: {}",
+                    unfinished.join(", ")
+                );
+            }
+
+            let undelivered = counts
+                .into_iter()
+                .map(|(name, counts)| (name, counts.pending()))
+                .filter(|(_, pending)| *pending > 0)
+                .collect::<BTreeMap<_, _>>();
+            emit!(TopologyShutdownSummary {
+                unfinished: &unfinished,
+                undelivered: &undelivered,
+            });
+
+            ShutdownReport {
+                unfinished,
+                undelivered,
+            }
+        };
 
         // Reports in intervals which components are still running.
-        let reporter = interval(Duration::from_secs(5))
-            .inspect(move |_| {
-                // Remove all tasks that have shutdown.
-                check_handles.retain(|_name, handles| {
-                    retain(handles, |handle| {
-                        handle.poll().map(|p| p.is_not_ready()).unwrap_or(false)
-                    });
-                    !handles.is_empty()
-                });
-                let remaining_components = check_handles.keys().cloned().collect::<Vec<_>>();
+        let reporter = async move {
+            let mut interval = interval(Duration::from_secs(5));
+            loop {
+                interval.tick().await;
+                let mut remaining_components = remaining
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                remaining_components.sort();
 
                 // TODO: replace with checked_duration_since once it's stable
                 let time_remaining = if deadline > Instant::now() {
@@ -190,32 +274,17 @@ impl RunningTopology {
                     remaining_components.join(", "),
                     time_remaining
                 );
-            })
-            .filter(|_| future::ready(false)) // Run indefinitely without emitting items
-            .into_future()
-            .map(|_| Ok(()));
-
-        // Finishes once all tasks have shutdown.
-        let success = futures01::future::join_all(wait_handles)
-            .map(|_| ())
-            .map_err(|_: futures01::future::SharedError<()>| ())
-            .compat();
-
-        // Aggregate future that ends once anything detects that all tasks have shutdown.
-        let shutdown_complete_future = future::select_all(vec![
-            Box::pin(timeout) as future::BoxFuture<'static, Result<(), ()>>,
-            Box::pin(reporter) as future::BoxFuture<'static, Result<(), ()>>,
-            Box::pin(success) as future::BoxFuture<'static, Result<(), ()>>,
-        ])
-        .map(|(result, _, _)| result.map(|_| ()).map_err(|_| ()))
-        .compat();
-
-        // Now kick off the shutdown process by shutting down the sources.
-        let source_shutdown_complete = self.shutdown_coordinator.shutdown_all(deadline);
+            }
+        };
 
-        source_shutdown_complete
-            .join(shutdown_complete_future)
-            .map(|_| ())
+        async move {
+            match future::select(Box::pin(shutdown), Box::pin(reporter)).await {
+                future::Either::Left((report, _)) => Ok(report),
+                future::Either::Right(_) => unreachable!("The reporter never finishes."),
+            }
+        }
+        .boxed()
+        .compat()
     }
 
     /// On Error, topology is in invalid state.
@@ -239,10 +308,10 @@ impl RunningTopology {
         let diff = ConfigDiff::new(&self.config, &new_config);
 
         // Checks passed so let's shutdown the difference.
-        self.shutdown_diff(&diff).await;
+        let buffers = self.shutdown_diff(&diff, &new_config).await;
 
         // Now let's actually build the new pieces.
-        if let Some(mut new_pieces) = validate(&new_config, &diff).await {
+        if let Some(mut new_pieces) = validate(&new_config, &diff, buffers.clone()).await {
             if self
                 .run_healthchecks(&diff, &mut new_pieces, require_healthy)
                 .await
@@ -258,7 +327,7 @@ impl RunningTopology {
         // We need to rebuild the removed.
         info!("Rebuilding old configuration.");
         let diff = diff.flip();
-        if let Some(mut new_pieces) = validate(&self.config, &diff).await {
+        if let Some(mut new_pieces) = validate(&self.config, &diff, buffers).await {
             if self
                 .run_healthchecks(&diff, &mut new_pieces, require_healthy)
                 .await
@@ -305,7 +374,14 @@ impl RunningTopology {
     }
 
     /// Shutdowns removed and replaced pieces of topology.
-    async fn shutdown_diff(&mut self, diff: &ConfigDiff) {
+    ///
+    /// Returns the buffers of changed sinks whose buffer config is unchanged, for their
+    /// replacements to take over.
+    async fn shutdown_diff(
+        &mut self,
+        diff: &ConfigDiff,
+        new_config: &Config,
+    ) -> HashMap<String, BuiltBuffer> {
         // Sources
         let timeout = Duration::from_secs(30); //sec
 
@@ -372,6 +448,7 @@ impl RunningTopology {
             let previous = self.tasks.remove(name).unwrap();
             drop(previous); // detach and forget
 
+            self.counts.remove(name);
             self.remove_inputs(&name);
             self.remove_outputs(&name);
         }
@@ -383,8 +460,54 @@ impl RunningTopology {
             let previous = self.tasks.remove(name).unwrap();
             drop(previous); // detach and forget
 
+            self.buffers.remove(name);
+            self.detach_triggers.remove(name);
+            self.counts.remove(name);
             self.remove_inputs(&name);
         }
+
+        // Sinks keeping their buffer have it taken away from them, so that they read no further
+        // and the rebuilt sink carries on from there.
+        let mut buffers = HashMap::new();
+        let mut previous_sinks = Vec::new();
+        for name in &diff.sinks.to_change {
+            if self.config.sinks[name].buffer != new_config.sinks[name].buffer {
+                continue;
+            }
+
+            if let Some((input, rx, acker)) = self.buffers.remove(name) {
+                if let Some(rx) = rx.lock().unwrap().take() {
+                    debug!("Reusing buffer of sink {:?}", name);
+                    let rx = Arc::new(Mutex::new(Some(rx)));
+                    buffers.insert(name.clone(), (input, rx, acker));
+                }
+            }
+            if let Some(trigger) = self.detach_triggers.remove(name) {
+                let _ = trigger.send(());
+            }
+            if let Some(previous) = self.tasks.remove(name) {
+                previous_sinks.push((name, previous));
+            }
+        }
+
+        // The old sinks get to flush what they were holding before the rebuilt ones start. A
+        // sink stuck retrying against an unreachable endpoint may never flush, so it's left
+        // running detached once the deadline passes.
+        let deadline = Instant::now() + timeout;
+        let flushed = previous_sinks
+            .into_iter()
+            .map(|(name, previous)| timeout_at(deadline, previous).map(move |res| (name, res)));
+        for (name, res) in future::join_all(flushed).await {
+            if res.is_err() {
+                warn!(
+                    "Sink {:?} did not flush within {} seconds, leaving it running detached",
+                    name,
+                    timeout.as_secs()
+                );
+            }
+        }
+
+        buffers
     }
 
     /// Rewires topology
@@ -409,6 +532,10 @@ impl RunningTopology {
             self.setup_inputs(&name, new_pieces);
         }
 
+        for name in &diff.transforms.to_rewire {
+            self.rewire_inputs(&name, new_pieces);
+        }
+
         // Sinks
         for name in &diff.sinks.to_change {
             self.replace_inputs(&name, new_pieces);
@@ -443,6 +570,10 @@ impl RunningTopology {
             self.spawn_transform(&name, &mut new_pieces);
         }
 
+        for name in &diff.transforms.to_rewire {
+            info!("Rewired transform {:?}", name);
+        }
+
         // Sinks
         for name in &diff.sinks.to_change {
             info!("Rebuilding sink {:?}", name);
@@ -463,6 +594,12 @@ impl RunningTopology {
         if let Some(previous) = self.tasks.insert(name.to_string(), spawned) {
             drop(previous); // detach and forget
         }
+
+        let buffer = new_pieces.buffers.remove(name).unwrap();
+        self.buffers.insert(name.to_string(), buffer);
+        let trigger = new_pieces.detach_triggers.remove(name).unwrap();
+        self.detach_triggers.insert(name.to_string(), trigger);
+        self.take_counts(name, new_pieces);
     }
 
     fn spawn_transform(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
@@ -473,6 +610,14 @@ impl RunningTopology {
         if let Some(previous) = self.tasks.insert(name.to_string(), spawned) {
             drop(previous); // detach and forget
         }
+        self.take_counts(name, new_pieces);
+    }
+
+    fn take_counts(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
+        match new_pieces.counts.remove(name) {
+            Some(counts) => self.counts.insert(name.to_string(), counts),
+            None => self.counts.remove(name),
+        };
     }
 
     fn spawn_source(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
@@ -596,6 +741,26 @@ impl RunningTopology {
 
         self.inputs.insert(name.to_string(), tx);
     }
+
+    /// Connects an unchanged transform to its new inputs, keeping it running.
+    fn rewire_inputs(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
+        let new_inputs = new_pieces.rewired_inputs.remove(name).unwrap();
+        let old_inputs = &self.config.transforms[name].inputs;
+        let tx = &self.inputs[name];
+
+        for input in old_inputs.iter().filter(|i| !new_inputs.contains(i)) {
+            if let Some(output) = self.outputs.get(input) {
+                // This can only fail if we are disconnected, which is a valid situation.
+                let _ = output.unbounded_send(fanout::ControlMessage::Remove(name.to_string()));
+            }
+        }
+
+        for input in new_inputs.iter().filter(|i| !old_inputs.contains(i)) {
+            // This can only fail if we are disconnected, which is a valid situation.
+            let _ = self.outputs[input]
+                .unbounded_send(fanout::ControlMessage::Add(name.to_string(), tx.get()));
+        }
+    }
 }
 
 fn handle_errors(
@@ -613,15 +778,19 @@ fn handle_errors(
         })
 }
 
-/// If the closure returns false, then the element is removed
-fn retain<T>(vec: &mut Vec<T>, mut retain_filter: impl FnMut(&mut T) -> bool) {
-    let mut i = 0;
-    while let Some(data) = vec.get_mut(i) {
-        if retain_filter(data) {
-            i += 1;
-        } else {
-            let _ = vec.remove(i);
+/// Waits for a component to finish until `deadline`, returning its name if it didn't.
+async fn drain(
+    name: String,
+    task: TaskHandle,
+    deadline: Instant,
+    remaining: Arc<Mutex<HashSet<String>>>,
+) -> Option<String> {
+    match timeout_at(deadline, task).await {
+        Ok(_) => {
+            remaining.lock().unwrap().remove(&name);
+            None
         }
+        Err(_) => Some(name),
     }
 }
 
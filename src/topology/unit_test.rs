@@ -1,9 +1,15 @@
 use crate::{
     conditions::{Condition, ConditionConfig},
-    config::{Config, TestCondition, TestDefinition, TestInput, TestInputValue, TransformContext},
-    event::{Event, Value},
+    config::{
+        Config, TestCondition, TestDefinition, TestEvent, TestInput, TestInputValue, TestMetric,
+        TransformContext,
+    },
+    enrichment_tables::EnrichmentTables,
+    event::{self, metric::MetricValue, Event, LogEvent, Value},
+    sinks::util::{EncodedPayload, TestEncoder},
     transforms::Transform,
 };
+use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use std::collections::HashMap;
 
@@ -12,6 +18,8 @@ use std::collections::HashMap;
 pub struct UnitTestCheck {
     extract_from: String,
     conditions: Vec<Box<dyn Condition>>,
+    metrics: Vec<TestMetric>,
+    events: Option<Vec<TestEvent>>,
 }
 
 pub struct UnitTestTransform {
@@ -19,6 +27,42 @@ pub struct UnitTestTransform {
     next: Vec<String>,
 }
 
+/// Stands in for a sink by running its encoder, turning each payload it
+/// would have sent into a log event that conditions can be checked against.
+struct UnitTestSink {
+    encoder: Box<dyn TestEncoder>,
+}
+
+impl Transform for UnitTestSink {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        match self.encoder.encode(event) {
+            Ok(payload) => payload.map(payload_to_event),
+            Err(error) => {
+                let mut log = LogEvent::default();
+                log.insert("error", error.to_string());
+                Some(Event::Log(log))
+            }
+        }
+    }
+}
+
+/// Exposes the payload as `body`, `headers.<name>` and `partition` fields,
+/// plus `json` holding the parsed body when it is valid JSON.
+fn payload_to_event(payload: EncodedPayload) -> Event {
+    let mut log = LogEvent::default();
+    if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&payload.body) {
+        log.insert("json", Value::from(json));
+    }
+    log.insert("body", String::from_utf8_lossy(&payload.body).into_owned());
+    for (name, value) in payload.headers {
+        log.insert(format!("headers.{}", name.to_lowercase()), value);
+    }
+    if let Some(partition) = payload.partition {
+        log.insert("partition", partition);
+    }
+    Event::Log(log)
+}
+
 pub struct UnitTest {
     pub name: String,
     inputs: Vec<(Vec<String>, Event)>,
@@ -55,6 +99,107 @@ fn events_to_string(name: &str, events: &[Event]) -> String {
     }
 }
 
+fn test_value(value: &TestInputValue) -> Value {
+    match value {
+        TestInputValue::String(s) => s.as_bytes().to_vec().into(),
+        TestInputValue::Boolean(b) => (*b).into(),
+        TestInputValue::Integer(i) => (*i).into(),
+        TestInputValue::Float(f) => (*f).into(),
+    }
+}
+
+fn check_metric(expected: &TestMetric, event: &Event) -> Result<(), String> {
+    let metric = match event {
+        Event::Metric(metric) => metric,
+        Event::Log(_) => return Err("expected a metric, found a log".to_owned()),
+    };
+
+    let mut failures = Vec::new();
+    if let Some(name) = &expected.name {
+        if &metric.name != name {
+            failures.push(format!(
+                "name: expected '{}', found '{}'",
+                name, metric.name
+            ));
+        }
+    }
+    if let Some(kind) = &expected.kind {
+        if &metric.kind != kind {
+            failures.push(format!(
+                "kind: expected {:?}, found {:?}",
+                kind, metric.kind
+            ));
+        }
+    }
+    for (tag, value) in &expected.tags {
+        match metric.tags.as_ref().and_then(|tags| tags.get(tag)) {
+            Some(found) if found == value => (),
+            Some(found) => failures.push(format!(
+                "tags.{}: expected '{}', found '{}'",
+                tag, value, found
+            )),
+            None => failures.push(format!("tags.{}: missing", tag)),
+        }
+    }
+    if let Some(value) = expected.value {
+        match &metric.value {
+            MetricValue::Counter { value: found } | MetricValue::Gauge { value: found } => {
+                if (found - value).abs() > expected.tolerance {
+                    failures.push(format!(
+                        "value: expected {} (± {}), found {}",
+                        value, expected.tolerance, found
+                    ));
+                }
+            }
+            _ => failures.push("value: only counters and gauges have a single value".to_owned()),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "metric assertions failed: [ {} ]",
+            failures.join(", ")
+        ))
+    }
+}
+
+fn check_event(expected: &TestEvent, event: &Event) -> Result<(), String> {
+    if let Some(metric) = &expected.metric {
+        return check_metric(metric, event);
+    }
+
+    let log = match event {
+        Event::Log(log) => log,
+        Event::Metric(_) => return Err("expected a log, found a metric".to_owned()),
+    };
+    let failures = expected
+        .log_fields
+        .iter()
+        .flatten()
+        .filter_map(|(path, value)| {
+            let value = test_value(value);
+            match log.get(&path.as_str().into()) {
+                Some(found) if *found == value => None,
+                Some(found) => Some(format!(
+                    "{}: expected '{}', found '{}'",
+                    path,
+                    value.to_string_lossy(),
+                    found.to_string_lossy()
+                )),
+                None => Some(format!("{}: missing", path)),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("log fields failed: [ {} ]", failures.join(", ")))
+    }
+}
+
 fn walk(
     node: &str,
     mut inputs: Vec<Event>,
@@ -75,9 +220,13 @@ fn walk(
         walk(&child, results.clone(), transforms, aggregated_results);
     }
 
+    // Keep events in the order they were produced so that `events`
+    // expectations can check sequences.
     if let Some((mut e_inputs, mut e_results)) = aggregated_results.remove(node) {
-        inputs.append(&mut e_inputs);
-        results.append(&mut e_results);
+        e_inputs.append(&mut inputs);
+        e_results.append(&mut results);
+        inputs = e_inputs;
+        results = e_results;
     }
     aggregated_results.insert(node.into(), (inputs, results));
 }
@@ -102,7 +251,8 @@ impl UnitTest {
 
         for check in &self.checks {
             if let Some((inputs, outputs)) = results.get(&check.extract_from) {
-                if check.conditions.is_empty() {
+                if check.conditions.is_empty() && check.metrics.is_empty() && check.events.is_none()
+                {
                     inspections.push(format!(
                         "check transform '{}' payloads (events encoded as JSON):\n{}\n{}",
                         check.extract_from,
@@ -111,7 +261,7 @@ impl UnitTest {
                     ));
                     continue;
                 }
-                let failed_conditions = check
+                let mut failed_conditions = check
                     .conditions
                     .iter()
                     .enumerate()
@@ -137,6 +287,43 @@ impl UnitTest {
                         }
                     })
                     .collect::<Vec<_>>();
+                for (i, metric) in check.metrics.iter().enumerate() {
+                    let metric_errs = outputs
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(j, e)| {
+                            check_metric(metric, e).err().map(|err| {
+                                if outputs.len() > 1 {
+                                    format!("metrics[{}], payload[{}]: {}", i, j, err)
+                                } else {
+                                    format!("metrics[{}]: {}", i, err)
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    if metric_errs.len() == outputs.len() {
+                        failed_conditions.extend(metric_errs);
+                    }
+                }
+                if let Some(events) = &check.events {
+                    if events.len() != outputs.len() {
+                        failed_conditions.push(format!(
+                            "events: expected {} events, received {}",
+                            events.len(),
+                            outputs.len()
+                        ));
+                    } else {
+                        failed_conditions.extend(
+                            events.iter().zip(outputs.iter()).enumerate().filter_map(
+                                |(i, (expected, e))| {
+                                    check_event(expected, e)
+                                        .err()
+                                        .map(|err| format!("events[{}]: {}", i, err))
+                                },
+                            ),
+                        );
+                    }
+                }
                 if !failed_conditions.is_empty() {
                     errors.push(format!(
                         "check transform '{}' failed conditions:\n  {}\npayloads (events encoded as JSON):\n{}\n{}",
@@ -146,7 +333,7 @@ impl UnitTest {
                         events_to_string("output", outputs),
                     ));
                 }
-                if outputs.is_empty() {
+                if outputs.is_empty() && check.events.is_none() {
                     errors.push(format!(
                         "check transform '{}' failed, no events received.",
                         check.extract_from,
@@ -233,6 +420,19 @@ fn reduce_transforms(
     });
 }
 
+/// Only the inputs are frozen. Templates and transforms still use the current time for the
+/// events that transforms create without a timestamp.
+fn freeze_timestamp(event: &mut Event, timestamp: DateTime<Utc>) {
+    match event {
+        Event::Log(log) => {
+            log.insert(event::log_schema().timestamp_key().clone(), timestamp);
+        }
+        Event::Metric(metric) => {
+            metric.timestamp = Some(timestamp);
+        }
+    }
+}
+
 fn build_input(config: &Config, input: &TestInput) -> Result<(Vec<String>, Event), String> {
     let target = config.get_inputs(&input.insert_at);
 
@@ -245,13 +445,9 @@ fn build_input(config: &Config, input: &TestInput) -> Result<(Vec<String>, Event
             if let Some(log_fields) = &input.log_fields {
                 let mut event = Event::from("");
                 for (path, value) in log_fields {
-                    let value: Value = match value {
-                        TestInputValue::String(s) => s.as_bytes().to_vec().into(),
-                        TestInputValue::Boolean(b) => (*b).into(),
-                        TestInputValue::Integer(i) => (*i).into(),
-                        TestInputValue::Float(f) => (*f).into(),
-                    };
-                    event.as_mut_log().insert(path.to_owned(), value);
+                    event
+                        .as_mut_log()
+                        .insert(path.to_owned(), test_value(value));
                 }
                 Ok((target, event))
             } else {
@@ -294,6 +490,15 @@ fn build_inputs(
         }
     }
 
+    if let Some(timestamp) = &definition.frozen_timestamp {
+        match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(timestamp) => inputs
+                .iter_mut()
+                .for_each(|(_, event)| freeze_timestamp(event, timestamp.with_timezone(&Utc))),
+            Err(err) => errors.push(format!("invalid frozen_timestamp '{}': {}", timestamp, err)),
+        }
+    }
+
     if errors.is_empty() {
         Ok(inputs)
     } else {
@@ -304,6 +509,7 @@ fn build_inputs(
 fn build_unit_test(
     definition: &TestDefinition,
     config: &super::Config,
+    enrichment_tables: &EnrichmentTables,
 ) -> Result<UnitTest, Vec<String>> {
     let mut errors = vec![];
 
@@ -331,6 +537,17 @@ fn build_unit_test(
         })
     });
 
+    // Sinks can also be output targets, in which case their encoders are run
+    // in place of a transform. They never have outputs of their own.
+    config.sinks.iter().for_each(|(k, s)| {
+        s.inputs.iter().for_each(|i| {
+            if let Some(outputs) = transform_outputs.get_mut(i) {
+                outputs.insert(k.to_string(), ());
+            }
+        });
+        transform_outputs.entry(k.clone()).or_default();
+    });
+
     for (i, (input_target, _)) in inputs.iter().enumerate() {
         for target in input_target {
             if !transform_outputs.contains_key(target) {
@@ -370,7 +587,8 @@ fn build_unit_test(
     let mut transforms: IndexMap<String, UnitTestTransform> = IndexMap::new();
     for (name, transform_config) in &config.transforms {
         if let Some(outputs) = transform_outputs.remove(name) {
-            match transform_config.inner.build(TransformContext::new_test()) {
+            let cx = TransformContext::new_test().with_enrichment_tables(enrichment_tables.clone());
+            match transform_config.inner.build(cx) {
                 Ok(transform) => {
                     transforms.insert(
                         name.clone(),
@@ -387,6 +605,33 @@ fn build_unit_test(
         }
     }
 
+    // Build the encoders of any sinks left in the reduced topology.
+    for (name, sink) in &config.sinks {
+        if transform_outputs.remove(name).is_some() {
+            match sink.inner.build_test_encoder() {
+                Ok(Some(encoder)) => {
+                    transforms.insert(
+                        name.clone(),
+                        UnitTestTransform {
+                            transform: Box::new(UnitTestSink { encoder }),
+                            next: Vec::new(),
+                        },
+                    );
+                }
+                Ok(None) => {
+                    errors.push(format!(
+                        "sink '{}' of type '{}' does not support unit tests",
+                        name,
+                        sink.inner.sink_type()
+                    ));
+                }
+                Err(err) => {
+                    errors.push(format!("failed to build sink '{}': {}", name, err));
+                }
+            }
+        }
+    }
+
     if !errors.is_empty() {
         return Err(errors);
     }
@@ -452,9 +697,19 @@ fn build_unit_test(
                     }
                 }
             }
+            for (index, event) in o.events.iter().flatten().enumerate() {
+                if event.log_fields.is_some() == event.metric.is_some() {
+                    errors.push(format!(
+                        "events[{}]: expected exactly one of `log_fields` or `metric`",
+                        index
+                    ));
+                }
+            }
             UnitTestCheck {
                 extract_from: o.extract_from.clone(),
                 conditions,
+                metrics: o.metrics.clone(),
+                events: o.events.clone(),
             }
         })
         .collect();
@@ -482,10 +737,10 @@ pub fn build_unit_tests(config: &mut super::Config) -> Result<Vec<UnitTest>, Vec
     let mut tests = vec![];
     let mut errors = vec![];
 
-    config
-        .tests
-        .iter()
-        .for_each(|test| match build_unit_test(test, config) {
+    let enrichment_tables = EnrichmentTables::load(&config.enrichment_tables)?;
+
+    config.tests.iter().for_each(
+        |test| match build_unit_test(test, config, &enrichment_tables) {
             Ok(t) => tests.push(t),
             Err(errs) => {
                 let mut test_err = errs.join("\n");
@@ -494,7 +749,8 @@ pub fn build_unit_tests(config: &mut super::Config) -> Result<Vec<UnitTest>, Vec
                 test_err.insert_str(0, &format!("Failed to build test '{}':\n  ", test.name));
                 errors.push(test_err);
             }
-        });
+        },
+    );
 
     if errors.is_empty() {
         Ok(tests)
@@ -988,6 +1244,47 @@ mod tests {
         assert_eq!(tests[0].run().1, Vec::<String>::new());
     }
 
+    #[cfg(feature = "remap")]
+    #[test]
+    fn test_expression_conditions() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "swimlanes"
+  [transforms.foo.lanes.errors]
+    type = "expression"
+    source = '.status >= 500 || exists(.error)'
+
+[transforms.bar]
+  inputs = ["foo.errors"]
+  type = "add_fields"
+  [transforms.bar.fields]
+    alert = true
+
+[[tests]]
+  name = "successful expression test"
+
+  [tests.input]
+    insert_at = "foo"
+    type = "log"
+    [tests.input.log_fields]
+      status = 503
+      message = "oh no"
+
+  [[tests.outputs]]
+    extract_from = "bar"
+    [[tests.outputs.conditions]]
+      type = "expression"
+      source = '.status == 503 && .alert && (.message == "oh no" || .message == "nope")'
+      "#,
+        )
+        .unwrap();
+
+        let mut tests = build_unit_tests(&mut config).unwrap();
+        assert_eq!(tests[0].run().1, Vec::<String>::new());
+    }
+
     #[test]
     fn test_fail_no_outputs() {
         let mut config = config::load_from_str(
@@ -1242,6 +1539,247 @@ mod tests {
         assert_eq!(tests[0].run().1, Vec::<String>::new());
     }
 
+    #[test]
+    fn test_metric_assertions() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_tags"
+  [transforms.foo.tags]
+    new_tag = "new value added"
+
+[[tests]]
+  name = "passing metric assertions"
+
+  [tests.input]
+    insert_at = "foo"
+    type = "metric"
+    [tests.input.metric]
+      kind = "incremental"
+      name = "foometric"
+      [tests.input.metric.counter]
+        value = 100.0
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.metrics]]
+      name = "foometric"
+      kind = "incremental"
+      tags = { new_tag = "new value added" }
+      value = 99.5
+      tolerance = 0.5
+
+[[tests]]
+  name = "failing metric assertions"
+
+  [tests.input]
+    insert_at = "foo"
+    type = "metric"
+    [tests.input.metric]
+      kind = "incremental"
+      name = "foometric"
+      [tests.input.metric.counter]
+        value = 100.0
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.metrics]]
+      kind = "absolute"
+      tags = { new_tag = "other value", missing = "value" }
+      value = 99.0
+      "#,
+        )
+        .unwrap();
+
+        let mut tests = build_unit_tests(&mut config).unwrap();
+        assert_eq!(tests[0].run().1, Vec::<String>::new());
+
+        let errors = tests[1].run().1;
+        assert_eq!(errors.len(), 1);
+        for failure in &[
+            "metrics[0]: metric assertions failed: [ kind: expected Absolute, found Incremental",
+            "tags.new_tag: expected 'other value', found 'new value added'",
+            "tags.missing: missing",
+            "value: expected 99 (± 0), found 100 ]",
+        ] {
+            assert!(errors[0].contains(failure), "{}", errors[0]);
+        }
+    }
+
+    #[test]
+    fn test_events_sequence() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_fields"
+  [transforms.foo.fields]
+    new_field = "string value"
+
+[[tests]]
+  name = "ordered events"
+
+  [[tests.inputs]]
+    insert_at = "foo"
+    value = "first"
+
+  [[tests.inputs]]
+    insert_at = "foo"
+    value = "second"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { message = "first", new_field = "string value" }
+    [[tests.outputs.events]]
+      log_fields = { message = "second" }
+
+[[tests]]
+  name = "misordered events"
+
+  [[tests.inputs]]
+    insert_at = "foo"
+    value = "first"
+
+  [[tests.inputs]]
+    insert_at = "foo"
+    value = "second"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { message = "second" }
+    [[tests.outputs.events]]
+      log_fields = { message = "first" }
+
+[[tests]]
+  name = "too few events"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "first"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { message = "first" }
+    [[tests.outputs.events]]
+      log_fields = { message = "second" }
+      "#,
+        )
+        .unwrap();
+
+        let mut tests = build_unit_tests(&mut config).unwrap();
+        assert_eq!(tests[0].run().1, Vec::<String>::new());
+
+        let errors = tests[1].run().1;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains(
+            "events[0]: log fields failed: [ message: expected 'second', found 'first' ]"
+        ));
+        assert!(errors[0].contains(
+            "events[1]: log fields failed: [ message: expected 'first', found 'second' ]"
+        ));
+
+        let errors = tests[2].run().1;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("events: expected 2 events, received 1"));
+    }
+
+    #[test]
+    fn test_frozen_timestamp() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_fields"
+  [transforms.foo.fields]
+    date = "{{ message }}-%Y-%m-%d"
+
+[[tests]]
+  name = "frozen timestamp"
+  frozen_timestamp = "2020-02-03T04:05:06Z"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "day"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { date = "day-2020-02-03" }
+      "#,
+        )
+        .unwrap();
+
+        let mut tests = build_unit_tests(&mut config).unwrap();
+        assert_eq!(tests[0].run().1, Vec::<String>::new());
+    }
+
+    #[test]
+    fn parse_invalid_expectations() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_fields"
+  [transforms.foo.fields]
+    new_field = "string value"
+
+[[tests]]
+  name = "broken test"
+  frozen_timestamp = "yesterday"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "first"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { message = "first" }
+      "#,
+        )
+        .unwrap();
+
+        let errs = build_unit_tests(&mut config).err().unwrap();
+        assert_eq!(errs.len(), 1);
+        assert!(errs[0].contains("invalid frozen_timestamp 'yesterday'"));
+
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_fields"
+  [transforms.foo.fields]
+    new_field = "string value"
+
+[[tests]]
+  name = "broken test"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "first"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { message = "first" }
+      metric = { name = "foo" }
+      "#,
+        )
+        .unwrap();
+
+        let errs = build_unit_tests(&mut config).err().unwrap();
+        assert_eq!(
+            errs,
+            vec![r#"Failed to build test 'broken test':
+  events[0]: expected exactly one of `log_fields` or `metric`"#
+                .to_owned(),]
+        );
+    }
+
     #[test]
     fn test_success_over_gap() {
         let mut config = config::load_from_str(
@@ -1437,4 +1975,147 @@ mod tests {
                     ]);
                 */
     }
+
+    #[cfg(feature = "sinks-http")]
+    #[test]
+    fn test_http_sink_payloads() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_fields"
+  [transforms.foo.fields]
+    new_field = "string value"
+
+[sinks.bar]
+  inputs = ["foo"]
+  type = "http"
+  uri = "http://localhost:8080/logs"
+  encoding.codec = "ndjson"
+  encoding.only_fields = ["message"]
+  headers.X-Source = "vector"
+
+[[tests]]
+  name = "http payload"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "hello"
+
+  [[tests.outputs]]
+    extract_from = "bar"
+    [[tests.outputs.events]]
+      log_fields = { body = "{\"message\":\"hello\"}\n", "json.message" = "hello", "headers.content-type" = "application/x-ndjson", "headers.x-source" = "vector" }
+
+[[tests]]
+  name = "missing field"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "hello"
+
+  [[tests.outputs]]
+    extract_from = "bar"
+    [[tests.outputs.conditions]]
+      type = "check_fields"
+      "json.new_field.exists" = true
+      "#,
+        )
+        .unwrap();
+
+        let mut tests = build_unit_tests(&mut config).unwrap();
+        assert_eq!(tests[0].run().1, Vec::<String>::new());
+
+        let errors = tests[1].run().1;
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("check transform 'bar' failed conditions"));
+    }
+
+    #[cfg(feature = "sinks-file")]
+    #[test]
+    fn test_file_sink_partitions() {
+        let mut config = config::load_from_str(
+            r#"
+[sinks.foo]
+  inputs = ["ignored"]
+  type = "file"
+  path = "/tmp/{{ app }}/%Y-%m-%d.log"
+  encoding.codec = "text"
+
+[[tests]]
+  name = "file partition"
+  frozen_timestamp = "2020-02-03T04:05:06Z"
+
+  [tests.input]
+    insert_at = "foo"
+    type = "log"
+    [tests.input.log_fields]
+      app = "web"
+      message = "hello"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.events]]
+      log_fields = { partition = "/tmp/web/2020-02-03.log", body = "hello" }
+
+[[tests]]
+  name = "missing partition key"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "hello"
+
+  [[tests.outputs]]
+    extract_from = "foo"
+    [[tests.outputs.conditions]]
+      type = "check_fields"
+      "error.exists" = true
+      "#,
+        )
+        .unwrap();
+
+        let mut tests = build_unit_tests(&mut config).unwrap();
+        assert_eq!(tests[0].run().1, Vec::<String>::new());
+        assert_eq!(tests[1].run().1, Vec::<String>::new());
+    }
+
+    #[cfg(feature = "sinks-blackhole")]
+    #[test]
+    fn parse_unsupported_sink() {
+        let mut config = config::load_from_str(
+            r#"
+[transforms.foo]
+  inputs = ["ignored"]
+  type = "add_fields"
+  [transforms.foo.fields]
+    new_field = "string value"
+
+[sinks.bar]
+  inputs = ["foo"]
+  type = "blackhole"
+
+[[tests]]
+  name = "blackhole"
+
+  [tests.input]
+    insert_at = "foo"
+    value = "hello"
+
+  [[tests.outputs]]
+    extract_from = "bar"
+    [[tests.outputs.conditions]]
+      type = "check_fields"
+      "message.exists" = true
+      "#,
+        )
+        .unwrap();
+
+        let errs = build_unit_tests(&mut config).err().unwrap();
+        assert_eq!(
+            errs,
+            vec![r#"Failed to build test 'blackhole':
+  sink 'bar' of type 'blackhole' does not support unit tests"#
+                .to_owned(),]
+        );
+    }
 }
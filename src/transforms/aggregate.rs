@@ -0,0 +1,328 @@
+use super::Transform;
+use crate::{
+    config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    event::metric::{Metric, MetricKind, MetricValue},
+    event::Event,
+    internal_events::{AggregateEventRecorded, AggregateFlushed},
+};
+use async_stream::stream;
+use futures::{
+    compat::{Compat, Compat01As03},
+    stream, StreamExt,
+};
+use futures01::Stream as Stream01;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    mem::{discriminant, Discriminant},
+    time::Duration,
+};
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct AggregateConfig {
+    /// The interval between flushes, in milliseconds.
+    pub interval_ms: Option<u64>,
+}
+
+inventory::submit! {
+    TransformDescription::new::<AggregateConfig>("aggregate")
+}
+
+#[typetag::serde(name = "aggregate")]
+impl TransformConfig for AggregateConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(Aggregate::new(self)?))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "aggregate"
+    }
+}
+
+/// Identifies a series: metrics are only combined with others of the same
+/// name, tags, kind and value type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SeriesKey {
+    name: String,
+    tags: Option<BTreeMap<String, String>>,
+    kind: MetricKind,
+    value: Discriminant<MetricValue>,
+}
+
+impl SeriesKey {
+    fn new(metric: &Metric) -> Self {
+        Self {
+            name: metric.name.clone(),
+            tags: metric.tags.clone(),
+            kind: metric.kind.clone(),
+            value: discriminant(&metric.value),
+        }
+    }
+}
+
+pub struct Aggregate {
+    interval: Duration,
+    series: HashMap<SeriesKey, Metric>,
+}
+
+impl Aggregate {
+    pub fn new(config: &AggregateConfig) -> crate::Result<Self> {
+        let interval_ms = config.interval_ms.unwrap_or(10 * 1000);
+        if interval_ms == 0 {
+            return Err("`interval_ms` must be greater than zero".into());
+        }
+
+        Ok(Self {
+            interval: Duration::from_millis(interval_ms),
+            series: HashMap::new(),
+        })
+    }
+
+    fn record(&mut self, event: Event) {
+        emit!(AggregateEventRecorded);
+
+        // Only log events carry finalizers, so none are lost by combining
+        // metrics.
+        let metric = event.into_metric();
+        match self.series.entry(SeriesKey::new(&metric)) {
+            Entry::Vacant(entry) => {
+                entry.insert(metric);
+            }
+            // Absolute metrics already carry the full value, so the latest
+            // one replaces whatever was held for the series.
+            Entry::Occupied(mut entry) if metric.kind.is_absolute() => {
+                entry.insert(metric);
+            }
+            // Incremental counters and gauges are summed while sets and
+            // distributions are merged.
+            Entry::Occupied(mut entry) => {
+                let existing = entry.get_mut();
+                existing.add(&metric);
+                existing.timestamp = metric.timestamp.or(existing.timestamp);
+            }
+        }
+    }
+
+    fn flush_into(&mut self, output: &mut Vec<Event>) {
+        if self.series.is_empty() {
+            return;
+        }
+
+        emit!(AggregateFlushed {
+            series: self.series.len()
+        });
+        output.extend(self.series.drain().map(|(_, metric)| Event::Metric(metric)));
+    }
+}
+
+impl Transform for Aggregate {
+    // Only used in tests
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        self.record(event);
+        None
+    }
+
+    fn transform_stream(
+        self: Box<Self>,
+        input_rx: Box<dyn Stream01<Item = Event, Error = ()> + Send>,
+    ) -> Box<dyn Stream01<Item = Event, Error = ()> + Send>
+    where
+        Self: 'static,
+    {
+        let mut me = self;
+
+        let mut flush_stream = tokio::time::interval(me.interval);
+        let mut input_stream = Compat01As03::new(input_rx);
+
+        let stream = stream! {
+          loop {
+            let mut output = Vec::new();
+            let done = tokio::select! {
+                _ = flush_stream.next() => {
+                  me.flush_into(&mut output);
+                  false
+                }
+                maybe_event = input_stream.next() => {
+                  match maybe_event {
+                    None => {
+                      me.flush_into(&mut output);
+                      true
+                    }
+                    Some(Ok(event)) => {
+                      me.record(event);
+                      false
+                    }
+                    Some(Err(())) => panic!("Unexpected error reading channel"),
+                  }
+                }
+            };
+            yield stream::iter(output.into_iter());
+            if done { break }
+          }
+        }
+        .flatten();
+
+        // Needed for compat
+        let try_stream = Box::pin(stream.map::<Result<Event, ()>, _>(Ok));
+
+        Box::new(Compat::new(try_stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::metric::StatisticKind;
+    use std::collections::BTreeSet;
+
+    fn aggregate() -> Aggregate {
+        Aggregate::new(&AggregateConfig::default()).unwrap()
+    }
+
+    fn metric(name: &str, kind: MetricKind, value: MetricValue) -> Event {
+        Event::Metric(Metric {
+            name: name.into(),
+            timestamp: None,
+            tags: None,
+            kind,
+            value,
+        })
+    }
+
+    fn counter(name: &str, value: f64) -> Event {
+        metric(
+            name,
+            MetricKind::Incremental,
+            MetricValue::Counter { value },
+        )
+    }
+
+    fn flush(aggregate: &mut Aggregate) -> Vec<Metric> {
+        let mut output = Vec::new();
+        aggregate.flush_into(&mut output);
+        let mut metrics = output
+            .into_iter()
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+        metrics.sort_by(|a, b| a.name.cmp(&b.name));
+        metrics
+    }
+
+    #[test]
+    fn sums_counters() {
+        let mut aggregate = aggregate();
+        assert!(aggregate.transform(counter("requests", 1.0)).is_none());
+        aggregate.transform(counter("requests", 2.0));
+        aggregate.transform(counter("errors", 1.0));
+
+        let metrics = flush(&mut aggregate);
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].value, MetricValue::Counter { value: 1.0 });
+        assert_eq!(metrics[1].value, MetricValue::Counter { value: 3.0 });
+
+        assert!(flush(&mut aggregate).is_empty());
+    }
+
+    #[test]
+    fn keeps_last_absolute_gauge() {
+        let mut aggregate = aggregate();
+        for value in &[3.0, 1.0, 2.0] {
+            aggregate.transform(metric(
+                "memory",
+                MetricKind::Absolute,
+                MetricValue::Gauge { value: *value },
+            ));
+        }
+
+        let metrics = flush(&mut aggregate);
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].value, MetricValue::Gauge { value: 2.0 });
+    }
+
+    #[test]
+    fn merges_sets_and_distributions() {
+        let mut aggregate = aggregate();
+        for user in &["a", "b", "a"] {
+            aggregate.transform(metric(
+                "users",
+                MetricKind::Incremental,
+                MetricValue::Set {
+                    values: vec![user.to_string()].into_iter().collect(),
+                },
+            ));
+        }
+        for value in &[1.0, 2.0] {
+            aggregate.transform(metric(
+                "latency",
+                MetricKind::Incremental,
+                MetricValue::Distribution {
+                    values: vec![*value],
+                    sample_rates: vec![1],
+                    statistic: StatisticKind::Histogram,
+                },
+            ));
+        }
+
+        let metrics = flush(&mut aggregate);
+        assert_eq!(
+            metrics[0].value,
+            MetricValue::Distribution {
+                values: vec![1.0, 2.0],
+                sample_rates: vec![1, 1],
+                statistic: StatisticKind::Histogram,
+            }
+        );
+        assert_eq!(
+            metrics[1].value,
+            MetricValue::Set {
+                values: vec!["a".to_owned(), "b".to_owned()]
+                    .into_iter()
+                    .collect::<BTreeSet<_>>(),
+            }
+        );
+    }
+
+    #[test]
+    fn separates_series() {
+        let mut aggregate = aggregate();
+        aggregate.transform(counter("requests", 1.0));
+
+        let mut tagged = counter("requests", 2.0);
+        tagged.as_mut_metric().tags = Some(
+            vec![("host".to_owned(), "a".to_owned())]
+                .into_iter()
+                .collect(),
+        );
+        aggregate.transform(tagged);
+
+        aggregate.transform(metric(
+            "requests",
+            MetricKind::Absolute,
+            MetricValue::Counter { value: 10.0 },
+        ));
+        aggregate.transform(metric(
+            "requests",
+            MetricKind::Incremental,
+            MetricValue::Gauge { value: 5.0 },
+        ));
+
+        assert_eq!(flush(&mut aggregate).len(), 4);
+    }
+
+    #[test]
+    fn rejects_zero_interval() {
+        assert!(Aggregate::new(&AggregateConfig {
+            interval_ms: Some(0)
+        })
+        .is_err());
+    }
+}
@@ -2,14 +2,30 @@ use super::Transform;
 
 use crate::{
     config::{DataType, TransformConfig, TransformContext},
-    event::Event,
+    event::{Event, Value},
+    internal_events::{GeoipDatabaseReloadFailed, GeoipDatabaseReloaded},
 };
+use maxminddb::geoip2;
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
 use string_cache::DefaultAtom as Atom;
 
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
 use tracing::field;
 
+/// How long the database has to be left alone before it's reloaded, as it's usually replaced
+/// by an updater writing a new file.
+const RELOAD_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct GeoipConfig {
@@ -17,27 +33,87 @@ pub struct GeoipConfig {
     pub database: String,
     #[serde(default = "default_geoip_target_field")]
     pub target: String,
+    /// The fields to add. Defaults to those of the database type.
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+    /// The locale of names, falling back to English for names missing in that locale.
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    #[serde(default = "crate::serde::default_true")]
+    pub reload: bool,
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Unable to open database {:?}: {}", path, source))]
+    OpenDatabase {
+        path: String,
+        source: maxminddb::MaxMindDBError,
+    },
+    #[snafu(display("Unable to read database {:?}: {}", path, source))]
+    ReadDatabase {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Unsupported database type {:?}", database_type))]
+    UnsupportedDatabase { database_type: String },
+    #[snafu(display("Field {:?} is not available in {} databases", field, database))]
+    UnknownField {
+        field: String,
+        database: &'static str,
+    },
+    #[snafu(display("Could not watch {:?}: {}", path, source))]
+    InvalidWatch {
+        path: PathBuf,
+        source: notify::Error,
+    },
 }
 
 pub struct Geoip {
-    pub dbreader: maxminddb::Reader<Vec<u8>>,
-    pub source: Atom,
-    pub target: String,
+    dbreader: maxminddb::Reader<Vec<u8>>,
+    database: Database,
+    source: Atom,
+    target: String,
+    fields: Vec<String>,
+    locale: String,
+    watch: Option<Watch>,
 }
 
 fn default_geoip_target_field() -> String {
     "geoip".to_string()
 }
 
+fn default_locale() -> String {
+    "en".to_string()
+}
+
 #[typetag::serde(name = "geoip")]
 impl TransformConfig for GeoipConfig {
     fn build(&self, _cx: TransformContext) -> Result<Box<dyn Transform>, crate::Error> {
-        let reader = maxminddb::Reader::open_readfile(self.database.clone())?;
-        Ok(Box::new(Geoip::new(
-            reader,
-            self.source.clone(),
-            self.target.clone(),
-        )))
+        let reader = open(&self.database)?;
+        let database = Database::detect(&reader)?;
+        let fields = match &self.fields {
+            Some(fields) => {
+                database.check_fields(fields)?;
+                fields.clone()
+            }
+            None => database.default_fields(),
+        };
+        let watch = if self.reload {
+            Some(Watch::new(&self.database)?)
+        } else {
+            None
+        };
+
+        Ok(Box::new(Geoip {
+            dbreader: reader,
+            database,
+            source: self.source.clone(),
+            target: self.target.clone(),
+            fields,
+            locale: self.locale.clone(),
+            watch,
+        }))
     }
 
     fn input_type(&self) -> DataType {
@@ -53,124 +129,371 @@ impl TransformConfig for GeoipConfig {
     }
 }
 
-// MaxMind GeoIP database files have a type field we can use to recognize specific
-// products. If we encounter one of these two types, we look for ASN/ISP information;
-// otherwise we expect to be working with a City database.
-const ASN_DATABASE_TYPE: &str = "GeoLite2-ASN";
-const ISP_DATABASE_TYPE: &str = "GeoIP2-ISP";
+fn open(path: &str) -> Result<maxminddb::Reader<Vec<u8>>, BuildError> {
+    maxminddb::Reader::open_readfile(path).context(OpenDatabase { path })
+}
+
+/// The MaxMind database products, recognized by the type in their metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Database {
+    City,
+    Country,
+    Asn,
+    Isp,
+    ConnectionType,
+    AnonymousIp,
+}
 
-impl Geoip {
-    pub fn new(dbreader: maxminddb::Reader<Vec<u8>>, source: Atom, target: String) -> Self {
-        Geoip {
-            dbreader,
-            source,
-            target,
+const CITY_FIELDS: &[&str] = &[
+    "city_name",
+    "continent_code",
+    "continent_name",
+    "country_code",
+    "country_name",
+    "region_code",
+    "region_name",
+    "timezone",
+    "latitude",
+    "longitude",
+    "metro_code",
+    "postal_code",
+];
+const COUNTRY_FIELDS: &[&str] = &[
+    "continent_code",
+    "continent_name",
+    "country_code",
+    "country_name",
+];
+const ISP_FIELDS: &[&str] = &[
+    "autonomous_system_number",
+    "autonomous_system_organization",
+    "isp",
+    "organization",
+];
+const CONNECTION_TYPE_FIELDS: &[&str] = &["connection_type"];
+const ANONYMOUS_IP_FIELDS: &[&str] = &[
+    "is_anonymous",
+    "is_anonymous_vpn",
+    "is_hosting_provider",
+    "is_public_proxy",
+    "is_tor_exit_node",
+];
+
+impl Database {
+    fn detect(reader: &maxminddb::Reader<Vec<u8>>) -> Result<Self, BuildError> {
+        let database_type = reader.metadata.database_type.as_str();
+        let database = if database_type.contains("City") || database_type.contains("Enterprise") {
+            Database::City
+        } else if database_type.ends_with("-Country") {
+            Database::Country
+        } else if database_type.ends_with("-ASN") {
+            Database::Asn
+        } else if database_type.ends_with("-ISP") {
+            Database::Isp
+        } else if database_type.ends_with("-Connection-Type") {
+            Database::ConnectionType
+        } else if database_type.ends_with("-Anonymous-IP") {
+            Database::AnonymousIp
+        } else {
+            return Err(BuildError::UnsupportedDatabase {
+                database_type: database_type.to_owned(),
+            });
+        };
+        Ok(database)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Database::City => "City",
+            Database::Country => "Country",
+            Database::Asn => "ASN",
+            Database::Isp => "ISP",
+            Database::ConnectionType => "Connection-Type",
+            Database::AnonymousIp => "Anonymous-IP",
         }
     }
 
-    fn has_isp_db(&self) -> bool {
-        self.dbreader.metadata.database_type == ASN_DATABASE_TYPE
-            || self.dbreader.metadata.database_type == ISP_DATABASE_TYPE
+    /// ASN databases share the lookup of ISP databases, and so their fields, though only the
+    /// autonomous system ones are ever set.
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            Database::City => CITY_FIELDS,
+            Database::Country => COUNTRY_FIELDS,
+            Database::Asn | Database::Isp => ISP_FIELDS,
+            Database::ConnectionType => CONNECTION_TYPE_FIELDS,
+            Database::AnonymousIp => ANONYMOUS_IP_FIELDS,
+        }
+    }
+
+    /// City databases add the fields they always did, the others all of theirs.
+    fn default_fields(self) -> Vec<String> {
+        let fields: &[&str] = match self {
+            Database::City => &[
+                "city_name",
+                "continent_code",
+                "country_code",
+                "timezone",
+                "latitude",
+                "longitude",
+                "postal_code",
+            ],
+            database => database.fields(),
+        };
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    fn check_fields(self, fields: &[String]) -> Result<(), BuildError> {
+        match fields
+            .iter()
+            .find(|field| !self.fields().contains(&field.as_str()))
+        {
+            Some(field) => Err(BuildError::UnknownField {
+                field: field.clone(),
+                database: self.name(),
+            }),
+            None => Ok(()),
+        }
     }
 }
 
-#[derive(Default, Serialize)]
-struct ISP<'a> {
-    autonomous_system_number: i64,
-    autonomous_system_organization: &'a str,
-    isp: &'a str,
-    organization: &'a str,
+/// The value of a field the database has no data for.
+fn missing_value(field: &str) -> Value {
+    match field {
+        "autonomous_system_number" => Value::Integer(0),
+        "latitude" | "longitude" | "metro_code" => Value::Null,
+        field if field.starts_with("is_") => Value::Boolean(false),
+        _ => Value::from(""),
+    }
 }
 
-#[derive(Default, Serialize)]
-struct City<'a> {
-    city_name: &'a str,
-    continent_code: &'a str,
-    country_code: &'a str,
-    timezone: &'a str,
-    latitude: String,  // converted from f64 as per original design
-    longitude: String, // converted from f64 as per original design
-    postal_code: &'a str,
+#[derive(Deserialize)]
+struct ConnectionType {
+    connection_type: Option<String>,
 }
 
-impl Transform for Geoip {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
-        let mut isp: ISP = Default::default();
-        let mut city: City = Default::default();
-        let target_field = self.target.clone();
-        let ipaddress = event
-            .as_log()
-            .get(&self.source)
-            .map(|s| s.to_string_lossy());
-        if let Some(ipaddress) = &ipaddress {
-            if let Ok(ip) = FromStr::from_str(ipaddress) {
-                if self.has_isp_db() {
-                    if let Ok(data) = self.dbreader.lookup::<maxminddb::geoip2::Isp>(ip) {
-                        if let Some(as_number) = data.autonomous_system_number {
-                            isp.autonomous_system_number = as_number as i64;
-                        }
-                        if let Some(as_organization) = data.autonomous_system_organization {
-                            isp.autonomous_system_organization = as_organization;
-                        }
-                        if let Some(isp_name) = data.isp {
-                            isp.isp = isp_name;
-                        }
-                        if let Some(organization) = data.organization {
-                            isp.organization = organization;
-                        }
+#[derive(Deserialize)]
+struct AnonymousIp {
+    is_anonymous: Option<bool>,
+    is_anonymous_vpn: Option<bool>,
+    is_hosting_provider: Option<bool>,
+    is_public_proxy: Option<bool>,
+    is_tor_exit_node: Option<bool>,
+}
+
+/// The name in `locale`, or in English if there's none in that locale.
+fn localized(names: Option<BTreeMap<&str, &str>>, locale: &str) -> Option<Value> {
+    let names = names?;
+    names
+        .get(locale)
+        .or_else(|| names.get("en"))
+        .map(|name| Value::from(*name))
+}
+
+impl Geoip {
+    /// All the fields the database has data for.
+    fn lookup(&self, ip: IpAddr) -> HashMap<&'static str, Value> {
+        let mut found = HashMap::new();
+        let mut insert = |field, value: Option<Value>| {
+            if let Some(value) = value {
+                found.insert(field, value);
+            }
+        };
+        let locale = self.locale.as_str();
+
+        match self.database {
+            Database::City => {
+                if let Ok(data) = self.dbreader.lookup::<geoip2::City>(ip) {
+                    let city = data.city;
+                    insert("city_name", localized(city.and_then(|c| c.names), locale));
+                    if let Some(continent) = data.continent {
+                        insert("continent_code", continent.code.map(Value::from));
+                        insert("continent_name", localized(continent.names, locale));
                     }
-                } else if let Ok(data) = self.dbreader.lookup::<maxminddb::geoip2::City>(ip) {
-                    if let Some(city_names) = data.city.and_then(|c| c.names) {
-                        if let Some(city_name) = city_names.get("en") {
-                            city.city_name = city_name;
-                        }
+                    if let Some(country) = data.country {
+                        insert("country_code", country.iso_code.map(Value::from));
+                        insert("country_name", localized(country.names, locale));
                     }
-
-                    if let Some(continent_code) = data.continent.and_then(|c| c.code) {
-                        city.continent_code = continent_code;
+                    if let Some(region) = data.subdivisions.and_then(|s| s.into_iter().next()) {
+                        insert("region_code", region.iso_code.map(Value::from));
+                        insert("region_name", localized(region.names, locale));
+                    }
+                    if let Some(location) = data.location {
+                        insert("timezone", location.time_zone.map(Value::from));
+                        insert("latitude", location.latitude.map(Value::from));
+                        insert("longitude", location.longitude.map(Value::from));
+                        insert(
+                            "metro_code",
+                            location.metro_code.map(|code| Value::Integer(code.into())),
+                        );
+                    }
+                    insert(
+                        "postal_code",
+                        data.postal.and_then(|p| p.code).map(Value::from),
+                    );
+                }
+            }
+            Database::Country => {
+                if let Ok(data) = self.dbreader.lookup::<geoip2::Country>(ip) {
+                    if let Some(continent) = data.continent {
+                        insert("continent_code", continent.code.map(Value::from));
+                        insert("continent_name", localized(continent.names, locale));
+                    }
+                    if let Some(country) = data.country {
+                        insert("country_code", country.iso_code.map(Value::from));
+                        insert("country_name", localized(country.names, locale));
                     }
+                }
+            }
+            Database::Asn | Database::Isp => {
+                if let Ok(data) = self.dbreader.lookup::<geoip2::Isp>(ip) {
+                    insert(
+                        "autonomous_system_number",
+                        data.autonomous_system_number
+                            .map(|number| Value::Integer(number.into())),
+                    );
+                    insert(
+                        "autonomous_system_organization",
+                        data.autonomous_system_organization.map(Value::from),
+                    );
+                    insert("isp", data.isp.map(Value::from));
+                    insert("organization", data.organization.map(Value::from));
+                }
+            }
+            Database::ConnectionType => {
+                if let Ok(data) = self.dbreader.lookup::<ConnectionType>(ip) {
+                    insert("connection_type", data.connection_type.map(Value::from));
+                }
+            }
+            Database::AnonymousIp => {
+                if let Ok(data) = self.dbreader.lookup::<AnonymousIp>(ip) {
+                    insert("is_anonymous", data.is_anonymous.map(Value::from));
+                    insert("is_anonymous_vpn", data.is_anonymous_vpn.map(Value::from));
+                    insert(
+                        "is_hosting_provider",
+                        data.is_hosting_provider.map(Value::from),
+                    );
+                    insert("is_public_proxy", data.is_public_proxy.map(Value::from));
+                    insert("is_tor_exit_node", data.is_tor_exit_node.map(Value::from));
+                }
+            }
+        }
 
-                    if let Some(country_code) = data.country.and_then(|cy| cy.iso_code) {
-                        city.country_code = country_code;
-                    };
+        found
+    }
 
-                    if let Some(time_zone) = data.location.clone().and_then(|loc| loc.time_zone) {
-                        city.timezone = time_zone;
-                    }
+    /// Reopen the database if it changed on disk. The current database is kept if the new one
+    /// can't be opened, or lacks some of the fields.
+    fn reload_if_changed(&mut self) {
+        let changed = match &self.watch {
+            Some(watch) => watch.changed(),
+            None => return,
+        };
+        if !changed {
+            return;
+        }
 
-                    if let Some(latitude) = data.location.clone().and_then(|loc| loc.latitude) {
-                        city.latitude = latitude.to_string();
-                    }
+        let database = &self.watch.as_ref().expect("checked above").database;
+        let reloaded = open(database).and_then(|reader| {
+            let database = Database::detect(&reader)?;
+            database.check_fields(&self.fields)?;
+            Ok((reader, database))
+        });
+        match reloaded {
+            Ok((reader, kind)) => {
+                self.dbreader = reader;
+                self.database = kind;
+                emit!(GeoipDatabaseReloaded { database });
+            }
+            Err(error) => emit!(GeoipDatabaseReloadFailed {
+                database,
+                error: error.to_string(),
+            }),
+        }
+    }
+}
 
-                    if let Some(longitude) = data.location.clone().and_then(|loc| loc.longitude) {
-                        city.longitude = longitude.to_string();
-                    }
+/// Watches the directory holding the database, as updaters replace the file rather than
+/// writing to it.
+struct Watch {
+    database: String,
+    path: PathBuf,
+    receiver: Receiver<DebouncedEvent>,
+    _watcher: RecommendedWatcher,
+}
 
-                    if let Some(postal_code) = data.postal.clone().and_then(|p| p.code) {
-                        city.postal_code = postal_code;
-                    }
+impl Watch {
+    fn new(database: &str) -> Result<Self, BuildError> {
+        let path = fs::canonicalize(database).context(ReadDatabase { path: database })?;
+        let dir = path.parent().unwrap_or(&path).to_owned();
+        let (sender, receiver) = channel();
+        let mut watcher =
+            watcher(sender, RELOAD_DELAY).context(InvalidWatch { path: dir.clone() })?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .context(InvalidWatch { path: dir })?;
+
+        Ok(Self {
+            database: database.to_owned(),
+            path,
+            receiver,
+            _watcher: watcher,
+        })
+    }
+
+    fn changed(&self) -> bool {
+        self.receiver.try_iter().fold(false, |changed, event| {
+            changed
+                || match event {
+                    DebouncedEvent::Create(path)
+                    | DebouncedEvent::Write(path)
+                    | DebouncedEvent::Rename(_, path) => path == self.path,
+                    _ => false,
                 }
-            } else {
+        })
+    }
+}
+
+impl Transform for Geoip {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        self.reload_if_changed();
+
+        let target_field = self.target.clone();
+        let ipaddress = event
+            .as_log()
+            .get(&self.source)
+            .map(|s| s.to_string_lossy());
+        let found = match &ipaddress {
+            Some(ipaddress) => match FromStr::from_str(ipaddress) {
+                Ok(ip) => self.lookup(ip),
+                Err(_) => {
+                    debug!(
+                        message = "IP Address not parsed correctly.",
+                        ipaddr = &field::display(&ipaddress),
+                    );
+                    HashMap::new()
+                }
+            },
+            None => {
                 debug!(
-                    message = "IP Address not parsed correctly.",
-                    ipaddr = &field::display(&ipaddress),
+                    message = "Field does not exist.",
+                    field = self.source.as_ref(),
                 );
+                HashMap::new()
             }
-        } else {
-            debug!(
-                message = "Field does not exist.",
-                field = self.source.as_ref(),
-            );
         };
 
-        let json_value = if self.has_isp_db() {
-            serde_json::to_value(isp)
-        } else {
-            serde_json::to_value(city)
-        };
-        if let Ok(json_value) = json_value {
-            event.as_mut_log().insert(target_field, json_value);
-        }
+        let geoip = self
+            .fields
+            .iter()
+            .map(|field| {
+                let value = found
+                    .get(field.as_str())
+                    .cloned()
+                    .unwrap_or_else(|| missing_value(field));
+                (field.clone(), value)
+            })
+            .collect::<BTreeMap<_, _>>();
+        event.as_mut_log().insert(target_field, Value::Map(geoip));
 
         Some(event)
     }
@@ -179,24 +502,48 @@ impl Transform for Geoip {
 #[cfg(feature = "transforms-json_parser")]
 #[cfg(test)]
 mod tests {
-    use super::Geoip;
-    use crate::{
-        event::Event,
-        transforms::json_parser::{JsonParser, JsonParserConfig},
-        transforms::Transform,
-    };
+    use super::*;
+    use crate::transforms::json_parser::{JsonParser, JsonParserConfig};
     use std::collections::HashMap;
-    use string_cache::DefaultAtom as Atom;
 
-    #[test]
-    fn geoip_city_lookup_success() {
+    fn geoip(database: &str, extra: &str) -> Box<dyn Transform> {
+        toml::from_str::<GeoipConfig>(&format!(
+            r#"
+            source = "remote_addr"
+            database = "tests/data/{}"
+            target = "geo"
+            reload = false
+            {}
+            "#,
+            database, extra
+        ))
+        .unwrap()
+        .build(TransformContext::new_test())
+        .unwrap()
+    }
+
+    fn lookup(augment: &mut Box<dyn Transform>, ip: &str) -> Event {
         let mut parser = JsonParser::from(JsonParserConfig::default());
-        let event = Event::from(r#"{"remote_addr": "2.125.160.216", "request_path": "foo/bar"}"#);
+        let event = Event::from(format!(
+            r#"{{"remote_addr": "{}", "request_path": "foo/bar"}}"#,
+            ip
+        ));
         let event = parser.transform(event).unwrap();
-        let reader = maxminddb::Reader::open_readfile("tests/data/GeoIP2-City-Test.mmdb").unwrap();
+        augment.transform(event).unwrap()
+    }
 
-        let mut augment = Geoip::new(reader, Atom::from("remote_addr"), "geo".to_string());
-        let new_event = augment.transform(event).unwrap();
+    fn assert_fields(event: &Event, expected: &HashMap<&str, &str>) {
+        for field in expected.keys() {
+            let k = Atom::from(format!("geo.{}", field).to_string());
+            let geodata = event.as_log().get(&k).unwrap().to_string_lossy();
+            assert_eq!(&geodata, expected.get(field).expect("field exists"));
+        }
+    }
+
+    #[test]
+    fn geoip_city_lookup_success() {
+        let mut augment = geoip("GeoIP2-City-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "2.125.160.216");
 
         let mut exp_geoip_attr = HashMap::new();
         exp_geoip_attr.insert("city_name", "Boxford");
@@ -206,23 +553,19 @@ mod tests {
         exp_geoip_attr.insert("latitude", "51.75");
         exp_geoip_attr.insert("longitude", "-1.25");
         exp_geoip_attr.insert("postal_code", "OX1");
+        assert_fields(&new_event, &exp_geoip_attr);
 
-        for field in exp_geoip_attr.keys() {
-            let k = Atom::from(format!("geo.{}", field).to_string());
-            let geodata = new_event.as_log().get(&k).unwrap().to_string_lossy();
-            assert_eq!(&geodata, exp_geoip_attr.get(field).expect("field exists"));
-        }
+        assert_eq!(
+            new_event.as_log()[&"geo.latitude".into()],
+            Value::Float(51.75)
+        );
+        assert!(new_event.as_log().get(&"geo.region_name".into()).is_none());
     }
 
     #[test]
     fn geoip_city_lookup_partial_results() {
-        let mut parser = JsonParser::from(JsonParserConfig::default());
-        let event = Event::from(r#"{"remote_addr": "67.43.156.9", "request_path": "foo/bar"}"#);
-        let event = parser.transform(event).unwrap();
-        let reader = maxminddb::Reader::open_readfile("tests/data/GeoIP2-City-Test.mmdb").unwrap();
-
-        let mut augment = Geoip::new(reader, Atom::from("remote_addr"), "geo".to_string());
-        let new_event = augment.transform(event).unwrap();
+        let mut augment = geoip("GeoIP2-City-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "67.43.156.9");
 
         let mut exp_geoip_attr = HashMap::new();
         exp_geoip_attr.insert("city_name", "");
@@ -232,49 +575,68 @@ mod tests {
         exp_geoip_attr.insert("latitude", "27.5");
         exp_geoip_attr.insert("longitude", "90.5");
         exp_geoip_attr.insert("postal_code", "");
-
-        for field in exp_geoip_attr.keys() {
-            let k = Atom::from(format!("geo.{}", field).to_string());
-            let geodata = new_event.as_log().get(&k).unwrap().to_string_lossy();
-            assert_eq!(&geodata, exp_geoip_attr.get(field).expect("field exists"));
-        }
+        assert_fields(&new_event, &exp_geoip_attr);
     }
 
     #[test]
     fn geoip_city_lookup_no_results() {
-        let mut parser = JsonParser::from(JsonParserConfig::default());
-        let event = Event::from(r#"{"remote_addr": "10.1.12.1", "request_path": "foo/bar"}"#);
-        let event = parser.transform(event).unwrap();
-        let reader = maxminddb::Reader::open_readfile("tests/data/GeoIP2-City-Test.mmdb").unwrap();
-
-        let mut augment = Geoip::new(reader, Atom::from("remote_addr"), "geo".to_string());
-        let new_event = augment.transform(event).unwrap();
+        let mut augment = geoip("GeoIP2-City-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "10.1.12.1");
 
         let mut exp_geoip_attr = HashMap::new();
         exp_geoip_attr.insert("city_name", "");
         exp_geoip_attr.insert("country_code", "");
         exp_geoip_attr.insert("continent_code", "");
         exp_geoip_attr.insert("timezone", "");
-        exp_geoip_attr.insert("latitude", "");
-        exp_geoip_attr.insert("longitude", "");
         exp_geoip_attr.insert("postal_code", "");
+        assert_fields(&new_event, &exp_geoip_attr);
 
-        for field in exp_geoip_attr.keys() {
-            let k = Atom::from(format!("geo.{}", field).to_string());
-            let geodata = new_event.as_log().get(&k).unwrap().to_string_lossy();
-            assert_eq!(&geodata, exp_geoip_attr.get(field).expect("fields exists"));
-        }
+        assert_eq!(new_event.as_log()[&"geo.latitude".into()], Value::Null);
+        assert_eq!(new_event.as_log()[&"geo.longitude".into()], Value::Null);
     }
 
     #[test]
-    fn geoip_isp_lookup_success() {
-        let mut parser = JsonParser::from(JsonParserConfig::default());
-        let event = Event::from(r#"{"remote_addr": "208.192.1.2", "request_path": "foo/bar"}"#);
-        let event = parser.transform(event).unwrap();
-        let reader = maxminddb::Reader::open_readfile("tests/data/GeoIP2-ISP-Test.mmdb").unwrap();
+    fn geoip_city_selected_fields_and_locale() {
+        let mut augment = geoip(
+            "GeoIP2-City-Test.mmdb",
+            r#"
+            fields = ["city_name", "country_name", "continent_name", "region_code", "region_name"]
+            locale = "de"
+            "#,
+        );
+        let new_event = lookup(&mut augment, "2.125.160.216");
+
+        let mut exp_geoip_attr = HashMap::new();
+        // There is no German name of the city, so the English one is used.
+        exp_geoip_attr.insert("city_name", "Boxford");
+        exp_geoip_attr.insert("country_name", "Vereinigtes Königreich");
+        exp_geoip_attr.insert("continent_name", "Europa");
+        exp_geoip_attr.insert("region_code", "ENG");
+        exp_geoip_attr.insert("region_name", "England");
+        assert_fields(&new_event, &exp_geoip_attr);
+
+        assert!(new_event.as_log().get(&"geo.country_code".into()).is_none());
+    }
+
+    #[test]
+    fn geoip_country_lookup_success() {
+        let mut augment = geoip("GeoIP2-Country-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "2.125.160.216");
+
+        let mut exp_geoip_attr = HashMap::new();
+        exp_geoip_attr.insert("continent_code", "EU");
+        exp_geoip_attr.insert("continent_name", "Europe");
+        exp_geoip_attr.insert("country_code", "GB");
+        exp_geoip_attr.insert("country_name", "United Kingdom");
+        assert_fields(&new_event, &exp_geoip_attr);
+
+        assert!(new_event.as_log().get(&"geo.city_name".into()).is_none());
+    }
 
-        let mut augment = Geoip::new(reader, Atom::from("remote_addr"), "geo".to_string());
-        let new_event = augment.transform(event).unwrap();
+    #[test]
+    fn geoip_isp_lookup_success() {
+        let mut augment = geoip("GeoIP2-ISP-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "208.192.1.2");
 
         let mut exp_geoip_attr = HashMap::new();
         exp_geoip_attr.insert("autonomous_system_number", "701");
@@ -284,57 +646,132 @@ mod tests {
         );
         exp_geoip_attr.insert("isp", "Verizon Business");
         exp_geoip_attr.insert("organization", "Verizon Business");
-
-        for field in exp_geoip_attr.keys() {
-            let k = Atom::from(format!("geo.{}", field).to_string());
-            let geodata = new_event.as_log().get(&k).unwrap().to_string_lossy();
-            assert_eq!(&geodata, exp_geoip_attr.get(field).expect("field exists"));
-        }
+        assert_fields(&new_event, &exp_geoip_attr);
     }
 
     #[test]
     fn geoip_isp_lookup_partial_results() {
-        let mut parser = JsonParser::from(JsonParserConfig::default());
-        let event = Event::from(r#"{"remote_addr": "2600:7000::1", "request_path": "foo/bar"}"#);
-        let event = parser.transform(event).unwrap();
-        let reader = maxminddb::Reader::open_readfile("tests/data/GeoLite2-ASN-Test.mmdb").unwrap();
-
-        let mut augment = Geoip::new(reader, Atom::from("remote_addr"), "geo".to_string());
-        let new_event = augment.transform(event).unwrap();
+        let mut augment = geoip("GeoLite2-ASN-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "2600:7000::1");
 
         let mut exp_geoip_attr = HashMap::new();
         exp_geoip_attr.insert("autonomous_system_number", "6939");
         exp_geoip_attr.insert("autonomous_system_organization", "Hurricane Electric, Inc.");
         exp_geoip_attr.insert("isp", "");
         exp_geoip_attr.insert("organization", "");
-
-        for field in exp_geoip_attr.keys() {
-            let k = Atom::from(format!("geo.{}", field).to_string());
-            let geodata = new_event.as_log().get(&k).unwrap().to_string_lossy();
-            assert_eq!(&geodata, exp_geoip_attr.get(field).expect("field exists"));
-        }
+        assert_fields(&new_event, &exp_geoip_attr);
     }
 
     #[test]
     fn geoip_isp_lookup_no_results() {
-        let mut parser = JsonParser::from(JsonParserConfig::default());
-        let event = Event::from(r#"{"remote_addr": "10.1.12.1", "request_path": "foo/bar"}"#);
-        let event = parser.transform(event).unwrap();
-        let reader = maxminddb::Reader::open_readfile("tests/data/GeoLite2-ASN-Test.mmdb").unwrap();
-
-        let mut augment = Geoip::new(reader, Atom::from("remote_addr"), "geo".to_string());
-        let new_event = augment.transform(event).unwrap();
+        let mut augment = geoip("GeoLite2-ASN-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "10.1.12.1");
 
         let mut exp_geoip_attr = HashMap::new();
         exp_geoip_attr.insert("autonomous_system_number", "0");
         exp_geoip_attr.insert("autonomous_system_organization", "");
         exp_geoip_attr.insert("isp", "");
         exp_geoip_attr.insert("organization", "");
+        assert_fields(&new_event, &exp_geoip_attr);
+    }
 
-        for field in exp_geoip_attr.keys() {
-            let k = Atom::from(format!("geo.{}", field).to_string());
-            let geodata = new_event.as_log().get(&k).unwrap().to_string_lossy();
-            assert_eq!(&geodata, exp_geoip_attr.get(field).expect("fields exists"));
+    #[test]
+    fn geoip_asn_selected_fields() {
+        let mut augment = geoip(
+            "GeoLite2-ASN-Test.mmdb",
+            r#"fields = ["autonomous_system_number"]"#,
+        );
+        let new_event = lookup(&mut augment, "2600:7000::1");
+
+        assert_eq!(
+            new_event.as_log()[&"geo.autonomous_system_number".into()],
+            Value::Integer(6939)
+        );
+        assert!(new_event.as_log().get(&"geo.isp".into()).is_none());
+    }
+
+    #[test]
+    fn geoip_connection_type_lookup() {
+        let mut augment = geoip("GeoIP2-Connection-Type-Test.mmdb", "");
+
+        let new_event = lookup(&mut augment, "201.243.200.1");
+        let mut exp_geoip_attr = HashMap::new();
+        exp_geoip_attr.insert("connection_type", "Corporate");
+        assert_fields(&new_event, &exp_geoip_attr);
+
+        let new_event = lookup(&mut augment, "10.1.12.1");
+        exp_geoip_attr.insert("connection_type", "");
+        assert_fields(&new_event, &exp_geoip_attr);
+    }
+
+    #[test]
+    fn geoip_anonymous_ip_lookup() {
+        let mut augment = geoip("GeoIP2-Anonymous-IP-Test.mmdb", "");
+        let new_event = lookup(&mut augment, "186.30.236.1");
+
+        let mut exp_geoip_attr = HashMap::new();
+        exp_geoip_attr.insert("is_anonymous", "true");
+        exp_geoip_attr.insert("is_anonymous_vpn", "false");
+        exp_geoip_attr.insert("is_hosting_provider", "false");
+        exp_geoip_attr.insert("is_public_proxy", "true");
+        exp_geoip_attr.insert("is_tor_exit_node", "false");
+        assert_fields(&new_event, &exp_geoip_attr);
+    }
+
+    #[test]
+    fn geoip_rejects_unavailable_fields() {
+        let error = toml::from_str::<GeoipConfig>(
+            r#"
+            source = "remote_addr"
+            database = "tests/data/GeoIP2-Country-Test.mmdb"
+            fields = ["city_name"]
+            "#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test())
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Field \"city_name\" is not available in Country databases"
+        );
+    }
+
+    #[test]
+    fn geoip_reloads_changed_database() {
+        let dir = crate::test_util::temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let database = dir.join("GeoIP2.mmdb");
+        fs::copy("tests/data/GeoIP2-Country-Test.mmdb", &database).unwrap();
+
+        let mut augment = toml::from_str::<GeoipConfig>(&format!(
+            r#"
+            source = "remote_addr"
+            database = "{}"
+            target = "geo"
+            fields = ["country_code"]
+            "#,
+            database.display()
+        ))
+        .unwrap()
+        .build(TransformContext::new_test())
+        .unwrap();
+
+        let new_event = lookup(&mut augment, "67.43.156.9");
+        assert_eq!(new_event.as_log()[&"geo.country_code".into()], "".into());
+
+        // Replaced the way updaters do, with a database that knows the address.
+        let replacement = dir.join("GeoIP2.mmdb.tmp");
+        fs::copy("tests/data/GeoIP2-City-Test.mmdb", &replacement).unwrap();
+        fs::rename(&replacement, &database).unwrap();
+
+        for _ in 0..50 {
+            std::thread::sleep(Duration::from_millis(100));
+            let new_event = lookup(&mut augment, "67.43.156.9");
+            if new_event.as_log()[&"geo.country_code".into()] == "BT".into() {
+                return;
+            }
         }
+        panic!("database was not reloaded");
     }
 }
@@ -0,0 +1,292 @@
+use super::Transform;
+use crate::{
+    config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    enrichment_tables::{Condition, EnrichmentTable, Match},
+    event::{Event, Value},
+    internal_events::{LookupEventProcessed, LookupNoMatch, LookupTargetOverwritten},
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LookupConfig {
+    /// The name of a table in the global `enrichment_tables` section.
+    pub table: String,
+    pub conditions: Vec<LookupCondition>,
+    /// The map the columns of the matching row are merged into.
+    pub target: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LookupCondition {
+    pub field: String,
+    pub column: String,
+    #[serde(default, rename = "match")]
+    pub mode: Match,
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Unknown enrichment table {:?}", table))]
+    UnknownTable { table: String },
+    #[snafu(display("At least one condition is required"))]
+    NoConditions,
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<LookupConfig>("lookup")
+}
+
+#[typetag::serde(name = "lookup")]
+impl TransformConfig for LookupConfig {
+    fn build(&self, cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        if self.conditions.is_empty() {
+            return Err(BuildError::NoConditions.into());
+        }
+        let table =
+            cx.enrichment_tables()
+                .get(&self.table)
+                .ok_or_else(|| BuildError::UnknownTable {
+                    table: self.table.clone(),
+                })?;
+
+        Ok(Box::new(Lookup {
+            name: self.table.clone(),
+            table,
+            conditions: self
+                .conditions
+                .iter()
+                .map(|condition| (condition.field.as_str().into(), condition.clone()))
+                .collect(),
+            target: self.target.as_str().into(),
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "lookup"
+    }
+}
+
+pub struct Lookup {
+    name: String,
+    table: EnrichmentTable,
+    conditions: Vec<(Atom, LookupCondition)>,
+    target: Atom,
+}
+
+impl Transform for Lookup {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        emit!(LookupEventProcessed);
+
+        let log = event.as_mut_log();
+        let values = self
+            .conditions
+            .iter()
+            .map(|(field, _)| log.get(field).map(Value::to_string_lossy))
+            .collect::<Option<Vec<_>>>();
+        let values = match values {
+            Some(values) => values,
+            None => {
+                emit!(LookupNoMatch { table: &self.name });
+                return Some(event);
+            }
+        };
+        let conditions = self
+            .conditions
+            .iter()
+            .zip(values.iter())
+            .map(|((_, condition), value)| Condition {
+                column: &condition.column,
+                value,
+                mode: condition.mode,
+            })
+            .collect::<Vec<_>>();
+
+        let table = self.table.current();
+        let row = match table.find(&conditions) {
+            Some(row) => row,
+            None => {
+                emit!(LookupNoMatch { table: &self.name });
+                return Some(event);
+            }
+        };
+
+        match log.get_mut(&self.target) {
+            Some(Value::Map(map)) => map.extend(row.clone()),
+            target => {
+                if target.is_some() {
+                    emit!(LookupTargetOverwritten {
+                        field: &self.target
+                    });
+                }
+                log.insert(self.target.clone(), Value::Map(row.clone()));
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enrichment_tables::{EnrichmentTableConfig, EnrichmentTables, Table},
+        event::LogEvent,
+    };
+    use std::collections::BTreeMap;
+
+    fn row(columns: &[(&str, &str)]) -> BTreeMap<String, Value> {
+        columns
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::from(*value)))
+            .collect()
+    }
+
+    fn transform(config: &str) -> crate::Result<Box<dyn Transform>> {
+        let table = Table::new(vec![
+            row(&[
+                ("network", "10.0.0.0/8"),
+                ("service", "api"),
+                ("owner", "core"),
+            ]),
+            row(&[
+                ("network", "10.1.0.0/16"),
+                ("service", "web"),
+                ("owner", "frontend"),
+            ]),
+        ]);
+        let table = EnrichmentTable::new(
+            EnrichmentTableConfig {
+                path: "services.csv".into(),
+                format: None,
+                reload: false,
+            },
+            table,
+        );
+        let tables = EnrichmentTables::from_tables(vec![("services".to_owned(), table)]);
+
+        toml::from_str::<LookupConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test().with_enrichment_tables(tables))
+    }
+
+    fn log(fields: &[(&str, Value)]) -> Event {
+        let mut event = Event::new_empty_log();
+        for (name, value) in fields {
+            event.as_mut_log().insert(*name, value.clone());
+        }
+        event
+    }
+
+    fn lookup(transform: &mut Box<dyn Transform>, fields: &[(&str, Value)]) -> LogEvent {
+        transform.transform(log(fields)).unwrap().into_log()
+    }
+
+    #[test]
+    fn merges_matching_row() {
+        let mut transform = transform(
+            r#"
+            table = "services"
+            target = "service"
+            conditions = [{ field = "name", column = "service", match = "case_insensitive" }]
+            "#,
+        )
+        .unwrap();
+
+        let event = lookup(
+            &mut transform,
+            &[("name", "WEB".into()), ("service.tier", "gold".into())],
+        );
+        assert_eq!(event[&"service.owner".into()], "frontend".into());
+        assert_eq!(event[&"service.network".into()], "10.1.0.0/16".into());
+        assert_eq!(event[&"service.tier".into()], "gold".into());
+
+        let event = lookup(&mut transform, &[("name", "db".into())]);
+        assert!(event.get(&"service".into()).is_none());
+    }
+
+    #[test]
+    fn matches_all_conditions() {
+        let mut transform = transform(
+            r#"
+            table = "services"
+            target = "enriched"
+            [[conditions]]
+            field = "ip"
+            column = "network"
+            match = "cidr"
+            [[conditions]]
+            field = "owner"
+            column = "owner"
+            "#,
+        )
+        .unwrap();
+
+        let event = lookup(
+            &mut transform,
+            &[("ip", "10.1.2.3".into()), ("owner", "core".into())],
+        );
+        assert_eq!(event[&"enriched.service".into()], "api".into());
+
+        let event = lookup(
+            &mut transform,
+            &[("ip", "10.2.0.1".into()), ("owner", "frontend".into())],
+        );
+        assert!(event.get(&"enriched".into()).is_none());
+
+        let event = lookup(&mut transform, &[("ip", "10.1.2.3".into())]);
+        assert!(event.get(&"enriched".into()).is_none());
+    }
+
+    #[test]
+    fn overwrites_non_map_target() {
+        let mut transform = transform(
+            r#"
+            table = "services"
+            target = "service"
+            conditions = [{ field = "service", column = "service" }]
+            "#,
+        )
+        .unwrap();
+
+        let event = lookup(&mut transform, &[("service", "api".into())]);
+        assert_eq!(event[&"service.owner".into()], "core".into());
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        let error = transform(
+            r#"
+            table = "missing"
+            target = "service"
+            conditions = [{ field = "service", column = "service" }]
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "Unknown enrichment table \"missing\"");
+
+        let error = transform(
+            r#"
+            table = "services"
+            target = "service"
+            conditions = []
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "At least one condition is required");
+    }
+}
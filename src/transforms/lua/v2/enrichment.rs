@@ -0,0 +1,221 @@
+//! Read-only lookup tables, loaded from CSV or JSON files and shared between Lua transforms.
+use crate::{
+    enrichment_tables::{self, Format, Row},
+    event::Value,
+};
+use once_cell::sync::Lazy;
+use rlua::{prelude::*, MetaMethod, UserData, UserDataMethods};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, Weak},
+    time::SystemTime,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct TableConfig {
+    pub path: PathBuf,
+    /// The column rows are looked up by. Rows are only accessible by position if unset.
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum LoadError {
+    #[snafu(display("Could not read {:?}: {}", path, source))]
+    Read { path: PathBuf, source: io::Error },
+    #[snafu(display("{}", source))]
+    Load {
+        source: enrichment_tables::LoadError,
+    },
+    #[snafu(display("Row {} of {:?} has no {:?} column", row, path, key))]
+    MissingKey {
+        path: PathBuf,
+        row: usize,
+        key: String,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Table {
+    rows: Vec<Row>,
+    index: HashMap<String, usize>,
+}
+
+impl Table {
+    fn load(config: &TableConfig) -> Result<Self, LoadError> {
+        let path = &config.path;
+        let format = Format::from_path(path).context(Load)?;
+        let rows = enrichment_tables::read_rows(path, format).context(Load)?;
+
+        let mut index = HashMap::new();
+        if let Some(key) = &config.key {
+            for (position, row) in rows.iter().enumerate() {
+                let value = row.get(key).ok_or_else(|| LoadError::MissingKey {
+                    path: path.clone(),
+                    row: position + 1,
+                    key: key.clone(),
+                })?;
+                index.entry(value.to_string_lossy()).or_insert(position);
+            }
+        }
+
+        Ok(Self { rows, index })
+    }
+
+    /// The row with the given key. The first row wins for duplicate keys.
+    pub fn get(&self, key: &str) -> Option<&Row> {
+        self.index.get(key).map(|&position| &self.rows[position])
+    }
+
+    /// The row at the given 1-based position, as is customary in Lua.
+    pub fn nth(&self, position: i64) -> Option<&Row> {
+        let position = usize::try_from(position).ok()?.checked_sub(1)?;
+        self.rows.get(position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Tables loaded by any transform, so that transforms using the same file share the data.
+static TABLES: Lazy<Mutex<HashMap<TableConfig, (Option<SystemTime>, Weak<Table>)>>> =
+    Lazy::new(Default::default);
+
+/// Load the table, reusing the data of other transforms if the file didn't change since.
+pub fn load(config: &TableConfig) -> Result<Arc<Table>, LoadError> {
+    let modified = fs::metadata(&config.path)
+        .context(Read {
+            path: config.path.clone(),
+        })?
+        .modified()
+        .ok();
+
+    let mut tables = TABLES.lock().expect("enrichment table lock poisoned");
+    if let Some((loaded, table)) = tables.get(config) {
+        if modified.is_some() && *loaded == modified {
+            if let Some(table) = table.upgrade() {
+                return Ok(table);
+            }
+        }
+    }
+
+    let table = Arc::new(Table::load(config)?);
+    tables.insert(config.clone(), (modified, Arc::downgrade(&table)));
+    Ok(table)
+}
+
+/// A table as seen from Lua. Rows are looked up with `table[key]`, or `table[n]` by position,
+/// and `#table` is the number of rows.
+#[derive(Clone)]
+pub struct LuaTable(pub Arc<Table>);
+
+impl UserData for LuaTable {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(MetaMethod::Index, |_ctx, this, key: LuaValue<'lua>| {
+            let row = match key {
+                LuaValue::Integer(position) => this.0.nth(position),
+                LuaValue::String(key) => this.0.get(key.to_str()?),
+                _ => None,
+            };
+            Ok(row.cloned().map(Value::Map))
+        });
+        methods.add_meta_method(MetaMethod::Len, |_ctx, this, ()| Ok(this.0.len()));
+    }
+}
+
+/// Create the read-only `enrichment_tables` global.
+pub fn register(ctx: LuaContext<'_>, tables: &HashMap<String, Arc<Table>>) -> LuaResult<()> {
+    let inner = ctx.create_table()?;
+    for (name, table) in tables {
+        inner.set(name.as_str(), LuaTable(Arc::clone(table)))?;
+    }
+
+    let metatable = ctx.create_table()?;
+    metatable.set("__index", inner)?;
+    metatable.set(
+        "__newindex",
+        ctx.create_function(|_, ()| -> LuaResult<()> {
+            Err(LuaError::RuntimeError(
+                "enrichment_tables are read-only".to_owned(),
+            ))
+        })?,
+    )?;
+    let proxy = ctx.create_table()?;
+    proxy.set_metatable(Some(metatable));
+    ctx.globals().set("enrichment_tables", proxy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_csv_by_key() {
+        let path = write("services.csv", "service,owner\napi,core\nweb,frontend\n");
+        let table = load(&TableConfig {
+            path,
+            key: Some("service".into()),
+        })
+        .unwrap();
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get("web").unwrap()["owner"], Value::from("frontend"));
+        assert_eq!(table.nth(1).unwrap()["owner"], Value::from("core"));
+        assert!(table.get("db").is_none());
+        assert!(table.nth(0).is_none());
+    }
+
+    #[test]
+    fn loads_json_rows() {
+        let path = write("tiers.json", r#"[{"customer": 42, "tier": "gold"}]"#);
+        let table = load(&TableConfig {
+            path,
+            key: Some("customer".into()),
+        })
+        .unwrap();
+
+        assert_eq!(table.get("42").unwrap()["tier"], Value::from("gold"));
+    }
+
+    #[test]
+    fn shares_unchanged_tables() {
+        let config = TableConfig {
+            path: write("shared.csv", "a,b\n1,2\n"),
+            key: None,
+        };
+
+        let first = load(&config).unwrap();
+        let second = load(&config).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn rejects_missing_keys() {
+        let path = write("missing.csv", "a,b\n1,2\n");
+        let error = load(&TableConfig {
+            path,
+            key: Some("c".into()),
+        })
+        .unwrap_err();
+        assert!(matches!(error, LoadError::MissingKey { row: 1, .. }));
+    }
+}
@@ -1,17 +1,27 @@
+mod enrichment;
 mod interop;
 
 use crate::{
     config::CONFIG_PATHS,
     config::{DataType, TransformContext},
+    emit,
     event::Event,
+    internal_events::{LuaScriptReloadFailed, LuaScriptReloaded},
     transforms::{
         util::runtime_transform::{RuntimeTransform, Timer},
         Transform,
     },
 };
+pub use enrichment::TableConfig;
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
 
 #[derive(Debug, Snafu)]
 enum BuildError {
@@ -19,6 +29,18 @@ enum BuildError {
     InvalidSearchDirs { source: rlua::Error },
     #[snafu(display("Cannot evaluate Lua code in \"source\": {}", source))]
     InvalidSource { source: rlua::Error },
+    #[snafu(display("Cannot load enrichment table {:?}: {}", name, source))]
+    InvalidEnrichmentTable {
+        name: String,
+        source: enrichment::LoadError,
+    },
+    #[snafu(display("Cannot register \"enrichment_tables\": {}", source))]
+    InvalidEnrichmentTables { source: rlua::Error },
+    #[snafu(display("Cannot watch {:?} for changes: {}", path, source))]
+    InvalidWatch {
+        path: PathBuf,
+        source: notify::Error,
+    },
 
     #[snafu(display("Cannot evaluate Lua code defining \"hooks.init\": {}", source))]
     InvalidHooksInit { source: rlua::Error },
@@ -42,7 +64,7 @@ enum BuildError {
     RuntimeErrorGC { source: rlua::Error },
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct LuaConfig {
     #[serde(default = "default_config_paths")]
@@ -51,6 +73,11 @@ pub struct LuaConfig {
     #[serde(default)]
     timers: Vec<TimerConfig>,
     source: Option<String>,
+    #[serde(default)]
+    enrichment_tables: HashMap<String, TableConfig>,
+    /// Recompile the scripts when files in `search_dirs` or the enrichment tables change.
+    #[serde(default)]
+    hot_reload: bool,
 }
 
 fn default_config_paths() -> Vec<PathBuf> {
@@ -67,14 +94,14 @@ fn default_config_paths() -> Vec<PathBuf> {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 struct HooksConfig {
     init: Option<String>,
     process: String,
     shutdown: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 struct TimerConfig {
     interval_seconds: u64,
     handler: String,
@@ -112,81 +139,64 @@ impl LuaConfig {
 // after each transform would have significant footprint on the performance.
 const GC_INTERVAL: usize = 16;
 
+// Changes are accumulated until no change happened for this long, so that editors saving files
+// in several steps only trigger a single reload.
+const RELOAD_DELAY: Duration = Duration::from_secs(1);
+
 pub struct Lua {
     lua: rlua::Lua,
     invocations_after_gc: usize,
     timers: Vec<Timer>,
+    config: LuaConfig,
+    // The watcher is kept only to keep the notifications coming.
+    changes: Option<(RecommendedWatcher, Receiver<DebouncedEvent>)>,
 }
 
 impl Lua {
     pub fn new(config: &LuaConfig) -> crate::Result<Self> {
-        let lua = rlua::Lua::new();
-
-        let additional_paths = config
-            .search_dirs
-            .iter()
-            .map(|d| format!("{}/?.lua", d.to_string_lossy()))
-            .collect::<Vec<_>>()
-            .join(";");
-
-        let mut timers = Vec::new();
-        lua.context(|ctx| -> crate::Result<()> {
-            if !additional_paths.is_empty() {
-                let package = ctx.globals().get::<_, rlua::Table<'_>>("package")?;
-                let current_paths = package
-                    .get::<_, String>("path")
-                    .unwrap_or_else(|_| ";".to_string());
-                let paths = format!("{};{}", additional_paths, current_paths);
-                package.set("path", paths)?;
-            }
-
-            if let Some(source) = &config.source {
-                ctx.load(source).eval().context(InvalidSource)?;
-            }
-
-            if let Some(hooks_init) = &config.hooks.init {
-                let hooks_init: rlua::Function<'_> =
-                    ctx.load(hooks_init).eval().context(InvalidHooksInit)?;
-                ctx.set_named_registry_value("hooks_init", Some(hooks_init))?;
-            }
-
-            let hooks_process: rlua::Function<'_> = ctx
-                .load(&config.hooks.process)
-                .eval()
-                .context(InvalidHooksProcess)?;
-            ctx.set_named_registry_value("hooks_process", hooks_process)?;
-
-            if let Some(hooks_shutdown) = &config.hooks.shutdown {
-                let hooks_shutdown: rlua::Function<'_> = ctx
-                    .load(hooks_shutdown)
-                    .eval()
-                    .context(InvalidHooksShutdown)?;
-                ctx.set_named_registry_value("hooks_shutdown", Some(hooks_shutdown))?;
-            }
-
-            for (id, timer) in config.timers.iter().enumerate() {
-                let handler: rlua::Function<'_> = ctx
-                    .load(&timer.handler)
-                    .eval()
-                    .context(InvalidTimerHandler)?;
-
-                ctx.set_named_registry_value(&format!("timer_handler_{}", id), handler)?;
-                timers.push(Timer {
-                    id: id as u32,
-                    interval_seconds: timer.interval_seconds,
-                });
-            }
-
-            Ok(())
-        })?;
+        let (lua, timers) = compile(config)?;
+        let changes = if config.hot_reload {
+            Some(watch(config)?)
+        } else {
+            None
+        };
 
         Ok(Self {
             lua,
             invocations_after_gc: 0,
             timers,
+            config: config.clone(),
+            changes,
         })
     }
 
+    /// Recompile the scripts if any watched file changed, and run `hooks.init` of the new
+    /// version. If the recompilation fails, the previous version keeps running.
+    fn reload_if_changed<F>(&mut self, emit_fn: F)
+    where
+        F: FnMut(Event),
+    {
+        let changed = match &self.changes {
+            Some((_, receiver)) => receiver.try_iter().filter(is_change).count() > 0,
+            None => false,
+        };
+        if !changed {
+            return;
+        }
+
+        match compile(&self.config) {
+            Ok((lua, _timers)) => {
+                self.lua = lua;
+                self.invocations_after_gc = 0;
+                emit!(LuaScriptReloaded);
+                self.hook_init(emit_fn);
+            }
+            Err(error) => emit!(LuaScriptReloadFailed {
+                error: error.to_string()
+            }),
+        }
+    }
+
     #[cfg(test)]
     fn process(&mut self, event: Event, output: &mut Vec<Event>) -> Result<(), rlua::Error> {
         let result = self.lua.context(|ctx: rlua::Context<'_>| {
@@ -226,6 +236,112 @@ impl Lua {
     }
 }
 
+/// Build a Lua runtime running the scripts of `config`.
+fn compile(config: &LuaConfig) -> crate::Result<(rlua::Lua, Vec<Timer>)> {
+    let lua = rlua::Lua::new();
+
+    let additional_paths = config
+        .search_dirs
+        .iter()
+        .map(|d| format!("{}/?.lua", d.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let mut timers = Vec::new();
+    lua.context(|ctx| -> crate::Result<()> {
+        if !additional_paths.is_empty() {
+            let package = ctx.globals().get::<_, rlua::Table<'_>>("package")?;
+            let current_paths = package
+                .get::<_, String>("path")
+                .unwrap_or_else(|_| ";".to_string());
+            let paths = format!("{};{}", additional_paths, current_paths);
+            package.set("path", paths)?;
+        }
+
+        let tables = config
+            .enrichment_tables
+            .iter()
+            .map(|(name, table)| {
+                enrichment::load(table)
+                    .map(|table| (name.clone(), table))
+                    .context(InvalidEnrichmentTable { name: name.clone() })
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        enrichment::register(ctx, &tables).context(InvalidEnrichmentTables)?;
+
+        if let Some(source) = &config.source {
+            ctx.load(source).eval().context(InvalidSource)?;
+        }
+
+        if let Some(hooks_init) = &config.hooks.init {
+            let hooks_init: rlua::Function<'_> =
+                ctx.load(hooks_init).eval().context(InvalidHooksInit)?;
+            ctx.set_named_registry_value("hooks_init", Some(hooks_init))?;
+        }
+
+        let hooks_process: rlua::Function<'_> = ctx
+            .load(&config.hooks.process)
+            .eval()
+            .context(InvalidHooksProcess)?;
+        ctx.set_named_registry_value("hooks_process", hooks_process)?;
+
+        if let Some(hooks_shutdown) = &config.hooks.shutdown {
+            let hooks_shutdown: rlua::Function<'_> = ctx
+                .load(hooks_shutdown)
+                .eval()
+                .context(InvalidHooksShutdown)?;
+            ctx.set_named_registry_value("hooks_shutdown", Some(hooks_shutdown))?;
+        }
+
+        for (id, timer) in config.timers.iter().enumerate() {
+            let handler: rlua::Function<'_> = ctx
+                .load(&timer.handler)
+                .eval()
+                .context(InvalidTimerHandler)?;
+
+            ctx.set_named_registry_value(&format!("timer_handler_{}", id), handler)?;
+            timers.push(Timer {
+                id: id as u32,
+                interval_seconds: timer.interval_seconds,
+            });
+        }
+
+        Ok(())
+    })?;
+
+    Ok((lua, timers))
+}
+
+/// Watch `search_dirs` and the enrichment tables for changes.
+fn watch(config: &LuaConfig) -> crate::Result<(RecommendedWatcher, Receiver<DebouncedEvent>)> {
+    let (sender, receiver) = channel();
+    let mut watcher = watcher(sender, RELOAD_DELAY)?;
+    let dirs = config
+        .search_dirs
+        .iter()
+        .map(|path| (path, RecursiveMode::Recursive));
+    let tables = config
+        .enrichment_tables
+        .values()
+        .map(|table| (&table.path, RecursiveMode::NonRecursive));
+    for (path, mode) in dirs.chain(tables) {
+        watcher
+            .watch(path, mode)
+            .context(InvalidWatch { path: path.clone() })?;
+    }
+    Ok((watcher, receiver))
+}
+
+fn is_change(event: &DebouncedEvent) -> bool {
+    matches!(
+        event,
+        DebouncedEvent::Create(_)
+            | DebouncedEvent::Write(_)
+            | DebouncedEvent::Remove(_)
+            | DebouncedEvent::Rename(_, _)
+    )
+}
+
 // A helper that reduces code duplication.
 fn wrap_emit_fn<'lua, 'scope, F: 'scope>(
     scope: &rlua::Scope<'lua, 'scope>,
@@ -241,10 +357,21 @@ where
 }
 
 impl RuntimeTransform for Lua {
-    fn hook_process<F>(self: &mut Self, event: Event, emit_fn: F)
+    fn hook_process<F>(self: &mut Self, mut event: Event, mut emit_fn: F)
     where
         F: FnMut(Event),
     {
+        self.reload_if_changed(&mut emit_fn);
+
+        // The event is rebuilt from a Lua table, so its finalizers are handed
+        // over to each event the script emits for it instead. Events emitted
+        // later, such as from timers, carry none.
+        let finalizers = event.take_finalizers();
+        let emit_fn = move |mut event: Event| {
+            event.add_finalizers(finalizers.clone());
+            emit_fn(event)
+        };
+
         let _ = self
             .lua
             .context(|ctx: rlua::Context<'_>| {
@@ -302,10 +429,12 @@ impl RuntimeTransform for Lua {
         self.attempt_gc();
     }
 
-    fn timer_handler<F>(self: &mut Self, timer: Timer, emit_fn: F)
+    fn timer_handler<F>(self: &mut Self, timer: Timer, mut emit_fn: F)
     where
         F: FnMut(Event),
     {
+        self.reload_if_changed(&mut emit_fn);
+
         let _ = self
             .lua
             .context(|ctx: rlua::Context<'_>| {
@@ -342,13 +471,14 @@ mod tests {
     use crate::{
         event::{
             metric::{Metric, MetricKind, MetricValue},
-            Event, Value,
+            BatchNotifier, Event, EventStatus, Value,
         },
-        test_util::trace_init,
+        test_util::{temp_dir, trace_init},
         transforms::Transform,
     };
-    use futures::compat::Future01CompatExt;
+    use futures::{compat::Future01CompatExt, FutureExt};
     use futures01::{stream, Stream};
+    use std::{fs, thread, time::Duration};
 
     fn from_config(config: &str) -> crate::Result<Lua> {
         Lua::new(&toml::from_str(config).unwrap())
@@ -465,6 +595,41 @@ mod tests {
         assert_eq!(out.len(), 2);
     }
 
+    #[test]
+    fn lua_emitted_events_carry_finalizers() {
+        trace_init();
+
+        let mut transform = from_config(
+            r#"
+            hooks.process = """function (event, emit)
+                emit(event)
+                emit(event)
+            end
+            """
+            "#,
+        )
+        .unwrap();
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let mut event = Event::new_empty_log();
+        event.add_batch_notifier(batch);
+        let mut out = Vec::new();
+        transform.transform_into(&mut out, event);
+        assert_eq!(out.len(), 2);
+
+        // The batch is only resolved once every emitted event is delivered.
+        let first = out[0].take_finalizers();
+        let second = out[1].take_finalizers();
+
+        first.update_status(EventStatus::Delivered);
+        drop(first);
+        assert_eq!((&mut receiver).now_or_never(), None);
+
+        second.update_status(EventStatus::Delivered);
+        drop(second);
+        assert_eq!(receiver.now_or_never(), Some(EventStatus::Delivered));
+    }
+
     #[test]
     fn lua_read_empty_field() {
         trace_init();
@@ -779,4 +944,104 @@ mod tests {
 
         assert_eq!(results.len(), n);
     }
+
+    #[test]
+    fn lua_enrichment_tables() {
+        trace_init();
+
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("services.csv");
+        fs::write(&path, "service,owner\napi,core\nweb,frontend\n").unwrap();
+
+        let mut transform = from_config(&format!(
+            r#"
+            enrichment_tables.services.path = {:?}
+            enrichment_tables.services.key = "service"
+            hooks.process = """function (event, emit)
+                local row = enrichment_tables.services[event.log.service]
+                event.log.owner = row.owner
+                event.log.services = #enrichment_tables.services
+                emit(event)
+            end
+            """
+            "#,
+            path
+        ))
+        .unwrap();
+
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert("service", "web");
+        let event = transform.transform(event).unwrap();
+
+        assert_eq!(event.as_log()[&"owner".into()], "frontend".into());
+        assert_eq!(event.as_log()[&"services".into()], Value::Integer(2));
+    }
+
+    #[test]
+    fn lua_enrichment_tables_are_read_only() {
+        trace_init();
+
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tiers.json");
+        fs::write(&path, r#"[{"customer": "acme", "tier": "gold"}]"#).unwrap();
+
+        let mut transform = from_config(&format!(
+            r#"
+            enrichment_tables.tiers.path = {:?}
+            hooks.process = """function (event, emit)
+                enrichment_tables.tiers = nil
+            end
+            """
+            "#,
+            path
+        ))
+        .unwrap();
+
+        let err = transform
+            .process_single(Event::new_empty_log())
+            .unwrap_err();
+        assert!(format_error(&err).contains("enrichment_tables are read-only"));
+    }
+
+    #[test]
+    fn lua_hot_reload() {
+        trace_init();
+
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("greeting.lua");
+        fs::write(&module, r#"return { text = "hello" }"#).unwrap();
+
+        let mut transform = from_config(&format!(
+            r#"
+            search_dirs = [{:?}]
+            hot_reload = true
+            source = "greeting = require('greeting')"
+            hooks.process = """function (event, emit)
+                event.log.greeting = greeting.text
+                emit(event)
+            end
+            """
+            "#,
+            dir
+        ))
+        .unwrap();
+
+        let greeting = |transform: &mut Lua| {
+            let event = transform.transform(Event::new_empty_log()).unwrap();
+            event.as_log()[&"greeting".into()].clone()
+        };
+        assert_eq!(greeting(&mut transform), "hello".into());
+
+        // A broken module keeps the previous version running.
+        fs::write(&module, r#"return { text = "#).unwrap();
+        thread::sleep(Duration::from_secs(3));
+        assert_eq!(greeting(&mut transform), "hello".into());
+
+        fs::write(&module, r#"return { text = "goodbye" }"#).unwrap();
+        thread::sleep(Duration::from_secs(3));
+        assert_eq!(greeting(&mut transform), "goodbye".into());
+    }
 }
@@ -0,0 +1,226 @@
+use super::Transform;
+use crate::{
+    config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    event::{log_schema, Event, LogEvent, Value},
+    internal_events::{MetricToLogEventProcessed, MetricToLogFailedSerialize},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct MetricToLogConfig {
+    /// A tag whose value is moved to the `host` field of the log event.
+    pub host_tag: Option<String>,
+}
+
+inventory::submit! {
+    TransformDescription::new::<MetricToLogConfig>("metric_to_log")
+}
+
+#[typetag::serde(name = "metric_to_log")]
+impl TransformConfig for MetricToLogConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(MetricToLog::new(self.host_tag.clone())))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "metric_to_log"
+    }
+}
+
+pub struct MetricToLog {
+    host_tag: Option<String>,
+}
+
+impl MetricToLog {
+    pub fn new(host_tag: Option<String>) -> Self {
+        Self { host_tag }
+    }
+}
+
+impl Transform for MetricToLog {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        emit!(MetricToLogEventProcessed);
+
+        let mut metric = event.into_metric();
+        let host = match (&self.host_tag, metric.tags.as_mut()) {
+            (Some(host_tag), Some(tags)) => tags.remove(host_tag),
+            _ => None,
+        };
+        let timestamp = metric.timestamp.take().unwrap_or_else(Utc::now);
+
+        // The metric value is flattened into the serialized metric under a key
+        // named after its type, e.g. `counter.value` or
+        // `distribution.sample_rates`.
+        let fields = match serde_json::to_value(&metric) {
+            Ok(JsonValue::Object(fields)) => fields,
+            Ok(_) => unreachable!("metrics serialize to maps"),
+            Err(error) => {
+                emit!(MetricToLogFailedSerialize { error });
+                return None;
+            }
+        };
+
+        let mut log = LogEvent::default();
+        for (key, value) in fields {
+            if value.is_null() {
+                continue;
+            }
+            log.insert_flat(key, Value::from(value));
+        }
+        log.insert(log_schema().timestamp_key().clone(), timestamp);
+        if let Some(host) = host {
+            log.insert(log_schema().host_key().clone(), host);
+        }
+
+        Some(Event::Log(log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::metric::{Metric, MetricKind, MetricValue, StatisticKind};
+    use chrono::{offset::TimeZone, DateTime};
+    use std::collections::BTreeMap;
+
+    fn ts() -> DateTime<Utc> {
+        Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 11)
+    }
+
+    fn tags() -> BTreeMap<String, String> {
+        vec![
+            ("host".to_owned(), "localhost".to_owned()),
+            ("code".to_owned(), "200".to_owned()),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn transform(host_tag: Option<&str>, value: MetricValue) -> LogEvent {
+        let metric = Metric {
+            name: "requests".into(),
+            timestamp: Some(ts()),
+            tags: Some(tags()),
+            kind: MetricKind::Absolute,
+            value,
+        };
+        MetricToLog::new(host_tag.map(Into::into))
+            .transform(Event::Metric(metric))
+            .unwrap()
+            .into_log()
+    }
+
+    #[test]
+    fn transform_counter() {
+        let log = transform(None, MetricValue::Counter { value: 1.0 });
+
+        assert_eq!(log[&"name".into()], "requests".into());
+        assert_eq!(log[&"kind".into()], "absolute".into());
+        assert_eq!(log[&"counter.value".into()], 1.0.into());
+        assert_eq!(log[&"tags.host".into()], "localhost".into());
+        assert_eq!(log[&"tags.code".into()], "200".into());
+        assert_eq!(log[log_schema().timestamp_key()], ts().into());
+        assert!(log.get(log_schema().host_key()).is_none());
+    }
+
+    #[test]
+    fn transform_host_tag() {
+        let log = transform(Some("host"), MetricValue::Gauge { value: 2.0 });
+
+        assert_eq!(log[&"gauge.value".into()], 2.0.into());
+        assert_eq!(log[log_schema().host_key()], "localhost".into());
+        assert!(log.get(&"tags.host".into()).is_none());
+        assert_eq!(log[&"tags.code".into()], "200".into());
+    }
+
+    #[test]
+    fn transform_set() {
+        let log = transform(
+            None,
+            MetricValue::Set {
+                values: vec!["one".into(), "two".into()].into_iter().collect(),
+            },
+        );
+
+        assert_eq!(
+            log[&"set.values".into()],
+            Value::Array(vec!["one".into(), "two".into()])
+        );
+    }
+
+    #[test]
+    fn transform_distribution() {
+        let log = transform(
+            None,
+            MetricValue::Distribution {
+                values: vec![1.0, 2.0],
+                sample_rates: vec![10, 20],
+                statistic: StatisticKind::Histogram,
+            },
+        );
+
+        assert_eq!(
+            log[&"distribution.values".into()],
+            Value::Array(vec![1.0.into(), 2.0.into()])
+        );
+        assert_eq!(
+            log[&"distribution.sample_rates".into()],
+            Value::Array(vec![10.into(), 20.into()])
+        );
+        assert_eq!(log[&"distribution.statistic".into()], "histogram".into());
+    }
+
+    #[test]
+    fn transform_histogram() {
+        let log = transform(
+            None,
+            MetricValue::AggregatedHistogram {
+                buckets: vec![1.0, 2.0],
+                counts: vec![10, 20],
+                count: 30,
+                sum: 50.0,
+            },
+        );
+
+        assert_eq!(
+            log[&"aggregated_histogram.buckets".into()],
+            Value::Array(vec![1.0.into(), 2.0.into()])
+        );
+        assert_eq!(
+            log[&"aggregated_histogram.counts".into()],
+            Value::Array(vec![10.into(), 20.into()])
+        );
+        assert_eq!(log[&"aggregated_histogram.count".into()], 30.into());
+        assert_eq!(log[&"aggregated_histogram.sum".into()], 50.0.into());
+    }
+
+    #[test]
+    fn transform_summary() {
+        let log = transform(
+            None,
+            MetricValue::AggregatedSummary {
+                quantiles: vec![0.5, 0.9],
+                values: vec![1.0, 2.0],
+                count: 30,
+                sum: 50.0,
+            },
+        );
+
+        assert_eq!(
+            log[&"aggregated_summary.quantiles".into()],
+            Value::Array(vec![0.5.into(), 0.9.into()])
+        );
+        assert_eq!(log[&"aggregated_summary.count".into()], 30.into());
+    }
+}
@@ -0,0 +1,557 @@
+use super::Transform;
+use crate::{
+    config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    event::metric::{Metric, MetricKind, MetricValue},
+    event::Event,
+    internal_events::{MetricTransformEventProcessed, MetricTransformTemplateRenderingError},
+    template::{Template, TemplateError},
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::mem::discriminant;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MetricTransformConfig {
+    pub operations: Vec<OperationConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OperationConfig {
+    /// Restricts the operation to metrics whose name matches this regex.
+    #[serde(default, rename = "match")]
+    pub names: Option<String>,
+    #[serde(flatten)]
+    pub action: ActionConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionConfig {
+    Rename {
+        pattern: String,
+        replacement: String,
+    },
+    RenameTag {
+        from: String,
+        to: String,
+    },
+    CopyTag {
+        from: String,
+        to: String,
+    },
+    SetTag {
+        tag: String,
+        value: String,
+    },
+    Scale {
+        factor: f64,
+    },
+    ToAbsolute,
+    ToIncremental,
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid regex {:?}: {}", pattern, source))]
+    InvalidRegex {
+        pattern: String,
+        source: regex::Error,
+    },
+    #[snafu(display("Invalid template {:?}: {}", template, source))]
+    InvalidTemplate {
+        template: String,
+        source: TemplateError,
+    },
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<MetricTransformConfig>("metric_transform")
+}
+
+#[typetag::serde(name = "metric_transform")]
+impl TransformConfig for MetricTransformConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        let operations = self
+            .operations
+            .iter()
+            .map(Operation::new)
+            .collect::<Result<_, _>>()?;
+        Ok(Box::new(MetricTransform { operations }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "metric_transform"
+    }
+}
+
+fn regex(pattern: &str) -> Result<Regex, BuildError> {
+    Regex::new(pattern).context(InvalidRegex { pattern })
+}
+
+type SeriesKey = (String, Option<BTreeMap<String, String>>);
+
+fn series_key(metric: &Metric) -> SeriesKey {
+    (metric.name.clone(), metric.tags.clone())
+}
+
+struct Operation {
+    names: Option<Regex>,
+    action: Action,
+}
+
+enum Action {
+    Rename { pattern: Regex, replacement: String },
+    RenameTag { from: String, to: String },
+    CopyTag { from: String, to: String },
+    SetTag { tag: String, value: Template },
+    Scale { factor: f64 },
+    ToAbsolute { state: HashMap<SeriesKey, Metric> },
+    ToIncremental { state: HashMap<SeriesKey, Metric> },
+}
+
+impl Operation {
+    fn new(config: &OperationConfig) -> Result<Self, BuildError> {
+        let names = config.names.as_deref().map(regex).transpose()?;
+        let action = match &config.action {
+            ActionConfig::Rename {
+                pattern,
+                replacement,
+            } => Action::Rename {
+                pattern: regex(pattern)?,
+                replacement: replacement.clone(),
+            },
+            ActionConfig::RenameTag { from, to } => Action::RenameTag {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            ActionConfig::CopyTag { from, to } => Action::CopyTag {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            ActionConfig::SetTag { tag, value } => Action::SetTag {
+                tag: tag.clone(),
+                value: Template::try_from(value.as_str())
+                    .context(InvalidTemplate { template: value })?,
+            },
+            ActionConfig::Scale { factor } => Action::Scale { factor: *factor },
+            ActionConfig::ToAbsolute => Action::ToAbsolute {
+                state: HashMap::new(),
+            },
+            ActionConfig::ToIncremental => Action::ToIncremental {
+                state: HashMap::new(),
+            },
+        };
+        Ok(Self { names, action })
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        if let Some(names) = &self.names {
+            if !names.is_match(&event.as_metric().name) {
+                return Some(event);
+            }
+        }
+
+        let mut event = event;
+        match &mut self.action {
+            Action::Rename {
+                pattern,
+                replacement,
+            } => {
+                let metric = event.as_mut_metric();
+                metric.name = pattern
+                    .replace_all(&metric.name, replacement.as_str())
+                    .into_owned();
+            }
+            Action::RenameTag { from, to } => {
+                if let Some(tags) = event.as_mut_metric().tags.as_mut() {
+                    if let Some(value) = tags.remove(from.as_str()) {
+                        tags.insert(to.clone(), value);
+                    }
+                }
+            }
+            Action::CopyTag { from, to } => {
+                if let Some(tags) = event.as_mut_metric().tags.as_mut() {
+                    if let Some(value) = tags.get(from.as_str()).cloned() {
+                        tags.insert(to.clone(), value);
+                    }
+                }
+            }
+            Action::SetTag { tag, value } => match value.render_string(&event) {
+                Ok(value) => {
+                    event
+                        .as_mut_metric()
+                        .tags
+                        .get_or_insert_with(BTreeMap::new)
+                        .insert(tag.clone(), value);
+                }
+                Err(missing_keys) => {
+                    emit!(MetricTransformTemplateRenderingError { tag, missing_keys });
+                }
+            },
+            Action::Scale { factor } => scale(&mut event.as_mut_metric().value, *factor),
+            Action::ToAbsolute { state } => return to_absolute(state, event.into_metric()),
+            Action::ToIncremental { state } => return to_incremental(state, event.into_metric()),
+        }
+        Some(event)
+    }
+}
+
+fn scale(value: &mut MetricValue, factor: f64) {
+    match value {
+        MetricValue::Counter { value } | MetricValue::Gauge { value } => *value *= factor,
+        MetricValue::Set { .. } => (),
+        MetricValue::Distribution { values, .. } => {
+            values.iter_mut().for_each(|value| *value *= factor)
+        }
+        MetricValue::AggregatedHistogram { buckets, sum, .. } => {
+            buckets.iter_mut().for_each(|bucket| *bucket *= factor);
+            *sum *= factor;
+        }
+        MetricValue::AggregatedSummary { values, sum, .. } => {
+            values.iter_mut().for_each(|value| *value *= factor);
+            *sum *= factor;
+        }
+    }
+}
+
+/// Only values with a bounded accumulated state are converted between kinds;
+/// sets and distributions pass through untouched.
+fn is_convertible(value: &MetricValue) -> bool {
+    matches!(
+        value,
+        MetricValue::Counter { .. }
+            | MetricValue::Gauge { .. }
+            | MetricValue::AggregatedHistogram { .. }
+    )
+}
+
+fn to_absolute(state: &mut HashMap<SeriesKey, Metric>, metric: Metric) -> Option<Event> {
+    if metric.kind.is_absolute() || !is_convertible(&metric.value) {
+        return Some(Event::Metric(metric));
+    }
+
+    let total = state
+        .entry(series_key(&metric))
+        .and_modify(|total| {
+            if discriminant(&total.value) == discriminant(&metric.value) {
+                total.add(&metric);
+                total.timestamp = metric.timestamp;
+            } else {
+                *total = metric.to_absolute();
+            }
+        })
+        .or_insert_with(|| metric.to_absolute());
+    Some(Event::Metric(total.clone()))
+}
+
+fn to_incremental(state: &mut HashMap<SeriesKey, Metric>, metric: Metric) -> Option<Event> {
+    if metric.kind.is_incremental() || !is_convertible(&metric.value) {
+        return Some(Event::Metric(metric));
+    }
+
+    // The first value of a series only establishes the baseline.
+    let previous = state.insert(series_key(&metric), metric.clone())?;
+    let value = match (metric.value, previous.value) {
+        (MetricValue::Counter { value }, MetricValue::Counter { value: previous }) => {
+            // A decreasing counter has been reset, so its whole value is new.
+            MetricValue::Counter {
+                value: if value >= previous {
+                    value - previous
+                } else {
+                    value
+                },
+            }
+        }
+        (MetricValue::Gauge { value }, MetricValue::Gauge { value: previous }) => {
+            MetricValue::Gauge {
+                value: value - previous,
+            }
+        }
+        (
+            MetricValue::AggregatedHistogram {
+                buckets,
+                counts,
+                count,
+                sum,
+            },
+            MetricValue::AggregatedHistogram {
+                buckets: previous_buckets,
+                counts: previous_counts,
+                count: previous_count,
+                sum: previous_sum,
+            },
+        ) => {
+            if buckets == previous_buckets && count >= previous_count {
+                MetricValue::AggregatedHistogram {
+                    counts: counts
+                        .iter()
+                        .zip(previous_counts.iter())
+                        .map(|(count, previous)| count.saturating_sub(*previous))
+                        .collect(),
+                    buckets,
+                    count: count - previous_count,
+                    sum: sum - previous_sum,
+                }
+            } else {
+                MetricValue::AggregatedHistogram {
+                    buckets,
+                    counts,
+                    count,
+                    sum,
+                }
+            }
+        }
+        // The series changed type, so start over from this value.
+        _ => return None,
+    };
+
+    Some(Event::Metric(Metric {
+        name: metric.name,
+        timestamp: metric.timestamp,
+        tags: metric.tags,
+        kind: MetricKind::Incremental,
+        value,
+    }))
+}
+
+pub struct MetricTransform {
+    operations: Vec<Operation>,
+}
+
+impl Transform for MetricTransform {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        emit!(MetricTransformEventProcessed);
+
+        self.operations
+            .iter_mut()
+            .try_fold(event, |event, operation| operation.apply(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(config: &str) -> Box<dyn Transform> {
+        toml::from_str::<MetricTransformConfig>(config)
+            .unwrap()
+            .build(TransformContext::new_test())
+            .unwrap()
+    }
+
+    fn metric(name: &str, kind: MetricKind, value: MetricValue) -> Event {
+        Event::Metric(Metric {
+            name: name.into(),
+            timestamp: None,
+            tags: Some(
+                vec![("host".to_owned(), "localhost".to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            kind,
+            value,
+        })
+    }
+
+    fn counter(kind: MetricKind, value: f64) -> Event {
+        metric("requests", kind, MetricValue::Counter { value })
+    }
+
+    fn value(event: Option<Event>) -> MetricValue {
+        event.unwrap().into_metric().value
+    }
+
+    #[test]
+    fn renames_metrics() {
+        let mut transform = transform(
+            r#"
+            [[operations]]
+            type = "rename"
+            pattern = "^(.+)_ms$"
+            replacement = "${1}_seconds"
+            "#,
+        );
+
+        let event = metric(
+            "latency_ms",
+            MetricKind::Incremental,
+            MetricValue::Gauge { value: 1.0 },
+        );
+        let metric = transform.transform(event).unwrap().into_metric();
+        assert_eq!(metric.name, "latency_seconds");
+
+        let metric = transform
+            .transform(counter(MetricKind::Incremental, 1.0))
+            .unwrap()
+            .into_metric();
+        assert_eq!(metric.name, "requests");
+    }
+
+    #[test]
+    fn edits_tags() {
+        let mut transform = transform(
+            r#"
+            [[operations]]
+            type = "copy_tag"
+            from = "host"
+            to = "source"
+
+            [[operations]]
+            type = "rename_tag"
+            from = "host"
+            to = "hostname"
+
+            [[operations]]
+            type = "set_tag"
+            tag = "series"
+            value = "{{ name }}@{{ tags.hostname }}"
+
+            [[operations]]
+            type = "set_tag"
+            tag = "missing"
+            value = "{{ tags.host }}"
+            "#,
+        );
+
+        let metric = transform
+            .transform(counter(MetricKind::Incremental, 1.0))
+            .unwrap()
+            .into_metric();
+        let tags = metric.tags.unwrap();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags["source"], "localhost");
+        assert_eq!(tags["hostname"], "localhost");
+        assert_eq!(tags["series"], "requests@localhost");
+    }
+
+    #[test]
+    fn scales_matching_metrics() {
+        let mut transform = transform(
+            r#"
+            [[operations]]
+            match = "_ms$"
+            type = "scale"
+            factor = 0.5
+            "#,
+        );
+
+        let event = metric(
+            "latency_ms",
+            MetricKind::Incremental,
+            MetricValue::AggregatedHistogram {
+                buckets: vec![100.0, 1000.0],
+                counts: vec![1, 2],
+                count: 3,
+                sum: 1500.0,
+            },
+        );
+        assert_eq!(
+            value(transform.transform(event)),
+            MetricValue::AggregatedHistogram {
+                buckets: vec![50.0, 500.0],
+                counts: vec![1, 2],
+                count: 3,
+                sum: 750.0,
+            }
+        );
+
+        assert_eq!(
+            value(transform.transform(counter(MetricKind::Incremental, 1000.0))),
+            MetricValue::Counter { value: 1000.0 }
+        );
+    }
+
+    #[test]
+    fn converts_to_absolute() {
+        let mut transform = transform(
+            r#"
+            [[operations]]
+            type = "to_absolute"
+            "#,
+        );
+
+        for (input, total) in &[(1.0, 1.0), (2.0, 3.0), (4.0, 7.0)] {
+            let metric = transform
+                .transform(counter(MetricKind::Incremental, *input))
+                .unwrap()
+                .into_metric();
+            assert_eq!(metric.kind, MetricKind::Absolute);
+            assert_eq!(metric.value, MetricValue::Counter { value: *total });
+        }
+
+        let mut other = counter(MetricKind::Incremental, 5.0);
+        other.as_mut_metric().tags = None;
+        assert_eq!(
+            value(transform.transform(other)),
+            MetricValue::Counter { value: 5.0 }
+        );
+    }
+
+    #[test]
+    fn converts_to_incremental() {
+        let mut transform = transform(
+            r#"
+            [[operations]]
+            type = "to_incremental"
+            "#,
+        );
+
+        assert!(transform
+            .transform(counter(MetricKind::Absolute, 10.0))
+            .is_none());
+
+        let metric = transform
+            .transform(counter(MetricKind::Absolute, 15.0))
+            .unwrap()
+            .into_metric();
+        assert_eq!(metric.kind, MetricKind::Incremental);
+        assert_eq!(metric.value, MetricValue::Counter { value: 5.0 });
+
+        // The counter was reset.
+        assert_eq!(
+            value(transform.transform(counter(MetricKind::Absolute, 3.0))),
+            MetricValue::Counter { value: 3.0 }
+        );
+
+        let gauge = |value| metric("memory", MetricKind::Absolute, MetricValue::Gauge { value });
+        assert!(transform.transform(gauge(10.0)).is_none());
+        assert_eq!(
+            value(transform.transform(gauge(4.0))),
+            MetricValue::Gauge { value: -6.0 }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        let config = toml::from_str::<MetricTransformConfig>(
+            r#"
+            [[operations]]
+            type = "rename"
+            pattern = "(["
+            replacement = ""
+            "#,
+        )
+        .unwrap();
+        assert!(config.build(TransformContext::new_test()).is_err());
+
+        assert!(toml::from_str::<MetricTransformConfig>(
+            r#"
+            [[operations]]
+            type = "explode"
+            "#,
+        )
+        .is_err());
+    }
+}
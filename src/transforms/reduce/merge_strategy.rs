@@ -12,6 +12,13 @@ pub enum MergeStrategy {
     Min,
     Array,
     Concat,
+    ConcatNewline,
+    ConcatRaw,
+    Retain,
+    FlatUnique,
+    ShortestArray,
+    LongestArray,
+    TimestampRange,
 }
 
 //------------------------------------------------------------------------------
@@ -40,15 +47,42 @@ impl ReduceValueMerger for DiscardMerger {
 
 //------------------------------------------------------------------------------
 
+#[derive(Debug, Clone)]
+struct RetainMerger {
+    v: Value,
+}
+
+impl RetainMerger {
+    fn new(v: Value) -> Self {
+        Self { v }
+    }
+}
+
+impl ReduceValueMerger for RetainMerger {
+    fn add(&mut self, v: Value) -> Result<(), String> {
+        self.v = v;
+        Ok(())
+    }
+
+    fn insert_into(self: Box<Self>, k: String, v: &mut LogEvent) -> Result<(), String> {
+        v.insert(k, self.v);
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+
 #[derive(Debug, Clone)]
 struct ConcatMerger {
     v: BytesMut,
+    separator: &'static [u8],
 }
 
 impl ConcatMerger {
-    fn new(v: Bytes) -> Self {
+    fn new(v: Bytes, separator: &'static [u8]) -> Self {
         Self {
             v: BytesMut::from(&v[..]),
+            separator,
         }
     }
 }
@@ -56,7 +90,7 @@ impl ConcatMerger {
 impl ReduceValueMerger for ConcatMerger {
     fn add(&mut self, v: Value) -> Result<(), String> {
         if let Value::Bytes(b) = v {
-            self.v.extend(&[b' ']);
+            self.v.extend_from_slice(self.separator);
             self.v.extend_from_slice(&b);
             Ok(())
         } else {
@@ -129,6 +163,122 @@ impl ReduceValueMerger for ArrayMerger {
 
 //------------------------------------------------------------------------------
 
+#[derive(Debug, Clone)]
+struct FlatUniqueMerger {
+    v: Vec<Value>,
+}
+
+impl FlatUniqueMerger {
+    fn new(v: Value) -> Self {
+        let mut merger = Self { v: Vec::new() };
+        merger.insert(v);
+        merger
+    }
+
+    fn insert(&mut self, v: Value) {
+        match v {
+            Value::Array(a) => a.into_iter().for_each(|v| self.insert(v)),
+            v => {
+                if !self.v.contains(&v) {
+                    self.v.push(v);
+                }
+            }
+        }
+    }
+}
+
+impl ReduceValueMerger for FlatUniqueMerger {
+    fn add(&mut self, v: Value) -> Result<(), String> {
+        self.insert(v);
+        Ok(())
+    }
+
+    fn insert_into(self: Box<Self>, k: String, v: &mut LogEvent) -> Result<(), String> {
+        v.insert(k, Value::Array(self.v));
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct ArrayLengthMerger {
+    v: Vec<Value>,
+    longest: bool,
+}
+
+impl ArrayLengthMerger {
+    fn new(v: Vec<Value>, longest: bool) -> Self {
+        Self { v, longest }
+    }
+}
+
+impl ReduceValueMerger for ArrayLengthMerger {
+    fn add(&mut self, v: Value) -> Result<(), String> {
+        if let Value::Array(a) = v {
+            // Ties keep the earliest array.
+            let replace = if self.longest {
+                a.len() > self.v.len()
+            } else {
+                a.len() < self.v.len()
+            };
+            if replace {
+                self.v = a;
+            }
+            Ok(())
+        } else {
+            Err(format!(
+                "expected array value, found: '{}'",
+                v.to_string_lossy()
+            ))
+        }
+    }
+
+    fn insert_into(self: Box<Self>, k: String, v: &mut LogEvent) -> Result<(), String> {
+        v.insert(k, Value::Array(self.v));
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+struct TimestampRangeMerger {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+impl TimestampRangeMerger {
+    fn new(v: DateTime<Utc>) -> Self {
+        Self { start: v, end: v }
+    }
+}
+
+impl ReduceValueMerger for TimestampRangeMerger {
+    fn add(&mut self, v: Value) -> Result<(), String> {
+        if let Value::Timestamp(ts) = v {
+            // Events are not guaranteed to arrive in order, so track the
+            // earliest and latest timestamps rather than the first and last.
+            self.start = self.start.min(ts);
+            self.end = self.end.max(ts);
+            Ok(())
+        } else {
+            Err(format!(
+                "expected timestamp value, found: '{}'",
+                v.to_string_lossy()
+            ))
+        }
+    }
+
+    fn insert_into(self: Box<Self>, k: String, v: &mut LogEvent) -> Result<(), String> {
+        v.insert(format!("{}_start", k), Value::Timestamp(self.start));
+        v.insert(format!("{}_end", k), Value::Timestamp(self.end));
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+
 #[derive(Debug, Clone)]
 struct TimestampWindowMerger {
     started: DateTime<Utc>,
@@ -400,14 +550,51 @@ pub fn get_value_merger(v: Value, m: &MergeStrategy) -> Result<Box<dyn ReduceVal
             )),
         },
         MergeStrategy::Concat => match v {
-            Value::Bytes(b) => Ok(Box::new(ConcatMerger::new(b))),
+            Value::Bytes(b) => Ok(Box::new(ConcatMerger::new(b, b" "))),
             Value::Array(a) => Ok(Box::new(ConcatArrayMerger::new(a))),
             _ => Err(format!(
                 "expected string or array value, found: '{}'",
                 v.to_string_lossy()
             )),
         },
+        MergeStrategy::ConcatNewline => match v {
+            Value::Bytes(b) => Ok(Box::new(ConcatMerger::new(b, b"\n"))),
+            _ => Err(format!(
+                "expected string value, found: '{}'",
+                v.to_string_lossy()
+            )),
+        },
+        MergeStrategy::ConcatRaw => match v {
+            Value::Bytes(b) => Ok(Box::new(ConcatMerger::new(b, b""))),
+            _ => Err(format!(
+                "expected string value, found: '{}'",
+                v.to_string_lossy()
+            )),
+        },
+        MergeStrategy::ShortestArray => match v {
+            Value::Array(a) => Ok(Box::new(ArrayLengthMerger::new(a, false))),
+            _ => Err(format!(
+                "expected array value, found: '{}'",
+                v.to_string_lossy()
+            )),
+        },
+        MergeStrategy::LongestArray => match v {
+            Value::Array(a) => Ok(Box::new(ArrayLengthMerger::new(a, true))),
+            _ => Err(format!(
+                "expected array value, found: '{}'",
+                v.to_string_lossy()
+            )),
+        },
+        MergeStrategy::TimestampRange => match v {
+            Value::Timestamp(ts) => Ok(Box::new(TimestampRangeMerger::new(ts))),
+            _ => Err(format!(
+                "expected timestamp value, found: '{}'",
+                v.to_string_lossy()
+            )),
+        },
         MergeStrategy::Array => Ok(Box::new(ArrayMerger::new(v))),
+        MergeStrategy::FlatUnique => Ok(Box::new(FlatUniqueMerger::new(v))),
+        MergeStrategy::Retain => Ok(Box::new(RetainMerger::new(v))),
         MergeStrategy::Discard => Ok(Box::new(DiscardMerger::new(v))),
     }
 }
@@ -476,6 +663,29 @@ mod test {
         assert!(get_value_merger(json!(null).into(), &MergeStrategy::Min).is_err());
         assert!(get_value_merger(json!(null).into(), &MergeStrategy::Array).is_ok());
         assert!(get_value_merger(json!(null).into(), &MergeStrategy::Concat).is_err());
+
+        for strategy in &[MergeStrategy::Retain, MergeStrategy::FlatUnique] {
+            assert!(get_value_merger("foo".into(), strategy).is_ok());
+            assert!(get_value_merger(42.into(), strategy).is_ok());
+            assert!(get_value_merger(json!([]).into(), strategy).is_ok());
+            assert!(get_value_merger(json!(null).into(), strategy).is_ok());
+        }
+
+        for strategy in &[MergeStrategy::ConcatNewline, MergeStrategy::ConcatRaw] {
+            assert!(get_value_merger("foo".into(), strategy).is_ok());
+            assert!(get_value_merger(42.into(), strategy).is_err());
+            assert!(get_value_merger(json!([]).into(), strategy).is_err());
+        }
+
+        for strategy in &[MergeStrategy::ShortestArray, MergeStrategy::LongestArray] {
+            assert!(get_value_merger(json!([]).into(), strategy).is_ok());
+            assert!(get_value_merger("foo".into(), strategy).is_err());
+            assert!(get_value_merger(42.into(), strategy).is_err());
+        }
+
+        assert!(get_value_merger(Utc::now().into(), &MergeStrategy::TimestampRange).is_ok());
+        assert!(get_value_merger("foo".into(), &MergeStrategy::TimestampRange).is_err());
+        assert!(get_value_merger(42.into(), &MergeStrategy::TimestampRange).is_err());
     }
 
     #[test]
@@ -550,6 +760,88 @@ mod test {
             merge(json!([]).into(), 42.into(), &MergeStrategy::Concat),
             Ok(json!([42]).into())
         );
+
+        assert_eq!(
+            merge("foo".into(), "bar".into(), &MergeStrategy::ConcatNewline),
+            Ok("foo\nbar".into())
+        );
+        assert_eq!(
+            merge("foo".into(), "bar".into(), &MergeStrategy::ConcatRaw),
+            Ok("foobar".into())
+        );
+        assert!(merge("foo".into(), 42.into(), &MergeStrategy::ConcatRaw).is_err());
+
+        assert_eq!(
+            merge("foo".into(), 42.into(), &MergeStrategy::Retain),
+            Ok(42.into())
+        );
+        assert_eq!(
+            merge(json!(null).into(), "bar".into(), &MergeStrategy::Retain),
+            Ok("bar".into())
+        );
+
+        assert_eq!(
+            merge(
+                json!(["foo", 42]).into(),
+                json!(["bar", "foo"]).into(),
+                &MergeStrategy::FlatUnique
+            ),
+            Ok(json!(["foo", 42, "bar"]).into())
+        );
+        assert_eq!(
+            merge("foo".into(), "foo".into(), &MergeStrategy::FlatUnique),
+            Ok(json!(["foo"]).into())
+        );
+
+        assert_eq!(
+            merge(
+                json!([1, 2]).into(),
+                json!([3]).into(),
+                &MergeStrategy::ShortestArray
+            ),
+            Ok(json!([3]).into())
+        );
+        assert_eq!(
+            merge(
+                json!([1, 2]).into(),
+                json!([3]).into(),
+                &MergeStrategy::LongestArray
+            ),
+            Ok(json!([1, 2]).into())
+        );
+        assert_eq!(
+            merge(
+                json!([1]).into(),
+                json!([2]).into(),
+                &MergeStrategy::LongestArray
+            ),
+            Ok(json!([1]).into())
+        );
+        assert!(merge(
+            json!([]).into(),
+            "foo".into(),
+            &MergeStrategy::ShortestArray
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn merging_timestamp_range() {
+        let earliest = Utc::now();
+        let middle = earliest + chrono::Duration::seconds(1);
+        let latest = earliest + chrono::Duration::seconds(2);
+
+        let mut merger = get_value_merger(middle.into(), &MergeStrategy::TimestampRange).unwrap();
+        merger.add(latest.into()).unwrap();
+        merger.add(earliest.into()).unwrap();
+        assert!(merger.add("foo".into()).is_err());
+
+        let mut output = Event::new_empty_log();
+        let output = output.as_mut_log();
+        merger.insert_into("ts".into(), output).unwrap();
+        assert_eq!(output[&"ts_start".into()], earliest.into());
+        assert_eq!(output[&"ts_end".into()], latest.into());
+        assert!(output.get(&"ts".into()).is_none());
     }
 
     fn merge(initial: Value, additional: Value, strategy: &MergeStrategy) -> Result<Value, String> {
@@ -3,7 +3,7 @@ use crate::{
     conditions::{AnyCondition, Condition},
     config::{DataType, TransformConfig, TransformContext, TransformDescription},
     event::discriminant::Discriminant,
-    event::{Event, LogEvent},
+    event::{Event, EventFinalizers, LogEvent},
 };
 use async_stream::stream;
 use futures::{
@@ -41,6 +41,18 @@ pub struct ReduceConfig {
     /// An optional condition that determines when an event is the end of a
     /// reduce.
     pub ends_when: Option<AnyCondition>,
+
+    /// An optional condition that determines when an event is the start of a
+    /// new reduce, flushing any reduce already in progress.
+    pub starts_when: Option<AnyCondition>,
+
+    /// The maximum number of events merged into a single reduce before it is
+    /// flushed.
+    pub max_events: Option<usize>,
+
+    /// The maximum size in bytes of the events merged into a single reduce
+    /// before it is flushed, measured by their JSON encoding.
+    pub max_bytes: Option<usize>,
 }
 
 inventory::submit! {
@@ -71,12 +83,20 @@ impl TransformConfig for ReduceConfig {
 struct ReduceState {
     fields: HashMap<String, Box<dyn ReduceValueMerger>>,
     stale_since: Instant,
+    events: usize,
+    bytes: usize,
+    /// The finalizers of the merged events, handed over to the flushed event
+    /// so that they are only resolved once it is delivered.
+    finalizers: EventFinalizers,
 }
 
 impl ReduceState {
-    fn new(e: LogEvent, strategies: &IndexMap<String, MergeStrategy>) -> Self {
+    fn new(mut e: LogEvent, bytes: usize, strategies: &IndexMap<String, MergeStrategy>) -> Self {
         Self {
             stale_since: Instant::now(),
+            events: 1,
+            bytes,
+            finalizers: e.take_finalizers(),
             fields: e
                 .into_iter()
                 .filter_map(|(k, v)| {
@@ -96,7 +116,13 @@ impl ReduceState {
         }
     }
 
-    fn add_event(&mut self, e: LogEvent, strategies: &IndexMap<String, MergeStrategy>) {
+    fn add_event(
+        &mut self,
+        mut e: LogEvent,
+        bytes: usize,
+        strategies: &IndexMap<String, MergeStrategy>,
+    ) {
+        self.finalizers.merge(e.take_finalizers());
         for (k, v) in e.into_iter() {
             let strategy = strategies.get(&k);
             match self.fields.entry(k) {
@@ -122,10 +148,18 @@ impl ReduceState {
             }
         }
         self.stale_since = Instant::now();
+        self.events += 1;
+        self.bytes += bytes;
+    }
+
+    fn is_full(&self, max_events: Option<usize>, max_bytes: Option<usize>) -> bool {
+        max_events.map_or(false, |max| self.events >= max)
+            || max_bytes.map_or(false, |max| self.bytes >= max)
     }
 
     fn flush(mut self) -> LogEvent {
         let mut event = Event::new_empty_log().into_log();
+        event.add_finalizers(std::mem::take(&mut self.finalizers));
         for (k, v) in self.fields.drain() {
             if let Err(err) = v.insert_into(k, &mut event) {
                 warn!("failed to merge values for field: {}", err);
@@ -144,6 +178,9 @@ pub struct Reduce {
     merge_strategies: IndexMap<String, MergeStrategy>,
     reduce_merge_states: HashMap<Discriminant, ReduceState>,
     ends_when: Option<Box<dyn Condition>>,
+    starts_when: Option<Box<dyn Condition>>,
+    max_events: Option<usize>,
+    max_bytes: Option<usize>,
 }
 
 impl Reduce {
@@ -153,6 +190,18 @@ impl Reduce {
         } else {
             None
         };
+        let starts_when = if let Some(starts_conf) = &config.starts_when {
+            Some(starts_conf.build()?)
+        } else {
+            None
+        };
+
+        if config.max_events == Some(0) {
+            return Err("`max_events` must be greater than zero".into());
+        }
+        if config.max_bytes == Some(0) {
+            return Err("`max_bytes` must be greater than zero".into());
+        }
 
         let identifier_fields = config
             .identifier_fields
@@ -168,9 +217,20 @@ impl Reduce {
             merge_strategies: config.merge_strategies.clone(),
             reduce_merge_states: HashMap::new(),
             ends_when,
+            starts_when,
+            max_events: config.max_events,
+            max_bytes: config.max_bytes,
         })
     }
 
+    fn event_bytes(&self, event: &LogEvent) -> usize {
+        if self.max_bytes.is_some() {
+            serde_json::to_vec(event).map_or(0, |encoded| encoded.len())
+        } else {
+            0
+        }
+    }
+
     fn flush_into(&mut self, output: &mut Vec<Event>) {
         let mut flush_discriminants = Vec::new();
         for (k, t) in &self.reduce_merge_states {
@@ -206,26 +266,47 @@ impl Transform for Reduce {
             .as_ref()
             .map(|c| c.check(&event))
             .unwrap_or(false);
+        let starts_here = self
+            .starts_when
+            .as_ref()
+            .map(|c| c.check(&event))
+            .unwrap_or(false);
 
         let event = event.into_log();
         let discriminant = Discriminant::from_log_event(&event, &self.identifier_fields);
+        let bytes = self.event_bytes(&event);
+
+        if starts_here {
+            if let Some(state) = self.reduce_merge_states.remove(&discriminant) {
+                output.push(Event::from(state.flush()));
+            }
+        }
 
         if ends_here {
             output.push(Event::from(
                 if let Some(mut state) = self.reduce_merge_states.remove(&discriminant) {
-                    state.add_event(event, &self.merge_strategies);
+                    state.add_event(event, bytes, &self.merge_strategies);
                     state.flush()
                 } else {
-                    ReduceState::new(event, &self.merge_strategies).flush()
+                    ReduceState::new(event, bytes, &self.merge_strategies).flush()
                 },
             ));
         } else {
-            match self.reduce_merge_states.entry(discriminant) {
+            let full = match self.reduce_merge_states.entry(discriminant.clone()) {
                 hash_map::Entry::Vacant(entry) => {
-                    entry.insert(ReduceState::new(event, &self.merge_strategies));
+                    let state = ReduceState::new(event, bytes, &self.merge_strategies);
+                    entry.insert(state).is_full(self.max_events, self.max_bytes)
                 }
                 hash_map::Entry::Occupied(mut entry) => {
-                    entry.get_mut().add_event(event, &self.merge_strategies);
+                    entry
+                        .get_mut()
+                        .add_event(event, bytes, &self.merge_strategies);
+                    entry.get().is_full(self.max_events, self.max_bytes)
+                }
+            };
+            if full {
+                if let Some(state) = self.reduce_merge_states.remove(&discriminant) {
+                    output.push(Event::from(state.flush()));
                 }
             }
         }
@@ -287,9 +368,10 @@ mod test {
     use super::ReduceConfig;
     use crate::{
         config::{TransformConfig, TransformContext},
-        event::Value,
+        event::{BatchNotifier, EventStatus, Value},
         Event,
     };
+    use futures::FutureExt;
     use serde_json::json;
 
     #[test]
@@ -575,4 +657,153 @@ merge_strategies.bar = "concat"
             json!([2, 4, 6, 8, "done"]).into()
         );
     }
+
+    #[test]
+    fn reduce_from_start_condition() {
+        let mut reduce = toml::from_str::<ReduceConfig>(
+            r#"
+identifier_fields = [ "request_id" ]
+
+merge_strategies.message = "concat_newline"
+
+[starts_when]
+  "test_start.exists" = true
+"#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test())
+        .unwrap();
+
+        let mut outputs = Vec::new();
+
+        let mut e = Event::from("first 1");
+        e.as_mut_log().insert("request_id", "1");
+        e.as_mut_log().insert("test_start", "yep");
+        reduce.transform_into(&mut outputs, e);
+
+        let mut e = Event::from("first 2");
+        e.as_mut_log().insert("request_id", "1");
+        reduce.transform_into(&mut outputs, e);
+
+        let mut e = Event::from("other");
+        e.as_mut_log().insert("request_id", "2");
+        e.as_mut_log().insert("test_start", "yep");
+        reduce.transform_into(&mut outputs, e);
+
+        assert!(outputs.is_empty());
+
+        let mut e = Event::from("second 1");
+        e.as_mut_log().insert("request_id", "1");
+        e.as_mut_log().insert("test_start", "yep");
+        reduce.transform_into(&mut outputs, e);
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(
+            outputs.first().unwrap().as_log()[&"message".into()],
+            "first 1\nfirst 2".into()
+        );
+
+        outputs.clear();
+        reduce.flush_all_into(&mut outputs);
+
+        let mut messages = outputs
+            .iter()
+            .map(|e| e.as_log()[&"message".into()].to_string_lossy())
+            .collect::<Vec<_>>();
+        messages.sort();
+        assert_eq!(messages, vec!["other", "second 1"]);
+    }
+
+    #[test]
+    fn reduce_max_events() {
+        let mut reduce = toml::from_str::<ReduceConfig>(
+            r#"
+identifier_fields = [ "request_id" ]
+max_events = 2
+"#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test())
+        .unwrap();
+
+        let mut outputs = Vec::new();
+        for counter in 1..=5 {
+            let mut e = Event::from("test message");
+            e.as_mut_log().insert("counter", counter);
+            e.as_mut_log().insert("request_id", "1");
+            reduce.transform_into(&mut outputs, e);
+        }
+
+        let counters = outputs
+            .iter()
+            .map(|e| e.as_log()[&"counter".into()].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(counters, vec![Value::from(3), Value::from(7)]);
+    }
+
+    #[test]
+    fn reduce_carries_finalizers() {
+        let mut reduce = toml::from_str::<ReduceConfig>(
+            r#"
+identifier_fields = [ "request_id" ]
+max_events = 2
+"#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test())
+        .unwrap();
+
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let mut outputs = Vec::new();
+        for _ in 0..2 {
+            let mut e = Event::from("test message");
+            e.as_mut_log().insert("request_id", "1");
+            e.add_batch_notifier(batch.clone());
+            reduce.transform_into(&mut outputs, e);
+        }
+        drop(batch);
+
+        // The batch is only resolved once the reduced event is delivered.
+        assert_eq!(outputs.len(), 1);
+        assert_eq!((&mut receiver).now_or_never(), None);
+
+        let finalizers = outputs.pop().unwrap().take_finalizers();
+        finalizers.update_status(EventStatus::Delivered);
+        drop(finalizers);
+        assert_eq!(receiver.now_or_never(), Some(EventStatus::Delivered));
+    }
+
+    #[test]
+    fn reduce_max_bytes() {
+        let mut reduce = toml::from_str::<ReduceConfig>(
+            r#"
+max_bytes = 150
+merge_strategies.message = "concat_raw"
+"#,
+        )
+        .unwrap()
+        .build(TransformContext::new_test())
+        .unwrap();
+
+        let mut outputs = Vec::new();
+        for _ in 0..3 {
+            reduce.transform_into(&mut outputs, Event::from("x".repeat(50)));
+        }
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(
+            outputs.first().unwrap().as_log()[&"message".into()],
+            "x".repeat(100).into()
+        );
+    }
+
+    #[test]
+    fn rejects_empty_caps() {
+        for config in &["max_events = 0", "max_bytes = 0"] {
+            assert!(toml::from_str::<ReduceConfig>(config)
+                .unwrap()
+                .build(TransformContext::new_test())
+                .is_err());
+        }
+    }
 }
@@ -0,0 +1,152 @@
+use super::Transform;
+use crate::{
+    config::{DataType, TransformConfig, TransformContext, TransformDescription},
+    event::Event,
+    internal_events::{RemapEventProcessed, RemapFailedMapping},
+    remap::{CompileError, Program},
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid remap program: {}", source))]
+    InvalidProgram { source: CompileError },
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct RemapConfig {
+    pub source: String,
+    pub drop_on_err: bool,
+}
+
+inventory::submit! {
+    TransformDescription::new::<RemapConfig>("remap")
+}
+
+#[typetag::serde(name = "remap")]
+impl TransformConfig for RemapConfig {
+    fn build(&self, _cx: TransformContext) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(Remap::new(self)?))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "remap"
+    }
+}
+
+#[derive(Debug)]
+pub struct Remap {
+    program: Program,
+    drop_on_err: bool,
+}
+
+impl Remap {
+    pub fn new(config: &RemapConfig) -> crate::Result<Self> {
+        let program = Program::compile(&config.source).context(InvalidProgram)?;
+        Ok(Self {
+            program,
+            drop_on_err: config.drop_on_err,
+        })
+    }
+}
+
+impl Transform for Remap {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        emit!(RemapEventProcessed);
+
+        if let Err(error) = self.program.execute(event.as_mut_log()) {
+            emit!(RemapFailedMapping {
+                event_dropped: self.drop_on_err,
+                error: &error,
+            });
+            if self.drop_on_err {
+                return None;
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Value;
+
+    fn remap(source: &str, drop_on_err: bool) -> Remap {
+        Remap::new(&RemapConfig {
+            source: source.to_owned(),
+            drop_on_err,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn reshapes_events() {
+        let mut transform = remap(
+            r#"
+                .parsed = parse_json(.message)
+                .level = upcase(.parsed.level)
+                .took_ms = to_int(.parsed.took) * 1000
+                del(.message)
+            "#,
+            false,
+        );
+
+        let event = transform
+            .transform(Event::from(r#"{"level": "info", "took": "2"}"#))
+            .unwrap();
+        let log = event.as_log();
+        assert_eq!(log[&"level".into()], "INFO".into());
+        assert_eq!(log[&"parsed.took".into()], "2".into());
+        assert_eq!(log[&"took_ms".into()], Value::Integer(2000));
+        assert!(log.get(&"message".into()).is_none());
+    }
+
+    #[test]
+    fn keeps_failed_events_by_default() {
+        let mut transform = remap(".a = 1\n.b = parse_json(.message)", false);
+
+        let event = transform.transform(Event::from("not json")).unwrap();
+        assert_eq!(event.as_log()[&"a".into()], Value::Integer(1));
+        assert!(event.as_log().get(&"b".into()).is_none());
+    }
+
+    #[test]
+    fn drops_failed_events() {
+        let mut transform = remap(".b = parse_json(.message)", true);
+
+        assert!(transform.transform(Event::from("not json")).is_none());
+        assert!(transform.transform(Event::from("{}")).is_some());
+    }
+
+    #[test]
+    fn reports_compile_errors() {
+        let error = Remap::new(&RemapConfig {
+            source: ".a = 1\n.b = upcse(.a)".to_owned(),
+            drop_on_err: false,
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            [
+                "Invalid remap program: unknown function `upcse` at line 2, column 6",
+                "  |",
+                "2 | .b = upcse(.a)",
+                "  |      ^^^^^",
+            ]
+            .join("\n")
+        );
+    }
+}
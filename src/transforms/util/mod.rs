@@ -1,2 +1,2 @@
-#[cfg(any(feature = "transforms-lua"))]
+#[cfg(any(feature = "transforms-lua", feature = "wasm"))]
 pub mod runtime_transform;
@@ -2,6 +2,7 @@ use super::Transform;
 use crate::{
     config::{DataType, TransformConfig, TransformContext, TransformDescription},
     event::Event,
+    transforms::util::runtime_transform::{RuntimeTransform, Timer},
     wasm::WasmModule,
 };
 use serde::{Deserialize, Serialize};
@@ -10,12 +11,18 @@ use std::path::PathBuf;
 use vector_wasm::{Role, WasmModuleConfig};
 
 pub mod defaults {
-    pub(super) const HEAP_MEMORY_SIZE: usize = 16 * 64 * 1024 * 10; // 10MB
-    pub const fn heap_memory_size() -> usize {
-        HEAP_MEMORY_SIZE
+    pub use crate::wasm::defaults::heap_memory_size;
+    pub const fn batch_size() -> usize {
+        1
+    }
+    pub const fn batch_timeout_secs() -> u64 {
+        1
     }
 }
 
+/// The timer id used to flush incomplete batches. Modules can't register timers with this id.
+const FLUSH_TIMER_ID: u32 = u32::MAX;
+
 /// Transform specific information needed to construct a [`WasmModuleConfig`].
 // Note: We have a separate type here for crate boundary purposes.
 //       `WasmConfig` is in `vector-wasm`, so we can't do impl's on it here.
@@ -28,6 +35,15 @@ pub struct WasmConfig {
     pub artifact_cache: PathBuf,
     #[serde(default = "defaults::heap_memory_size")]
     pub heap_memory_size: usize,
+    /// The amount of fuel a single call into the module may consume. Unlimited if unset.
+    #[serde(default)]
+    pub max_fuel: Option<u64>,
+    /// The maximum number of events passed to the module in a single call.
+    #[serde(default = "defaults::batch_size")]
+    pub batch_size: usize,
+    /// The maximum time an incomplete batch is held before it's passed to the module.
+    #[serde(default = "defaults::batch_timeout_secs")]
+    pub batch_timeout_secs: u64,
     /// Options to be passed to the WASM module.
     #[serde(default)]
     pub options: HashMap<String, serde_json::Value>,
@@ -35,13 +51,15 @@ pub struct WasmConfig {
 
 impl Into<WasmModuleConfig> for WasmConfig {
     fn into(self) -> WasmModuleConfig {
-        WasmModuleConfig::new(
+        let mut config = WasmModuleConfig::new(
             Role::Transform,
             self.module,
             self.artifact_cache,
             self.options,
-            defaults::HEAP_MEMORY_SIZE,
-        )
+            self.heap_memory_size,
+        );
+        config.set_max_fuel(self.max_fuel);
+        config
     }
 }
 
@@ -71,29 +89,104 @@ impl TransformConfig for WasmConfig {
 #[derive(Debug)]
 pub struct Wasm {
     module: WasmModule,
+    batch: Vec<Event>,
+    batch_size: usize,
+    batch_timeout_secs: u64,
 }
 
 impl Wasm {
     pub fn new(config: WasmConfig) -> crate::Result<Self> {
+        if config.batch_size == 0 {
+            return Err("`batch_size` must be positive.".into());
+        }
+        if config.batch_timeout_secs == 0 {
+            return Err("`batch_timeout_secs` must be positive.".into());
+        }
+        let batch_size = config.batch_size;
+        let batch_timeout_secs = config.batch_timeout_secs;
         let module = WasmModule::build(config)?;
+        if module
+            .timers()
+            .iter()
+            .any(|timer| timer.id == FLUSH_TIMER_ID)
+        {
+            return Err(format!("Timer id {} is reserved.", FLUSH_TIMER_ID).into());
+        }
 
-        Ok(Self { module })
+        Ok(Self {
+            module,
+            batch: Vec::with_capacity(batch_size),
+            batch_size,
+            batch_timeout_secs,
+        })
+    }
+
+    fn flush<F>(&mut self, emit_fn: F)
+    where
+        F: FnMut(Event),
+    {
+        if self.batch.is_empty() {
+            return;
+        }
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(self.batch_size));
+        // Errors are already reported through internal events.
+        if let Ok(events) = self.module.process(batch) {
+            events.into_iter().for_each(emit_fn);
+        }
     }
 }
 
-impl Transform for Wasm {
-    fn transform(&mut self, event: Event) -> Option<Event> {
-        self.module
-            .process(event)
-            .map(|outputs| outputs.into_iter().next())
-            .unwrap_or(None)
+impl RuntimeTransform for Wasm {
+    fn hook_process<F>(&mut self, event: Event, emit_fn: F)
+    where
+        F: FnMut(Event),
+    {
+        self.batch.push(event);
+        if self.batch.len() >= self.batch_size {
+            self.flush(emit_fn);
+        }
+    }
+
+    fn hook_shutdown<F>(&mut self, mut emit_fn: F)
+    where
+        F: FnMut(Event),
+    {
+        self.flush(&mut emit_fn);
+        if let Ok(events) = self.module.shutdown() {
+            events.into_iter().for_each(emit_fn);
+        }
+    }
+
+    fn timer_handler<F>(&mut self, timer: Timer, emit_fn: F)
+    where
+        F: FnMut(Event),
+    {
+        if timer.id == FLUSH_TIMER_ID {
+            self.flush(emit_fn);
+        } else if let Ok(events) = self.module.timer_handler(timer.id) {
+            events.into_iter().for_each(emit_fn);
+        }
+    }
+
+    fn timers(&self) -> Vec<Timer> {
+        let mut timers = self.module.timers();
+        if self.batch_size > 1 {
+            timers.push(Timer {
+                id: FLUSH_TIMER_ID,
+                interval_seconds: self.batch_timeout_secs,
+            });
+        }
+        timers
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Wasm;
-    use crate::{event::Event, transforms::Transform};
+    use crate::{
+        event::Event,
+        transforms::{util::runtime_transform::RuntimeTransform, Transform},
+    };
     use serde_json::Value;
     use std::{collections::HashMap, fs, io::Read, path::Path};
 
@@ -191,6 +284,45 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn add_fields_batched() -> crate::Result<()> {
+        crate::test_util::trace_init();
+        let span = span!(
+            tracing::Level::TRACE,
+            "transforms::wasm::add_fields_batched"
+        );
+        let _enter = span.enter();
+
+        let mut transform = parse_config(
+            r#"
+            module = "target/wasm32-wasi/release/add_fields.wasm"
+            artifact_cache = "target/artifacts"
+            batch_size = 2
+            options.new_field = "new_value"
+            options.new_field_2 = "new_value_2"
+            "#,
+        )?;
+
+        let input =
+            parse_event_artifact("tests/data/wasm/add_fields/fixtures/a/input.json")?.unwrap();
+        let expected =
+            parse_event_artifact("tests/data/wasm/add_fields/fixtures/a/expected.json")?.unwrap();
+
+        let mut output = Vec::new();
+        transform.hook_process(input.clone(), |event| output.push(event));
+        assert!(output.is_empty());
+
+        transform.hook_process(input.clone(), |event| output.push(event));
+        assert_eq!(output, vec![expected.clone(), expected.clone()]);
+
+        // Incomplete batches are flushed on shutdown.
+        output.clear();
+        transform.hook_process(input, |event| output.push(event));
+        transform.hook_shutdown(|event| output.push(event));
+        assert_eq!(output, vec![expected]);
+        Ok(())
+    }
+
     #[test]
     fn drop() -> crate::Result<()> {
         crate::test_util::trace_init();
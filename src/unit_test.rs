@@ -1,6 +1,6 @@
 use crate::{config, topology::unit_test::UnitTest};
 use colored::*;
-use std::path::PathBuf;
+use std::{fmt::Write as _, fs, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -9,6 +9,115 @@ pub struct Opts {
     /// Any number of Vector config files to test. If none are specified the
     /// default config path `/etc/vector/vector.toml` will be targeted.
     paths: Vec<PathBuf>,
+
+    /// Write a JUnit XML report of the test results to this path.
+    #[structopt(long)]
+    junit_report: Option<PathBuf>,
+}
+
+/// The results of the tests in one config file.
+struct TestSuite {
+    path: String,
+    /// The name of each test along with its failures.
+    tests: Vec<(String, Vec<String>)>,
+    /// Errors that prevented the tests from being built.
+    errors: Vec<String>,
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn junit_report(suites: &[TestSuite]) -> String {
+    let count = |suite: &TestSuite| suite.tests.len().max(suite.errors.len().min(1));
+    let failures = |suite: &TestSuite| {
+        suite
+            .tests
+            .iter()
+            .filter(|(_, failures)| !failures.is_empty())
+            .count()
+    };
+
+    let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(
+        report,
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\">",
+        suites.iter().map(count).sum::<usize>(),
+        suites.iter().map(failures).sum::<usize>(),
+        suites
+            .iter()
+            .filter(|suite| !suite.errors.is_empty())
+            .count(),
+    )
+    .unwrap();
+    for suite in suites {
+        let path = escape_xml(&suite.path);
+        writeln!(
+            report,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">",
+            path,
+            count(suite),
+            failures(suite),
+            suite.errors.len().min(1),
+        )
+        .unwrap();
+        if !suite.errors.is_empty() {
+            writeln!(
+                report,
+                "    <testcase name=\"build\" classname=\"{}\">\n      <error message=\"failed to build tests\">{}</error>\n    </testcase>",
+                path,
+                escape_xml(&suite.errors.join("\n")),
+            )
+            .unwrap();
+        }
+        for (name, failures) in &suite.tests {
+            let name = escape_xml(name);
+            if failures.is_empty() {
+                writeln!(
+                    report,
+                    "    <testcase name=\"{}\" classname=\"{}\"/>",
+                    name, path
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    report,
+                    "    <testcase name=\"{}\" classname=\"{}\">\n      <failure message=\"test failed\">{}</failure>\n    </testcase>",
+                    name,
+                    path,
+                    escape_xml(&failures.join("\n\n")),
+                )
+                .unwrap();
+            }
+        }
+        report.push_str("  </testsuite>\n");
+    }
+    report.push_str("</testsuites>\n");
+    report
+}
+
+fn write_junit_report(opts: &Opts, suites: &[TestSuite]) -> Result<(), ()> {
+    if let Some(path) = &opts.junit_report {
+        fs::write(path, junit_report(suites)).map_err(|error| {
+            error!(
+                message = "Failed to write JUnit report.",
+                path = ?path,
+                %error
+            );
+        })?;
+    }
+    Ok(())
 }
 
 fn build_tests(path: PathBuf) -> Result<Vec<UnitTest>, Vec<String>> {
@@ -25,6 +134,7 @@ fn build_tests(path: PathBuf) -> Result<Vec<UnitTest>, Vec<String>> {
 pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
     let mut failed_files: Vec<(String, Vec<(String, Vec<String>)>)> = Vec::new();
     let mut inspected_files: Vec<(String, Vec<(String, Vec<String>)>)> = Vec::new();
+    let mut suites = Vec::new();
 
     let paths = config::process_paths(&opts.paths).unwrap_or_else(|| {
         std::process::exit(exitcode::CONFIG);
@@ -40,8 +150,10 @@ pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
             Ok(mut tests) => {
                 let mut aggregated_test_errors = Vec::new();
                 let mut aggregated_test_inspections = Vec::new();
+                let mut results = Vec::new();
                 tests.iter_mut().for_each(|t| {
                     let (test_inspections, test_errors) = t.run();
+                    results.push((t.name.clone(), test_errors.clone()));
                     if !test_inspections.is_empty() {
                         aggregated_test_inspections.push((t.name.clone(), test_inspections));
                     }
@@ -61,14 +173,29 @@ pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
                 if tests.is_empty() {
                     println!("{}", "no tests found".yellow());
                 }
+                suites.push(TestSuite {
+                    path: path_str.to_owned(),
+                    tests: results,
+                    errors: Vec::new(),
+                });
             }
             Err(errs) => {
                 error!("Failed to execute {} tests:\n{}", path_str, errs.join("\n"));
+                suites.push(TestSuite {
+                    path: path_str.to_owned(),
+                    tests: Vec::new(),
+                    errors: errs,
+                });
+                write_junit_report(opts, &suites).ok();
                 return exitcode::CONFIG;
             }
         }
     }
 
+    if write_junit_report(opts, &suites).is_err() {
+        return exitcode::IOERR;
+    }
+
     if !inspected_files.is_empty() {
         println!("\ninspections:");
         for (path, inspections) in inspected_files {
@@ -98,3 +225,49 @@ pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
         exitcode::OK
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_junit_report() {
+        let suites = vec![
+            TestSuite {
+                path: "vector.toml".to_owned(),
+                tests: vec![
+                    ("passes".to_owned(), Vec::new()),
+                    (
+                        "fails <badly>".to_owned(),
+                        vec!["expected \"a\" & \"b\"".to_owned()],
+                    ),
+                ],
+                errors: Vec::new(),
+            },
+            TestSuite {
+                path: "broken.toml".to_owned(),
+                tests: Vec::new(),
+                errors: vec!["unknown transform".to_owned()],
+            },
+        ];
+
+        assert_eq!(
+            junit_report(&suites),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="3" failures="1" errors="1">
+  <testsuite name="vector.toml" tests="2" failures="1" errors="0">
+    <testcase name="passes" classname="vector.toml"/>
+    <testcase name="fails &lt;badly&gt;" classname="vector.toml">
+      <failure message="test failed">expected &quot;a&quot; &amp; &quot;b&quot;</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="broken.toml" tests="1" failures="0" errors="1">
+    <testcase name="build" classname="broken.toml">
+      <error message="failed to build tests">unknown transform</error>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
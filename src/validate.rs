@@ -5,7 +5,7 @@ use crate::{
 };
 use colored::*;
 use exitcode::ExitCode;
-use std::{fmt, path::PathBuf};
+use std::{collections::HashMap, fmt, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -147,14 +147,14 @@ async fn validate_components(
         .set(config.global.log_schema.clone())
         .expect("Couldn't set schema");
 
-    match topology::builder::build_pieces(config, diff).await {
+    match topology::builder::build_pieces(config, diff, HashMap::new()).await {
         Ok(pieces) => {
             fmt.success("Component configuration");
             Some(pieces)
         }
         Err(errors) => {
             fmt.title("Component errors");
-            fmt.sub_error(errors);
+            fmt.sub_error(errors.iter().map(|error| config.redact(error)));
             None
         }
     }
@@ -1,5 +1,49 @@
-use crate::Event;
+use super::net::Network;
+use crate::{transforms::util::runtime_transform::Timer, Event};
 use std::collections::LinkedList;
+use tokio::runtime::Handle;
+use vector_wasm::{Registration, WasmModuleConfig};
+use wasmtime::ResourceLimiter;
+use wasmtime_wasi::WasiCtx;
+
+/// The host state of a module instance, available to the hostcalls.
+pub(super) struct State {
+    pub(super) config: WasmModuleConfig,
+    pub(super) registration: Option<Registration>,
+    pub(super) events: EventBuffer,
+    pub(super) raised: RaisedError,
+    pub(super) timers: Vec<Timer>,
+    /// Whether the module is still in its `init` call.
+    pub(super) initializing: bool,
+    pub(super) wasi: WasiCtx,
+    pub(super) limiter: HeapLimiter,
+    pub(super) network: Network,
+    /// The last HTTP response, waiting to be copied out by the `response` hostcall.
+    pub(super) response: Option<Vec<u8>>,
+    /// The runtime networking hostcalls block on.
+    pub(super) runtime: Option<Handle>,
+}
+
+impl State {
+    pub(super) fn new(config: WasmModuleConfig, wasi: WasiCtx, runtime: Option<Handle>) -> Self {
+        let limiter = HeapLimiter {
+            max_heap_memory_size: config.max_heap_memory_size,
+        };
+        Self {
+            config,
+            registration: None,
+            events: EventBuffer::new(),
+            raised: Default::default(),
+            timers: Vec::new(),
+            initializing: true,
+            wasi,
+            limiter,
+            network: Network::default(),
+            response: None,
+            runtime,
+        }
+    }
+}
 
 #[derive(Default)]
 pub(super) struct EventBuffer {
@@ -21,3 +65,18 @@ impl EventBuffer {
 pub(super) struct RaisedError {
     pub(super) error: Option<String>,
 }
+
+/// Keeps the linear memory of the module within `max_heap_memory_size`.
+pub(super) struct HeapLimiter {
+    max_heap_memory_size: usize,
+}
+
+impl ResourceLimiter for HeapLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> bool {
+        desired <= self.max_heap_memory_size
+    }
+
+    fn table_growing(&mut self, _current: u32, _desired: u32, _maximum: Option<u32>) -> bool {
+        true
+    }
+}
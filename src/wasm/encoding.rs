@@ -0,0 +1,122 @@
+//! Conversion of events to and from the data exchanged with the guests.
+use crate::{event::proto, Event, Result};
+use bytes::Buf;
+use prost::Message;
+use vector_wasm::Encoding;
+
+/// Encode the `events` to be passed to the guest.
+///
+/// A single JSON event is passed as an object, batches as an array of objects. Protobuf events are
+/// always length-delimited, so a batch is simply their concatenation.
+pub(super) fn encode(encoding: Encoding, events: Vec<Event>) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Json => {
+            let mut logs = events
+                .into_iter()
+                .map(|event| match event {
+                    Event::Log(log) => Ok(log),
+                    Event::Metric(_) => Err("Metrics can only be passed with protobuf encoding."),
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            if logs.len() == 1 {
+                Ok(serde_json::to_vec(&logs.remove(0))?)
+            } else {
+                Ok(serde_json::to_vec(&logs)?)
+            }
+        }
+        Encoding::Protobuf => {
+            let mut buf = Vec::new();
+            for event in events {
+                proto::EventWrapper::from(event).encode_length_delimited(&mut buf)?;
+            }
+            Ok(buf)
+        }
+    }
+}
+
+/// Decode the events emitted by the guest, see [`encode`].
+pub(super) fn decode(encoding: Encoding, data: &[u8]) -> Result<Vec<Event>> {
+    match encoding {
+        Encoding::Json => {
+            let value: serde_json::Value = serde_json::from_slice(data)?;
+            let objects = match value {
+                serde_json::Value::Array(values) => values,
+                value => vec![value],
+            };
+            objects
+                .into_iter()
+                .map(|object| {
+                    let mut event = Event::new_empty_log();
+                    // TODO: Add some usability around `LogEvent` for this.
+                    for (key, value) in object.as_object().ok_or("Passed JSON was not object.")? {
+                        event.as_mut_log().insert(key, value.clone());
+                    }
+                    Ok(event)
+                })
+                .collect()
+        }
+        Encoding::Protobuf => {
+            let mut buf = data;
+            let mut events = Vec::new();
+            while buf.has_remaining() {
+                let wrapper = proto::EventWrapper::decode_length_delimited(&mut buf)?;
+                events.push(Event::from(wrapper));
+            }
+            Ok(events)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::metric::{Metric, MetricKind, MetricValue};
+
+    fn log(message: &str) -> Event {
+        // No timestamp, as JSON doesn't preserve its type.
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert("message", message);
+        event
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let single = encode(Encoding::Json, vec![log("one")]).unwrap();
+        assert!(single.starts_with(b"{"));
+        assert_eq!(decode(Encoding::Json, &single).unwrap(), vec![log("one")]);
+
+        let batch = encode(Encoding::Json, vec![log("one"), log("two")]).unwrap();
+        assert!(batch.starts_with(b"["));
+        assert_eq!(
+            decode(Encoding::Json, &batch).unwrap(),
+            vec![log("one"), log("two")]
+        );
+    }
+
+    #[test]
+    fn protobuf_roundtrip() {
+        let metric = Event::from(Metric {
+            name: "counter".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+        let events = vec![log("one"), metric];
+
+        let data = encode(Encoding::Protobuf, events.clone()).unwrap();
+        assert_eq!(decode(Encoding::Protobuf, &data).unwrap(), events);
+    }
+
+    #[test]
+    fn json_rejects_metrics() {
+        let metric = Event::from(Metric {
+            name: "counter".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+        assert!(encode(Encoding::Json, vec![metric]).is_err());
+    }
+}
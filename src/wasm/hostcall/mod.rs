@@ -1,172 +1,295 @@
 //! Hostcall endpoints exposed to guests.
-use super::context::EventBuffer;
-use crate::wasm::context::RaisedError;
-use crate::wasm::WasmModuleConfig;
-use crate::Event;
-use lucet_runtime::vmctx::Vmctx;
+use super::context::State;
+use crate::{internal_events, transforms::util::runtime_transform::Timer, Result};
 use std::convert::TryInto;
-use vector_wasm::Registration;
-pub use wrapped_for_ffi::ensure_linked;
+use vector_wasm::{Encoding, HttpRequest, Registration};
+use wasmtime::{Caller, Extern, Linker, Memory};
 
-// Also add any new functions to the `ffi::ensure_linked` function!
+/// The hostcalls in the `env` import module.
 pub const HOSTCALL_LIST: [&str; 5] = ["emit", "register", "raise", "config_size", "config"];
+/// The hostcalls in the `vector` import module. They can't live in `env`, as `log` would clash
+/// with the libc function of the same name in the guests.
+pub const VECTOR_HOSTCALL_LIST: [&str; 5] =
+    ["timer", "log", "http_request", "response", "tcp_send"];
 
-pub fn emit(vmctx: &Vmctx, data: u32, length: u32) -> crate::Result<u32> {
-    let mut event_buffer = vmctx.get_embed_ctx_mut::<EventBuffer>();
-    let heap = vmctx.heap_mut();
-    let slice = &heap[data as usize..(length as usize + data as usize)];
+/// Define all the hostcalls in the `linker`.
+pub(super) fn add_to_linker(linker: &mut Linker<State>) -> Result<()> {
+    linker.func_wrap(
+        "env",
+        "emit",
+        |mut caller: Caller<'_, State>, data: u32, length: u32| -> u32 {
+            instrumented(&mut caller, "emit", |caller| emit(caller, data, length))
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "register",
+        |mut caller: Caller<'_, State>, data: u32, length: u32| {
+            instrumented(&mut caller, "register", |caller| {
+                register(caller, data, length)
+            })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "raise",
+        |mut caller: Caller<'_, State>, data: u32, length: u32| -> u32 {
+            instrumented(&mut caller, "raise", |caller| raise(caller, data, length))
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "config_size",
+        |mut caller: Caller<'_, State>| -> u32 {
+            instrumented(&mut caller, "config_size", config_size)
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "config",
+        |mut caller: Caller<'_, State>, buffer: u32, length: u32| {
+            instrumented(&mut caller, "config", |caller| {
+                config(caller, buffer, length)
+            })
+        },
+    )?;
+    linker.func_wrap(
+        "vector",
+        "timer",
+        |mut caller: Caller<'_, State>, id: u32, interval_seconds: u32| {
+            instrumented(&mut caller, "timer", |caller| {
+                timer(caller, id, interval_seconds)
+            })
+        },
+    )?;
+    linker.func_wrap(
+        "vector",
+        "log",
+        |mut caller: Caller<'_, State>, level: u32, data: u32, length: u32| {
+            instrumented(&mut caller, "log", |caller| {
+                log(caller, level, data, length)
+            })
+        },
+    )?;
+    linker.func_wrap(
+        "vector",
+        "http_request",
+        |mut caller: Caller<'_, State>, data: u32, length: u32| -> u32 {
+            instrumented(&mut caller, "http_request", |caller| {
+                http_request(caller, data, length)
+            })
+        },
+    )?;
+    linker.func_wrap(
+        "vector",
+        "response",
+        |mut caller: Caller<'_, State>, buffer: u32, length: u32| {
+            instrumented(&mut caller, "response", |caller| {
+                response(caller, buffer, length)
+            })
+        },
+    )?;
+    linker.func_wrap(
+        "vector",
+        "tcp_send",
+        |mut caller: Caller<'_, State>,
+         address: u32,
+         address_length: u32,
+         data: u32,
+         length: u32|
+         -> u32 {
+            instrumented(&mut caller, "tcp_send", |caller| {
+                tcp_send(caller, address, address_length, data, length)
+            })
+            .map_or(1, |()| 0)
+        },
+    )?;
+    Ok(())
+}
 
-    // TODO: Add some usability around `LogEvent` for this.
-    let value: serde_json::Value = serde_json::from_slice(slice)?;
-    let mut event = Event::new_empty_log();
-    for (key, value) in value.as_object().ok_or("Passed JSON was not object.")? {
-        event.as_mut_log().insert(key, value.clone());
+/// Run the hostcall, reporting its progress. Errors aren't propagated to the guest, which gets
+/// the default value instead.
+fn instrumented<T: Default>(
+    caller: &mut Caller<'_, State>,
+    call: &'static str,
+    f: impl FnOnce(&mut Caller<'_, State>) -> Result<T>,
+) -> T {
+    let internal_event =
+        internal_events::WasmHostcallProgress::begin(caller.data().config.role, call);
+    match f(caller) {
+        Ok(retval) => {
+            internal_event.complete();
+            retval
+        }
+        Err(e) => {
+            internal_event.error(format!("{}", e));
+            T::default()
+        }
     }
-
-    event_buffer.push_back(event);
-    Ok(event_buffer.events.len().try_into()?)
 }
 
-fn register(vmctx: &Vmctx, data: u32, length: u32) -> crate::Result<()> {
-    let heap = vmctx.heap_mut();
-    let slice = &heap[data as usize..(length as usize + data as usize)];
-    let value: Registration = serde_json::from_slice(slice).unwrap();
+fn memory(caller: &mut Caller<'_, State>) -> Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| "Module does not export its memory.".into())
+}
 
-    let mut maybe_registration = vmctx.get_embed_ctx_mut::<Option<Registration>>();
-    *maybe_registration = Some(value);
+/// Copy `length` bytes at `data` out of the guest memory.
+fn read(caller: &mut Caller<'_, State>, data: u32, length: u32) -> Result<Vec<u8>> {
+    let memory = memory(caller)?;
+    let (start, end) = (data as usize, data as usize + length as usize);
+    memory
+        .data(&*caller)
+        .get(start..end)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| "Guest memory access out of bounds.".into())
+}
 
+/// Copy `buf` into the guest memory at `data`.
+fn write(caller: &mut Caller<'_, State>, data: u32, buf: &[u8]) -> Result<()> {
+    let memory = memory(caller)?;
+    let (start, end) = (data as usize, data as usize + buf.len());
+    memory
+        .data_mut(&mut *caller)
+        .get_mut(start..end)
+        .ok_or("Guest memory access out of bounds.")?
+        .copy_from_slice(buf);
     Ok(())
 }
 
-fn raise(vmctx: &Vmctx, data: u32, length: u32) -> crate::Result<u32> {
-    let heap = vmctx.heap_mut();
-    let slice = &heap[data as usize..(length as usize + data as usize)];
+fn emit(caller: &mut Caller<'_, State>, data: u32, length: u32) -> Result<u32> {
+    let slice = read(caller, data, length)?;
+    let encoding = caller
+        .data()
+        .registration
+        .as_ref()
+        .map(Registration::encoding)
+        .unwrap_or(Encoding::Json);
+
+    let state = caller.data_mut();
+    for event in super::encoding::decode(encoding, &slice)? {
+        state.events.push_back(event);
+    }
+    Ok(state.events.events.len().try_into()?)
+}
+
+fn register(caller: &mut Caller<'_, State>, data: u32, length: u32) -> Result<()> {
+    let slice = read(caller, data, length)?;
+    let value: Registration = serde_json::from_slice(&slice)?;
 
-    let value = String::from_utf8(slice.into())?;
+    let state = caller.data_mut();
+    if value.role() != state.config.role {
+        return Err(format!(
+            "Module registered as a {}, but is configured as a {}.",
+            value.role().as_const_str(),
+            state.config.role.as_const_str()
+        )
+        .into());
+    }
+    state.registration = Some(value);
 
-    let mut maybe_error = vmctx.get_embed_ctx_mut::<RaisedError>();
+    Ok(())
+}
+
+fn raise(caller: &mut Caller<'_, State>, data: u32, length: u32) -> Result<u32> {
+    let slice = read(caller, data, length)?;
+    let value = String::from_utf8(slice)?;
+
+    let maybe_error = &mut caller.data_mut().raised;
     maybe_error.error = Some(value);
     Ok(if maybe_error.error.is_some() { 1 } else { 0 })
 }
 
-fn config_size(vmctx: &Vmctx) -> crate::Result<u32> {
-    let config = vmctx.get_embed_ctx::<WasmModuleConfig>();
-    let buf = serde_json::to_vec(&*config)?;
+fn config_size(caller: &mut Caller<'_, State>) -> Result<u32> {
+    let buf = serde_json::to_vec(&caller.data().config)?;
     Ok(buf.len().try_into()?)
 }
 
-fn config(vmctx: &Vmctx, buffer: u32, length: u32) -> crate::Result<()> {
-    let config = vmctx.get_embed_ctx::<WasmModuleConfig>();
-    let buf = serde_json::to_vec(&*config)?;
-
-    let mut heap = vmctx.heap_mut();
-    let slice = &mut heap[buffer as usize..(length as usize + buffer as usize)];
+fn config(caller: &mut Caller<'_, State>, buffer: u32, length: u32) -> Result<()> {
+    let buf = serde_json::to_vec(&caller.data().config)?;
+    if buf.len() != length as usize {
+        return Err("Config buffer size does not match `config_size`.".into());
+    }
+    write(caller, buffer, &buf)
+}
 
-    slice.copy_from_slice(buf.as_ref());
+fn timer(caller: &mut Caller<'_, State>, id: u32, interval_seconds: u32) -> Result<()> {
+    let state = caller.data_mut();
+    if !state.initializing {
+        return Err("Timers can only be registered during `init`.".into());
+    }
+    if interval_seconds == 0 {
+        return Err("Timer interval must be positive.".into());
+    }
+    state.timers.push(Timer {
+        id,
+        interval_seconds: interval_seconds.into(),
+    });
     Ok(())
 }
 
-/// All functions here must be fully C ABI compatible for wasm32-wasi.
-mod wrapped_for_ffi {
-    use crate::internal_events;
-    use lucet_runtime::{lucet_hostcall, vmctx::Vmctx};
-    use std::sync::Once;
-    use vector_wasm::Role;
-
-    static HOSTCALL_API_INIT: Once = Once::new();
-
-    /// This is pretty hackish; we will hopefully be able to avoid this altogether once [this
-    /// issue](https://github.com/rust-lang/rust/issues/58037) is addressed.
-    #[no_mangle]
-    #[doc(hidden)]
-    pub extern "C" fn ensure_linked() {
-        use std::ptr::read_volatile;
-        // Also add any new functions to the `super::HOSTCALL_LIST` const!
-        HOSTCALL_API_INIT.call_once(|| unsafe {
-            read_volatile(emit as *const extern "C" fn());
-            read_volatile(raise as *const extern "C" fn());
-            read_volatile(config as *const extern "C" fn());
-            read_volatile(config_size as *const extern "C" fn());
-            lucet_wasi::export_wasi_funcs();
-            lucet_runtime::lucet_internal_ensure_linked();
-        });
-    }
+fn log(caller: &mut Caller<'_, State>, level: u32, data: u32, length: u32) -> Result<()> {
+    let slice = read(caller, data, length)?;
+    let message = String::from_utf8_lossy(&slice);
+    let role = caller.data().config.role.as_const_str();
+    let module = caller.data().config.path.display();
 
-    #[lucet_hostcall]
-    #[no_mangle]
-    pub extern "C" fn register(vmctx: &Vmctx, data: u32, length: u32) {
-        let internal_event =
-            internal_events::WasmHostcallProgress::begin(Role::Transform, "register");
-        match super::register(vmctx, data, length) {
-            Ok(_) => internal_event.complete(),
-            Err(e) => {
-                internal_event.error(format!("{}", e));
-            }
-        }
+    match level {
+        1 => error!(message = %message, %role, %module, rate_limit_secs = 30),
+        2 => warn!(message = %message, %role, %module, rate_limit_secs = 30),
+        3 => info!(message = %message, %role, %module),
+        4 => debug!(message = %message, %role, %module),
+        5 => trace!(message = %message, %role, %module),
+        _ => return Err(format!("Unknown log level {}.", level).into()),
     }
+    Ok(())
+}
 
-    #[lucet_hostcall]
-    #[no_mangle]
-    pub extern "C" fn emit(vmctx: &Vmctx, data: u32, length: u32) -> u32 {
-        let internal_event = internal_events::WasmHostcallProgress::begin(Role::Transform, "emit");
-        match super::emit(vmctx, data, length) {
-            Ok(retval) => {
-                internal_event.complete();
-                retval
-            }
-            Err(e) => {
-                internal_event.error(format!("{}", e));
-                0
-            }
-        }
-    }
+/// Returns the size of the response, which the guest copies out with `response`. Failures are
+/// signaled by a size of 0.
+fn http_request(caller: &mut Caller<'_, State>, data: u32, length: u32) -> Result<u32> {
+    let slice = read(caller, data, length)?;
+    let request: HttpRequest = serde_json::from_slice(&slice)?;
 
-    #[lucet_hostcall]
-    #[no_mangle]
-    pub extern "C" fn raise(vmctx: &Vmctx, data: u32, length: u32) -> u32 {
-        let internal_event = internal_events::WasmHostcallProgress::begin(Role::Transform, "raise");
-        match super::raise(vmctx, data, length) {
-            Ok(retval) => {
-                internal_event.complete();
-                retval
-            }
-            Err(e) => {
-                internal_event.error(format!("{}", e));
-                0
-            }
-        }
-    }
+    let state = caller.data_mut();
+    state.response = None;
+    let response =
+        state
+            .network
+            .http_request(state.runtime.as_ref(), &state.config.allowed_hosts, request)?;
+    let buf = serde_json::to_vec(&response)?;
+    let size = buf.len().try_into()?;
+    state.response = Some(buf);
+    Ok(size)
+}
 
-    #[lucet_hostcall]
-    #[no_mangle]
-    pub extern "C" fn config_size(vmctx: &Vmctx) -> u32 {
-        let internal_event =
-            internal_events::WasmHostcallProgress::begin(Role::Transform, "config_size");
-        match super::config_size(vmctx) {
-            Ok(retval) => {
-                internal_event.complete();
-                retval
-            }
-            Err(e) => {
-                internal_event.error(format!("{}", e));
-                0
-            }
-        }
+fn response(caller: &mut Caller<'_, State>, buffer: u32, length: u32) -> Result<()> {
+    let buf = caller
+        .data_mut()
+        .response
+        .take()
+        .ok_or("There is no pending response.")?;
+    if buf.len() != length as usize {
+        return Err("Response buffer size does not match `http_request`.".into());
     }
+    write(caller, buffer, &buf)
+}
 
-    #[lucet_hostcall]
-    #[no_mangle]
-    pub extern "C" fn config(vmctx: &Vmctx, buffer: u32, length: u32) {
-        let internal_event =
-            internal_events::WasmHostcallProgress::begin(Role::Transform, "config");
-        match super::config(vmctx, buffer, length) {
-            Ok(retval) => {
-                internal_event.complete();
-                retval
-            }
-            Err(e) => {
-                internal_event.error(format!("{}", e));
-            }
-        }
-    }
+/// Returns `Some(())` once sent, as failures get the default of `None`.
+fn tcp_send(
+    caller: &mut Caller<'_, State>,
+    address: u32,
+    address_length: u32,
+    data: u32,
+    length: u32,
+) -> Result<Option<()>> {
+    let address = String::from_utf8(read(caller, address, address_length)?)?;
+    let slice = read(caller, data, length)?;
+
+    let state = caller.data_mut();
+    state
+        .network
+        .tcp_send(&state.config.allowed_hosts, &address, &slice)?;
+    Ok(Some(()))
 }
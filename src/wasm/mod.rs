@@ -5,45 +5,76 @@
 //!
 //! **Note:** This code is experimental.
 
-use crate::{internal_events, Event, Result};
-use lucet_runtime::{DlModule, InstanceHandle, Limits, MmapRegion, Region};
-use lucet_wasi::WasiCtxBuilder;
-use lucetc::{Bindings, Lucetc, LucetcOpts};
-use std::collections::LinkedList;
-use std::{fmt::Debug, fs, path::Path};
-use vector_wasm::{Registration, Role, WasmModuleConfig};
+use crate::{internal_events, transforms::util::runtime_transform::Timer, Event, Result};
+use std::{convert::TryInto, fmt::Debug, fs, mem, path::Path};
+use tokio::runtime::Handle;
+use vector_wasm::{Delivery, Encoding, Registration, Role, WasmModuleConfig};
+use wasmtime::{Config, Engine, Instance, Linker, Module, Store, Trap};
+use wasmtime_wasi::sync::WasiCtxBuilder;
 mod artifact_cache;
 mod fingerprint;
 pub use artifact_cache::ArtifactCache;
 pub use fingerprint::Fingerprint;
 
 mod context;
+mod encoding;
+mod net;
 
-pub mod hostcall; // Pub is required for lucet.
+pub mod hostcall;
 
-/// Compiles a WASM module located at `input` and writes an optimized shared object to `output`.
+pub mod defaults {
+    pub const HEAP_MEMORY_SIZE: usize = 16 * 64 * 1024 * 10; // 10MB
+    pub const fn heap_memory_size() -> usize {
+        HEAP_MEMORY_SIZE
+    }
+}
+
+/// Compiles a WASM module located at `input` and writes the precompiled module to `output`.
 fn compile(
+    engine: &Engine,
     input: impl AsRef<Path> + Debug,
     output: impl AsRef<Path> + Debug,
-) -> Result<Fingerprint> {
+) -> Result<(Fingerprint, Module)> {
     let input = input.as_ref();
     let fingerprint = Fingerprint::new(input)?;
 
-    let mut bindings = Bindings::empty();
-    bindings.extend(&lucet_wasi::bindings())?;
-    bindings.extend(&Bindings::env(
-        hostcall::HOSTCALL_LIST
-            .iter()
-            .cloned()
-            .map(|f| (String::from(f), String::from(f)))
-            .collect(),
-    ))?;
+    let module = Module::from_file(engine, input)?;
+    fs::write(output, module.serialize()?)?;
 
-    Lucetc::new(input)
-        .with_bindings(bindings)
-        .shared_object_file(output)?;
+    Ok((fingerprint, module))
+}
+
+/// Loads the module at `config.path`, going through the artifact cache.
+fn load(engine: &Engine, config: &WasmModuleConfig) -> Result<Module> {
+    let output_file = config
+        .artifact_cache
+        .join(config.path.file_stem().ok_or("A file is required")?)
+        .with_extension("cwasm");
+
+    fs::create_dir_all(&config.artifact_cache)?;
+    let mut artifact_cache = ArtifactCache::new(config.artifact_cache.clone())?;
+
+    let internal_event_compilation = internal_events::WasmCompilationProgress::begin(config.role);
+    if artifact_cache.has_fresh(&config.path)? {
+        // Safety: The artifact was written by `compile` above. Artifacts built by a different
+        // engine configuration or version are rejected, and get recompiled below.
+        if let Ok(module) = unsafe { Module::deserialize(engine, fs::read(&output_file)?) } {
+            internal_event_compilation.cached();
+            return Ok(module);
+        }
+    }
 
-    Ok(fingerprint)
+    match compile(engine, &config.path, &output_file) {
+        Ok((fingerprint, module)) => {
+            artifact_cache.upsert(&config.path, fingerprint)?;
+            internal_event_compilation.complete();
+            Ok(module)
+        }
+        Err(e) => {
+            internal_event_compilation.error(format!("{}", e));
+            Err(e)
+        }
+    }
 }
 
 /// A plugin module that is operating as a WASM guest.
@@ -52,9 +83,22 @@ fn compile(
 pub struct WasmModule {
     /// A stored version of the config for later referencing.
     config: WasmModuleConfig,
-    /// The handle to the Lucet instance.
     #[derivative(Debug = "ignore")]
-    instance: InstanceHandle,
+    engine: Engine,
+    #[derivative(Debug = "ignore")]
+    module: Module,
+    #[derivative(Debug = "ignore")]
+    linker: Linker<context::State>,
+    /// The store holding the instance and its host state.
+    #[derivative(Debug = "ignore")]
+    store: Store<context::State>,
+    #[derivative(Debug = "ignore")]
+    instance: Instance,
+    /// The runtime the networking hostcalls block on, if the module was built inside one.
+    #[derivative(Debug = "ignore")]
+    runtime: Option<Handle>,
+    /// The timers the module registered during `init`.
+    timers: Vec<Timer>,
     role: Role,
 }
 
@@ -62,134 +106,258 @@ impl WasmModule {
     /// Build the WASM instance from a given config.
     pub fn build(config: impl Into<WasmModuleConfig> + Debug) -> Result<Self> {
         let config = config.into();
-        let output_file = config
-            .artifact_cache
-            .join(config.path.file_stem().ok_or("A file is required")?)
-            .with_extension("so");
-
-        // Prepwork
-        fs::create_dir_all(&config.artifact_cache)?;
-        hostcall::ensure_linked();
 
-        let artifact_cache = ArtifactCache::new(config.artifact_cache.clone())?;
+        let mut engine_config = Config::new();
+        engine_config.consume_fuel(config.max_fuel.is_some());
+        let engine = Engine::new(&engine_config)?;
 
-        let internal_event_compilation =
-            internal_events::WasmCompilationProgress::begin(config.role);
-        if artifact_cache.has_fresh(&config.path)? {
-            // We can be lazy and do nothing! How wonderful.
-            internal_event_compilation.cached();
-        } else {
-            let fingerprint = compile(&config.path, &output_file)?;
-            let mut artifact_cache = artifact_cache; // Just for this scope.
-            artifact_cache.upsert(&config.path, fingerprint)?;
-            internal_event_compilation.complete();
-        }
-
-        // load the compiled Lucet module
-        let module = DlModule::load(&output_file)?;
-
-        // create a new memory region with default limits on heap and stack size
-        let region = &MmapRegion::create(
-            1,
-            &Limits {
-                heap_memory_size: config.max_heap_memory_size,
-                ..Limits::default()
-            },
-        )?;
+        let module = load(&engine, &config)?;
 
-        // instantiate the module in the memory region
-        let instance = region
-            .new_instance_builder(module)
-            .with_embed_ctx::<WasmModuleConfig>(config.clone())
-            .with_embed_ctx::<Option<Registration>>(None)
-            .with_embed_ctx::<context::RaisedError>(Default::default())
-            .build()?;
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::add_to_linker(&mut linker, |state: &mut context::State| &mut state.wasi)?;
+        hostcall::add_to_linker(&mut linker)?;
 
+        let runtime = Handle::try_current().ok();
+        let (store, instance) = instantiate(&engine, &module, &linker, &config, runtime.clone())?;
+        let role = config.role;
         let mut wasm_module = Self {
             config,
+            engine,
+            module,
+            linker,
+            store,
             instance,
-            role: Role::Transform,
+            runtime,
+            timers: Vec::new(),
+            role,
         };
 
-        let wasi_ctx = WasiCtxBuilder::new().inherit_stdio().build()?;
-        wasm_module.instance.insert_embed_ctx(wasi_ctx);
+        wasm_module.init()?;
+        wasm_module.timers = mem::take(&mut wasm_module.store.data_mut().timers);
 
-        wasm_module.instance.run("init", &[])?.returned()?;
-        let registration = wasm_module
-            .instance
-            .remove_embed_ctx::<Option<Registration>>()
-            .and_then(|v| v);
+        Ok(wasm_module)
+    }
+
+    /// Run `f` against the module on the blocking thread pool, as hostcalls may block.
+    pub async fn blocking<T, F>(self, f: F) -> Result<(Self, T)>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Self) -> T + Send + 'static,
+    {
+        let (module, value) = tokio::task::spawn_blocking(move || {
+            let mut module = self;
+            let value = f(&mut module);
+            (module, value)
+        })
+        .await?;
+        Ok((module, value))
+    }
 
-        if let None = registration {
-            error!("Not registered! Please fill your `init` call with a `Registration::transform().register()`!");
+    /// The timers the module registered during `init`.
+    pub fn timers(&self) -> Vec<Timer> {
+        self.timers.clone()
+    }
+
+    /// Process a batch of events, returning the events the module emitted.
+    ///
+    /// Batches are passed in a single call if the module exports `process_batch`, and event by
+    /// event through `process` otherwise.
+    pub fn process(&mut self, events: Vec<Event>) -> Result<Vec<Event>> {
+        if events.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok(wasm_module)
+        let batching = self
+            .instance
+            .get_func(&mut self.store, "process_batch")
+            .is_some();
+        self.invoke(|module| {
+            if batching && events.len() > 1 {
+                module.call_with_events("process_batch", events)?;
+            } else {
+                for event in events {
+                    module.call_with_events("process", vec![event])?;
+                }
+            }
+            Ok(())
+        })
     }
 
-    pub fn process(&mut self, mut data: Event) -> Result<LinkedList<Event>> {
-        let internal_event_processing = internal_events::EventProcessingProgress::begin(self.role);
+    /// Run the `poll` export of a source module, returning the events it emitted.
+    pub fn poll(&mut self) -> Result<Vec<Event>> {
+        self.invoke(|module| {
+            let poll = module
+                .instance
+                .get_typed_func::<(), u32, _>(&mut module.store, "poll")?;
+            let _num_events = poll.call(&mut module.store, ())?;
+            Ok(())
+        })
+    }
 
-        self.instance.insert_embed_ctx(context::EventBuffer::new());
-        self.instance
-            .insert_embed_ctx::<context::RaisedError>(Default::default());
+    /// Pass a batch of events to the `send` export of a sink module.
+    ///
+    /// Events that can't be encoded are an error. Calls that fail before the module decides are
+    /// retried.
+    pub fn send(&mut self, events: Vec<Event>) -> Result<Delivery> {
+        let data_buf = self.encode(events)?;
+        let mut delivery = Delivery::Retry;
+        self.invoke(|module| {
+            let code = module.call_with_buffer("send", &data_buf)?;
+            delivery = Delivery::from_u32(code)
+                .ok_or_else(|| format!("Unknown delivery result {}.", code))?;
+            Ok(())
+        })?;
+        Ok(delivery)
+    }
+
+    /// Run the module's `timer_handler` for the timer `id`, returning the events it emitted.
+    pub fn timer_handler(&mut self, id: u32) -> Result<Vec<Event>> {
+        self.invoke(|module| {
+            let timer_handler = module
+                .instance
+                .get_typed_func::<u32, (), _>(&mut module.store, "timer_handler")?;
+            timer_handler.call(&mut module.store, id)?;
+            Ok(())
+        })
+    }
+
+    /// Run the module's `shutdown`, returning the events it emitted.
+    pub fn shutdown(&mut self) -> Result<Vec<Event>> {
+        self.invoke(|module| {
+            let shutdown = module
+                .instance
+                .get_typed_func::<(), (), _>(&mut module.store, "shutdown")?;
+            shutdown.call(&mut module.store, ())?;
+            Ok(())
+        })
+    }
 
-        // We unfortunately can't pass our `Event` type easily over FFI.
-        // This can definitely be improved later with some `Event` type changes.
-        let data_buf = serde_json::to_vec(data.as_mut_log())?;
-        let guest_data_size = data_buf.len();
-        let guest_data_ptr = self
+    fn init(&mut self) -> Result<()> {
+        self.refuel()?;
+        let init = self
             .instance
-            .run("allocate_buffer", &[(guest_data_size as u32).into()])?
-            .returned()?
-            .as_u32();
-        let guest_data_buf: &mut [u8] = self.instance.heap_mut()
-            [guest_data_ptr as usize..(guest_data_ptr as usize + guest_data_size as usize)]
-            .as_mut();
-        guest_data_buf.copy_from_slice(&data_buf);
-
-        match self.instance.run(
-            "process",
-            &[
-                (guest_data_ptr as u32).into(),
-                (guest_data_size as u32).into(),
-            ],
-        ) {
-            Ok(_num_events) => {
-                let context::EventBuffer { events: out } = self
-                    .instance
-                    .remove_embed_ctx()
-                    .ok_or("Could not retrieve context after processing.")?;
-
-                if let Some(context::RaisedError { error: Some(error) }) =
-                    self.instance.remove_embed_ctx()
-                {
-                    internal_event_processing.error(error);
-                } else {
-                    internal_event_processing.complete()
+            .get_typed_func::<(), (), _>(&mut self.store, "init")?;
+        init.call(&mut self.store, ())?;
+
+        let state = self.store.data_mut();
+        state.initializing = false;
+        if state.registration.is_none() {
+            error!(
+                "Not registered! Please fill your `init` call with a `Registration::{}().register()`!",
+                self.role.as_const_str()
+            );
+        }
+        Ok(())
+    }
+
+    /// Run `call` against the module, collecting the events it emitted and any error it raised.
+    ///
+    /// If the module traps, the instance is reset and the events are dropped.
+    fn invoke(&mut self, call: impl FnOnce(&mut Self) -> Result<()>) -> Result<Vec<Event>> {
+        let internal_event_processing = internal_events::EventProcessingProgress::begin(self.role);
+
+        let state = self.store.data_mut();
+        state.events = context::EventBuffer::new();
+        state.raised = Default::default();
+        self.refuel()?;
+
+        match call(self) {
+            Ok(()) => {
+                let state = self.store.data_mut();
+                let out = mem::take(&mut state.events.events).into_iter().collect();
+                match state.raised.error.take() {
+                    Some(error) => internal_event_processing.error(error),
+                    None => internal_event_processing.complete(),
                 }
                 Ok(out)
             }
-            Err(lucet_runtime::Error::RuntimeFault(fault)) => {
-                let error = format!(
-                    "WASM instance faulted, resetting: {:?}",
-                    fault.clone().rip_addr_details.and_then(|v| v.file_name)
-                );
-                internal_event_processing.error(error);
-                self.instance.reset()?;
-                Ok(Default::default())
+            Err(e) if e.is::<Trap>() => {
+                internal_event_processing.error(format!("WASM instance trapped, resetting: {}", e));
+                self.reset()?;
+                Ok(Vec::new())
             }
             Err(e) => {
-                internal_event_processing.error(format!("{:?}", e));
-                Ok(Default::default())
+                internal_event_processing.error(format!("{}", e));
+                Ok(Vec::new())
             }
         }
     }
 
-    pub fn shutdown(&mut self) -> Result<()> {
-        let _worked = self.instance.run("shutdown", &[])?;
+    /// Encode `events` with the encoding the module registered.
+    fn encode(&self, events: Vec<Event>) -> Result<Vec<u8>> {
+        let encoding = self
+            .store
+            .data()
+            .registration
+            .as_ref()
+            .map(Registration::encoding)
+            .unwrap_or(Encoding::Json);
+        encoding::encode(encoding, events)
+    }
+
+    /// Encode `events` into a guest buffer and pass it to `export`, returning its result.
+    fn call_with_events(&mut self, export: &str, events: Vec<Event>) -> Result<u32> {
+        let data_buf = self.encode(events)?;
+        self.call_with_buffer(export, &data_buf)
+    }
+
+    /// Copy `data_buf` into a guest buffer and pass it to `export`, returning its result.
+    fn call_with_buffer(&mut self, export: &str, data_buf: &[u8]) -> Result<u32> {
+        let guest_data_size: u32 = data_buf.len().try_into()?;
+
+        let allocate_buffer = self
+            .instance
+            .get_typed_func::<u32, u32, _>(&mut self.store, "allocate_buffer")?;
+        let guest_data_ptr = allocate_buffer.call(&mut self.store, guest_data_size)?;
+
+        let memory = self
+            .instance
+            .get_memory(&mut self.store, "memory")
+            .ok_or("Module does not export its memory.")?;
+        memory.write(&mut self.store, guest_data_ptr as usize, data_buf)?;
+
+        let process = self
+            .instance
+            .get_typed_func::<(u32, u32), u32, _>(&mut self.store, export)?;
+        Ok(process.call(&mut self.store, (guest_data_ptr, guest_data_size))?)
+    }
+
+    /// Top the fuel of the store back up to `max_fuel`.
+    fn refuel(&mut self) -> Result<()> {
+        if let Some(max_fuel) = self.config.max_fuel {
+            let remaining = self.store.consume_fuel(0)?;
+            self.store.add_fuel(max_fuel.saturating_sub(remaining))?;
+        }
         Ok(())
     }
+
+    /// Replace the instance with a fresh one, keeping the timers registered at startup.
+    fn reset(&mut self) -> Result<()> {
+        let (store, instance) = instantiate(
+            &self.engine,
+            &self.module,
+            &self.linker,
+            &self.config,
+            self.runtime.clone(),
+        )?;
+        self.store = store;
+        self.instance = instance;
+        self.init()
+    }
+}
+
+fn instantiate(
+    engine: &Engine,
+    module: &Module,
+    linker: &Linker<context::State>,
+    config: &WasmModuleConfig,
+    runtime: Option<Handle>,
+) -> Result<(Store<context::State>, Instance)> {
+    let wasi = WasiCtxBuilder::new().inherit_stdio().build();
+    let mut store = Store::new(engine, context::State::new(config.clone(), wasi, runtime));
+    store.limiter(|state| &mut state.limiter);
+    let instance = linker.instantiate(&mut store, module)?;
+    Ok((store, instance))
 }
 
 #[test]
@@ -222,7 +390,7 @@ fn protobuf() -> Result<()> {
         HashMap::new(),
         16 * 64 * 1024 * 10, // 10MB
     ))?;
-    let out = module.process(event.clone())?;
+    let out = module.process(vec![event.clone()])?;
     module.shutdown()?;
 
     let retval = out.into_iter().next().unwrap();
@@ -0,0 +1,173 @@
+//! Sandboxed outbound networking for guests, limited to the `allowed_hosts` of the module.
+use crate::Result;
+use hyper::{body::to_bytes as body_to_bytes, client::HttpConnector, Body, Client, Request, Uri};
+use hyper_openssl::HttpsConnector;
+use std::{collections::HashMap, io::Write, net::TcpStream};
+use tokio::runtime::Handle;
+use vector_wasm::{HttpRequest, HttpResponse};
+
+/// The outbound connections of a module instance.
+#[derive(Default)]
+pub(super) struct Network {
+    http: Option<Client<HttpsConnector<HttpConnector>>>,
+    tcp: HashMap<String, TcpStream>,
+}
+
+/// Returns true if `host` and `port` match an entry of `allowed_hosts`. Entries without a port
+/// allow all ports of the host.
+pub(super) fn is_allowed(allowed_hosts: &[String], host: &str, port: u16) -> bool {
+    allowed_hosts
+        .iter()
+        .any(|allowed| match split_host_port(allowed) {
+            Some((allowed_host, allowed_port)) => {
+                allowed_host.eq_ignore_ascii_case(host) && allowed_port == port
+            }
+            None => allowed.eq_ignore_ascii_case(host),
+        })
+}
+
+fn split_host_port(address: &str) -> Option<(&str, u16)> {
+    let mut parts = address.rsplitn(2, ':');
+    let port = parts.next()?.parse().ok()?;
+    let host = parts.next()?.trim_start_matches('[').trim_end_matches(']');
+    Some((host, port))
+}
+
+impl Network {
+    /// Make `request`, blocking until the response body is read.
+    ///
+    /// Requires a handle to the runtime, as the module itself runs outside of it.
+    pub(super) fn http_request(
+        &mut self,
+        runtime: Option<&Handle>,
+        allowed_hosts: &[String],
+        request: HttpRequest,
+    ) -> Result<HttpResponse> {
+        let runtime = runtime.ok_or("HTTP requests are not available outside of a runtime.")?;
+        let uri: Uri = request.uri.parse()?;
+        let host = uri
+            .host()
+            .ok_or("Request URI has no host.")?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+            Some("https") => 443,
+            _ => 80,
+        });
+        if !is_allowed(allowed_hosts, host, port) {
+            return Err(format!("Host {}:{} is not in `allowed_hosts`.", host, port).into());
+        }
+
+        let mut builder = Request::builder().method(request.method.as_str()).uri(uri);
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let request = builder.body(Body::from(request.body))?;
+
+        let client = match &self.http {
+            Some(client) => client.clone(),
+            None => {
+                let client = Client::builder().build(HttpsConnector::new()?);
+                self.http = Some(client.clone());
+                client
+            }
+        };
+
+        runtime.enter(|| {
+            futures::executor::block_on(async move {
+                let response = client.request(request).await?;
+                let (parts, body) = response.into_parts();
+                let body = body_to_bytes(body).await?;
+                Ok::<_, crate::Error>(HttpResponse {
+                    status: parts.status.as_u16(),
+                    headers: parts
+                        .headers
+                        .iter()
+                        .filter_map(|(name, value)| {
+                            Some((name.to_string(), value.to_str().ok()?.to_owned()))
+                        })
+                        .collect(),
+                    body: body.to_vec(),
+                })
+            })
+        })
+    }
+
+    /// Write `data` to `address`, reusing the connection of earlier calls. Broken connections
+    /// are dropped, and reopened by the next call.
+    pub(super) fn tcp_send(
+        &mut self,
+        allowed_hosts: &[String],
+        address: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        let (host, port) = split_host_port(address).ok_or("Address must be `host:port`.")?;
+        if !is_allowed(allowed_hosts, host, port) {
+            return Err(format!("Address {} is not in `allowed_hosts`.", address).into());
+        }
+
+        if !self.tcp.contains_key(address) {
+            let stream = TcpStream::connect((host, port))?;
+            self.tcp.insert(address.to_owned(), stream);
+        }
+        let stream = self
+            .tcp
+            .get_mut(address)
+            .expect("connection was just opened");
+        if let Err(error) = stream.write_all(data).and_then(|_| stream.flush()) {
+            self.tcp.remove(address);
+            return Err(error.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(hosts: &[&str]) -> Vec<String> {
+        hosts.iter().map(|host| host.to_string()).collect()
+    }
+
+    #[test]
+    fn allows_listed_hosts() {
+        let hosts = allowed(&["example.com", "localhost:9000", "[::1]:8080"]);
+
+        assert!(is_allowed(&hosts, "example.com", 443));
+        assert!(is_allowed(&hosts, "EXAMPLE.com", 80));
+        assert!(is_allowed(&hosts, "localhost", 9000));
+        assert!(is_allowed(&hosts, "::1", 8080));
+
+        assert!(!is_allowed(&hosts, "localhost", 9001));
+        assert!(!is_allowed(&hosts, "sub.example.com", 443));
+        assert!(!is_allowed(&hosts, "::1", 80));
+        assert!(!is_allowed(&[], "example.com", 443));
+    }
+
+    #[test]
+    fn rejects_unlisted_tcp_addresses() {
+        let mut network = Network::default();
+        let hosts = allowed(&["localhost:9000"]);
+
+        assert!(network.tcp_send(&hosts, "localhost:9001", b"data").is_err());
+        assert!(network.tcp_send(&hosts, "localhost", b"data").is_err());
+        assert!(network.tcp.is_empty());
+    }
+
+    #[test]
+    fn rejects_unlisted_http_hosts() {
+        let mut network = Network::default();
+        let hosts = allowed(&["example.com:8080"]);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let request = HttpRequest::new("GET", "http://example.com/");
+        let error = network
+            .http_request(Some(runtime.handle()), &hosts, request)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Host example.com:80 is not in `allowed_hosts`."
+        );
+    }
+}
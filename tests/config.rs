@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use vector::{
     config::{self, ConfigDiff},
     topology,
@@ -9,7 +10,7 @@ async fn load(config: &str) -> Result<Vec<String>, Vec<String>> {
             let diff = ConfigDiff::initial(&c);
             match (
                 config::check(&c),
-                topology::builder::build_pieces(&c, &diff).await,
+                topology::builder::build_pieces(&c, &diff, HashMap::new()).await,
             ) {
                 (Ok(warnings), Ok(_new_pieces)) => Ok(warnings),
                 (Err(t_errors), Err(p_errors)) => {
@@ -0,0 +1,46 @@
+//! Delivery
+//!
+//! A sink answering every batch with the `delivery` in its options, after asking Vector to retry
+//! the first `retries` batches.
+
+#![deny(improper_ctypes)]
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use vector_wasm::{hostcall, Delivery, Registration, Role};
+// This is **required**.
+pub use vector_wasm::interop::*;
+
+static DELIVERY: AtomicU32 = AtomicU32::new(Delivery::Ack as u32);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+
+#[no_mangle]
+pub extern "C" fn init() {
+    let config = hostcall::config().unwrap();
+    assert_eq!(config.role, Role::Sink);
+
+    if let Some(delivery) = config.options.get("delivery") {
+        let delivery: Delivery = serde_json::from_value(delivery.clone()).unwrap();
+        DELIVERY.store(delivery as u32, Ordering::SeqCst);
+    }
+    if let Some(retries) = config
+        .options
+        .get("retries")
+        .and_then(|value| value.as_u64())
+    {
+        RETRIES.store(retries, Ordering::SeqCst);
+    }
+
+    Registration::sink().register().unwrap();
+}
+
+#[no_mangle]
+pub extern "C" fn send(_data: u32, _length: u32) -> u32 {
+    if RETRIES.load(Ordering::SeqCst) > 0 {
+        RETRIES.fetch_sub(1, Ordering::SeqCst);
+        return Delivery::Retry as u32;
+    }
+    DELIVERY.load(Ordering::SeqCst)
+}
+
+#[no_mangle]
+pub extern "C" fn shutdown() {}
@@ -0,0 +1,35 @@
+//! Poll
+//!
+//! A source emitting an event on every poll, and on every tick of the timer it registers.
+
+#![deny(improper_ctypes)]
+
+use serde_json::json;
+use vector_wasm::{hostcall, Registration, Role};
+// This is **required**.
+pub use vector_wasm::interop::*;
+
+#[no_mangle]
+pub extern "C" fn init() {
+    let config = hostcall::config().unwrap();
+    assert_eq!(config.role, Role::Source);
+
+    hostcall::timer(1, 1).unwrap();
+    Registration::source().register().unwrap();
+}
+
+#[no_mangle]
+pub extern "C" fn poll() -> u32 {
+    let event = json!({ "message": "polled" });
+    hostcall::emit(serde_json::to_vec(&event).unwrap()).unwrap();
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn timer_handler(id: u32) {
+    let event = json!({ "message": "timer", "timer": id });
+    hostcall::emit(serde_json::to_vec(&event).unwrap()).unwrap();
+}
+
+#[no_mangle]
+pub extern "C" fn shutdown() {}
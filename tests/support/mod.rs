@@ -17,7 +17,10 @@ use vector::config::{
 };
 use vector::event::{self, metric::MetricValue, Event, Value};
 use vector::shutdown::ShutdownSignal;
-use vector::sinks::{util::StreamSink, Healthcheck, RouterSink};
+use vector::sinks::{
+    util::{EncodedEvent, StreamSink},
+    Healthcheck, RouterSink,
+};
 use vector::sources::Source;
 use vector::transforms::Transform;
 use vector::Pipeline;
@@ -277,7 +280,8 @@ where
         let sink = sink.sink_map_err(|error| {
             error!(message = "Ingesting an event failed at mock sink", ?error)
         });
-        let sink = StreamSink::new(sink, cx.acker());
+        let sink = StreamSink::new(sink, cx.acker())
+            .with_flat_map(|event| stream::iter_ok(EncodedEvent::encode(event, Some)));
         let healthcheck = if self.healthy {
             future::ok(())
         } else {
@@ -1,18 +1,21 @@
 mod support;
 
-use crate::support::{sink, sink_failing_healthcheck, source, transform, MockSourceConfig};
+use crate::support::{
+    sink, sink_dead, sink_failing_healthcheck, source, transform, MockSourceConfig,
+};
 use futures::compat::Future01CompatExt;
 use futures01::{
     future, future::Future, sink::Sink, stream::iter_ok, stream::Stream, sync::mpsc::SendError,
 };
 use std::{
+    collections::HashMap,
     iter,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
 };
-use tokio::time::{delay_for, Duration};
+use tokio::time::{self, delay_for, Duration};
 use vector::{
     config::Config,
     event::{self, Event},
@@ -120,6 +123,51 @@ async fn topology_source_and_sink() {
     assert_eq!(vec![event], res);
 }
 
+#[tokio::test]
+async fn topology_stop_drains_sinks() {
+    let (in1, source1) = source();
+    let (out1, sink1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("out1", &["in1"], sink1);
+
+    let (topology, _crash) = start_topology(config, false).await;
+
+    let event = Event::from("this");
+    in1.send(event.clone()).compat().await.unwrap();
+
+    let h_out1 = tokio::spawn(out1.collect().compat());
+    let report = topology.stop().compat().await.unwrap();
+
+    assert!(report.unfinished.is_empty());
+    assert!(!report.dropped_events());
+    assert_eq!(vec![event], h_out1.await.unwrap().unwrap());
+}
+
+#[tokio::test]
+async fn topology_stop_reports_undelivered_events() {
+    let (in1, source1) = source();
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("out1", &["in1"], sink_dead());
+    config.sinks["out1"].shutdown_timeout_secs = 1;
+
+    let (topology, _crash) = start_topology(config, false).await;
+
+    let events = (0..5)
+        .map(|i| Event::from(format!("event {}", i)))
+        .collect::<Vec<_>>();
+    in1.send_all(iter_ok(events)).compat().await.unwrap();
+    delay_for(Duration::from_millis(50)).await;
+
+    let report = topology.stop().compat().await.unwrap();
+
+    assert_eq!(report.unfinished, vec!["out1".to_owned()]);
+    assert_eq!(report.undelivered["out1"], 5);
+}
+
 #[tokio::test]
 async fn topology_multiple_sources() {
     let (in1, source1) = source();
@@ -386,6 +434,142 @@ async fn topology_swap_sink() {
     assert_eq!(vec![event], res2);
 }
 
+#[tokio::test]
+async fn topology_reload_keeps_sink_buffer() {
+    let (in1, source1) = source();
+    let (out1v1, sink1v1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("out1", &["in1"], sink1v1);
+
+    let (mut topology, _crash) = start_topology(config, false).await;
+
+    // Nothing reads from the old sink yet, so most of these wait in its buffer.
+    let events = (0..50)
+        .map(|i| Event::from(format!("event {}", i)))
+        .collect::<Vec<_>>();
+    in1.send_all(iter_ok(events.clone()))
+        .compat()
+        .await
+        .unwrap();
+    delay_for(Duration::from_millis(50)).await;
+
+    let (out1v2, sink1v2) = sink(100);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source().1);
+    config.add_sink("out1", &["in1"], sink1v2);
+    config.sinks["out1"].healthcheck = false;
+
+    // The old sink only lets go of the buffer once it has flushed what it holds.
+    let h_out1v1 = tokio::spawn(async move {
+        delay_for(Duration::from_millis(50)).await;
+        out1v1.collect().compat().await
+    });
+    assert!(topology
+        .reload_config_and_respawn(config, false)
+        .await
+        .unwrap());
+    let h_out1v2 = tokio::spawn(out1v2.collect().compat());
+    topology.stop().compat().await.unwrap();
+
+    let res1v1 = h_out1v1.await.unwrap().unwrap();
+    let res1v2 = h_out1v2.await.unwrap().unwrap();
+
+    assert!(!res1v2.is_empty());
+    assert_eq!(events, [res1v1, res1v2].concat());
+}
+
+#[tokio::test]
+async fn topology_reload_takes_buffer_from_sink_that_never_flushes() {
+    let (in1, source1) = source();
+    let (out1v1, sink1v1) = sink(1);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("out1", &["in1"], sink1v1);
+
+    let (mut topology, _crash) = start_topology(config, false).await;
+
+    let events = (0..50)
+        .map(|i| Event::from(format!("event {}", i)))
+        .collect::<Vec<_>>();
+    in1.send_all(iter_ok(events.clone()))
+        .compat()
+        .await
+        .unwrap();
+    delay_for(Duration::from_millis(50)).await;
+
+    let (out1v2, sink1v2) = sink(100);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source().1);
+    config.add_sink("out1", &["in1"], sink1v2);
+    config.sinks["out1"].healthcheck = false;
+
+    // Nothing reads from the old sink until the reload is done, so it never flushes and the
+    // reload only goes through once it gives up waiting on it.
+    time::pause();
+    let reload = topology.reload_config_and_respawn(config, false);
+    let advance = async {
+        loop {
+            time::advance(Duration::from_millis(100)).await;
+        }
+    };
+    let reloaded = tokio::select! {
+        reloaded = reload => reloaded,
+        _ = advance => unreachable!(),
+    };
+    assert!(reloaded.unwrap());
+
+    let h_out1v1 = tokio::spawn(out1v1.collect().compat());
+    let h_out1v2 = tokio::spawn(out1v2.collect().compat());
+    topology.stop().compat().await.unwrap();
+
+    let res1v1 = h_out1v1.await.unwrap().unwrap();
+    let res1v2 = h_out1v2.await.unwrap().unwrap();
+
+    // The old sink still delivers what it held, and the rebuilt one picks up the rest from
+    // the same buffer.
+    assert!(!res1v2.is_empty());
+    assert_eq!(events, [res1v1, res1v2].concat());
+}
+
+#[tokio::test]
+async fn topology_rewire_transform() {
+    let (in1, source1) = source();
+    let (in2, source2) = source();
+    let (out1, sink1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_source("in2", source2);
+    config.add_transform("t1", &["in1"], transform(" transformed", 0.0));
+    config.add_sink("out1", &["t1"], sink1);
+
+    let (mut topology, _crash) = start_topology(config, false).await;
+
+    let mut config = Config::empty();
+    config.add_source("in1", source().1);
+    config.add_source("in2", source().1);
+    config.add_transform("t1", &["in2"], transform(" transformed", 0.0));
+    config.add_sink("out1", &["t1"], sink(10).1);
+
+    assert!(topology
+        .reload_config_and_respawn(config, false)
+        .await
+        .unwrap());
+
+    let h_out1 = tokio::spawn(out1.map(into_message).collect().compat());
+    in1.send(Event::from("from in1")).compat().await.unwrap();
+    in2.send(Event::from("from in2")).compat().await.unwrap();
+    topology.stop().compat().await.unwrap();
+
+    let res1 = h_out1.await.unwrap().unwrap();
+    assert_eq!(vec!["from in2 transformed"], res1);
+}
+
 #[tokio::test]
 async fn topology_swap_transform() {
     let (in1, source1) = source();
@@ -496,7 +680,9 @@ async fn topology_swap_transform_is_atomic() {
 async fn topology_required_healthcheck_fails_start() {
     let config = basic_config_with_sink_failing_healthcheck();
     let diff = vector::config::ConfigDiff::initial(&config);
-    let pieces = topology::validate(&config, &diff).await.unwrap();
+    let pieces = topology::validate(&config, &diff, HashMap::new())
+        .await
+        .unwrap();
     assert!(topology::start_validated(config, diff, pieces, true)
         .await
         .is_none());
@@ -506,7 +692,9 @@ async fn topology_required_healthcheck_fails_start() {
 async fn topology_optional_healthcheck_does_not_fail_start() {
     let config = basic_config_with_sink_failing_healthcheck();
     let diff = vector::config::ConfigDiff::initial(&config);
-    let pieces = topology::validate(&config, &diff).await.unwrap();
+    let pieces = topology::validate(&config, &diff, HashMap::new())
+        .await
+        .unwrap();
     assert!(topology::start_validated(config, diff, pieces, false)
         .await
         .is_some());